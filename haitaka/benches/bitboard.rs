@@ -0,0 +1,57 @@
+// Benchmark for iterating over the squares of a bitboard
+//
+// Compares the u128 `trailing_zeros` iterator with `for_each_square`,
+// which splits the bitboard into its low and high 64-bit halves.
+
+use std::time::Duration;
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use haitaka::{BitBoard, Board, Color, Piece};
+
+const POSITIONS: &[&str] = &[
+    "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+    "ln1g5/1r4k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b b BG2SLPnp 61",
+    "ln1gk1snl/1r5b1/p1ppppgpp/1s4p2/1p7/P1P3R2/1P1PPPP1P/1BG3S2/LNS1KG1NL b P",
+];
+
+pub fn criterion_benchmark(criterion: &mut Criterion) {
+    // a mix of sparse and dense bitboards, as seen in move generation
+    let mut bitboards: Vec<BitBoard> = vec![BitBoard::FULL, BitBoard::EDGES];
+    for sfen in POSITIONS {
+        let board: Board = sfen.parse().unwrap();
+        bitboards.push(board.occupied());
+        bitboards.push(!board.occupied());
+        for color in Color::ALL {
+            for piece in Piece::ALL {
+                bitboards.push(board.colored_pieces(color, piece));
+            }
+        }
+    }
+
+    criterion
+        .bench_function("BitBoard iter", |b| {
+            b.iter(|| {
+                for &bb in &bitboards {
+                    for square in black_box(bb) {
+                        black_box(square);
+                    }
+                }
+            });
+        })
+        .bench_function("BitBoard for_each_square", |b| {
+            b.iter(|| {
+                for &bb in &bitboards {
+                    black_box(bb).for_each_square(|square| {
+                        black_box(square);
+                    });
+                }
+            });
+        });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(100).measurement_time(Duration::from_secs(5));
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
@@ -92,6 +92,30 @@ pub fn criterion_benchmark(criterion: &mut Criterion) {
         "bishops"
     );
     bench_piece!(criterion, positions, Piece::King, king_attacks, "kings");
+
+    // Validating the legal moves of each position, as when probing hash moves
+    let to_check: Vec<_> = positions
+        .iter()
+        .flat_map(|board| {
+            let mut moves = Vec::new();
+            board.generate_moves(|mvs| {
+                moves.extend(mvs.into_iter().map(|mv| (board, mv)));
+                false
+            });
+            moves
+        })
+        .collect();
+
+    criterion
+        .benchmark_group("legality")
+        .throughput(Throughput::Elements(to_check.len() as u64))
+        .bench_function("legal moves", |b| {
+            b.iter(|| {
+                for &(board, mv) in &to_check {
+                    black_box(board.is_legal(mv));
+                }
+            })
+        });
 }
 
 criterion_group! {
@@ -120,6 +120,30 @@ pub fn criterion_benchmark(criterion: &mut Criterion) {
                 }
             });
         })
+        .bench_function("Generate captures", |b| {
+            b.iter(|| {
+                for (board, _) in &positions {
+                    board.generate_captures(|moves| {
+                        for mv in moves {
+                            black_box(mv);
+                        }
+                        false
+                    });
+                }
+            });
+        })
+        .bench_function("Generate captures and promotions", |b| {
+            b.iter(|| {
+                for (board, _) in &positions {
+                    board.generate_captures_and_promotions(|moves| {
+                        for mv in moves {
+                            black_box(mv);
+                        }
+                        false
+                    });
+                }
+            });
+        })
         .bench_function("Startpos perft 3", |b| {
             b.iter(|| {
                 let pos = black_box(&startpos);
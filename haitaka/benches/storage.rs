@@ -0,0 +1,77 @@
+// Benchmark for the board storage layout
+//
+// Run this with and without the internal `color-major-storage` feature to compare
+// the piece-major and color-major layouts of the board bitboards:
+//
+//     cargo bench --bench storage
+//     cargo bench --bench storage --features color-major-storage
+
+use std::time::Duration;
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use haitaka::{Board, Color, Piece};
+
+const POSITIONS: &[&str] = &[
+    "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+    "ln1g5/1r4k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b b BG2SLPnp 61",
+    "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+    "ln1gk1snl/1r5b1/p1ppppgpp/1s4p2/1p7/P1P3R2/1P1PPPP1P/1BG3S2/LNS1KG1NL b P",
+];
+
+pub fn criterion_benchmark(criterion: &mut Criterion) {
+    let positions = POSITIONS
+        .iter()
+        .map(|pos| {
+            let board: Board = pos.parse().unwrap();
+            let mut all_moves = Vec::new();
+            board.generate_moves(|moves| {
+                all_moves.extend(moves);
+                false
+            });
+            (board, all_moves)
+        })
+        .collect::<Vec<_>>();
+
+    criterion
+        .bench_function("Colored pieces", |b| {
+            b.iter(|| {
+                for (board, _) in &positions {
+                    for &color in Color::ALL.iter() {
+                        for &piece in Piece::ALL.iter() {
+                            black_box(board.colored_pieces(color, piece));
+                        }
+                    }
+                }
+            });
+        })
+        .bench_function("Pieces", |b| {
+            b.iter(|| {
+                for (board, _) in &positions {
+                    for &piece in Piece::ALL.iter() {
+                        black_box(board.pieces(piece));
+                    }
+                }
+            });
+        })
+        .bench_function("Play and generate", |b| {
+            b.iter(|| {
+                for (board, moves) in &positions {
+                    for &mv in moves {
+                        let mut board = board.clone();
+                        board.play_unchecked(mv);
+                        board.generate_moves(|moves| {
+                            black_box(moves);
+                            false
+                        });
+                    }
+                }
+            });
+        });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(100).measurement_time(Duration::from_secs(10));
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
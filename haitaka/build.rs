@@ -23,10 +23,10 @@ use haitaka_types::*;
 // - num of zero stretches: 9105
 // - average length of zero stretches: 12
 
-#[cfg(not(feature = "qugiy"))]
+#[cfg(not(any(feature = "qugiy", feature = "lazy-tables")))]
 const GENERATED_FILE_NAME: &str = "sliding_moves_table.rs";
 
-#[cfg(not(feature = "qugiy"))]
+#[cfg(not(any(feature = "qugiy", feature = "lazy-tables")))]
 fn write_moves(
     table: &mut [u128],
     relevant_blockers: impl Fn(Square) -> BitBoard,
@@ -47,14 +47,15 @@ fn write_moves(
     assert!(table.len() != zeros, "write_moves only generated zeros!");
 }
 
-#[cfg(feature = "qugiy")]
+#[cfg(any(feature = "qugiy", feature = "lazy-tables"))]
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "qugiy")]
     println!("cargo:warning=INFO: The 'qugiy' feature is active in build.rs.");
-    return; // Exit early, do nothing
+    // with `lazy-tables` (and without `qugiy`) the tables are built at runtime
 }
 
-#[cfg(not(feature = "qugiy"))]
+#[cfg(not(any(feature = "qugiy", feature = "lazy-tables")))]
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
 
@@ -79,37 +80,23 @@ fn main() {
     out_file.push(GENERATED_FILE_NAME);
 
     let mut out_file = BufWriter::new(File::create(out_file).unwrap());
-    let mut num = 0;
-    write!(
+    writeln!(
         &mut out_file,
-        "const ROOK_MOVES: &[u128; {}] = &[",
-        rook_table.len()
+        "static SLIDING_MOVES: SlidingMoves = SlidingMoves {{"
     )
     .unwrap();
-    for &magic in &rook_table {
-        write!(&mut out_file, "0x{:x},", magic).unwrap();
-        num += 1;
-        if num == 4 {
-            writeln!(&mut out_file).unwrap();
-            num = 0;
-        }
-    }
-    writeln!(&mut out_file, "];").unwrap();
-
-    num = 0;
-    write!(
-        &mut out_file,
-        "const BISHOP_MOVES: &[u128; {}] = &[",
-        bishop_table.len()
-    )
-    .unwrap();
-    for magic in &bishop_table {
-        write!(&mut out_file, "0x{:x},", magic).unwrap();
-        num += 1;
-        if num == 4 {
-            writeln!(&mut out_file).unwrap();
-            num = 0;
+    for (name, table) in [("rook", &rook_table), ("bishop", &bishop_table)] {
+        let mut num = 0;
+        write!(&mut out_file, "{}: [", name).unwrap();
+        for &moves in table {
+            write!(&mut out_file, "0x{:x},", moves).unwrap();
+            num += 1;
+            if num == 4 {
+                writeln!(&mut out_file).unwrap();
+                num = 0;
+            }
         }
+        writeln!(&mut out_file, "],").unwrap();
     }
-    writeln!(&mut out_file, "];").unwrap();
+    writeln!(&mut out_file, "}};").unwrap();
 }
@@ -41,9 +41,7 @@ impl Rng {
             .0
             .wrapping_mul(0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645);
         let rot = (self.0 >> 122) as u32;
-        let xsl = ((self.0 >> 64) as u64 ^ self.0 as u64).rotate_right(rot);
-
-        xsl
+        ((self.0 >> 64) as u64 ^ self.0 as u64).rotate_right(rot)
     }
 
     // random a pseudo-random u64 with approx. 8 bits set
@@ -204,7 +202,7 @@ fn find_magic(
         // in order to find a multiplier that works for all configs.
 
         let x = merge(mask).wrapping_mul(magic) & 0xFFFC_0000_0000_0000;
-        if x.count_ones() < 6 as u32 {
+        if x.count_ones() < 6 {
             // bad magic
             bad_magics += 1;
             continue;
@@ -1,9 +1,8 @@
 use std::env::args;
 use std::time::Instant;
 
-// Copied from `cozy-chess` with only trivial modifications.
-// Note that bulk counting on leave nodes significantly speeds up the run.
-
+// Count the move sequences of the game tree with the `haitaka::perft` module.
+//
 // The function `perft(ply)` returns the total number of _nodes_ visited by
 // generating all nodes of the game tree up to (including) a given depth (ply).
 // It counts all visited nodes and does not discount duplicated positions.
@@ -13,75 +12,6 @@ use std::time::Instant;
 
 use haitaka::*;
 
-fn perft<const DROPS: bool>(board: &Board, depth: u8) -> u64 {
-    if depth == 0 {
-        1
-    } else {
-        let mut nodes = 0;
-        board.generate_board_moves(|moves| {
-            for mv in moves {
-                let mut board = board.clone();
-                board.play_unchecked(mv);
-                nodes += perft::<DROPS>(&board, depth - 1);
-            }
-            false
-        });
-        if DROPS {
-            board.generate_drops(|moves| {
-                for mv in moves {
-                    let mut board = board.clone();
-                    board.play_unchecked(mv);
-                    nodes += perft::<DROPS>(&board, depth - 1);
-                }
-                false
-            });
-        }
-        nodes
-    }
-}
-
-fn perft_bulk<const DROPS: bool>(board: &Board, depth: u8) -> u64 {
-    let mut nodes = 0;
-    match depth {
-        0 => nodes += 1,
-        1 => {
-            board.generate_board_moves(|moves| {
-                nodes += moves.into_iter().len() as u64;
-                false
-            });
-            if DROPS {
-                board.generate_drops(|moves| {
-                    nodes += moves.into_iter().len() as u64;
-                    false
-                });
-            }
-        }
-        _ => {
-            board.generate_board_moves(|moves| {
-                for mv in moves {
-                    let mut board = board.clone();
-                    board.play_unchecked(mv);
-                    let child_nodes = perft_bulk::<DROPS>(&board, depth - 1);
-                    nodes += child_nodes;
-                }
-                false
-            });
-            if DROPS {
-                board.generate_drops(|moves| {
-                    for mv in moves {
-                        let mut board = board.clone();
-                        board.play_unchecked(mv);
-                        let child_nodes = perft_bulk::<DROPS>(&board, depth - 1);
-                        nodes += child_nodes;
-                    }
-                    false
-                });
-            }
-        }
-    }
-    nodes
-}
-
 fn format_with_underscores(num: u64) -> String {
     let num_str = num.to_string();
     let mut formatted = String::new();
@@ -100,27 +30,33 @@ fn format_with_underscores(num: u64) -> String {
 }
 
 fn help_message() {
-    eprintln!("USAGE: perft <depth> [<SFEN>] [--no-bulk] [--help]");
+    eprintln!("USAGE: perft <depth> [<SFEN>] [--divide] [--hash] [--parallel] [--help]");
     eprintln!("  Defaults to the start position if no SFEN is specified.");
     eprintln!("  OPTIONS:");
-    eprintln!("    --no-drops: Do not count drops.");
-    eprintln!("    --no-bulk:  Disable bulk counting on leaf node parents.");
+    eprintln!("    --divide:   Print the count for every first move.");
+    eprintln!("    --hash:     Use a transposition table (with 2^22 entries).");
+    eprintln!("    --parallel: Use all cores (requires the `rayon` feature).");
     eprintln!("    --help:     Print this message.");
 }
 
 fn main() {
     let mut depth = None;
     let mut board = None;
-    let mut bulk = true;
-    let mut drops = true;
     let mut help = false;
+    let mut divide = false;
+    let mut hash = false;
+    let mut parallel = false;
     for arg in args().skip(1) {
-        if arg == "--no-bulk" {
-            bulk = false;
+        if arg == "--divide" {
+            divide = true;
+            continue;
+        }
+        if arg == "--parallel" {
+            parallel = true;
             continue;
         }
-        if arg == "--no-drops" {
-            drops = false;
+        if arg == "--hash" {
+            hash = true;
             continue;
         }
         if arg == "--help" {
@@ -162,23 +98,29 @@ fn main() {
         help_message();
         return;
     };
-    let board = if board.is_some() {
-        board.unwrap()
-    } else {
-        Board::startpos()
-    };
+    let board = board.unwrap_or_else(Board::startpos);
+
+    if divide {
+        for (mv, nodes) in perft::perft_divide(&board, depth) {
+            println!("{}: {}", mv, nodes);
+        }
+    }
 
     let start = Instant::now();
-    let nodes = if bulk {
-        if drops {
-            perft_bulk::<true>(&board, depth)
-        } else {
-            perft_bulk::<false>(&board, depth)
+    let nodes = if parallel {
+        #[cfg(feature = "rayon")]
+        {
+            perft::perft_parallel(&board, depth)
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            eprintln!("ERROR: --parallel requires the `rayon` feature.");
+            return;
         }
-    } else if drops {
-        perft::<true>(&board, depth)
+    } else if hash {
+        perft::perft_hashed(&board, depth, 1 << 22)
     } else {
-        perft::<false>(&board, depth)
+        perft::perft(&board, depth)
     };
     let elapsed = start.elapsed();
     let nps = nodes as f64 / elapsed.as_secs_f64();
@@ -0,0 +1,239 @@
+// Run the standard benchmark set and append the results to a JSON Lines file.
+//
+// Each run appends one line with the git commit, the crate version and features, and
+// the node counts and timings of every benchmark, so that the performance can be
+// tracked across commits and releases. Build it in release mode:
+//
+//     cargo run --release --features bench-history --bin bench-history -- [OPTIONS]
+//
+// The timings are the best of several runs, to reduce the noise of other processes.
+
+use std::env::args;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::Command;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use haitaka::*;
+
+// (name, SFEN, perft depth)
+const PERFT_POSITIONS: &[(&str, &str, u8)] = &[
+    ("perft startpos", SFEN_STARTPOS, 5),
+    (
+        "perft middle game",
+        "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+        4,
+    ),
+    (
+        "perft opening",
+        "ln1gk1snl/1r5b1/p1ppppgpp/1s4p2/1p7/P1P3R2/1P1PPPP1P/1BG3S2/LNS1KG1NL b P 1",
+        4,
+    ),
+];
+
+// The positions for the legality benchmark.
+const LEGALITY_POSITIONS: &[&str] = &[
+    SFEN_STARTPOS,
+    "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+    "ln1gk1snl/1r5b1/p1ppppgpp/1s4p2/1p7/P1P3R2/1P1PPPP1P/1BG3S2/LNS1KG1NL b P 1",
+];
+
+// Number of times every move is checked in the legality benchmark.
+const LEGALITY_ROUNDS: u64 = 100_000;
+
+// Every feature of the crate, in the order of Cargo.toml.
+const FEATURES: &[(&str, bool)] = &[
+    ("std", cfg!(feature = "std")),
+    ("qugiy", cfg!(feature = "qugiy")),
+    ("lazy-tables", cfg!(feature = "lazy-tables")),
+    ("viz", cfg!(feature = "viz")),
+    ("reference-eval", cfg!(feature = "reference-eval")),
+    ("encoding", cfg!(feature = "encoding")),
+    ("serde", cfg!(feature = "serde")),
+    ("rayon", cfg!(feature = "rayon")),
+    ("bench-history", cfg!(feature = "bench-history")),
+    ("verify-checks", cfg!(feature = "verify-checks")),
+    ("color-major-storage", cfg!(feature = "color-major-storage")),
+];
+
+struct BenchResult {
+    name: &'static str,
+    nodes: u64,
+    nanos: u128,
+}
+
+fn perft(board: &Board, depth: u8) -> u64 {
+    let mut nodes = 0;
+    if depth <= 1 {
+        board.generate_moves(|moves| {
+            nodes += moves.into_iter().len() as u64;
+            false
+        });
+    } else {
+        board.generate_moves(|moves| {
+            for mv in moves {
+                let mut board = board.clone();
+                board.play_unchecked(mv);
+                nodes += perft(&board, depth - 1);
+            }
+            false
+        });
+    }
+    nodes
+}
+
+fn legality(moves: &[(Board, Move)]) -> u64 {
+    let mut legal = 0;
+    for _ in 0..LEGALITY_ROUNDS {
+        for (board, mv) in moves {
+            legal += std::hint::black_box(board.is_legal(*mv)) as u64;
+        }
+    }
+    legal
+}
+
+// Run `f` `repeat` times and keep the best time.
+fn best_of(name: &'static str, repeat: usize, mut f: impl FnMut() -> u64) -> BenchResult {
+    let mut best = None;
+    let mut nodes = 0;
+    for _ in 0..repeat {
+        let start = Instant::now();
+        nodes = f();
+        let nanos = start.elapsed().as_nanos();
+        best = Some(best.map_or(nanos, |best: u128| best.min(nanos)));
+    }
+    BenchResult {
+        name,
+        nodes,
+        nanos: best.unwrap_or_default(),
+    }
+}
+
+fn git_sha() -> String {
+    if let Ok(sha) = std::env::var("GIT_SHA") {
+        return sha;
+    }
+    Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|sha| sha.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Only names and SHAs are written as strings, but escape them anyway.
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn to_json(results: &[BenchResult]) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let features: Vec<String> = FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| json_string(name))
+        .collect();
+    let results: Vec<String> = results
+        .iter()
+        .map(|result| {
+            let nps = result.nodes as u128 * 1_000_000_000 / result.nanos.max(1);
+            format!(
+                "{{\"name\":{},\"nodes\":{},\"time_ns\":{},\"nps\":{}}}",
+                json_string(result.name),
+                result.nodes,
+                result.nanos,
+                nps
+            )
+        })
+        .collect();
+    format!(
+        "{{\"timestamp\":{},\"git_sha\":{},\"version\":{},\"debug\":{},\"features\":[{}],\"results\":[{}]}}",
+        timestamp,
+        json_string(&git_sha()),
+        json_string(capabilities().version),
+        cfg!(debug_assertions),
+        features.join(","),
+        results.join(",")
+    )
+}
+
+fn help_message() {
+    eprintln!("USAGE: bench-history [--output <FILE>] [--repeat <N>] [--help]");
+    eprintln!("  Runs the standard benchmarks and appends one JSON line with the results.");
+    eprintln!("  OPTIONS:");
+    eprintln!("    --output <FILE>: The file to append to (default: bench-history.jsonl).");
+    eprintln!("    --repeat <N>:    Keep the best time of N runs (default: 3).");
+    eprintln!("    --help:          Print this message.");
+}
+
+fn main() {
+    let mut output = String::from("bench-history.jsonl");
+    let mut repeat = 3;
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => match args.next() {
+                Some(path) => output = path,
+                None => return help_message(),
+            },
+            "--repeat" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => repeat = n,
+                _ => return help_message(),
+            },
+            _ => return help_message(),
+        }
+    }
+
+    if cfg!(debug_assertions) {
+        eprintln!("WARNING: This is a debug build. Use --release for meaningful timings.");
+    }
+
+    let mut results = Vec::new();
+    for &(name, sfen, depth) in PERFT_POSITIONS {
+        let board: Board = sfen.parse().unwrap();
+        results.push(best_of(name, repeat, || perft(&board, depth)));
+    }
+
+    let mut moves = Vec::new();
+    for sfen in LEGALITY_POSITIONS {
+        let board: Board = sfen.parse().unwrap();
+        board.generate_moves(|mvs| {
+            moves.extend(mvs.into_iter().map(|mv| (board.clone(), mv)));
+            false
+        });
+    }
+    let result = best_of("legality", repeat, || legality(&moves));
+    assert_eq!(result.nodes, moves.len() as u64 * LEGALITY_ROUNDS);
+    results.push(result);
+
+    for result in &results {
+        let millis = result.nanos as f64 / 1_000_000.0;
+        println!(
+            "{:<20} {:>12} nodes {:>10.2} ms",
+            result.name, result.nodes, millis
+        );
+    }
+
+    let json = to_json(&results);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&output)
+        .unwrap_or_else(|error| panic!("Cannot open {}: {}", output, error));
+    writeln!(file, "{}", json).unwrap();
+    println!("Appended the results to {}", output);
+}
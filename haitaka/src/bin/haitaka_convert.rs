@@ -0,0 +1,221 @@
+// Convert game records between KIF, CSA and SFEN.
+//
+// The input format is taken from --from, or else from the file extension (.kif, .kifu,
+// .csa, .sfen, .usi), or else guessed from the contents. SFEN records are a USI
+// `position` command on one line (`position startpos moves 7g7f ...`, the `position`
+// keyword may be left out); they only keep the start position and the moves.
+//
+//     cargo run --features std --bin haitaka-convert -- --to csa game.kif
+//
+// Without files, a record is read from stdin. The converted records are written to
+// stdout, or with --out-dir to files with the extension of the output format.
+
+use std::env::args;
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+
+use haitaka::record::GameRecord;
+use haitaka::usi::Position;
+use haitaka::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Kif,
+    Csa,
+    Sfen,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "kif" | "kifu" => Some(Self::Kif),
+            "csa" => Some(Self::Csa),
+            "sfen" | "usi" => Some(Self::Sfen),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Kif => "kif",
+            Self::Csa => "csa",
+            Self::Sfen => "sfen",
+        }
+    }
+
+    // Guess the format of a record from its first lines.
+    fn detect(text: &str) -> Self {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        for line in lines.by_ref().take(20) {
+            if line.starts_with("position") || line.starts_with("startpos") {
+                return Self::Sfen;
+            }
+            if line.contains('：') || line.starts_with("手数") {
+                return Self::Kif;
+            }
+            if ["V2", "N+", "N-", "PI", "P1", "$"]
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+            {
+                return Self::Csa;
+            }
+        }
+        Self::Sfen
+    }
+}
+
+fn decode(bytes: Vec<u8>) -> Result<String, String> {
+    #[cfg(feature = "encoding")]
+    {
+        encoding::decode(&bytes)
+            .map(|text| text.into_owned())
+            .map_err(|_| "The record is neither valid UTF-8 nor valid Shift-JIS".to_string())
+    }
+    #[cfg(not(feature = "encoding"))]
+    {
+        String::from_utf8(bytes).map_err(|_| "The record is not valid UTF-8".to_string())
+    }
+}
+
+fn parse_sfen(text: &str) -> Result<GameRecord, String> {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    let command = if line.starts_with("position") {
+        line.to_string()
+    } else if line.starts_with("startpos") || line.starts_with("sfen") {
+        format!("position {}", line)
+    } else {
+        format!("position sfen {}", line)
+    };
+    let position: Position = command.parse().map_err(|e| format!("{} (line 1)", e))?;
+    position.board().map_err(|e| format!("{} (line 1)", e))?;
+    let mut record = GameRecord::new(position.start);
+    for mv in position.moves {
+        record.push(mv);
+    }
+    Ok(record)
+}
+
+fn parse(text: &str, format: Format) -> Result<GameRecord, String> {
+    match format {
+        Format::Kif => kif::parse(text).map_err(|e| e.to_string()),
+        Format::Csa => csa::parse(text).map_err(|e| e.to_string()),
+        Format::Sfen => parse_sfen(text),
+    }
+}
+
+fn write(record: GameRecord, format: Format) -> Result<String, String> {
+    match format {
+        Format::Kif => kif::write(&record).map_err(|e| e.to_string()),
+        Format::Csa => csa::write(&record).map_err(|e| e.to_string()),
+        Format::Sfen => {
+            let position = Position {
+                start: record.start,
+                moves: record.moves,
+            };
+            Ok(format!("{}\n", position))
+        }
+    }
+}
+
+fn convert(bytes: Vec<u8>, from: Option<Format>, to: Format) -> Result<String, String> {
+    let text = decode(bytes)?;
+    let from = from.unwrap_or_else(|| Format::detect(&text));
+    write(parse(&text, from)?, to)
+}
+
+fn help_message() -> ExitCode {
+    eprintln!("USAGE: haitaka-convert --to <FORMAT> [--from <FORMAT>] [--out-dir <DIR>] [FILE]...");
+    eprintln!("  Converts game records between KIF, CSA and SFEN (kif, csa, sfen).");
+    eprintln!("  Without files, a record is read from stdin.");
+    eprintln!("  OPTIONS:");
+    eprintln!("    --to <FORMAT>:   The output format.");
+    eprintln!("    --from <FORMAT>: The input format (default: from the file extension or the");
+    eprintln!("                     contents).");
+    eprintln!("    --out-dir <DIR>: Write <name>.<format> files to DIR instead of to stdout.");
+    eprintln!("    --help:          Print this message.");
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let mut to = None;
+    let mut from = None;
+    let mut out_dir = None;
+    let mut files = Vec::new();
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => match args.next().as_deref().and_then(Format::from_name) {
+                Some(format) => to = Some(format),
+                None => return help_message(),
+            },
+            "--from" => match args.next().as_deref().and_then(Format::from_name) {
+                Some(format) => from = Some(format),
+                None => return help_message(),
+            },
+            "--out-dir" => match args.next() {
+                Some(dir) => out_dir = Some(dir),
+                None => return help_message(),
+            },
+            _ if arg.starts_with("--") => return help_message(),
+            _ => files.push(arg),
+        }
+    }
+    let Some(to) = to else {
+        return help_message();
+    };
+
+    if files.is_empty() {
+        let mut bytes = Vec::new();
+        if let Err(e) = std::io::stdin().read_to_end(&mut bytes) {
+            eprintln!("<stdin>: {}", e);
+            return ExitCode::FAILURE;
+        }
+        return match convert(bytes, from, to) {
+            Ok(text) => {
+                print!("{}", text);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("<stdin>: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let mut status = ExitCode::SUCCESS;
+    for file in &files {
+        let path = Path::new(file);
+        let from = from.or_else(|| {
+            path.extension()
+                .and_then(|ext| Format::from_name(ext.to_str()?))
+        });
+        let result = std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| convert(bytes, from, to));
+        let text = match result {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                status = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        match &out_dir {
+            Some(dir) => {
+                let name = path.with_extension(to.extension());
+                let output = Path::new(dir).join(name.file_name().unwrap_or_default());
+                if let Err(e) = std::fs::write(&output, text) {
+                    eprintln!("{}: {}", output.display(), e);
+                    status = ExitCode::FAILURE;
+                }
+            }
+            None => print!("{}", text),
+        }
+    }
+    status
+}
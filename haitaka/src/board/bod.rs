@@ -0,0 +1,384 @@
+//! Board diagrams (BOD)
+use core::fmt::Write;
+
+use crate::*;
+
+helpers::simple_error! {
+    /// An error while parsing a board diagram.
+    #[non_exhaustive]
+    pub enum BODParseError {
+        MissingBoard = "The text has no board diagram.",
+        InvalidBoard = "The board diagram is invalid.",
+        InvalidHands = "The hands are invalid.",
+        InvalidMoveNumber = "The move number is invalid."
+    }
+}
+
+// The piece characters of the diagram, in the order of `Piece::ALL`.
+pub(super) const PIECE_CHARS: [char; Piece::NUM] = [
+    '歩', '香', '桂', '銀', '角', '飛', '金', '玉', 'と', '杏', '圭', '全', '馬', '龍',
+];
+
+// Other characters that are accepted when reading.
+const PIECE_ALIASES: [(char, Piece); 2] = [('王', Piece::King), ('竜', Piece::PRook)];
+
+// The order in which the pieces in hand are written.
+pub(super) const HAND_ORDER: [Piece; Piece::HAND_NUM] = [
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Gold,
+    Piece::Silver,
+    Piece::Knight,
+    Piece::Lance,
+    Piece::Pawn,
+];
+
+pub(super) const NUMBERS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+const FILE_HEADER: &str = "  ９ ８ ７ ６ ５ ４ ３ ２ １";
+const BORDER: &str = "+---------------------------+";
+
+// What Tsume Shogi diagrams write in White's hand for all the pieces that are left.
+const REMAINING_PIECES: [&str; 2] = ["残り全部", "残り駒全部"];
+
+fn parse_piece(c: char) -> Option<Piece> {
+    match PIECE_CHARS.iter().position(|&p| p == c) {
+        Some(index) => Some(Piece::index(index)),
+        None => PIECE_ALIASES
+            .iter()
+            .find_map(|&(alias, piece)| (alias == c).then_some(piece)),
+    }
+}
+
+// Parse a count written in kanji, such as `四` or `十八`.
+fn parse_count(text: &str) -> Option<u8> {
+    let digit = |c: char| NUMBERS.iter().position(|&n| n == c).map(|n| n as u8 + 1);
+    let mut chars = text.chars();
+    let count = match (chars.next(), chars.next(), chars.next()) {
+        (None, _, _) => 1,
+        (Some('十'), None, _) => 10,
+        (Some('十'), Some(c), None) => 10 + digit(c)?,
+        (Some(c), None, _) => digit(c)?,
+        _ => return None,
+    };
+    Some(count)
+}
+
+fn write_count(text: &mut String, count: u8) {
+    if count >= 10 {
+        text.push('十');
+    }
+    if !count.is_multiple_of(10) && count != 1 {
+        text.push(NUMBERS[(count % 10 - 1) as usize]);
+    }
+}
+
+impl Board {
+    /// Parse a board diagram (BOD), as used in KIF files and on Japanese Shogi sites.
+    ///
+    /// A diagram has the hands of both sides (`後手の持駒：` and `先手の持駒：`, or
+    /// `上手` and `下手` in handicap games) and one line per rank, with a `v` in front
+    /// of White's pieces and `・` for empty squares. The side to move is Black, unless
+    /// there is a `後手番` (or `上手番`) line. A `手数＝` line gives the number of moves
+    /// played, so the move number is one more. All other lines, such as the file
+    /// numbers, borders and headers, are skipped.
+    ///
+    /// Diagrams without a Black King are Tsume Shogi problems (see [`BoardMode`]). Their
+    /// White hand may be written as `残り全部` (all remaining pieces). Apart from that,
+    /// the position is checked as by [`Board::from_sfen`].
+    ///
+    /// # Errors
+    /// Errors with [`BODParseError::MissingBoard`] if the text has no rank lines, and
+    /// with [`BODParseError::InvalidBoard`] if the ranks are malformed or the position
+    /// is invalid.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let bod = "\
+    /// 後手の持駒：なし
+    ///   ９ ８ ７ ６ ５ ４ ３ ２ １
+    /// +---------------------------+
+    /// |v香v桂v銀v金v玉v金v銀v桂v香|一
+    /// | ・v飛 ・ ・ ・ ・ ・ ・ ・|二
+    /// |v歩v歩v歩v歩v歩v歩 ・v歩v歩|三
+    /// | ・ ・ ・ ・ ・ ・v歩 ・ ・|四
+    /// | ・ ・ ・ ・ ・ ・ ・ ・ ・|五
+    /// | ・ ・ 歩 ・ ・ ・ ・ ・ ・|六
+    /// | 歩 歩 ・ 歩 歩 歩 歩 歩 歩|七
+    /// | ・ ・ ・ ・ ・ ・ ・ 飛 ・|八
+    /// | 香 桂 銀 金 玉 金 銀 桂 香|九
+    /// +---------------------------+
+    /// 先手の持駒：角二
+    /// 手数＝4  ▲２二角成  まで
+    /// ";
+    /// let board = Board::from_bod(bod).unwrap();
+    /// assert_eq!(
+    ///     board.to_string(),
+    ///     "lnsgkgsnl/1r7/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b 2B 5"
+    /// );
+    /// ```
+    pub fn from_bod(bod: &str) -> Result<Self, BODParseError> {
+        use BODParseError::*;
+
+        let mut builder = BoardBuilder::new();
+        let mut ranks = 0;
+        let mut remaining_pieces = false;
+        for line in bod.lines().map(str::trim) {
+            if let Some(row) = line.strip_prefix('|') {
+                let rank = Rank::try_index(ranks).ok_or(InvalidBoard)?;
+                let mut chars = row.chars();
+                for file in File::ALL.into_iter().rev() {
+                    let color = match chars.next() {
+                        Some('v') => Color::White,
+                        Some(' ') => Color::Black,
+                        _ => return Err(InvalidBoard),
+                    };
+                    let square = Square::new(file, rank);
+                    match chars.next() {
+                        Some('・') if color == Color::Black => {}
+                        Some(c) => {
+                            builder.put(color, parse_piece(c).ok_or(InvalidBoard)?, square);
+                        }
+                        None => return Err(InvalidBoard),
+                    }
+                }
+                if chars.next() != Some('|') {
+                    return Err(InvalidBoard);
+                }
+                ranks += 1;
+            } else if let Some((key, hand)) = line.split_once('：') {
+                let color = match key {
+                    "先手の持駒" | "下手の持駒" => Color::Black,
+                    "後手の持駒" | "上手の持駒" => Color::White,
+                    _ => continue,
+                };
+                let hand = hand.trim();
+                if color == Color::White && REMAINING_PIECES.contains(&hand) {
+                    remaining_pieces = true;
+                    continue;
+                }
+                if hand == "なし" {
+                    continue;
+                }
+                for token in hand.split_whitespace() {
+                    let mut chars = token.chars();
+                    let piece = chars.next().and_then(parse_piece);
+                    let count = parse_count(chars.as_str());
+                    match piece.zip(count) {
+                        Some((piece, count))
+                            if (piece as usize) < Piece::HAND_NUM
+                                && count <= Piece::MAX_HAND[piece as usize] =>
+                        {
+                            builder.set_hand(color, piece, count);
+                        }
+                        _ => return Err(InvalidHands),
+                    }
+                }
+            } else if let Some(moves) = line.strip_prefix("手数＝") {
+                let digits = moves
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(moves.len());
+                let moves: u16 = moves[..digits].parse().map_err(|_| InvalidMoveNumber)?;
+                builder.move_number(moves.checked_add(1).ok_or(InvalidMoveNumber)?);
+            } else if line == "後手番" || line == "上手番" {
+                builder.side_to_move(Color::White);
+            } else if line == "先手番" || line == "下手番" {
+                builder.side_to_move(Color::Black);
+            }
+        }
+        match ranks {
+            0 => return Err(MissingBoard),
+            9 => {}
+            _ => return Err(InvalidBoard),
+        }
+
+        let mut board = builder.build(Validation::None).unwrap();
+        if remaining_pieces {
+            board.piece_counts_make_valid();
+        }
+        let tsume = board.mode() == BoardMode::TsumeAttackerNoKing;
+        board
+            .validate_position(tsume)
+            .map_err(|error| match error {
+                PositionError::InvalidMoveNumber => InvalidMoveNumber,
+                _ => InvalidBoard,
+            })?;
+        Ok(board)
+    }
+
+    /// Write the position as a board diagram (BOD).
+    ///
+    /// The diagram is written as by Kifu for Windows, with the hands of White and Black
+    /// above and below the board. A `後手番` line is added if White is to move, and a
+    /// `手数＝` line with the number of moves played if the move number is not 1. The
+    /// result can be read back with [`Board::from_bod`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 w G2P 2".parse().unwrap();
+    /// assert_eq!(board.to_bod(), "\
+    /// 後手の持駒：なし
+    ///   ９ ８ ７ ６ ５ ４ ３ ２ １
+    /// +---------------------------+
+    /// | ・ ・ ・ ・v玉 ・ ・ ・ ・|一
+    /// | ・ ・ ・ ・ ・ ・ ・ ・ ・|二
+    /// | ・ ・ ・ ・ 歩 ・ ・ ・ ・|三
+    /// | ・ ・ ・ ・ ・ ・ ・ ・ ・|四
+    /// | ・ ・ ・ ・ ・ ・ ・ ・ ・|五
+    /// | ・ ・ ・ ・ ・ ・ ・ ・ ・|六
+    /// | ・ ・ ・ ・ ・ ・ ・ ・ ・|七
+    /// | ・ ・ ・ ・ ・ ・ ・ ・ ・|八
+    /// | ・ ・ ・ ・ 玉 ・ ・ ・ ・|九
+    /// +---------------------------+
+    /// 先手の持駒：金　歩二
+    /// 後手番
+    /// 手数＝1
+    /// ");
+    /// assert_eq!(Board::from_bod(&board.to_bod()).unwrap(), board);
+    /// ```
+    pub fn to_bod(&self) -> String {
+        let mut text = String::new();
+        self.write_bod_hand(&mut text, Color::White);
+        text.push_str(FILE_HEADER);
+        text.push('\n');
+        text.push_str(BORDER);
+        text.push('\n');
+        for rank in Rank::ALL {
+            text.push('|');
+            for file in File::ALL.into_iter().rev() {
+                let square = Square::new(file, rank);
+                match self.color_on(square).zip(self.piece_on(square)) {
+                    Some((color, piece)) => {
+                        text.push(if color == Color::White { 'v' } else { ' ' });
+                        text.push(PIECE_CHARS[piece as usize]);
+                    }
+                    None => text.push_str(" ・"),
+                }
+            }
+            text.push('|');
+            text.push(NUMBERS[rank as usize]);
+            text.push('\n');
+        }
+        text.push_str(BORDER);
+        text.push('\n');
+        self.write_bod_hand(&mut text, Color::Black);
+        if self.side_to_move() == Color::White {
+            text.push_str("後手番\n");
+        }
+        if self.move_number != 1 {
+            let _ = writeln!(text, "手数＝{}", self.move_number - 1);
+        }
+        text
+    }
+
+    fn write_bod_hand(&self, text: &mut String, color: Color) {
+        text.push_str(match color {
+            Color::White => "後手の持駒：",
+            Color::Black => "先手の持駒：",
+        });
+        self.write_kanji_hand(text, color);
+        text.push('\n');
+    }
+
+    // Write the hand of `color` as in a diagram, such as `飛　歩二`, or `なし`.
+    pub(super) fn write_kanji_hand(&self, text: &mut String, color: Color) {
+        let mut empty = true;
+        for piece in HAND_ORDER {
+            let count = self.num_in_hand(color, piece);
+            if count > 0 {
+                if !empty {
+                    text.push('　');
+                }
+                text.push(PIECE_CHARS[piece as usize]);
+                write_count(text, count);
+                empty = false;
+            }
+        }
+        if empty {
+            text.push_str("なし");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bod_round_trip() {
+        for sfen in include_str!("test_data/valid.sfens").lines() {
+            let board = Board::from_sfen(sfen).unwrap();
+            assert_eq!(Board::from_bod(&board.to_bod()).unwrap(), board, "{sfen}");
+        }
+    }
+
+    #[test]
+    fn counts() {
+        for count in 1..=18 {
+            let mut text = String::new();
+            write_count(&mut text, count);
+            assert_eq!(parse_count(&text), Some(count), "{text}");
+        }
+        assert_eq!(parse_count("十九十"), None);
+        assert_eq!(parse_count("x"), None);
+    }
+
+    #[test]
+    fn tsume_diagrams() {
+        let bod = "\
+後手の持駒：残り全部
+  ９ ８ ７ ６ ５ ４ ３ ２ １
++---------------------------+
+| ・ ・ ・ ・ ・ ・ ・v桂v香|一
+| ・ ・ ・ ・ ・ ・v金 ・v王|二
+| ・ ・ ・ ・ ・ ・v歩v歩 ・|三
+| ・ ・ ・ ・ ・ ・ ・ 竜 ・|四
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|五
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|六
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|七
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|八
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|九
++---------------------------+
+先手の持駒：金
+";
+        let board = Board::from_bod(bod).unwrap();
+        let expected = Board::tsume("7nl/6g1k/6pp1/7+R1/9/9/9/9/9 b G 1").unwrap();
+        assert_eq!(board, expected);
+        assert_eq!(board.mode(), BoardMode::TsumeAttackerNoKing);
+    }
+
+    #[test]
+    fn errors() {
+        let bod = Board::startpos().to_bod();
+        assert!(matches!(
+            Board::from_bod("先手の持駒：なし\n"),
+            Err(BODParseError::MissingBoard)
+        ));
+        for (from, to) in [
+            ("|v香", "|v香v香"),
+            ("|v香", "| 香"),
+            ("v玉", "v金"),
+            (" ・|四", "|四"),
+            ("|一\n", "|一\n|一\n"),
+        ] {
+            let bod = bod.replacen(from, to, 1);
+            assert!(
+                matches!(Board::from_bod(&bod), Err(BODParseError::InvalidBoard)),
+                "{bod}"
+            );
+        }
+        for hand in ["歩十九", "玉", "金五", "金　x"] {
+            let bod = bod.replacen("先手の持駒：なし", &format!("先手の持駒：{hand}"), 1);
+            assert!(
+                matches!(Board::from_bod(&bod), Err(BODParseError::InvalidHands)),
+                "{bod}"
+            );
+        }
+        let bod = bod.replacen("+\n", "+\n手数＝x\n", 1);
+        assert!(matches!(
+            Board::from_bod(&bod),
+            Err(BODParseError::InvalidMoveNumber)
+        ));
+    }
+}
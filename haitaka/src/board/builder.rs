@@ -0,0 +1,234 @@
+//! Setting up positions piece by piece
+use crate::*;
+
+/// How much [`BoardBuilder::build`] checks the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Validation {
+    /// All checks of [`Board::from_sfen_validated`], including nifu.
+    #[default]
+    Strict,
+    /// The checks of [`Board::from_sfen`], which allow nifu.
+    Lenient,
+    /// No checks. Only the checkers and pins are computed. Move generation and other
+    /// methods may give wrong results or panic on invalid positions (for instance,
+    /// if the side to move has no King, or more than one).
+    None,
+}
+
+/// A builder to set up a position piece by piece, for instance in a board editor.
+///
+/// The builder starts from an empty board (or any position, with
+/// [`BoardBuilder::from_board`]). Pieces can be put on and removed from the board in
+/// any order, since the position is only checked by [`BoardBuilder::build`].
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let mut builder = BoardBuilder::new();
+/// builder
+///     .put(Color::White, Piece::King, Square::A5)
+///     .put(Color::Black, Piece::King, Square::I5)
+///     .put(Color::Black, Piece::Gold, Square::C5)
+///     .set_hand(Color::Black, Piece::Gold, 1);
+/// let board = builder.build(Validation::Strict).unwrap();
+/// assert_eq!(board.to_string(), "4k4/9/4G4/9/9/9/9/9/4K4 b G 1");
+/// assert_eq!(board.mate_in_one(), Some("G*5b".parse().unwrap()));
+///
+/// // Without the Black King, the position is only valid for Tsume Shogi
+/// builder.remove(Square::I5);
+/// assert!(builder.build(Validation::Lenient).is_err());
+/// assert!(builder.build(Validation::None).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardBuilder {
+    board: Board,
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoardBuilder {
+    /// Start with an empty board, with no pieces in hand, Black to move and move
+    /// number 1.
+    pub fn new() -> Self {
+        Self {
+            board: Board {
+                move_number: 1,
+                ..Board::default()
+            },
+        }
+    }
+
+    /// Start with the position of `board`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut builder = BoardBuilder::from_board(&Board::startpos());
+    /// builder.remove(Square::H8).remove(Square::H2);
+    /// assert_eq!(
+    ///     builder.build(Validation::Strict).unwrap().to_string(),
+    ///     "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/9/LNSGKGSNL b - 1"
+    /// );
+    /// ```
+    pub fn from_board(board: &Board) -> Self {
+        Self {
+            board: board.clone(),
+        }
+    }
+
+    /// Get the piece on `square`, with its color.
+    pub fn piece_on(&self, square: Square) -> Option<(Color, Piece)> {
+        self.board.color_on(square).zip(self.board.piece_on(square))
+    }
+
+    /// Put a piece on `square`, replacing the piece that is there.
+    pub fn put(&mut self, color: Color, piece: Piece, square: Square) -> &mut Self {
+        self.remove(square);
+        self.board.inner.set_square(piece, color, square);
+        self
+    }
+
+    /// Remove the piece on `square`, if any.
+    pub fn remove(&mut self, square: Square) -> &mut Self {
+        if let Some((color, piece)) = self.piece_on(square) {
+            self.board.inner.clear_square(piece, color, square);
+        }
+        self
+    }
+
+    /// Set the number of `piece`s in the hand of `color`.
+    ///
+    /// # Panics
+    /// This panics if `piece` can't be in hand.
+    pub fn set_hand(&mut self, color: Color, piece: Piece, count: u8) -> &mut Self {
+        assert!(
+            (piece as usize) < Piece::HAND_NUM,
+            "{piece:?} can't be in hand"
+        );
+        self.board.inner.unchecked_set_hand(color, piece, count);
+        self
+    }
+
+    /// Set the side to move.
+    pub fn side_to_move(&mut self, color: Color) -> &mut Self {
+        if color != self.board.side_to_move() {
+            self.board.inner.toggle_side_to_move();
+        }
+        self
+    }
+
+    /// Set the move number.
+    pub fn move_number(&mut self, move_number: u16) -> &mut Self {
+        self.board.move_number = move_number;
+        self
+    }
+
+    /// Finish the position, with the given validation.
+    ///
+    /// This computes the checkers and pins of the side to move, and then checks the
+    /// position.
+    ///
+    /// # Errors
+    /// Errors with the first [`PositionError`] found. Nifu is only checked with
+    /// [`Validation::Strict`], and nothing is checked with [`Validation::None`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut builder = BoardBuilder::from_board(&Board::startpos());
+    /// // A second Black Pawn on file 7 (and one White Pawn less, to keep 18 Pawns)
+    /// builder.put(Color::Black, Piece::Pawn, Square::E7).remove(Square::C3);
+    /// assert_eq!(
+    ///     builder.build(Validation::Strict),
+    ///     Err(PositionError::DoublePawn { color: Color::Black, file: File::Seven })
+    /// );
+    /// assert!(builder.build(Validation::Lenient).is_ok());
+    /// ```
+    pub fn build(&self, validation: Validation) -> Result<Board, PositionError> {
+        let mut board = self.board.clone();
+        for color in Color::ALL {
+            let mut pawnless = BitBoard::FULL;
+            for square in board.colored_pieces(color, Piece::Pawn) {
+                pawnless &= !square.file().bitboard();
+            }
+            board.pawnless_files[color as usize] = pawnless;
+        }
+
+        if validation == Validation::None {
+            let (checkers, pinned) = board.calculate_checkers_and_pins(board.side_to_move());
+            board.checkers = checkers;
+            board.pinned = pinned;
+            return Ok(board);
+        }
+
+        board.validate_position(false)?;
+        let (checkers, pinned) = board.calculate_checkers_and_pins(board.side_to_move());
+        board.checkers = checkers;
+        board.pinned = pinned;
+        if validation == Validation::Strict {
+            board.validate_pawn_files()?;
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_same_boards_as_from_sfen() {
+        for sfen in include_str!("test_data/valid.sfens").lines() {
+            let expected = Board::from_sfen(sfen).unwrap();
+            let mut builder = BoardBuilder::new();
+            for (color, piece, square) in expected.iter_pieces() {
+                builder.put(color, piece, square);
+            }
+            for color in Color::ALL {
+                for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+                    builder.set_hand(color, piece, expected.num_in_hand(color, piece));
+                }
+            }
+            builder
+                .side_to_move(expected.side_to_move())
+                .move_number(expected.move_number());
+            for validation in [Validation::Lenient, Validation::None] {
+                assert_eq!(builder.build(validation).unwrap(), expected, "{sfen}");
+            }
+            assert_eq!(
+                builder.build(Validation::Strict).ok(),
+                Board::from_sfen_validated(sfen).ok()
+            );
+        }
+    }
+
+    #[test]
+    fn replaces_pieces() {
+        let mut builder = BoardBuilder::from_board(&Board::startpos());
+        builder
+            .put(Color::White, Piece::Pawn, Square::G7)
+            .remove(Square::C7)
+            .put(Color::Black, Piece::Pawn, Square::F7)
+            .move_number(0);
+        assert_eq!(
+            builder.piece_on(Square::G7),
+            Some((Color::White, Piece::Pawn))
+        );
+        assert_eq!(
+            builder.build(Validation::Lenient),
+            Err(PositionError::InvalidMoveNumber)
+        );
+        let board = builder.move_number(1).build(Validation::Lenient).unwrap();
+        assert_eq!(
+            board.to_string(),
+            "lnsgkgsnl/1r5b1/pp1pppppp/9/9/2P6/PPpPPPPPP/1B5R1/LNSGKGSNL b - 1"
+        );
+        assert_eq!(board.pawnless_files(Color::Black), BitBoard::EMPTY);
+        assert_eq!(board.pawnless_files(Color::White), BitBoard::EMPTY);
+    }
+}
@@ -0,0 +1,151 @@
+//! Camp, promotion zone and entering King queries
+use crate::*;
+
+impl Board {
+    /// Get the pieces of `color` in the camp of `camp_of`.
+    ///
+    /// The camp of a player consists of the three ranks closest to that player.
+    /// The camp of one player is the promotion zone of the other player, so
+    /// `board.pieces_in_camp(color, !color)` are the pieces of `color` that
+    /// have entered the promotion zone.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert_eq!(board.pieces_in_camp(Color::Black, Color::Black), board.colors(Color::Black));
+    /// assert!(board.pieces_in_camp(Color::Black, Color::White).is_empty());
+    ///
+    /// let board: Board = "lnsgkgsnl/1r5b1/ppppppPpp/9/9/9/PPPPPP1PP/1B5R1/LNSGKGSNL b P 1"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(board.pieces_in_camp(Color::Black, Color::White), Square::C3.bitboard());
+    /// ```
+    pub fn pieces_in_camp(&self, color: Color, camp_of: Color) -> BitBoard {
+        self.colors(color) & prom_zone(!camp_of)
+    }
+
+    /// Has the King of `color` entered the opponent's camp (nyuugyoku, 入玉)?
+    ///
+    /// Returns false if `color` has no King on the board.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert!(!Board::startpos().king_entered(Color::Black));
+    ///
+    /// let board: Board = "2K6/9/9/9/4k4/9/9/9/9 b - 1".parse().unwrap();
+    /// assert!(board.king_entered(Color::Black));
+    /// assert!(!board.king_entered(Color::White));
+    /// ```
+    pub fn king_entered(&self, color: Color) -> bool {
+        !(self.colored_pieces(color, Piece::King) & prom_zone(color)).is_empty()
+    }
+
+    /// Have both Kings entered the opponent's camp (ai-nyuugyoku, 相入玉)?
+    ///
+    /// In such positions neither side can realistically be mated, so the game is
+    /// usually decided by the impasse (jishogi) rules.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert!(!Board::startpos().double_entering());
+    ///
+    /// let board: Board = "2K6/9/9/9/4k4/9/9/9/9 b - 1".parse().unwrap();
+    /// assert!(!board.double_entering());
+    ///
+    /// let board: Board = "2K6/9/9/9/9/9/9/6k2/9 b - 1".parse().unwrap();
+    /// assert!(board.double_entering());
+    /// ```
+    pub fn double_entering(&self) -> bool {
+        self.king_entered(Color::Black) && self.king_entered(Color::White)
+    }
+
+    /// Get the pieces of `color` that could promote on their next move.
+    ///
+    /// These are the unpromoted Pawns, Lances, Knights, Silvers, Bishops and Rooks of
+    /// `color` that can move into the promotion zone, or that are already in the
+    /// promotion zone and can move at all. This looks at attacks only, so it ignores pins
+    /// and checks. It may be called for either side, regardless of the side to move.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert!(board.promotable_next_move(Color::Black).is_empty());
+    ///
+    /// // After 7g7f 3c3d, the Black Bishop can promote on 2b
+    /// let board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(board.promotable_next_move(Color::Black), Square::H8.bitboard());
+    /// assert_eq!(board.promotable_next_move(Color::White), Square::B2.bitboard());
+    /// ```
+    pub fn promotable_next_move(&self, color: Color) -> BitBoard {
+        let zone = prom_zone(color);
+        let occ = self.occupied();
+        let targets = !self.colors(color);
+        let mut promotable = BitBoard::EMPTY;
+
+        for piece in [
+            Piece::Pawn,
+            Piece::Lance,
+            Piece::Knight,
+            Piece::Silver,
+            Piece::Bishop,
+            Piece::Rook,
+        ] {
+            for square in self.colored_pieces(color, piece) {
+                let moves = match piece {
+                    Piece::Pawn => pawn_attacks(color, square),
+                    Piece::Lance => get_lance_moves(color, square, occ),
+                    Piece::Knight => knight_attacks(color, square),
+                    Piece::Silver => silver_attacks(color, square),
+                    Piece::Bishop => lazy_bishop_moves(color, square, occ),
+                    _ => lazy_rook_moves(color, square, occ),
+                } & targets;
+                if !(moves & zone).is_empty() || (zone.has(square) && !moves.is_empty()) {
+                    promotable |= square.bitboard();
+                }
+            }
+        }
+        promotable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn promotable_next_move_matches_move_generation() {
+        for sfen in [
+            "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3",
+            "ln1gk1snl/1r5b1/p1ppppgpp/1s4p2/1p7/P1P3R2/1P1PPPP1P/1BG3S2/LNS1KG1NL b P 1",
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+        ] {
+            let board: Board = sfen.parse().unwrap();
+            let mut expected = BitBoard::EMPTY;
+            board.generate_board_moves(|moves| {
+                for mv in moves {
+                    if mv.is_promotion() {
+                        expected |= mv.from().unwrap().bitboard();
+                    }
+                }
+                false
+            });
+            assert!(
+                board.checkers().is_empty() && board.pinned().is_empty(),
+                "{}",
+                sfen
+            );
+            assert_eq!(
+                board.promotable_next_move(board.side_to_move()),
+                expected,
+                "{}",
+                sfen
+            );
+        }
+    }
+}
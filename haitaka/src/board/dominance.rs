@@ -0,0 +1,232 @@
+//! Hand dominance helpers for solver caches
+use super::zobrist::hand_key;
+use crate::*;
+
+// The pieces that can be held in hand.
+const HAND_PIECES: [Piece; 7] = [
+    Piece::Pawn,
+    Piece::Lance,
+    Piece::Knight,
+    Piece::Silver,
+    Piece::Gold,
+    Piece::Bishop,
+    Piece::Rook,
+];
+
+/// An iterator over the hashes of positions that dominate a given position.
+///
+/// Created by [`Board::dominating_variants`].
+#[derive(Debug, Clone)]
+pub struct DominatingVariants {
+    color: Color,
+    hash: u64,
+    ours: [u8; HAND_PIECES.len()],
+    theirs: [u8; HAND_PIECES.len()],
+    extra: [u8; HAND_PIECES.len()],
+    done: bool,
+}
+
+impl DominatingVariants {
+    /// The maximum number of pieces moved from the opponent's hand into ours.
+    ///
+    /// Solvers store the positions they actually visited, and those rarely differ
+    /// from the probed position by more than a piece or two in hand. Without a cap,
+    /// a Tsume Shogi defender holding all remaining pieces would give rise to
+    /// tens of thousands of variants.
+    pub const MAX_EXTRA: u8 = 2;
+
+    fn new(board: &Board) -> Self {
+        let color = board.side_to_move();
+        Self {
+            color,
+            hash: board.hash(),
+            ours: HAND_PIECES.map(|piece| board.num_in_hand(color, piece)),
+            theirs: HAND_PIECES.map(|piece| board.num_in_hand(!color, piece)),
+            extra: [0; HAND_PIECES.len()],
+            done: false,
+        }
+    }
+
+    // Step to the next combination of extra pieces, in mixed radix order.
+    //
+    // Lower digits are zero whenever a digit is incremented, so if the total
+    // exceeds the cap, so do all further values of this digit.
+    fn advance(&mut self) -> bool {
+        let mut total: u8 = self.extra.iter().sum();
+        for i in 0..self.extra.len() {
+            if self.extra[i] < self.theirs[i] && total < Self::MAX_EXTRA {
+                self.extra[i] += 1;
+                return true;
+            }
+            total -= self.extra[i];
+            self.extra[i] = 0;
+        }
+        false
+    }
+}
+
+impl Iterator for DominatingVariants {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || !self.advance() {
+            self.done = true;
+            return None;
+        }
+
+        let mut hash = self.hash;
+        for (i, &piece) in HAND_PIECES.iter().enumerate() {
+            let extra = self.extra[i];
+            if extra > 0 {
+                let (ours, theirs) = (self.ours[i], self.theirs[i]);
+                hash ^= hand_key(self.color, piece, ours)
+                    ^ hand_key(self.color, piece, ours + extra)
+                    ^ hand_key(!self.color, piece, theirs)
+                    ^ hand_key(!self.color, piece, theirs - extra);
+            }
+        }
+        Some(hash)
+    }
+}
+
+impl Board {
+    /// Iterate over the hashes of positions that dominate this position.
+    ///
+    /// A dominating position has the same pieces on the board and the same side to move,
+    /// but the side to move has more pieces in hand (see [`Board::dominates`]).
+    /// The variants are formed by moving up to [`DominatingVariants::MAX_EXTRA`] pieces
+    /// from the opponent's hand into the hand of the side to move, so that the total
+    /// material stays the same. The position itself is not included.
+    ///
+    /// This is meant for probing solver caches (such as df-pn transposition tables)
+    /// that are keyed by [`Board::hash`]: if a dominating position is known not to have
+    /// a forced mate for the side to move, then neither does this position.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b G2s 1".parse().unwrap();
+    /// let variants: Vec<u64> = board.dominating_variants().collect();
+    /// // One or two extra Silvers
+    /// assert_eq!(variants.len(), 2);
+    ///
+    /// let dominating: Board = "4k4/9/9/9/9/9/9/9/4K4 b GSs 1".parse().unwrap();
+    /// assert_eq!(dominating.dominates(&board), Dominance::Dominates);
+    /// assert!(variants.contains(&dominating.hash()));
+    /// ```
+    pub fn dominating_variants(&self) -> DominatingVariants {
+        DominatingVariants::new(self)
+    }
+
+    /// Get the part of [`Board::hash`] that comes from the pieces in hand.
+    ///
+    /// The hash is the XOR of a key for the pieces on the board and the side to move,
+    /// and this key for the hands. So `board.hash() ^ board.hands_key()` is the same for
+    /// all positions that differ only in the pieces in hand. Solvers can use it to
+    /// index positions by the board alone, and then compare the hands for dominance.
+    ///
+    /// The key is 0 if both hands are empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let a: Board = "4k4/9/9/9/9/9/9/9/4K4 b G2s 1".parse().unwrap();
+    /// let b: Board = "4k4/9/9/9/9/9/9/9/4K4 b GSs 1".parse().unwrap();
+    /// assert_ne!(a.hands_key(), b.hands_key());
+    /// assert_eq!(a.hash() ^ a.hands_key(), b.hash() ^ b.hands_key());
+    ///
+    /// assert_eq!(Board::startpos().hands_key(), 0);
+    /// ```
+    pub fn hands_key(&self) -> u64 {
+        let mut key = 0;
+        for color in Color::ALL {
+            for piece in HAND_PIECES {
+                let count = self.num_in_hand(color, piece);
+                if count > 0 {
+                    key ^= hand_key(color, piece, 0) ^ hand_key(color, piece, count);
+                }
+            }
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dominates_compares_both_hands() {
+        // Only Black's hands differ, so White's hands alone can't tell them apart
+        let sfen = "4k4/9/9/9/9/9/9/9/4K4";
+        let more: Board = format!("{sfen} b Gs 1").parse().unwrap();
+        let less: Board = format!("{sfen} b s 1").parse().unwrap();
+        assert_eq!(more.dominates(&less), Dominance::Dominates);
+        assert_eq!(less.dominates(&more), Dominance::DominatedBy);
+        assert_eq!(more.dominates(&more), Dominance::Equal);
+
+        // and the other way around, with White to move
+        let more: Board = format!("{sfen} w Sg 1").parse().unwrap();
+        let less: Board = format!("{sfen} w S 1").parse().unwrap();
+        assert_eq!(more.dominates(&less), Dominance::Dominates);
+        assert_eq!(less.dominates(&more), Dominance::DominatedBy);
+    }
+
+    #[test]
+    fn dominating_variants_match_boards() {
+        let board = Board::tsume("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1").unwrap();
+        let color = board.side_to_move();
+
+        // build the variants explicitly and compare the hashes
+        let mut expected = Vec::new();
+        for (i, &a) in HAND_PIECES.iter().enumerate() {
+            for &b in &HAND_PIECES[i..] {
+                for pieces in [&[a][..], &[a, b][..]] {
+                    let mut variant = board.clone();
+                    let mut ok = true;
+                    for &piece in pieces {
+                        let theirs = variant.num_in_hand(!color, piece);
+                        let ours = variant.num_in_hand(color, piece);
+                        ok &= theirs > 0;
+                        if ok {
+                            variant.unchecked_set_hand(!color, piece, theirs - 1);
+                            variant.unchecked_set_hand(color, piece, ours + 1);
+                        }
+                    }
+                    if ok && !expected.contains(&variant.hash()) {
+                        assert_eq!(variant.dominates(&board), Dominance::Dominates);
+                        expected.push(variant.hash());
+                    }
+                }
+            }
+        }
+
+        let mut actual: Vec<u64> = board.dominating_variants().collect();
+        assert!(!actual.contains(&board.hash()));
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+
+        // nothing dominates a position where the opponent has no pieces in hand
+        assert_eq!(Board::startpos().dominating_variants().count(), 0);
+    }
+
+    #[test]
+    fn hands_key_splits_the_hash() {
+        let sfen = "4k4/9/9/9/9/9/9/9/4K4 b";
+        let board: Board = format!("{sfen} - 1").parse().unwrap();
+        let board_key = board.hash() ^ board.hands_key();
+        for hand in [
+            "P",
+            "2P",
+            "p",
+            "Pp",
+            "BGN2r",
+            "RB2G4S4N4L18P",
+            "rb2g4s4n4l18p",
+        ] {
+            let other: Board = format!("{sfen} {hand} 1").parse().unwrap();
+            assert_eq!(other.hash() ^ other.hands_key(), board_key, "{hand}");
+        }
+    }
+}
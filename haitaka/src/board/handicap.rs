@@ -0,0 +1,164 @@
+//! Handicap (駒落ち) start positions
+use crate::*;
+
+/// A handicap (駒落ち): the stronger player (上手) plays White without some of their
+/// pieces, and makes the first move.
+///
+/// The handicaps are named by the pieces that White removes. "Left" and "right" are seen
+/// from White, so the Lance of [`Handicap::Lance`] is the one on 1a.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Handicap {
+    /// Lance handicap (香落ち): without the left Lance.
+    Lance,
+    /// Right Lance handicap (右香落ち): without the right Lance.
+    RightLance,
+    /// Bishop handicap (角落ち).
+    Bishop,
+    /// Rook handicap (飛車落ち).
+    Rook,
+    /// Rook and Lance handicap (飛香落ち): without the Rook and the left Lance.
+    RookLance,
+    /// Two-piece handicap (二枚落ち): without the Rook and the Bishop.
+    TwoPiece,
+    /// Four-piece handicap (四枚落ち): also without both Lances.
+    FourPiece,
+    /// Six-piece handicap (六枚落ち): also without both Knights.
+    SixPiece,
+    /// Eight-piece handicap (八枚落ち): also without both Silvers.
+    EightPiece,
+    /// Ten-piece handicap (十枚落ち): also without both Golds.
+    TenPiece,
+}
+
+// The pieces that White removes for the largest handicap, in the order of removal.
+const TEN_PIECES: [(Piece, Square); 10] = [
+    (Piece::Rook, Square::B8),
+    (Piece::Bishop, Square::B2),
+    (Piece::Lance, Square::A9),
+    (Piece::Lance, Square::A1),
+    (Piece::Knight, Square::A8),
+    (Piece::Knight, Square::A2),
+    (Piece::Silver, Square::A7),
+    (Piece::Silver, Square::A3),
+    (Piece::Gold, Square::A6),
+    (Piece::Gold, Square::A4),
+];
+
+impl Handicap {
+    /// The number of handicaps.
+    pub const NUM: usize = Self::ALL.len();
+
+    /// All handicaps, from the smallest to the largest.
+    pub const ALL: [Self; 10] = [
+        Self::Lance,
+        Self::RightLance,
+        Self::Bishop,
+        Self::Rook,
+        Self::RookLance,
+        Self::TwoPiece,
+        Self::FourPiece,
+        Self::SixPiece,
+        Self::EightPiece,
+        Self::TenPiece,
+    ];
+
+    /// Get the SFEN string of the start position.
+    ///
+    /// White is to move, and the move number is 1.
+    pub const fn sfen(self) -> &'static str {
+        match self {
+            Self::Lance => "lnsgkgsn1/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::RightLance => "1nsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::Bishop => "lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::Rook => "lnsgkgsnl/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::RookLance => "lnsgkgsn1/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::TwoPiece => "lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::FourPiece => "1nsgkgsn1/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::SixPiece => "2sgkgs2/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::EightPiece => "3gkg3/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::TenPiece => "4k4/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+        }
+    }
+
+    /// Get the Japanese name, as in the `手合割` header of KIF records.
+    pub const fn japanese_name(self) -> &'static str {
+        match self {
+            Self::Lance => "香落ち",
+            Self::RightLance => "右香落ち",
+            Self::Bishop => "角落ち",
+            Self::Rook => "飛車落ち",
+            Self::RookLance => "飛香落ち",
+            Self::TwoPiece => "二枚落ち",
+            Self::FourPiece => "四枚落ち",
+            Self::SixPiece => "六枚落ち",
+            Self::EightPiece => "八枚落ち",
+            Self::TenPiece => "十枚落ち",
+        }
+    }
+
+    /// Get the White pieces that are removed from the standard start position, with
+    /// their squares.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(
+    ///     Handicap::RookLance.removed_pieces(),
+    ///     [(Piece::Rook, Square::B8), (Piece::Lance, Square::A1)]
+    /// );
+    /// assert_eq!(Handicap::SixPiece.removed_pieces().len(), 6);
+    /// ```
+    pub fn removed_pieces(self) -> &'static [(Piece, Square)] {
+        match self {
+            Self::Lance => &TEN_PIECES[3..4],
+            Self::RightLance => &TEN_PIECES[2..3],
+            Self::Bishop => &TEN_PIECES[1..2],
+            Self::Rook => &TEN_PIECES[0..1],
+            Self::RookLance => &[(Piece::Rook, Square::B8), (Piece::Lance, Square::A1)],
+            Self::TwoPiece => &TEN_PIECES[..2],
+            Self::FourPiece => &TEN_PIECES[..4],
+            Self::SixPiece => &TEN_PIECES[..6],
+            Self::EightPiece => &TEN_PIECES[..8],
+            Self::TenPiece => &TEN_PIECES,
+        }
+    }
+}
+
+impl Board {
+    /// Get a board with the start position of a handicap game.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::handicap(Handicap::TwoPiece);
+    /// assert_eq!(board.side_to_move(), Color::White);
+    /// assert_eq!(board.move_number(), 1);
+    /// assert_eq!(board.to_string(), Handicap::TwoPiece.sfen());
+    /// assert!(board.colored_pieces(Color::White, Piece::Rook).is_empty());
+    /// ```
+    pub fn handicap(handicap: Handicap) -> Self {
+        Self::from_sfen(handicap.sfen()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_pieces_match_the_positions() {
+        for handicap in Handicap::ALL {
+            let mut builder = BoardBuilder::from_board(&Board::startpos());
+            for &(piece, square) in handicap.removed_pieces() {
+                assert_eq!(builder.piece_on(square), Some((Color::White, piece)));
+                builder.remove(square);
+            }
+            let expected = builder
+                .side_to_move(Color::White)
+                .build(Validation::Strict)
+                .unwrap();
+            assert_eq!(Board::handicap(handicap), expected, "{handicap:?}");
+        }
+    }
+}
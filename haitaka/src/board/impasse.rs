@@ -0,0 +1,274 @@
+//! Impasse (jishogi) declarations
+use crate::*;
+
+/// The rule used to decide a declaration of an entering King win (nyuugyoku sengen).
+///
+/// These are the rules supported by the `EnteringKingRule` option of USI engines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ImpasseRule {
+    /// The CSA 27-point rule: the declaring side wins with at least 28 points for Black
+    /// or 27 points for White. See [`Board::try_declare_win`] for all conditions.
+    Points27,
+    /// The 24-point rule: the declaring side wins with at least 31 points and draws
+    /// with 24 to 30 points. The other conditions are the same as for
+    /// [`ImpasseRule::Points27`].
+    Points24,
+    /// The Try rule: the declaring side wins if its King can legally move to the start
+    /// square of the opponent's King (5a for Black, 5i for White).
+    Try,
+}
+
+/// The result of a declaration of an entering King win.
+///
+/// A declaration ends the game, so it can only be won, drawn or lost: this enum is not
+/// `#[non_exhaustive]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Declaration {
+    /// The declaring side wins.
+    Win,
+    /// The game is a draw (only with [`ImpasseRule::Points24`]).
+    Draw,
+    /// The declaration was not valid: the declaring side loses.
+    Loss,
+}
+
+impl Board {
+    /// Count the impasse points of `color`.
+    ///
+    /// These are the points used to decide a declaration: Rooks and Bishops (also when
+    /// promoted) count 5 points, all other pieces except the King 1 point. Only the
+    /// pieces in hand and the pieces in the opponent's camp are counted.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().impasse_points(Color::Black), 0);
+    ///
+    /// let board: Board = "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b RB 1".parse().unwrap();
+    /// assert_eq!(board.impasse_points(Color::Black), 28);
+    /// assert_eq!(board.impasse_points(Color::White), 0);
+    /// ```
+    pub fn impasse_points(&self, color: Color) -> u32 {
+        let camp = self.pieces_in_camp(color, !color);
+        let hand = self.hand(color);
+        let mut points = 0;
+        for piece in Piece::ALL {
+            let value = match piece.unpromote() {
+                Piece::King => 0,
+                Piece::Rook | Piece::Bishop => 5,
+                _ => 1,
+            };
+            let count = (self.pieces(piece) & camp).len() + hand[piece as usize] as u32;
+            points += value * count;
+        }
+        points
+    }
+
+    /// Decide a declaration of an entering King win by `color`, under the given rule.
+    ///
+    /// For the point rules, the declaration is valid if all of the following hold:
+    /// - `color` is the side to move;
+    /// - the King of `color` has entered the opponent's camp;
+    /// - `color` has at least 10 pieces other than the King in the opponent's camp;
+    /// - the King of `color` is not in check;
+    /// - `color` has enough [impasse points](Board::impasse_points).
+    ///
+    /// For the Try rule, `color` has to be the side to move, and it must have a legal
+    /// King move to the start square of the opponent's King.
+    ///
+    /// An invalid declaration loses the game. The conditions on time that some
+    /// tournaments add are left to the caller.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b RB 1".parse().unwrap();
+    /// assert_eq!(board.try_declare_win(Color::Black, ImpasseRule::Points27), Declaration::Win);
+    /// assert_eq!(board.try_declare_win(Color::Black, ImpasseRule::Points24), Declaration::Draw);
+    /// assert_eq!(board.try_declare_win(Color::White, ImpasseRule::Points27), Declaration::Loss);
+    ///
+    /// // Black needs 28 points, White only 27
+    /// let board: Board = "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b R4P 1".parse().unwrap();
+    /// assert_eq!(board.try_declare_win(Color::Black, ImpasseRule::Points27), Declaration::Loss);
+    ///
+    /// let board: Board = "9/4K4/9/9/9/9/9/9/4k4 b - 1".parse().unwrap();
+    /// assert_eq!(board.try_declare_win(Color::Black, ImpasseRule::Try), Declaration::Win);
+    /// ```
+    pub fn try_declare_win(&self, color: Color, rule: ImpasseRule) -> Declaration {
+        if color != self.side_to_move() {
+            return Declaration::Loss;
+        }
+
+        if rule == ImpasseRule::Try {
+            let Some(from) = self.colored_pieces(color, Piece::King).next_square() else {
+                return Declaration::Loss;
+            };
+            let to = match color {
+                Color::Black => Square::A5,
+                Color::White => Square::I5,
+            };
+            let mv = Move::BoardMove {
+                from,
+                to,
+                promotion: false,
+            };
+            return if self.is_legal(mv) {
+                Declaration::Win
+            } else {
+                Declaration::Loss
+            };
+        }
+
+        let camp = self.pieces_in_camp(color, !color) & !self.pieces(Piece::King);
+        if !self.king_entered(color) || camp.len() < 10 || !self.checkers().is_empty() {
+            return Declaration::Loss;
+        }
+
+        let points = self.impasse_points(color);
+        match rule {
+            ImpasseRule::Points27 => {
+                let needed = match color {
+                    Color::Black => 28,
+                    Color::White => 27,
+                };
+                if points >= needed {
+                    Declaration::Win
+                } else {
+                    Declaration::Loss
+                }
+            }
+            _ => match points {
+                31.. => Declaration::Win,
+                24..=30 => Declaration::Draw,
+                _ => Declaration::Loss,
+            },
+        }
+    }
+
+    /// Get the status of the game, including entering King declarations under `rule`.
+    ///
+    /// This is [`Board::status`], except that an ongoing game in which the side to move
+    /// can [declare](Board::try_declare_win) a win is [`GameStatus::DeclarableWin`], and
+    /// one in which it can declare a draw is [`GameStatus::DeclarableDraw`]. The game
+    /// only ends if the player actually declares.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b RB 1".parse().unwrap();
+    /// assert_eq!(board.status(), GameStatus::Ongoing);
+    /// assert_eq!(board.status_with_impasse(ImpasseRule::Points27), GameStatus::DeclarableWin);
+    /// assert_eq!(board.status_with_impasse(ImpasseRule::Points24), GameStatus::DeclarableDraw);
+    ///
+    /// assert_eq!(Board::startpos().status_with_impasse(ImpasseRule::Try), GameStatus::Ongoing);
+    /// ```
+    pub fn status_with_impasse(&self, rule: ImpasseRule) -> GameStatus {
+        match self.status() {
+            GameStatus::Ongoing => match self.try_declare_win(self.side_to_move(), rule) {
+                Declaration::Win => GameStatus::DeclarableWin,
+                Declaration::Draw => GameStatus::DeclarableDraw,
+                Declaration::Loss => GameStatus::Ongoing,
+            },
+            status => status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declarations() {
+        use Declaration::*;
+        use ImpasseRule::*;
+
+        for (sfen, points, p27, p24) in [
+            // White needs only 27 points
+            (
+                "4K4/9/9/9/9/9/4k4/4g4/+p+p+p+p+p+p+p+b+r w r4p 1",
+                27,
+                Win,
+                Draw,
+            ),
+            (
+                "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b R4P 1",
+                27,
+                Loss,
+                Draw,
+            ),
+            // the King is in check
+            (
+                "+R+B+P+P+P+P+P+P+P/4G4/4K4/4g4/9/9/9/9/4k4 b R4P 1",
+                27,
+                Loss,
+                Loss,
+            ),
+            // only 9 pieces in camp
+            (
+                "+R+B+P+P+P+P+P+P+P/9/4K4/9/9/9/9/9/4k4 b RBGSNL 1",
+                31,
+                Loss,
+                Loss,
+            ),
+            (
+                "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b RBG 1",
+                29,
+                Win,
+                Draw,
+            ),
+            (
+                "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b RBGSNL 1",
+                32,
+                Win,
+                Win,
+            ),
+            // the King has not entered
+            (
+                "+R+B+P+P+P+P+P+P+P/4G4/9/4K4/9/9/9/9/4k4 b RBGSNL 1",
+                32,
+                Loss,
+                Loss,
+            ),
+        ] {
+            let board: Board = sfen.parse().unwrap();
+            let color = board.side_to_move();
+            assert_eq!(board.impasse_points(color), points, "{sfen}");
+            assert_eq!(board.try_declare_win(color, Points27), p27, "{sfen}");
+            assert_eq!(board.try_declare_win(color, Points24), p24, "{sfen}");
+
+            let status = |declaration| match declaration {
+                Win => GameStatus::DeclarableWin,
+                Draw => GameStatus::DeclarableDraw,
+                Loss => GameStatus::Ongoing,
+            };
+            assert_eq!(board.status_with_impasse(Points27), status(p27), "{sfen}");
+            assert_eq!(board.status_with_impasse(Points24), status(p24), "{sfen}");
+        }
+    }
+
+    #[test]
+    fn try_rule() {
+        for (sfen, result) in [
+            ("9/4K4/9/9/9/9/9/9/4k4 b - 1", Declaration::Win),
+            ("4K4/9/9/9/9/9/9/4k4/9 w - 1", Declaration::Win),
+            // the King would move into check
+            ("8r/4K4/9/9/9/9/9/9/4k4 b - 1", Declaration::Loss),
+            ("4G4/4K4/9/9/9/9/9/9/4k4 b - 1", Declaration::Loss),
+            ("9/9/4K4/9/9/9/9/9/4k4 b - 1", Declaration::Loss),
+        ] {
+            let board: Board = sfen.parse().unwrap();
+            let color = board.side_to_move();
+            assert_eq!(
+                board.try_declare_win(color, ImpasseRule::Try),
+                result,
+                "{sfen}"
+            );
+            assert_eq!(
+                board.try_declare_win(!color, ImpasseRule::Try),
+                Declaration::Loss
+            );
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! KI2 (kanji) move notation
+use crate::kif::{FILE_CHARS, PIECE_NAMES, RANK_CHARS, parse_file, parse_piece, parse_rank};
+use crate::*;
+
+helpers::simple_error! {
+    /// An error while parsing a move in KI2 notation.
+    #[non_exhaustive]
+    pub enum KI2ParseError {
+        InvalidMove = "The move is malformed.",
+        IllegalMove = "No legal move matches the notation.",
+        AmbiguousMove = "More than one legal move matches the notation."
+    }
+}
+
+// How far to the right `square` lies, from the point of view of `color`.
+// Black's right is file 1, White's right is file 9.
+fn rightness(color: Color, square: Square) -> i32 {
+    match color {
+        Color::Black => -(square.file() as i32),
+        Color::White => square.file() as i32,
+    }
+}
+
+// The direction of a board move: 上 (forward), 引 (backward) or 寄 (sideways).
+fn direction(color: Color, from: Square, to: Square) -> char {
+    let forward = match color {
+        Color::Black => from.rank() as i32 - to.rank() as i32,
+        Color::White => to.rank() as i32 - from.rank() as i32,
+    };
+    match forward {
+        1.. => '上',
+        0 => '寄',
+        _ => '引',
+    }
+}
+
+// Does the piece move straight forward (直)? This is not used for the sliding pieces.
+fn is_straight(color: Color, piece: Piece, from: Square, to: Square) -> bool {
+    !matches!(
+        piece,
+        Piece::Rook | Piece::Bishop | Piece::PRook | Piece::PBishop
+    ) && from.file() == to.file()
+        && direction(color, from, to) == '上'
+}
+
+// Is `square` strictly right (or left) of all other squares in `squares`?
+fn is_outermost(color: Color, square: Square, squares: BitBoard, right: bool) -> bool {
+    let sign = if right { 1 } else { -1 };
+    (squares - square.bitboard())
+        .into_iter()
+        .all(|other| sign * rightness(color, square) > sign * rightness(color, other))
+}
+
+impl Board {
+    // The squares of the pieces of the side to move of type `piece` that can move to `to`.
+    // Move notations use this to decide whether a move needs to be disambiguated.
+    pub(crate) fn legal_origins(&self, piece: Piece, to: Square) -> BitBoard {
+        let ours = self.colored_pieces(self.side_to_move(), piece);
+        let mut candidates = BitBoard::EMPTY;
+        for &mv in self.legal_moves().iter() {
+            if let Move::BoardMove { from, .. } = mv
+                && mv.to() == to
+                && ours.has(from)
+            {
+                candidates |= from.bitboard();
+            }
+        }
+        candidates
+    }
+
+    /// Format a legal move in KI2 notation, such as `▲７六歩` or `△２二銀右上`.
+    ///
+    /// KI2 names a move by the side to move (`▲` for Black, `△` for White), the target
+    /// square and the piece. If more than one piece of the same type can move to the
+    /// target square, the move is disambiguated by its direction (`上` forward, `引`
+    /// backward, `寄` sideways), by `直` for a move straight forward, by the position of
+    /// the piece among the others (`右` or `左`, from the point of view of the player),
+    /// or by a position and a direction. Drops are marked with `打` only if a piece on the
+    /// board could move to the same square. Promotions are marked with `成`, and moves
+    /// that could promote but don't with `不成`.
+    ///
+    /// Records write `同` for a move to the target square of the previous move. The board
+    /// does not know the previous move, so this always writes the square.
+    ///
+    /// # Panics
+    /// This may panic if the move is not legal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert_eq!(board.format_move_ki2("7g7f".parse().unwrap()), "▲７六歩");
+    /// // both Golds can move to 5h
+    /// assert_eq!(board.format_move_ki2("6i5h".parse().unwrap()), "▲５八金左");
+    /// // the Silver on 7i moves straight forward, the Gold on 6i diagonally
+    /// assert_eq!(board.format_move_ki2("7i7h".parse().unwrap()), "▲７八銀");
+    /// assert_eq!(board.format_move_ki2("6i7h".parse().unwrap()), "▲７八金");
+    ///
+    /// let board: Board = "4k4/9/9/9/9/9/9/3G5/3GK4 w g 1".parse().unwrap();
+    /// assert_eq!(board.format_move_ki2("G*5b".parse().unwrap()), "△５二金");
+    /// ```
+    pub fn format_move_ki2(&self, mv: Move) -> String {
+        let color = self.side_to_move();
+        let to = mv.to();
+        let mut text = String::new();
+        text.push(match color {
+            Color::Black => '▲',
+            Color::White => '△',
+        });
+        text.push(FILE_CHARS[to.file() as usize]);
+        text.push(RANK_CHARS[to.rank() as usize]);
+
+        match mv {
+            Move::Drop { piece, .. } => {
+                text.push_str(PIECE_NAMES[piece as usize]);
+                if !self.legal_origins(piece, to).is_empty() {
+                    text.push('打');
+                }
+            }
+            Move::BoardMove {
+                from, promotion, ..
+            } => {
+                let piece = self
+                    .piece_on(from)
+                    .expect("Missing piece on move's `from` square");
+                text.push_str(PIECE_NAMES[piece as usize]);
+
+                let candidates = self.legal_origins(piece, to);
+                if candidates.len() > 1 {
+                    let dir = direction(color, from, to);
+                    let same_dir: BitBoard = candidates
+                        .into_iter()
+                        .filter(|&other| direction(color, other, to) == dir)
+                        .collect();
+                    if same_dir.len() == 1 {
+                        text.push(dir);
+                    } else if is_straight(color, piece, from, to) {
+                        text.push('直');
+                    } else if is_outermost(color, from, candidates, true) {
+                        text.push('右');
+                    } else if is_outermost(color, from, candidates, false) {
+                        text.push('左');
+                    } else {
+                        if is_outermost(color, from, same_dir, true) {
+                            text.push('右');
+                        } else if is_outermost(color, from, same_dir, false) {
+                            text.push('左');
+                        }
+                        text.push(dir);
+                    }
+                }
+
+                if promotion {
+                    text.push('成');
+                } else if PromotionStatus::for_move(color, piece, from, to)
+                    == PromotionStatus::MayPromote
+                {
+                    text.push_str("不成");
+                }
+            }
+        }
+        text
+    }
+
+    /// Parse a move in KI2 notation, such as `▲７六歩` or `２二銀右上`.
+    ///
+    /// The side marker (`▲`/`△`, or `☗`/`☖`) is optional, but if it is given, it must
+    /// be the side to move. The modifiers are read as described for
+    /// [`Board::format_move_ki2`], but need not be minimal: a modifier that is not needed
+    /// is accepted as long as it matches the move. A drop without `打` is accepted if no
+    /// piece on the board can move to the square.
+    ///
+    /// # Errors
+    /// Errors with [`KI2ParseError::InvalidMove`] if the text is malformed (this includes
+    /// `同`, since the board does not know the previous move), with
+    /// [`KI2ParseError::IllegalMove`] if no legal move matches, and with
+    /// [`KI2ParseError::AmbiguousMove`] if more than one legal move matches.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert_eq!(board.parse_move_ki2("▲７六歩").unwrap(), "7g7f".parse().unwrap());
+    /// assert_eq!(board.parse_move_ki2("５八金右").unwrap(), "4i5h".parse().unwrap());
+    /// assert!(matches!(
+    ///     board.parse_move_ki2("５八金"),
+    ///     Err(KI2ParseError::AmbiguousMove)
+    /// ));
+    /// assert!(matches!(
+    ///     board.parse_move_ki2("△３四歩"),
+    ///     Err(KI2ParseError::IllegalMove)
+    /// ));
+    /// ```
+    pub fn parse_move_ki2(&self, text: &str) -> Result<Move, KI2ParseError> {
+        use KI2ParseError::*;
+
+        let color = self.side_to_move();
+        let text = text.trim();
+        let text = match text.chars().next() {
+            Some('▲' | '☗') if color == Color::White => return Err(IllegalMove),
+            Some('△' | '☖') if color == Color::Black => return Err(IllegalMove),
+            Some(c @ ('▲' | '☗' | '△' | '☖')) => &text[c.len_utf8()..],
+            _ => text,
+        };
+
+        let mut chars = text.chars();
+        let file = chars.next().and_then(parse_file).ok_or(InvalidMove)?;
+        let rank = chars.next().and_then(parse_rank).ok_or(InvalidMove)?;
+        let to = Square::new(file, rank);
+        let (piece, mut rest) = parse_piece(chars.as_str()).ok_or(InvalidMove)?;
+
+        let mut position = None;
+        let mut dir = None;
+        let mut straight = false;
+        let mut drop = false;
+        let mut promotion = false;
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("不成") {
+                rest = after;
+                continue;
+            }
+            match c {
+                '右' | '左' if position.is_none() => position = Some(c == '右'),
+                '上' | '引' | '寄' if dir.is_none() => dir = Some(c),
+                '直' => straight = true,
+                '打' => drop = true,
+                '成' => promotion = true,
+                _ => return Err(InvalidMove),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+
+        let candidates = self.legal_origins(piece, to);
+        if candidates.is_empty() || drop {
+            let mv = Move::Drop { piece, to };
+            let modifiers = position.is_some() || dir.is_some() || straight || promotion;
+            return match self.is_legal(mv) && !modifiers {
+                true => Ok(mv),
+                false => Err(IllegalMove),
+            };
+        }
+
+        let mut candidates: BitBoard = candidates
+            .into_iter()
+            .filter(|&from| dir.is_none_or(|dir| direction(color, from, to) == dir))
+            .filter(|&from| !straight || is_straight(color, piece, from, to))
+            .collect();
+        if let Some(right) = position {
+            candidates = candidates
+                .into_iter()
+                .filter(|&from| is_outermost(color, from, candidates, right))
+                .collect();
+        }
+        let from = match candidates.len() {
+            0 => return Err(IllegalMove),
+            1 => candidates.next_square().unwrap(),
+            _ => return Err(AmbiguousMove),
+        };
+        let mv = Move::BoardMove {
+            from,
+            to,
+            promotion,
+        };
+        match self.is_legal(mv) {
+            true => Ok(mv),
+            false => Err(IllegalMove),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_round_trip(board: &Board) {
+        for &mv in board.legal_moves().iter() {
+            let text = board.format_move_ki2(mv);
+            assert_eq!(board.parse_move_ki2(&text).ok(), Some(mv), "{board} {text}");
+        }
+    }
+
+    #[test]
+    fn ki2_round_trip() {
+        for sfen in [
+            // two Dragons and two Horses that reach the same squares
+            "4k4/9/9/9/9/+B7+B/9/9/+R3K3+R b - 1",
+            "+r3k3+r/9/9/+b7+b/9/9/9/9/4K4 w - 1",
+            // Golds and pieces that move like Golds, with different names
+            "4k4/9/9/9/9/9/3+S+N+L3/3G+PG3/4K4 b - 1",
+            // Pieces that may or must promote, and drops next to them
+            "4k4/P1L6/2N6/1S7/9/9/9/9/4K4 b GSNLP 1",
+        ] {
+            check_round_trip(&sfen.parse().unwrap());
+        }
+        for board in random_positions(0x4b12, 10, 100) {
+            check_round_trip(&board);
+        }
+    }
+
+    #[test]
+    fn disambiguation() {
+        // Golds on 6h, 6i, 5i and 4i
+        let board: Board = "4k4/9/9/9/9/9/9/3G5/K2GGG3 b - 1".parse().unwrap();
+        for (mv, ki2) in [
+            ("5i5h", "▲５八金直"),
+            ("6i5h", "▲５八金左上"),
+            ("4i5h", "▲５八金右"),
+            ("6h5h", "▲５八金寄"),
+            ("4i4h", "▲４八金直"),
+            ("5i4h", "▲４八金左"),
+            ("6h5g", "▲５七金"),
+        ] {
+            let mv = mv.parse().unwrap();
+            assert_eq!(board.format_move_ki2(mv), ki2);
+            assert_eq!(board.parse_move_ki2(ki2).unwrap(), mv);
+        }
+
+        // White's right is file 9
+        let board: Board = "k2ggg3/9/9/9/9/9/9/9/4K4 w - 1".parse().unwrap();
+        assert_eq!(board.format_move_ki2("6a5b".parse().unwrap()), "△５二金右");
+        assert_eq!(board.format_move_ki2("4a5b".parse().unwrap()), "△５二金左");
+
+        // Silvers on 4i, 6i and 4g: the two Silvers moving forward need both modifiers
+        let board: Board = "4k4/9/9/9/9/9/5S3/9/K2S1S3 b - 1".parse().unwrap();
+        for (mv, ki2) in [
+            ("4i5h", "▲５八銀右上"),
+            ("6i5h", "▲５八銀左"),
+            ("4g5h", "▲５八銀引"),
+        ] {
+            let mv = mv.parse().unwrap();
+            assert_eq!(board.format_move_ki2(mv), ki2);
+            assert_eq!(board.parse_move_ki2(ki2).unwrap(), mv);
+        }
+    }
+
+    #[test]
+    fn drops_and_promotions() {
+        let board: Board = "4k4/9/9/9/9/9/9/4G4/4K4 b GP 1".parse().unwrap();
+        assert_eq!(board.format_move_ki2("G*4h".parse().unwrap()), "▲４八金打");
+        assert_eq!(board.format_move_ki2("G*4f".parse().unwrap()), "▲４六金");
+        assert_eq!(
+            board.parse_move_ki2("４八金").unwrap(),
+            "5h4h".parse().unwrap()
+        );
+        assert_eq!(
+            board.parse_move_ki2("４八金打").unwrap(),
+            "G*4h".parse().unwrap()
+        );
+        assert_eq!(
+            board.parse_move_ki2("４七金打").unwrap(),
+            "G*4g".parse().unwrap()
+        );
+        assert!(matches!(
+            board.parse_move_ki2("５一歩打"),
+            Err(KI2ParseError::IllegalMove)
+        ));
+
+        let board: Board = "4k4/9/9/2P6/9/9/9/9/4K4 b - 1".parse().unwrap();
+        assert_eq!(board.format_move_ki2("7d7c+".parse().unwrap()), "▲７三歩成");
+        assert_eq!(
+            board.format_move_ki2("7d7c".parse().unwrap()),
+            "▲７三歩不成"
+        );
+        assert_eq!(
+            board.parse_move_ki2("７三歩").unwrap(),
+            "7d7c".parse().unwrap()
+        );
+        assert_eq!(
+            board.parse_move_ki2("7三歩成").unwrap(),
+            "7d7c+".parse().unwrap()
+        );
+        assert!(matches!(
+            board.parse_move_ki2("同　歩"),
+            Err(KI2ParseError::InvalidMove)
+        ));
+        assert!(matches!(
+            board.parse_move_ki2("７三歩x"),
+            Err(KI2ParseError::InvalidMove)
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+//! King safety features
+use crate::*;
+
+/// Default weights of the attackers of the King zone, indexed by [`Piece`].
+///
+/// Used by [`Board::king_attackers_count`]. The King itself never counts as an attacker.
+pub const KING_ATTACK_WEIGHTS: [u8; Piece::NUM] = [
+    1, // Pawn
+    2, // Lance
+    2, // Knight
+    3, // Silver
+    3, // Bishop
+    4, // Rook
+    3, // Gold
+    0, // King
+    3, // Tokin
+    3, // PLance
+    3, // PKnight
+    3, // PSilver
+    4, // PBishop
+    5, // PRook
+];
+
+impl Board {
+    /// Get the King zone of `color`: the King square and the squares next to it.
+    ///
+    /// Returns an empty [`BitBoard`] if `color` has no King on the board.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert_eq!(board.king_zone(Color::Black).len(), 6);
+    /// assert!(board.king_zone(Color::Black).has(Square::I5));
+    /// ```
+    pub fn king_zone(&self, color: Color) -> BitBoard {
+        if !self.has_king(color) {
+            return BitBoard::EMPTY;
+        }
+        let king = self.king(color);
+        king_attacks(color, king) | king.bitboard()
+    }
+
+    /// Count the opponent's pieces that attack the King zone of `color`.
+    ///
+    /// Returns `(attackers, attack_weight)`: the number of distinct opponent pieces that
+    /// attack at least one square of [`Board::king_zone`], and the sum of their
+    /// [`KING_ATTACK_WEIGHTS`]. This is the classic King safety feature of Bonanza-style
+    /// evaluation functions. Both values saturate at `u8::MAX`.
+    ///
+    /// The attackers are collected with one [`Board::attackers`] lookup per zone square,
+    /// which finds the attackers of all piece types at once. Pins are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().king_attackers_count(Color::Black), (0, 0));
+    ///
+    /// // The Gold and the Rook bear on the squares next to the Black King
+    /// let board: Board = "4kr3/9/9/9/9/9/4g4/9/4K4 b - 1".parse().unwrap();
+    /// assert_eq!(board.king_attackers_count(Color::Black), (2, 3 + 4));
+    /// assert_eq!(board.king_attackers_count(Color::White), (0, 0));
+    /// ```
+    pub fn king_attackers_count(&self, color: Color) -> (u8, u8) {
+        self.king_attackers_count_with(color, &KING_ATTACK_WEIGHTS)
+    }
+
+    /// Count the attackers of the King zone of `color`, with custom weights.
+    ///
+    /// This is [`Board::king_attackers_count`] with `weights` (indexed by [`Piece`])
+    /// instead of [`KING_ATTACK_WEIGHTS`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4kr3/9/9/9/9/9/4g4/9/4K4 b - 1".parse().unwrap();
+    /// let weights = [1; Piece::NUM];
+    /// assert_eq!(board.king_attackers_count_with(Color::Black, &weights), (2, 2));
+    /// ```
+    pub fn king_attackers_count_with(&self, color: Color, weights: &[u8; Piece::NUM]) -> (u8, u8) {
+        let mut attackers = BitBoard::EMPTY;
+        for square in self.king_zone(color) {
+            attackers |= self.attackers(square, !color);
+        }
+        attackers &= !self.pieces(Piece::King);
+
+        let mut weight: u32 = 0;
+        for piece in Piece::ALL {
+            let num = (attackers & self.pieces(piece)).len();
+            weight += num * weights[piece as usize] as u32;
+        }
+        (
+            attackers.len().min(u8::MAX as u32) as u8,
+            weight.min(u8::MAX as u32) as u8,
+        )
+    }
+}
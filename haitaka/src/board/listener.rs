@@ -0,0 +1,144 @@
+//! Callbacks for incremental evaluation
+use crate::*;
+
+/// Callbacks for the changes that playing or taking back a move makes to a board.
+///
+/// Pass a listener to [`Board::play_unchecked_with_listener`],
+/// [`Board::play_unchecked_with_undo_and_listener`] or [`Board::unplay_with_listener`]
+/// to update an evaluation incrementally (such as piece-square tables or the
+/// accumulator of an NNUE net) instead of rescanning the board after every move.
+///
+/// Only these `_with_listener` methods report changes. All other ways of changing a
+/// board do not: [`Board::play`], [`Board::try_play`], [`Board::play_unchecked`],
+/// [`Board::speculate`], [`Board::make_null`], [`Board::unchecked_put`],
+/// [`Board::unchecked_set_hand`] and building a board with a [`BoardBuilder`]. After
+/// any of these, initialize the listener again from the whole board.
+///
+/// The callbacks are called in the order in which the board changes. A capture first
+/// removes the captured piece and adds it to the hand, then moves the capturing piece.
+/// A promotion removes the unpromoted piece from its square and adds the promoted piece
+/// on the target square. The callbacks see the changes one at a time, so they should not
+/// look at the board. All callbacks do nothing by default, and `()` is a listener that
+/// ignores all changes.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// // Keep track of the material balance from Black's point of view
+/// struct Material(i32);
+///
+/// fn value(color: Color, piece: Piece) -> i32 {
+///     let value = if piece.is_promoted() { 2 } else { 1 };
+///     if color == Color::Black { value } else { -value }
+/// }
+///
+/// impl BoardListener for Material {
+///     fn piece_added(&mut self, color: Color, piece: Piece, _square: Square) {
+///         self.0 += value(color, piece);
+///     }
+///     fn piece_removed(&mut self, color: Color, piece: Piece, _square: Square) {
+///         self.0 -= value(color, piece);
+///     }
+///     fn hand_changed(&mut self, color: Color, piece: Piece, old_count: u8, new_count: u8) {
+///         self.0 += value(color, piece) * (new_count as i32 - old_count as i32);
+///     }
+/// }
+///
+/// let mut board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+///     .parse()
+///     .unwrap();
+/// let mut material = Material(0);
+/// // Black captures the Bishop and promotes
+/// let undo = board.play_unchecked_with_undo_and_listener("8h2b+".parse().unwrap(), &mut material);
+/// assert_eq!(material.0, 3);
+/// board.unplay_with_listener(undo, &mut material);
+/// assert_eq!(material.0, 0);
+/// ```
+pub trait BoardListener {
+    /// A piece of `color` was put on `square`.
+    fn piece_added(&mut self, color: Color, piece: Piece, square: Square) {
+        let _ = (color, piece, square);
+    }
+
+    /// A piece of `color` was taken off `square`.
+    fn piece_removed(&mut self, color: Color, piece: Piece, square: Square) {
+        let _ = (color, piece, square);
+    }
+
+    /// The number of `piece`s in the hand of `color` changed from `old_count` to
+    /// `new_count`. The counts always differ by one.
+    fn hand_changed(&mut self, color: Color, piece: Piece, old_count: u8, new_count: u8) {
+        let _ = (color, piece, old_count, new_count);
+    }
+}
+
+impl BoardListener for () {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::IndexedRandom;
+
+    // Mirrors the pieces and hands of a board from the callbacks alone
+    #[derive(Debug, PartialEq, Eq)]
+    struct Mirror {
+        squares: [Option<(Color, Piece)>; Square::NUM],
+        hands: [[u8; Piece::NUM]; Color::NUM],
+    }
+
+    impl Mirror {
+        fn new(board: &Board) -> Self {
+            let mut squares = [None; Square::NUM];
+            for sq in Square::ALL {
+                squares[sq as usize] = board.color_on(sq).zip(board.piece_on(sq));
+            }
+            Self {
+                squares,
+                hands: [*board.hand(Color::White), *board.hand(Color::Black)],
+            }
+        }
+    }
+
+    impl BoardListener for Mirror {
+        fn piece_added(&mut self, color: Color, piece: Piece, square: Square) {
+            assert_eq!(self.squares[square as usize], None);
+            self.squares[square as usize] = Some((color, piece));
+        }
+
+        fn piece_removed(&mut self, color: Color, piece: Piece, square: Square) {
+            assert_eq!(self.squares[square as usize], Some((color, piece)));
+            self.squares[square as usize] = None;
+        }
+
+        fn hand_changed(&mut self, color: Color, piece: Piece, old_count: u8, new_count: u8) {
+            assert_eq!(self.hands[color as usize][piece as usize], old_count);
+            assert_eq!(old_count.abs_diff(new_count), 1);
+            self.hands[color as usize][piece as usize] = new_count;
+        }
+    }
+
+    #[test]
+    fn listener_sees_every_change() {
+        let mut rng = StdRng::seed_from_u64(0x115e);
+        for _ in 0..20 {
+            let mut board = Board::startpos();
+            let mut mirror = Mirror::new(&board);
+            let mut undos = Vec::new();
+            for _ in 0..80 {
+                let moves = board.legal_moves();
+                let Some(&mv) = moves.choose(&mut rng) else {
+                    break;
+                };
+                let undo = board.play_unchecked_with_undo_and_listener(mv, &mut mirror);
+                undos.push(undo);
+                assert_eq!(mirror, Mirror::new(&board), "{board}");
+            }
+            while let Some(undo) = undos.pop() {
+                board.unplay_with_listener(undo, &mut mirror);
+                assert_eq!(mirror, Mirror::new(&board), "{board}");
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Mate threat detection
+use crate::*;
+
+impl Board {
+    /// Is the side to move checkmated?
+    ///
+    /// This is true if the side to move is in check and has no legal moves. Note that
+    /// the board doesn't know the last move: if that was a mate by Pawn drop (uchifuzume),
+    /// this still returns true, although the move was illegal and the side that dropped
+    /// the Pawn loses.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert!(!Board::startpos().is_checkmate());
+    ///
+    /// let board: Board = "4k4/4G4/4P4/9/9/9/9/9/4K4 w - 1".parse().unwrap();
+    /// assert!(board.is_checkmate());
+    /// ```
+    pub fn is_checkmate(&self) -> bool {
+        !self.checkers.is_empty() && !self.generate_moves(|_| true)
+    }
+
+    /// Find a move that checkmates the opponent.
+    ///
+    /// Returns the first mating move found, or None if there is no mate in one. Board
+    /// moves and drops are both considered, but a mate by Pawn drop (uchifuzume) is
+    /// illegal and never returned. A mate by moving a Pawn on the board is allowed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+    /// assert_eq!(board.mate_in_one(), Some("G*5b".parse().unwrap()));
+    ///
+    /// // P*5b would be mate, but mate by Pawn drop is illegal
+    /// let board: Board = "3lkl3/3p1p3/4G4/9/9/9/9/9/4K4 b P 1".parse().unwrap();
+    /// assert!(board.after_unchecked("P*5b".parse().unwrap()).is_checkmate());
+    /// assert_eq!(board.mate_in_one(), None);
+    /// ```
+    pub fn mate_in_one(&self) -> Option<Move> {
+        let mut mate = None;
+        self.generate_checks(|moves| {
+            mate = moves
+                .into_iter()
+                .find(|&mv| self.after_unchecked(mv).is_checkmate());
+            mate.is_some()
+        });
+        mate
+    }
+
+    /// Does the opponent threaten mate in `depth` moves?
+    ///
+    /// This gives the opponent a free move (by playing a [`Board::null_move`] for the
+    /// side to move) and then searches for a forced mate by the opponent with at most
+    /// `depth` checks. If this returns true, the position is _tsume-ro_ (詰めろ) for the
+    /// side to move: unless they defend, they will be mated. If every legal move of the
+    /// side to move still leaves such a threat, the position is _hisshi_ (必至).
+    ///
+    /// To test whether a move creates a mate threat, play it and call this method on the
+    /// resulting position.
+    ///
+    /// The search is a plain depth-first search over checks and all evasions, so
+    /// its cost grows quickly with `depth`. Returns false if `depth` is 0, if the side
+    /// to move is in check (a null move is then not possible), or if the side to move
+    /// has no King.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// // White threatens to drop the Gold on 5h
+    /// let board: Board = "4k4/9/9/9/9/9/4p4/9/4K4 b g 1".parse().unwrap();
+    /// assert!(board.has_mate_threat(1));
+    ///
+    /// // Black is in check, so there is no free move to give away
+    /// let board: Board = "4k4/9/9/9/9/9/9/4p4/4K4 b g 1".parse().unwrap();
+    /// assert!(!board.has_mate_threat(1));
+    ///
+    /// assert!(!Board::startpos().has_mate_threat(2));
+    /// ```
+    pub fn has_mate_threat(&self, depth: u32) -> bool {
+        if depth == 0 || !self.has_king(self.side_to_move()) {
+            return false;
+        }
+        match self.null_move() {
+            Some(board) => board.has_mate_in(depth),
+            None => false,
+        }
+    }
+
+    // Can the side to move force mate with at most `depth` checks?
+    fn has_mate_in(&self, depth: u32) -> bool {
+        if depth == 0 {
+            return false;
+        }
+        self.generate_checks(|moves| {
+            moves.into_iter().any(|mv| {
+                let mut board = self.clone();
+                board.play_unchecked(mv);
+                board.is_mated_in(depth - 1)
+            })
+        })
+    }
+
+    // Is the side to move (in check) mated, or mated in `depth` moves after every evasion?
+    //
+    // Since the move generator never generates a mate by Pawn drop, having no legal
+    // moves here really is checkmate.
+    fn is_mated_in(&self, depth: u32) -> bool {
+        !self.generate_moves(|moves| {
+            moves.into_iter().any(|mv| {
+                let mut board = self.clone();
+                board.play_unchecked(mv);
+                !board.has_mate_in(depth)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mate_in_one_matches_brute_force() {
+        for sfen in [
+            "lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1",
+            "+P+n1g1+Pp+P1/2gg+p+s+pLn/1gppP1S+Pp/1+s+PPSPPPk/N1L2N+PL1/6L1+P/9/9/9 b - 1",
+            "4k4/9/4P4/9/9/9/9/9/4K4 b G 1",
+            "3lkl3/3p1p3/4G4/9/9/9/9/9/4K4 b P 1",
+            SFEN_STARTPOS,
+        ] {
+            let board = Board::tsume(sfen).unwrap();
+            let mut mates = Vec::new();
+            board.generate_moves(|moves| {
+                mates.extend(
+                    moves
+                        .into_iter()
+                        .filter(|&mv| board.after_unchecked(mv).is_checkmate()),
+                );
+                false
+            });
+            match board.mate_in_one() {
+                Some(mv) => assert!(mates.contains(&mv), "{sfen} {mv}"),
+                None => assert!(mates.is_empty(), "{sfen}"),
+            }
+        }
+    }
+
+    #[test]
+    fn mate_threat_requires_forced_mate() {
+        // The Gold drop on 5h is defended by the Pawn on 5g...
+        let board: Board = "4k4/9/9/9/9/9/4p4/9/4K4 b g 1".parse().unwrap();
+        assert!(board.has_mate_threat(1));
+        assert!(!board.has_mate_threat(0));
+
+        // ...but not when Black has a Silver on 4i to take it back
+        let board: Board = "4k4/9/9/9/9/9/4p4/9/4KS3 b g 1".parse().unwrap();
+        assert!(!board.has_mate_threat(1));
+
+        // White threatens B*7g K8i G*8h, but there is no mate with one check
+        let board: Board = "4k4/9/9/9/9/9/9/L8/K8 b bg 1".parse().unwrap();
+        assert!(!board.has_mate_threat(1));
+        assert!(board.has_mate_threat(2));
+    }
+}
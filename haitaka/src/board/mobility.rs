@@ -0,0 +1,83 @@
+//! Mobility counts
+use crate::*;
+
+impl Board {
+    /// Count the legal destination squares of the pieces of `color`, per piece type.
+    ///
+    /// The array is indexed by `piece as usize`. Every board move counts once, whether
+    /// or not the piece can also promote on that square. Drops are not counted. The
+    /// counts are taken in bulk from the target squares found by the move generator,
+    /// without iterating over the moves.
+    ///
+    /// If `color` is not the side to move, the counts are for the position after a
+    /// [`Board::null_move`]. If the side to move is in check, this is not possible and
+    /// all counts are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let mobility = board.mobility(Color::Black);
+    /// assert_eq!(mobility[Piece::Pawn as usize], 9);
+    /// assert_eq!(mobility[Piece::Rook as usize], 6);
+    /// assert_eq!(mobility[Piece::Bishop as usize], 0);
+    /// assert_eq!(mobility.iter().map(|&n| n as usize).sum::<usize>(), 30);
+    /// assert_eq!(board.mobility(Color::White), mobility);
+    /// ```
+    pub fn mobility(&self, color: Color) -> [u8; Piece::NUM] {
+        let mut counts = [0; Piece::NUM];
+        let null_move;
+        let board = if color == self.side_to_move() {
+            self
+        } else {
+            match self.null_move() {
+                Some(board) => {
+                    null_move = board;
+                    &null_move
+                }
+                None => return counts,
+            }
+        };
+
+        board.generate_board_moves(|moves| {
+            if let PieceMoves::BoardMoves { piece, to, .. } = moves {
+                counts[piece as usize] += to.len() as u8;
+            }
+            false
+        });
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mobility_counts_distinct_board_moves() {
+        for sfen in [
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+            "R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1",
+        ] {
+            let board: Board = sfen.parse().unwrap();
+            let mut expected = [0; Piece::NUM];
+            let mut seen = Vec::new();
+            board.generate_board_moves(|moves| {
+                for mv in moves {
+                    let (from, to) = (mv.from().unwrap(), mv.to());
+                    if !seen.contains(&(from, to)) {
+                        seen.push((from, to));
+                        expected[board.piece_on(from).unwrap() as usize] += 1;
+                    }
+                }
+                false
+            });
+            assert_eq!(board.mobility(board.side_to_move()), expected, "{sfen}");
+        }
+
+        // no null move when in check
+        let board: Board = "4k4/9/9/9/9/9/9/4r4/4K4 b - 1".parse().unwrap();
+        assert_eq!(board.mobility(Color::White), [0; Piece::NUM]);
+    }
+}
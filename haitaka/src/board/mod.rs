@@ -1,17 +1,63 @@
 //! The Shogi [`Board`] representation and move generation functions
 use crate::*;
 use core::hash::{Hash, Hasher};
+mod bod;
+mod builder;
+mod camp;
+mod dominance;
+mod handicap;
+mod impasse;
+mod ki2;
+mod king_safety;
+mod listener;
+mod mate;
+mod mobility;
+mod mode;
 mod movegen;
 mod parse;
+mod phase;
+mod pins;
+mod region;
+mod render;
+mod see;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "interop-shogi-core")]
+mod shogi_core_impls;
+mod storage;
+mod symmetry;
+mod threats;
+mod undo;
 mod validate;
 mod zobrist;
 
+pub use bod::*;
+pub use builder::*;
+pub use dominance::*;
+pub use handicap::*;
+pub use impasse::*;
+pub use ki2::*;
+pub use king_safety::*;
+pub use listener::*;
+pub use mode::*;
+#[cfg(test)]
+pub(crate) use movegen::tests::random_positions;
 pub use movegen::*;
 pub use parse::*;
+pub use phase::*;
+pub use pins::*;
+pub use region::*;
+pub use render::*;
+pub use see::*;
+pub use undo::*;
+pub use validate::*;
+pub use zobrist::Dominance;
 use zobrist::*;
+pub(crate) use zobrist::{hand_key, piece_key, side_to_move_key};
 
 /// The current state of the game.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum GameStatus {
     /// The game ended in a win for *the other side*
     /// (not the current side_to_move, but see also [`Board::status`])
@@ -20,35 +66,87 @@ pub enum GameStatus {
     Drawn,
     /// The game is still ongoing.
     Ongoing,
+    /// The game is still ongoing, but the side to move can declare an entering King
+    /// win (see [`Board::status_with_impasse`]).
+    DeclarableWin,
+    /// The game is still ongoing, but the side to move can declare an entering King
+    /// draw (only with [`ImpasseRule::Points24`], see [`Board::status_with_impasse`]).
+    DeclarableDraw,
 }
 
-helpers::simple_error! {
-    /// An error returned when the move played was illegal.
-    pub struct IllegalMoveError = "The move played was illegal.";
+/// An error returned when the move played was illegal.
+///
+/// The error holds the illegal move. The error returned by [`Board::try_play_verbose`]
+/// also holds the SFEN of the position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IllegalMoveError {
+    mv: Move,
+    sfen: Option<String>,
+}
+
+impl IllegalMoveError {
+    pub(crate) fn new(mv: Move) -> Self {
+        Self {
+            mv,
+            sfen: None,
+        }
+    }
+
+    // Record the position in which the move was played.
+    fn with_position(self, board: &Board) -> Self {
+        Self {
+            sfen: Some(board.to_string()),
+            ..self
+        }
+    }
+
+    /// The illegal move.
+    pub fn mv(&self) -> Move {
+        self.mv
+    }
+
+    /// The SFEN of the position in which the move was played, if it was recorded.
+    pub fn sfen(&self) -> Option<&str> {
+        self.sfen.as_deref()
+    }
 }
 
+impl core::fmt::Display for IllegalMoveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "The move played was illegal: {}", self.mv)?;
+        if let Some(sfen) = &self.sfen {
+            write!(f, " (in position {})", sfen)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IllegalMoveError {}
+
 /// SFEN string representing the start position
 pub const SFEN_STARTPOS: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
 
-// TODO: In handicap games is white's first move numbered 1 or 2? For now, to be consistent, I label it '2'.
-
 /// SFEN string for 6-piece handicap
-pub const SFEN_6PIECE_HANDICAP: &str = "2sgkgs2/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 2";
+#[deprecated(since = "0.4.0", note = "use `Handicap::SixPiece.sfen()` instead")]
+pub const SFEN_6PIECE_HANDICAP: &str = Handicap::SixPiece.sfen();
 
 /// SFEN string for 4-piece handicap
-pub const SFEN_4PIECE_HANDICAP: &str =
-    "1nsgkgsn1/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 2";
+#[deprecated(since = "0.4.0", note = "use `Handicap::FourPiece.sfen()` instead")]
+pub const SFEN_4PIECE_HANDICAP: &str = Handicap::FourPiece.sfen();
 
 /// SFEN string for 2-piece handicap
-pub const SFEN_2PIECE_HANDICAP: &str =
-    "lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 2";
+#[deprecated(since = "0.4.0", note = "use `Handicap::TwoPiece.sfen()` instead")]
+pub const SFEN_2PIECE_HANDICAP: &str = Handicap::TwoPiece.sfen();
 
 /// A Shogi board.
 ///
 /// This keeps about as much state as a SFEN string. It does not keep track of history.
 /// More in particular it also does not track the repetition status of positions.
 /// Keeping track of that is a concern of a game-playing engine; the Board is only
-/// concerned with representing, validating and modifying a position.
+/// concerned with representing, validating and modifying a position. See
+/// [`GameHistory`](crate::repetition::GameHistory) for a board that does keep its history,
+/// and [`Game`](crate::game::Game) for the full outcome of a game.
 ///
 /// Before playing a move, `checkers` is the bitboard of all the opponent's pieces that
 /// give check to our (side-to-move) King. The `pinned` bitboard has all our (side-to-move)
@@ -130,9 +228,14 @@ impl Board {
         self.inner.take_in_hand(color, piece);
     }
 
+    /// Put a piece on `square`, without any checks.
+    ///
+    /// The square should be empty. On an occupied square, the piece is added next to the
+    /// piece that is there, which [`Board::validate`] reports as
+    /// [`PositionError::OverlappingPieces`], and [`Board::piece_on`] gives the new piece.
     #[inline(always)]
     pub fn unchecked_put(&mut self, color: Color, piece: Piece, square: Square) {
-        self.inner.xor_square(piece, color, square);
+        self.inner.overlay_square(piece, color, square);
         if piece == Piece::Pawn {
             self.pawnless_files[color as usize] &= !square.file().bitboard();
         }
@@ -159,6 +262,23 @@ impl Board {
         self.inner.golds_and_promoted_pieces()
     }
 
+    /// Get a [`BitBoard`] of the files without a Pawn of the given color.
+    ///
+    /// Since a player may not have two unpromoted Pawns on one file (nifu), Pawns can
+    /// only be dropped on these files.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/9/9/9/9/2P6/9/4K4 b P 1".parse().unwrap();
+    /// assert_eq!(board.pawnless_files(Color::Black), !File::Seven.bitboard());
+    /// assert_eq!(board.pawnless_files(Color::White), BitBoard::FULL);
+    /// ```
+    #[inline(always)]
+    pub fn pawnless_files(&self, color: Color) -> BitBoard {
+        self.pawnless_files[color as usize]
+    }
+
     /// Get a [`BitBoard`] of all small pieces in the current position that move like Gold.
     ///
     /// This includes the Golds and all small promoted pieces, excluding PRook and PBishop.
@@ -193,6 +313,30 @@ impl Board {
         self.inner.colors(color)
     }
 
+    /// Get a copy of all piece and color bitboards at once.
+    ///
+    /// The first array holds [`Board::pieces`] for each piece, indexed by `piece as usize`,
+    /// and the second holds [`Board::colors`] for each color, indexed by `color as usize`.
+    /// This is meant for serializers, evaluators and other code that hands the whole
+    /// position to another representation.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let (pieces, colors) = board.bitboards_snapshot();
+    /// assert_eq!(pieces[Piece::Pawn as usize], board.pieces(Piece::Pawn));
+    /// assert_eq!(colors[Color::Black as usize], board.colors(Color::Black));
+    /// assert_eq!(pieces.iter().fold(BitBoard::EMPTY, |all, &bb| all | bb), board.occupied());
+    /// ```
+    #[inline(always)]
+    pub fn bitboards_snapshot(&self) -> ([BitBoard; Piece::NUM], [BitBoard; Color::NUM]) {
+        (
+            Piece::ALL.map(|piece| self.inner.pieces(piece)),
+            Color::ALL.map(|color| self.inner.colors(color)),
+        )
+    }
+
     /// Get a [`BitBoard`] of all the pieces of a certain color and piece type.
     /// Shorthand for `board.colors(color) & board.pieces(piece)`.
     ///
@@ -239,7 +383,79 @@ impl Board {
     /// ```
     #[inline(always)]
     pub fn colored_pieces(&self, color: Color, piece: Piece) -> BitBoard {
-        self.colors(color) & self.pieces(piece)
+        self.inner.colored_pieces(color, piece)
+    }
+
+    /// Call `f` with the color, piece type and square of every piece on the board.
+    ///
+    /// The pieces are visited by color (see [`Color::ALL`]), then by piece type (see
+    /// [`Piece::ALL`]), then by square. This uses the piece bitboards directly, so it
+    /// avoids looking up the piece on every square.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let mut pawns = 0;
+    /// board.for_each_piece(|_color, piece, _square| {
+    ///     if piece == Piece::Pawn {
+    ///         pawns += 1;
+    ///     }
+    /// });
+    /// assert_eq!(pawns, 18);
+    /// ```
+    pub fn for_each_piece(&self, mut f: impl FnMut(Color, Piece, Square)) {
+        for color in Color::ALL {
+            for piece in Piece::ALL {
+                for square in self.colored_pieces(color, piece) {
+                    f(color, piece, square);
+                }
+            }
+        }
+    }
+
+    /// Iterate over the color, piece type and square of every piece on the board.
+    ///
+    /// The order is the same as for [`Board::for_each_piece`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert_eq!(board.iter_pieces().count(), 40);
+    /// assert!(board.iter_pieces().all(|(color, piece, square)| {
+    ///     board.colored_piece_on(square) == Some(ColoredPiece { piece, color })
+    /// }));
+    /// ```
+    pub fn iter_pieces(&self) -> impl Iterator<Item = (Color, Piece, Square)> + '_ {
+        Color::ALL.into_iter().flat_map(move |color| {
+            Piece::ALL.into_iter().flat_map(move |piece| {
+                self.colored_pieces(color, piece)
+                    .into_iter()
+                    .map(move |square| (color, piece, square))
+            })
+        })
+    }
+
+    /// Get the [`BitBoard`]s of the pieces of `color`, indexed by [`Piece`].
+    ///
+    /// This is [`Board::colored_pieces`] for all piece types at once.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let pieces = board.occupied_by_piece(Color::Black);
+    /// assert_eq!(pieces[Piece::Pawn as usize], Rank::G.bitboard());
+    /// assert_eq!(pieces[Piece::King as usize], Square::I5.bitboard());
+    /// assert!(pieces[Piece::Tokin as usize].is_empty());
+    /// assert_eq!(
+    ///     pieces.iter().fold(BitBoard::EMPTY, |all, &bb| all | bb),
+    ///     board.colors(Color::Black)
+    /// );
+    /// ```
+    pub fn occupied_by_piece(&self, color: Color) -> [BitBoard; Piece::NUM] {
+        Piece::ALL.map(|piece| self.colored_pieces(color, piece))
     }
 
     /// Get a [`BitBoard`] of all the sliders for color.
@@ -323,6 +539,13 @@ impl Board {
 
     /// Get the incrementally updated position hash.
     ///
+    /// The hash covers the pieces on the board, the side to move and the pieces in hand:
+    /// the number of pieces of each type in the hand of each side has its own key. So
+    /// positions that differ only in their hands (even with the same total material, or
+    /// with the hands swapped) hash differently, as required for detecting repetitions
+    /// (Sennichite) and for transposition tables. [`Board::hands_key`] gives the part of
+    /// the hash that comes from the hands.
+    ///
     /// Does not include the move number.
     ///
     /// # Examples
@@ -343,6 +566,55 @@ impl Board {
         self.inner.hash()
     }
 
+    /// Get the [`Board::hash`] of the position after `mv`, without playing it.
+    ///
+    /// This only looks up the keys of the pieces that the move changes (see
+    /// [`zobrist`](crate::zobrist)), so it is much cheaper than
+    /// [`Board::after_unchecked`]. It can be used to prefetch transposition table
+    /// entries, or to skip moves into positions that were seen before.
+    ///
+    /// The move must be legal, or at least pseudo-legal.
+    ///
+    /// # Panics
+    /// This may panic if the move is not pseudo-legal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let mv = "7g7f".parse().unwrap();
+    /// assert_eq!(board.hash_after(mv), board.after_unchecked(mv).hash());
+    /// ```
+    pub fn hash_after(&self, mv: Move) -> u64 {
+        let color = self.side_to_move();
+        let mut hash = self.hash() ^ side_to_move_key();
+        match mv {
+            Move::Drop { piece, to } => {
+                let count = self.num_in_hand(color, piece);
+                hash ^= hand_key(color, piece, count) ^ hand_key(color, piece, count - 1);
+                hash ^= piece_key(color, piece, to);
+            }
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => {
+                let piece = self
+                    .piece_on(from)
+                    .expect("Missing piece on move's `from` square");
+                if let Some(capture) = self.piece_on(to) {
+                    let captured = capture.unpromote();
+                    let count = self.num_in_hand(color, captured);
+                    hash ^= piece_key(!color, capture, to);
+                    hash ^= hand_key(color, captured, count) ^ hand_key(color, captured, count + 1);
+                }
+                let final_piece = if promotion { piece.promote() } else { piece };
+                hash ^= piece_key(color, piece, from) ^ piece_key(color, final_piece, to);
+            }
+        }
+        hash
+    }
+
     /// Get the pinned pieces for the side to move.
     ///
     /// Note that this counts pieces regardless of color!
@@ -393,6 +665,50 @@ impl Board {
         self.checkers
     }
 
+    /// Get the pieces of the given type that are currently giving check.
+    ///
+    /// This is [`Board::checkers`] restricted to one piece type, so that evasions can
+    /// be handled by checker type without looking up the piece on each checker square.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use haitaka::*;
+    /// let sfen: &str = "ln2+r1r2/5s+Pkl/3+B1p1p1/p4B2p/2P6/P6PP/1PNP1P3/2G3SK1/L4G1NL w 2GSN3Ps3p 76";
+    /// let board = Board::from_sfen(sfen).unwrap();
+    /// assert_eq!(board.checkers_by(Piece::Tokin), Square::B3.bitboard());
+    /// assert_eq!(board.checkers_by(Piece::Bishop), Square::D4.bitboard());
+    /// assert_eq!(board.checkers_by(Piece::Rook), BitBoard::EMPTY);
+    /// ```
+    #[inline(always)]
+    pub fn checkers_by(&self, piece: Piece) -> BitBoard {
+        self.checkers & self.pieces(piece)
+    }
+
+    /// Is the side to move in check by a Lance, Bishop, Rook or promoted Bishop or Rook?
+    ///
+    /// Only checks by sliders can be blocked by interposing a piece, so if this returns
+    /// false, there is no point in generating drops or other interpositions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use haitaka::*;
+    /// let sfen: &str = "ln3gsn1/7kl/3+B1p1p1/p4s2p/2P6/P2B3PP/1PNP+rPP2/2G3SK1/L4G1NL b G3Prs3p 65";
+    /// let mut board = Board::from_sfen(sfen).unwrap();
+    /// assert!(!board.is_checked_by_slider());
+    /// board.play("6f4d".parse().unwrap());
+    /// assert!(board.is_checked_by_slider());
+    ///
+    /// let board = Board::from_sfen("4k4/4P4/9/9/9/9/9/9/4K4 w - 1").unwrap();
+    /// assert!(!board.checkers().is_empty());
+    /// assert!(!board.is_checked_by_slider());
+    /// ```
+    #[inline(always)]
+    pub fn is_checked_by_slider(&self) -> bool {
+        !(self.checkers & self.sliders(!self.side_to_move())).is_empty()
+    }
+
     /// Get the [move number].
     ///
     /// In Shogi, other than in International Chess, moves are always numbered
@@ -450,10 +766,7 @@ impl Board {
     /// ```
     #[inline(always)]
     pub fn piece_on(&self, square: Square) -> Option<Piece> {
-        Piece::ALL
-            .iter()
-            .copied()
-            .find(|&p| self.pieces(p).has(square))
+        self.inner.piece_on(square)
     }
 
     /// Get the [`Color`] of the piece on `square`, if there is one.
@@ -562,7 +875,9 @@ impl Board {
     /// Due to the rather complicated rules related to Sennichite and Jishogi
     /// the Board cannot always determine what the actual game status is. So
     /// this function has a pretty limited use. The final determination needs
-    /// to be made by a game playing engine.
+    /// to be made by a game playing engine. [`Game::outcome`](crate::game::Game::outcome)
+    /// is a reference implementation of these rules. [`Board::status_with_impasse`]
+    /// also tells whether the side to move can declare an entering King win.
     ///
     /// The rules for winning and losing in Shogi are:
     ///
@@ -632,7 +947,7 @@ impl Board {
     /// indicates that whoever is side-to-to-move has the advantage of the first move.
     ///
     /// # Examples
-    /// 
+    ///
     /// # use haitaka::*;
     /// let sfen1 = "9/7k1/9/7S1/9/9/9/7L1/9 b -";
     /// let board1 = Board::tsume(sfen1).unwrap();
@@ -645,7 +960,7 @@ impl Board {
     /// assert_eq!(board2.dominates(board1), Dominance::DominatedBy);
     /// assert_eq!(board2.dominates(board3), Dominance::Incomparable);
     /// assert_eq!(board3.dominates(board2), Dominance::Incomparable);
-    /// 
+    ///
     pub fn dominates(&self, other: &Self) -> Dominance {
         self.inner.dominates(&other.inner)
     }
@@ -679,14 +994,101 @@ impl Board {
     ///
     /// # Errors
     /// Errors with [`IllegalMoveError`] if the move was illegal.
+    /// The error holds the move, but not the position. See [`Board::try_play_verbose`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut board = Board::startpos();
+    /// let mv: Move = "7g7e".parse().unwrap();
+    /// let error = board.try_play(mv).unwrap_err();
+    /// assert_eq!(error.mv(), mv);
+    /// assert_eq!(format!("{}", error), "The move played was illegal: 7g7e");
+    /// ```
     pub fn try_play(&mut self, mv: Move) -> Result<(), IllegalMoveError> {
         if !self.is_legal(mv) {
-            return Err(IllegalMoveError);
+            return Err(IllegalMoveError::new(mv));
         }
         self.play_unchecked(mv);
         Ok(())
     }
 
+    /// Like [`Board::try_play`], but on error also records the SFEN of the position.
+    ///
+    /// Formatting the SFEN string takes some time, but only when the move is illegal.
+    /// This is meant for applications that want to log illegal moves with their context.
+    ///
+    /// # Errors
+    /// Errors with [`IllegalMoveError`] if the move was illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut board = Board::startpos();
+    /// let error = board.try_play_verbose("7g7e".parse().unwrap()).unwrap_err();
+    /// assert_eq!(error.sfen(), Some(SFEN_STARTPOS));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     format!("The move played was illegal: 7g7e (in position {SFEN_STARTPOS})")
+    /// );
+    /// assert_eq!(board.try_play("7g7e".parse().unwrap()).unwrap_err().sfen(), None);
+    /// ```
+    pub fn try_play_verbose(&mut self, mv: Move) -> Result<(), IllegalMoveError> {
+        self.try_play(mv).map_err(|error| error.with_position(self))
+    }
+
+    /// Get the position after a move, without changing this board.
+    ///
+    /// This is a shorthand for cloning the board and calling [`Board::try_play`].
+    ///
+    /// # Errors
+    /// Errors with [`IllegalMoveError`] if the move was illegal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let child = board.after("7g7f".parse().unwrap()).unwrap();
+    /// assert_eq!(child.side_to_move(), Color::White);
+    /// assert_eq!(board, Board::startpos());
+    ///
+    /// assert!(board.after("7g7e".parse().unwrap()).is_err());
+    /// ```
+    pub fn after(&self, mv: Move) -> Result<Board, IllegalMoveError> {
+        let mut board = self.clone();
+        board.try_play(mv)?;
+        Ok(board)
+    }
+
+    /// Unchecked version of [`Board::after`].
+    ///
+    /// This is a shorthand for cloning the board and calling [`Board::play_unchecked`],
+    /// with the same caveats: only legal moves should be passed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// fn perft(board: &Board, depth: u8) -> u64 {
+    ///     if depth == 0 {
+    ///         return 1;
+    ///     }
+    ///     let mut nodes = 0;
+    ///     board.generate_moves(|moves| {
+    ///         for mv in moves {
+    ///             nodes += perft(&board.after_unchecked(mv), depth - 1);
+    ///         }
+    ///         false
+    ///     });
+    ///     nodes
+    /// }
+    /// assert_eq!(perft(&Board::startpos(), 2), 900);
+    /// ```
+    pub fn after_unchecked(&self, mv: Move) -> Board {
+        let mut board = self.clone();
+        board.play_unchecked(mv);
+        board
+    }
+
     /// Unchecked version of [`Board::play`].
     ///
     /// Use this method with caution. Only legal moves should ever be passed.
@@ -697,6 +1099,7 @@ impl Board {
     ///
     /// Playing illegal moves corrupts the board state, which may cause further panics.
     /// See [`Board::play`] for a variant _guaranteed_ to panic immediately on illegal moves.
+    /// See [`Board::play_unchecked_with_undo`] for a variant whose moves can be taken back.
     ///
     /// # Examples
     /// ```
@@ -710,14 +1113,25 @@ impl Board {
     /// assert_eq!(format!("{}", board), expected);
     /// ```
     pub fn play_unchecked(&mut self, mv: Move) {
+        self.play_unchecked_with_listener(mv, &mut ());
+    }
+
+    /// Like [`Board::play_unchecked`], but reports every change to the board to `listener`.
+    ///
+    /// See [`BoardListener`] for the order of the callbacks. Use
+    /// [`Board::play_unchecked_with_undo_and_listener`] to take the move back later.
+    pub fn play_unchecked_with_listener(&mut self, mv: Move, listener: &mut impl BoardListener) {
         let color = self.inner.side_to_move();
 
         if let Move::Drop { piece, to } = mv {
             // take piece out of hand
             self.inner.take_from_hand(color, piece);
+            let count = self.num_in_hand(color, piece);
+            listener.hand_changed(color, piece, count + 1, count);
 
             // drop the piece
-            self.inner.xor_square(piece, color, to);
+            self.inner.set_square(piece, color, to);
+            listener.piece_added(color, piece, to);
 
             // update pawn_on_file
             if piece == Piece::Pawn {
@@ -725,7 +1139,7 @@ impl Board {
             }
 
             // update checkers and pins
-            if self.has(!color, Piece::King) {
+            if self.has_king(!color) {
                 // opponent has a King - see which of our pieces are giving check
                 self.update_checkers_and_pins(color, piece, to);
             } else {
@@ -747,9 +1161,13 @@ impl Board {
             // optional capture
             if let Some(capture) = self.piece_on(to) {
                 // remove capture
-                self.inner.xor_square(capture, !color, to);
+                self.inner.clear_square(capture, !color, to);
+                listener.piece_removed(!color, capture, to);
                 // take in hand
-                self.inner.take_in_hand(color, capture.unpromote());
+                let captured = capture.unpromote();
+                self.inner.take_in_hand(color, captured);
+                let count = self.num_in_hand(color, captured);
+                listener.hand_changed(color, captured, count - 1, count);
 
                 // update pawn_on_file
                 if capture == Piece::Pawn {
@@ -758,11 +1176,13 @@ impl Board {
             }
 
             // lift piece up
-            self.inner.xor_square(piece, color, from);
+            self.inner.clear_square(piece, color, from);
+            listener.piece_removed(color, piece, from);
 
             // perhaps promote then drop piece
             let final_piece = if promotion { piece.promote() } else { piece };
-            self.inner.xor_square(final_piece, color, to);
+            self.inner.set_square(final_piece, color, to);
+            listener.piece_added(color, final_piece, to);
 
             // update pawn_on_file
             if piece == Piece::Pawn && promotion {
@@ -770,7 +1190,7 @@ impl Board {
             }
 
             // update checkers and pins (if the other side has a King)
-            if self.has(!color, Piece::King) {
+            if self.has_king(!color) {
                 // opponent has a King
                 self.update_checkers_and_pins(color, final_piece, to);
             } else {
@@ -794,7 +1214,7 @@ impl Board {
 
         // update for non-sliders
         let them = !color;
-        debug_assert!(self.has(them, Piece::King));
+        debug_assert!(self.has_king(them));
         let their_king = self.king(them);
 
         match piece {
@@ -853,9 +1273,7 @@ impl Board {
     /// If the King is in check, this function returns None. In that case a null
     /// move would make no sense (it would immediately lose).
     ///
-    /// # Panics
-    ///
-    /// This function will panic if side-to-move has no King (Tsume Shogi).
+    /// See [`Board::make_null`] to make a null move without cloning the board.
     ///
     /// # Examples
     ///
@@ -869,35 +1287,9 @@ impl Board {
     /// assert_eq!(sfen_out, sfen2);
     /// ```
     pub fn null_move(&self) -> Option<Board> {
-        if self.checkers.is_empty() {
-            let mut board = self.clone();
-            let color = board.side_to_move();
-
-            // update move number and switch side-to-move
-            board.move_number += 1;
-            board.inner.toggle_side_to_move();
-
-            // we only need to update pinned
-            board.pinned = BitBoard::EMPTY;
-            let our_king = board.king(color);
-            let them = board.colors(color);
-            let their_attackers = them
-                & (
-                    bishop_pseudo_attacks(our_king) | rook_pseudo_attacks(our_king)
-                    // already includes Lance attacks
-                );
-            let occ = board.occupied();
-            for square in their_attackers {
-                let between = get_between_rays(our_king, square) & occ;
-                if between.len() == 1 {
-                    board.pinned |= between;
-                }
-            }
-
-            Some(board)
-        } else {
-            None
-        }
+        let mut board = self.clone();
+        board.make_null()?;
+        Some(board)
     }
 }
 
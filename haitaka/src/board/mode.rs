@@ -0,0 +1,149 @@
+//! Positions with and without Kings
+use crate::*;
+
+/// Which Kings a position has.
+///
+/// A board parsed with [`Board::from_sfen`] always has both Kings. A board parsed with
+/// [`Board::tsume`] represents a Tsume Shogi problem, in which the attacker (Black) may
+/// not have a King. The defender (White) always has a King.
+///
+/// The rules are the same in both modes, except for what follows from the missing King:
+///
+/// - Black can never be in check and none of Black's pieces are pinned. So every
+///   pseudo-legal move of Black is legal (apart from the rules on drops and
+///   promotions, which apply as usual).
+/// - White's moves and drops never give check. White can not be checkmated by a
+///   Pawn drop either, so the rule against mate by Pawn drop only restricts Black.
+/// - Black can not be checkmated. If Black has no legal moves (for instance because
+///   Black has no pieces left), [`Board::status`] returns [`GameStatus::Won`] for White,
+///   which in Tsume Shogi means that the problem failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BoardMode {
+    /// Both sides have a King.
+    Standard,
+    /// Tsume Shogi: the attacker (Black) has no King.
+    TsumeAttackerNoKing,
+}
+
+impl Board {
+    /// Get the [`BoardMode`] of the position.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().mode(), BoardMode::Standard);
+    ///
+    /// let board = Board::tsume("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1").unwrap();
+    /// assert_eq!(board.mode(), BoardMode::TsumeAttackerNoKing);
+    ///
+    /// // Tsume positions may still have a Black King
+    /// let board = Board::tsume("4k4/9/4P4/9/9/9/9/9/4K4 b G 1").unwrap();
+    /// assert_eq!(board.mode(), BoardMode::Standard);
+    /// ```
+    pub fn mode(&self) -> BoardMode {
+        if self.has(Color::Black, Piece::King) {
+            BoardMode::Standard
+        } else {
+            BoardMode::TsumeAttackerNoKing
+        }
+    }
+
+    // Does `color` have a King? Only Black can be without one, see `BoardMode`.
+    #[inline(always)]
+    pub(crate) fn has_king(&self, color: Color) -> bool {
+        color == Color::White || self.mode() == BoardMode::Standard
+    }
+
+    /// Is there no material left to checkmate with?
+    ///
+    /// This is true if there are no pieces on the board other than Kings and both hands
+    /// are empty. Neither side can then ever give check, let alone checkmate. Shogi has
+    /// no rule that ends the game in this case, but an engine may want to treat it as a
+    /// draw. It practically never happens in a game, but it can happen in a Tsume Shogi
+    /// search once the attacker has run out of pieces.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert!(!Board::startpos().is_material_draw());
+    ///
+    /// let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b - 1".parse().unwrap();
+    /// assert!(board.is_material_draw());
+    ///
+    /// // A Pawn in hand is enough to (eventually) mate
+    /// let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b P 1".parse().unwrap();
+    /// assert!(!board.is_material_draw());
+    /// ```
+    pub fn is_material_draw(&self) -> bool {
+        self.occupied() == self.pieces(Piece::King)
+            && self.is_hand_empty(Color::White)
+            && self.is_hand_empty(Color::Black)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(board: &Board) -> Vec<Move> {
+        let mut moves = Vec::new();
+        board.generate_moves(|mvs| {
+            moves.extend(mvs);
+            false
+        });
+        moves
+    }
+
+    #[test]
+    fn tsume_mode_semantics() {
+        // Without a Black King, the White Rook on 5e pins nothing and checks nothing
+        let board = Board::tsume("4k4/9/9/9/4r4/9/4S4/9/9 b G 1").unwrap();
+        assert_eq!(board.mode(), BoardMode::TsumeAttackerNoKing);
+        assert!(board.checkers().is_empty() && board.pinned().is_empty());
+
+        // The Silver can move anywhere
+        let silver_moves: Vec<Move> = moves(&board)
+            .into_iter()
+            .filter(|mv| mv.from() == Some(Square::G5))
+            .collect();
+        assert_eq!(silver_moves.len(), 5);
+        assert!(silver_moves.iter().all(|&mv| board.is_legal(mv)));
+
+        // White's moves never give check
+        let mut board = board;
+        board.play("G*5b".parse().unwrap());
+        assert_eq!(board.checkers(), Square::B5.bitboard());
+        for mv in moves(&board) {
+            let mut board = board.clone();
+            board.play(mv);
+            assert!(board.checkers().is_empty() && board.pinned().is_empty());
+        }
+    }
+
+    #[test]
+    fn tsume_pawn_drop_mate_only_restricts_black() {
+        // P*1b would be mate by Pawn drop (the Knight on 2d guards 1b)
+        let board = Board::tsume("7nk/7s1/9/7N1/9/9/9/9/9 b P 1").unwrap();
+        assert!(!board.is_legal("P*1b".parse().unwrap()));
+
+        // White may drop a Pawn in front of any piece (there is no Black King to mate)
+        let mut board = Board::tsume("8k/9/9/9/9/9/9/9/R8 b - 1").unwrap();
+        board.play("9i9h".parse().unwrap());
+        assert!(board.is_legal("P*9g".parse().unwrap()));
+    }
+
+    #[test]
+    fn tsume_requires_the_defender_king() {
+        assert!(Board::tsume("9/9/9/9/9/9/9/9/4K4 b G 1").is_err());
+        assert!(Board::tsume("9/9/9/9/9/9/9/9/9 b G 1").is_err());
+    }
+
+    #[test]
+    fn bare_kings() {
+        let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b - 1".parse().unwrap();
+        assert!(board.is_material_draw());
+        assert_eq!(board.mode(), BoardMode::Standard);
+        assert_eq!(board.status(), GameStatus::Ongoing);
+    }
+}
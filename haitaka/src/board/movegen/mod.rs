@@ -1,10 +1,15 @@
 use super::*;
 
+mod move_list;
 mod piece_moves;
+mod scored_move_list;
+pub use move_list::*;
 pub use piece_moves::*;
+pub use scored_move_list::*;
 
 #[cfg(test)]
-mod tests;
+#[allow(clippy::len_zero)]
+pub(crate) mod tests;
 
 // The private `commoner` module defines the private Commoner trait.
 // This streamlines the implementation of move generation for all pieces apart from King.
@@ -41,14 +46,142 @@ mod commoner {
         Knight => knight_attacks(color, square),
         Silver => silver_attacks(color, square),
         Gold => gold_attacks(color, square),
-        Bishop => get_bishop_moves(color, square, blockers),
-        Rook => get_rook_moves(color, square, blockers),
+        Bishop => lazy_bishop_moves(color, square, blockers),
+        Rook => lazy_rook_moves(color, square, blockers),
         Tokin => gold_attacks(color, square),
         PLance => gold_attacks(color, square),
         PSilver => gold_attacks(color, square),
         PKnight => gold_attacks(color, square),
-        PBishop =>  get_bishop_moves(color, square, blockers) | gold_attacks(color, square),
-        PRook => get_rook_moves(color, square, blockers) | silver_attacks(color, square)
+        PBishop =>  lazy_bishop_moves(color, square, blockers) | gold_attacks(color, square),
+        PRook => lazy_rook_moves(color, square, blockers) | silver_attacks(color, square)
+    }
+}
+
+/// The maximum number of legal moves in any position.
+///
+/// This is the number of legal moves for Black in the position
+/// `R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1`, which is known to be
+/// the maximum. A buffer of this size can hold all moves generated by
+/// [`Board::generate_moves`] for any position.
+pub const MAX_LEGAL_MOVES: usize = 593;
+
+/// The maximum number of times [`Board::generate_moves`] calls its listener.
+///
+/// The listener is called at most three times for each of the (at most 40) pieces
+/// on the board and once for each of the 7 piece types in hand.
+pub const MAX_LISTENER_CALLS: usize = 3 * 40 + 7;
+
+/// The maximum number of moves in one [`PieceMoves::BoardMoves`].
+///
+/// An unobstructed Rook or Bishop on 5e reaches 16 squares, the most of any piece that
+/// can promote. The bound is twice that, since every target square may be reached both
+/// with and without promotion. The promoted Rook and Bishop reach at most 20 squares,
+/// and never promote.
+pub const MAX_BOARD_MOVES_PER_PIECE: usize = 32;
+
+/// The maximum number of moves in one [`PieceMoves::Drops`].
+///
+/// A piece can be dropped on any square but the square of the opponent's King.
+pub const MAX_DROPS_PER_PIECE: usize = Square::NUM - 1;
+
+/// The order of drops and board moves in [`Board::generate_moves_ordered`].
+///
+/// This order is stable: it does not change between releases, so searches that depend
+/// on the move order stay reproducible. Within the drops and within the board moves,
+/// the order of the listener calls is unspecified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum MoveGenOrder {
+    /// All drops before all board moves. This is the order of [`Board::generate_moves`].
+    #[default]
+    DropsFirst,
+    /// All board moves before all drops. Most engines want to try captures and other
+    /// board moves first.
+    BoardFirst,
+}
+
+/// Which non-promotions [`Board::generate_moves_with_non_promotions`] generates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum NonPromotions {
+    /// All legal non-promotions. This is what [`Board::generate_moves`] generates.
+    #[default]
+    All,
+    /// Only the non-promotions that can be better than the promotion.
+    ///
+    /// This leaves out the non-promotions of Pawns, Bishops and Rooks, and the
+    /// non-promotions of Lances to the second rank (from the point of view of the side
+    /// to move), which lose some moves of the piece and gain nothing. Strong engines skip
+    /// these moves to reduce the branching factor. The only exception is the rule against
+    /// mate by Pawn drop, so Tsume Shogi problems are sometimes solved by such a move.
+    Useful,
+}
+
+// The order in which `Board::generate_drops` generates the drops.
+const DEFAULT_DROP_ORDER: [Piece; Piece::HAND_NUM] = [
+    Piece::Pawn,
+    Piece::Lance,
+    Piece::Knight,
+    Piece::Silver,
+    Piece::Gold,
+    Piece::Rook,
+    Piece::Bishop,
+];
+
+// Wrap a listener to check the bounds above (only used in debug builds).
+#[cfg(debug_assertions)]
+fn bounds_checked(mut listener: impl FnMut(PieceMoves) -> bool) -> impl FnMut(PieceMoves) -> bool {
+    let mut calls = 0;
+    let mut total = 0;
+    move |moves: PieceMoves| {
+        let len = moves.into_iter().len();
+        let bound = match moves {
+            PieceMoves::BoardMoves { .. } => MAX_BOARD_MOVES_PER_PIECE,
+            PieceMoves::Drops { .. } => MAX_DROPS_PER_PIECE,
+        };
+        calls += 1;
+        total += len;
+        debug_assert!(len <= bound, "Too many moves for one piece: {moves:?}");
+        debug_assert!(calls <= MAX_LISTENER_CALLS, "Too many listener calls");
+        debug_assert!(total <= MAX_LEGAL_MOVES, "Too many legal moves");
+        listener(moves)
+    }
+}
+
+// Wrap a listener to check the invariants of the generated moves with
+// `PieceMoves::validate`, which is too slow for every debug build (only used in the unit
+// tests and with the internal `verify-checks` feature).
+#[cfg(any(test, feature = "verify-checks"))]
+fn validated<'a>(
+    board: &'a Board,
+    mut listener: impl FnMut(PieceMoves) -> bool + 'a,
+) -> impl FnMut(PieceMoves) -> bool + 'a {
+    move |moves: PieceMoves| {
+        if let Err(error) = moves.validate(board) {
+            panic!("{error}: {moves:?} in {board}");
+        }
+        listener(moves)
+    }
+}
+
+// Wrap a listener to verify that every generated check is legal and really gives check
+// (only used with the internal `verify-checks` feature).
+#[cfg(feature = "verify-checks")]
+fn verified_checks<'a>(
+    board: &'a Board,
+    mut listener: impl FnMut(PieceMoves) -> bool + 'a,
+) -> impl FnMut(PieceMoves) -> bool + 'a {
+    move |moves: PieceMoves| {
+        for mv in moves {
+            assert!(board.is_legal(mv), "Illegal check {mv} in {board}");
+            let mut child = board.clone();
+            child.play_unchecked(mv);
+            assert!(
+                !child.checkers().is_empty(),
+                "Move {mv} does not give check in {board}"
+            );
+        }
+        listener(moves)
     }
 }
 
@@ -112,7 +245,7 @@ impl Board {
     // Board moves
 
     // Generate legal moves for all the "commoners" (all pieces except King).
-    // `mask` is used to select from-squares
+    // `mask` is used to select from-squares, `targets` to select to-squares
     fn add_common_legals<
         P: commoner::Commoner,
         F: FnMut(PieceMoves) -> bool,
@@ -120,10 +253,11 @@ impl Board {
     >(
         &self,
         mask: BitBoard,
+        targets: BitBoard,
         prom_status: PromotionStatus,
         listener: &mut F,
     ) -> bool {
-        let target_squares = self.target_squares::<IN_CHECK>();
+        let target_squares = self.target_squares::<IN_CHECK>() & targets;
 
         if IN_CHECK && target_squares.is_empty() {
             return false;
@@ -134,37 +268,89 @@ impl Board {
         let pinned = self.pinned;
         let blockers = self.occupied();
 
+        // Note: Although `BitBoard::for_each_square` is much faster in isolation (see the
+        // bitboard bench), using `try_for_each_square` in these loops made perft about 25%
+        // slower on x86_64, so they keep the iterator.
         for from in pieces & !pinned {
             let to = P::pseudo_legals(color, from, blockers) & target_squares;
+            abort_if!(Self::add_board_moves(
+                color,
+                P::PIECE,
+                from,
+                to,
+                prom_status,
+                listener
+            ));
+        }
+
+        if !IN_CHECK && P::PIECE != Piece::Knight && self.has_king(color) {
+            // Pinned pieces (apart from Knight!) can still move along the attack ray between King and checker.
+            // Only consider pinned pieces when not in check, since a pinned piece can never capture a checker.
+            let our_king = self.king(color);
+            for from in pieces & pinned {
+                let to = P::pseudo_legals(color, from, blockers)
+                    & line_ray(our_king, from)
+                    & target_squares;
+                abort_if!(Self::add_board_moves(
+                    color,
+                    P::PIECE,
+                    from,
+                    to,
+                    prom_status,
+                    listener
+                ));
+            }
+        }
+        false
+    }
+
+    // Report the board moves of a single piece to the listener.
+    //
+    // If `prom_status` is Undecided, the target squares are first split into the
+    // squares where the piece must, may or cannot promote. The listener is then
+    // called at most three times, but each PieceMoves instance carries a fixed
+    // PromotionStatus, so that the PieceMovesIter does not need to check promotion
+    // rules for every single move.
+    #[inline(always)]
+    fn add_board_moves<F: FnMut(PieceMoves) -> bool>(
+        color: Color,
+        piece: Piece,
+        from: Square,
+        to: BitBoard,
+        prom_status: PromotionStatus,
+        listener: &mut F,
+    ) -> bool {
+        if prom_status != PromotionStatus::Undecided {
             if !to.is_empty() {
                 abort_if!(listener(PieceMoves::BoardMoves {
                     color,
-                    piece: P::PIECE,
+                    piece,
                     from,
                     to,
                     prom_status,
                 }));
             }
+            return false;
         }
 
-        if !IN_CHECK && P::PIECE != Piece::Knight && self.has(color, Piece::King) {
-            // Pinned pieces (apart from Knight!) can still move along the attack ray between King and checker.
-            // Only consider pinned pieces when not in check, since a pinned piece can never capture a checker.
-            let our_king = self.king(color);
-            for from in pieces & pinned {
-                let to = P::pseudo_legals(color, from, blockers)
-                    & line_ray(our_king, from)
-                    & target_squares;
+        let zone = prom_zone(color);
+        let must = to & must_prom_zone(color, piece);
+        let may = if zone.has(from) { to } else { to & zone } ^ must;
+        let cannot = to ^ must ^ may;
 
-                if !to.is_empty() {
-                    abort_if!(listener(PieceMoves::BoardMoves {
-                        color,
-                        piece: P::PIECE,
-                        from,
-                        to,
-                        prom_status,
-                    }));
-                }
+        for (to, prom_status) in [
+            (must, PromotionStatus::MustPromote),
+            (may, PromotionStatus::MayPromote),
+            (cannot, PromotionStatus::CannotPromote),
+        ] {
+            if !to.is_empty() {
+                abort_if!(listener(PieceMoves::BoardMoves {
+                    color,
+                    piece,
+                    from,
+                    to,
+                    prom_status,
+                }));
             }
         }
         false
@@ -174,9 +360,10 @@ impl Board {
     fn add_goldlike_legals<F: FnMut(PieceMoves) -> bool, const IN_CHECK: bool>(
         &self,
         mask: BitBoard,
+        targets: BitBoard,
         listener: &mut F,
     ) -> bool {
-        let target_squares = self.target_squares::<IN_CHECK>();
+        let target_squares = self.target_squares::<IN_CHECK>() & targets;
 
         if IN_CHECK && target_squares.is_empty() {
             return false;
@@ -201,7 +388,7 @@ impl Board {
             }
         }
 
-        if !IN_CHECK && self.has(color, Piece::King) {
+        if !IN_CHECK && self.has_king(color) {
             // Pinned gold-like pieces can still move along the attack ray between King and checker.
             // Only consider pinned pieces when not in check, since a pinned piece can never capture a checker!
             let our_king = self.king(color);
@@ -259,9 +446,12 @@ impl Board {
             }
         }
 
+        if self.attacked_by_steppers(square) {
+            return false;
+        }
+
         let color = self.side_to_move();
         let their_pieces = self.colors(!color);
-        let kings = self.pieces(Piece::King);
 
         // simulate moving the King to the square (for slider attack generation)
         let blockers =
@@ -270,18 +460,14 @@ impl Board {
         // testing the sliders takes up about half of the test time;
         // using lazy_and improves throughput by about 17%
         short_circuit! {
-            gold_attacks(color, square) & their_pieces & (self.pseudo_golds() | kings),
-            silver_attacks(color, square) & their_pieces & (self.pseudo_silvers() | kings),
-            knight_attacks(color, square) & their_pieces & self.pieces(Piece::Knight),
-            pawn_attacks(color, square) & their_pieces & self.pieces(Piece::Pawn),
             lazy_and! {
                 // by first filtering on pseudo attacks, this whole function becomes almost twice as fast
                 bishop_pseudo_attacks(square) & (self.pieces(Piece::Bishop) | self.pieces(Piece::PBishop)) & their_pieces,
-                get_bishop_moves(color, square, blockers)
+                lazy_bishop_moves(color, square, blockers)
             },
             lazy_and! {
                 rook_pseudo_attacks(square) & (self.pieces(Piece::Rook) | self.pieces(Piece::PRook)) & their_pieces,
-                get_rook_moves(color, square, blockers)
+                lazy_rook_moves(color, square, blockers)
             },
             lazy_and! {
                 lance_pseudo_attacks(color, square) & self.pieces(Piece::Lance) & their_pieces,
@@ -290,23 +476,88 @@ impl Board {
         }
     }
 
-    fn is_illegal_mate_by_pawn_drop(&self, to: Square) -> bool {
-        debug_assert!(self.checkers.is_empty());
+    // Is the square attacked by one of the opponent's pieces, not counting slider rays?
+    //
+    // This includes the step attacks of the King, PRook and PBishop.
+    #[inline]
+    fn attacked_by_steppers(&self, square: Square) -> bool {
+        let color = self.side_to_move();
+        let their_pieces = self.colors(!color);
+        let kings = self.pieces(Piece::King);
 
-        let them = !self.side_to_move();
-        if !self.has(them, Piece::King) {
-            return false;
-        }
+        !(gold_attacks(color, square) & their_pieces & (self.pseudo_golds() | kings)).is_empty()
+            || !(silver_attacks(color, square) & their_pieces & (self.pseudo_silvers() | kings))
+                .is_empty()
+            || !(knight_attacks(color, square) & their_pieces & self.pieces(Piece::Knight))
+                .is_empty()
+            || !(pawn_attacks(color, square) & their_pieces & self.pieces(Piece::Pawn)).is_empty()
+    }
 
-        let our_pawn_rank = to.rank() as usize;
-        let their_king_rank = self.king(them).rank() as usize;
+    /// Get all squares attacked by the sliders of `color`, given the occupancy `occ`.
+    ///
+    /// This is the union of the attacks of all Lances, Bishops and Rooks of `color`,
+    /// including the promoted Bishops and Rooks (with their King-like steps). The
+    /// attacks are computed in one pass over the sliders, which is cheaper than
+    /// testing the sliders for every square separately. The occupancy does not need to
+    /// be the actual occupancy of the board. For instance, removing the King from `occ`
+    /// gives all squares that the King cannot escape to along a slider ray.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/9/9/9/9/9/1B7/L3K4 b - 1".parse().unwrap();
+    /// assert_eq!(board.slider_attacks(Color::Black, board.occupied()), bitboard! {
+    ///     X . . . . . . . X
+    ///     X . . . . . . X .
+    ///     X . . . . . X . .
+    ///     X . . . . X . . .
+    ///     X . . . X . . . .
+    ///     X . . X . . . . .
+    ///     X . X . . . . . .
+    ///     X . . . . . . . .
+    ///     X . X . . . . . .
+    /// });
+    /// assert!(board.slider_attacks(Color::White, board.occupied()).is_empty());
+    /// ```
+    pub fn slider_attacks(&self, color: Color, occ: BitBoard) -> BitBoard {
+        let ours = self.colors(color);
+        let bishops = (self.pieces(Piece::Bishop) | self.pieces(Piece::PBishop)) & ours;
+        let rooks = (self.pieces(Piece::Rook) | self.pieces(Piece::PRook)) & ours;
+        let promoted = (self.pieces(Piece::PBishop) | self.pieces(Piece::PRook)) & ours;
 
-        if (them == Color::White && their_king_rank != our_pawn_rank - 1)
-            || (them == Color::Black && their_king_rank != our_pawn_rank + 1)
-        {
-            return false;
+        let mut attacks = BitBoard::EMPTY;
+        for square in self.pieces(Piece::Lance) & ours {
+            attacks |= get_lance_moves(color, square, occ);
+        }
+        for square in bishops {
+            attacks |= lazy_bishop_moves(color, square, occ);
+        }
+        for square in rooks {
+            attacks |= lazy_rook_moves(color, square, occ);
+        }
+        for square in promoted {
+            attacks |= king_attacks(color, square);
+        }
+        attacks
+    }
+
+    // Remove the square of an illegal mate by Pawn drop from the Pawn drop targets.
+    //
+    // A dropped Pawn can only give check on the square in front of their King, so
+    // that is the only square that needs to be checked.
+    fn without_pawn_drop_mate(&self, to: BitBoard) -> BitBoard {
+        let them = !self.side_to_move();
+        if !self.has_king(them) {
+            return to;
         }
+        match (pawn_attacks(them, self.king(them)) & to).next_square() {
+            Some(square) if self.is_illegal_mate_by_pawn_drop(square) => to.rm(square),
+            _ => to,
+        }
+    }
 
+    // Is dropping a Pawn on `to`, in front of their King, an illegal mate?
+    fn is_illegal_mate_by_pawn_drop(&self, to: Square) -> bool {
         // We know that our Pawn on `to` square attacks their King.
         //
         // (1) If to square is not attacked by them (apart from by their King), and
@@ -322,6 +573,7 @@ impl Board {
         });
 
         // don't call generate_moves (which could cause recursion!)
+        // drops can't block the check of an adjacent Pawn
         let mut has_legal_moves = false;
         board.generate_board_moves(|_| {
             has_legal_moves = true;
@@ -334,12 +586,13 @@ impl Board {
     fn add_king_legals<F: FnMut(PieceMoves) -> bool, const IN_CHECK: bool>(
         &self,
         mask: BitBoard,
+        targets: BitBoard,
         listener: &mut F,
     ) -> bool {
         const PIECE: Piece = Piece::King;
 
         let color = self.side_to_move();
-        if !self.has(color, Piece::King) {
+        if !self.has_king(color) {
             return false;
         }
 
@@ -348,14 +601,21 @@ impl Board {
         if !mask.has(our_king) {
             return false;
         }
-        let mut moves = king_attacks(color, our_king) & !our_pieces;
-        for to in moves {
+        // the slider attacks are computed once for all King moves (with the King removed,
+        // so that the King can't step back along the ray of a checking slider)
+        let blockers = self.occupied() ^ our_king.bitboard();
+        let mut moves = king_attacks(color, our_king) & !our_pieces & targets;
+        if moves.is_empty() {
+            return false;
+        }
+        moves &= !self.slider_attacks(!color, blockers);
+        moves.for_each_square(|to| {
             // removing unsafe squares should generally be more efficient than
             // adding safe squares since (until the endgame) most squares are safe
-            if !self.king_safe_on(to) {
+            if self.attacked_by_steppers(to) {
                 moves ^= to.bitboard();
             }
-        }
+        });
         if !moves.is_empty() {
             abort_if!(listener(PieceMoves::BoardMoves {
                 color,
@@ -371,33 +631,34 @@ impl Board {
     fn add_all_legals<F: FnMut(PieceMoves) -> bool, const IN_CHECK: bool>(
         &self,
         mask: BitBoard,
+        targets: BitBoard,
         listener: &mut F,
     ) -> bool {
         abort_if! {
-            self.add_common_legals::<commoner::Pawn, _, IN_CHECK>(mask, PromotionStatus::Undecided, listener),
-            self.add_common_legals::<commoner::Lance, _, IN_CHECK>(mask, PromotionStatus::Undecided, listener),
-            self.add_common_legals::<commoner::Knight, _, IN_CHECK>(mask, PromotionStatus::Undecided, listener),
-            self.add_common_legals::<commoner::Silver, _, IN_CHECK>(mask, PromotionStatus::Undecided, listener),
+            self.add_common_legals::<commoner::Pawn, _, IN_CHECK>(mask, targets, PromotionStatus::Undecided, listener),
+            self.add_common_legals::<commoner::Lance, _, IN_CHECK>(mask, targets, PromotionStatus::Undecided, listener),
+            self.add_common_legals::<commoner::Knight, _, IN_CHECK>(mask, targets, PromotionStatus::Undecided, listener),
+            self.add_common_legals::<commoner::Silver, _, IN_CHECK>(mask, targets, PromotionStatus::Undecided, listener),
 
             // doing all of the small gold-like pieces in one step consistently hurts the speed of move generation,
             // so for now I keep separate calls
-            self.add_common_legals::<commoner::Gold, _, IN_CHECK>(mask, PromotionStatus::CannotPromote, listener),
-            self.add_common_legals::<commoner::Tokin, _, IN_CHECK>(mask, PromotionStatus::CannotPromote, listener),
-            self.add_common_legals::<commoner::PLance, _, IN_CHECK>(mask, PromotionStatus::CannotPromote, listener),
-            self.add_common_legals::<commoner::PKnight, _, IN_CHECK>(mask, PromotionStatus::CannotPromote, listener),
-            self.add_common_legals::<commoner::PSilver, _, IN_CHECK>(mask, PromotionStatus::CannotPromote, listener),
-
-            self.add_common_legals::<commoner::Bishop, _, IN_CHECK>(mask, PromotionStatus::Undecided, listener),
-            self.add_common_legals::<commoner::Rook, _, IN_CHECK>(mask, PromotionStatus::Undecided, listener),
-            self.add_common_legals::<commoner::PBishop, _, IN_CHECK>(mask, PromotionStatus::CannotPromote, listener),
-            self.add_common_legals::<commoner::PRook, _, IN_CHECK>(mask, PromotionStatus::CannotPromote, listener),
-            self.add_king_legals::<_, IN_CHECK>(mask, listener)
+            self.add_common_legals::<commoner::Gold, _, IN_CHECK>(mask, targets, PromotionStatus::CannotPromote, listener),
+            self.add_common_legals::<commoner::Tokin, _, IN_CHECK>(mask, targets, PromotionStatus::CannotPromote, listener),
+            self.add_common_legals::<commoner::PLance, _, IN_CHECK>(mask, targets, PromotionStatus::CannotPromote, listener),
+            self.add_common_legals::<commoner::PKnight, _, IN_CHECK>(mask, targets, PromotionStatus::CannotPromote, listener),
+            self.add_common_legals::<commoner::PSilver, _, IN_CHECK>(mask, targets, PromotionStatus::CannotPromote, listener),
+
+            self.add_common_legals::<commoner::Bishop, _, IN_CHECK>(mask, targets, PromotionStatus::Undecided, listener),
+            self.add_common_legals::<commoner::Rook, _, IN_CHECK>(mask, targets, PromotionStatus::Undecided, listener),
+            self.add_common_legals::<commoner::PBishop, _, IN_CHECK>(mask, targets, PromotionStatus::CannotPromote, listener),
+            self.add_common_legals::<commoner::PRook, _, IN_CHECK>(mask, targets, PromotionStatus::CannotPromote, listener),
+            self.add_king_legals::<_, IN_CHECK>(mask, targets, listener)
         }
         false
     }
 
     // Drops
-    fn add_drops<P: commoner::Commoner, F: FnMut(PieceMoves) -> bool, const IN_CHECK: bool>(
+    fn add_drops<P: commoner::Commoner, F: FnMut(PieceMoves) -> bool>(
         &self,
         listener: &mut F,
         target_squares: BitBoard,
@@ -418,13 +679,7 @@ impl Board {
                     return false;
                 }
                 // check that the drop doesn't cause illegal checkmate
-                // note: if we're in check, this situation cannot occur!
-                if !IN_CHECK {
-                    let to_square = to.next_square().unwrap();
-                    if self.is_illegal_mate_by_pawn_drop(to_square) {
-                        to = to.rm(to_square);
-                    }
-                }
+                to = self.without_pawn_drop_mate(to);
             }
             if to.is_empty() {
                 return false;
@@ -435,28 +690,42 @@ impl Board {
         false
     }
 
-    fn add_all_drops<F: FnMut(PieceMoves) -> bool, const IN_CHECK: bool>(
+    // Limit the drop targets to the squares where each piece in hand may be dropped,
+    // indexed by piece. This excludes the forbidden drop ranks and, for Pawns, the
+    // files with a Pawn (nifu), but not a mate by Pawn drop.
+    fn drop_masks(&self, targets: BitBoard) -> [BitBoard; Piece::HAND_NUM] {
+        let color = self.side_to_move();
+        let mut masks = [BitBoard::EMPTY; Piece::HAND_NUM];
+        for (index, mask) in masks.iter_mut().enumerate() {
+            *mask = targets & drop_zone(color, Piece::index_const(index));
+        }
+        masks[Piece::Pawn as usize] &= self.pawnless_files[color as usize];
+        masks
+    }
+
+    // Generate the drops of the pieces in `order`, in that order.
+    // Pieces that can't be dropped are skipped.
+    fn add_all_drops<F: FnMut(PieceMoves) -> bool>(
         &self,
         listener: &mut F,
         targets: BitBoard,
+        order: &[Piece],
     ) -> bool {
         let color = self.side_to_move();
         if targets.is_empty() || self.is_hand_empty(color) {
             return false;
         }
-        abort_if! {
-            self.add_drops::<commoner::Pawn, _, IN_CHECK>(listener, targets),
-            self.add_drops::<commoner::Lance, _, IN_CHECK>(listener, targets),
-            self.add_drops::<commoner::Knight, _, IN_CHECK>(listener, targets),
-
-            self.has_in_hand(color, Piece::Silver) &&
-                listener(PieceMoves::Drops { color, piece: Piece::Silver, to: targets }),
-            self.has_in_hand(color, Piece::Gold) &&
-                listener(PieceMoves::Drops { color, piece: Piece::Gold, to: targets }),
-            self.has_in_hand(color, Piece::Rook) &&
-                listener(PieceMoves::Drops { color, piece: Piece::Rook, to: targets }),
-            self.has_in_hand(color, Piece::Bishop) &&
-                listener(PieceMoves::Drops { color, piece: Piece::Bishop, to: targets })
+        let masks = self.drop_masks(targets);
+        for &piece in order {
+            if piece as usize >= Piece::HAND_NUM || !self.has_in_hand(color, piece) {
+                continue;
+            }
+            let mut to = masks[piece as usize];
+            // check that a Pawn drop doesn't cause illegal checkmate
+            if piece == Piece::Pawn {
+                to = self.without_pawn_drop_mate(to);
+            }
+            abort_if!(!to.is_empty() && listener(PieceMoves::Drops { color, piece, to }));
         }
         false
     }
@@ -475,9 +744,12 @@ impl Board {
             let color = self.side_to_move();
 
             if piece == Piece::King
+                || !self.has_in_hand(color, piece)
                 || self.occupied().has(to)
                 || no_fly_zone(color, piece).has(to)
-                || (piece == Piece::Pawn && !self.pawn_drop_ok(color, to))
+                || (piece == Piece::Pawn
+                    && (!self.pawn_drop_ok(color, to)
+                        || self.without_pawn_drop_mate(to.bitboard()).is_empty()))
             {
                 return false;
             }
@@ -519,75 +791,106 @@ impl Board {
             }
 
             if promotion {
-                // `from` or `to` must be in the promotion zone
+                // only unpromoted pieces other than Gold and King can promote,
+                // and `from` or `to` must be in the promotion zone
                 let zone = prom_zone(color);
-                if !(zone.has(to) || zone.has(from)) {
+                if !piece.is_promotable() || !(zone.has(to) || zone.has(from)) {
                     return false;
                 }
             } else if piece.must_promote(color, to) {
                 return false;
             }
 
+            // can the piece reach `to` on this board?
+            // only the slider rays need the squares in between
+            let unblocked = || (get_between_rays(from, to) & self.occupied()).is_empty();
+            let reachable = match piece {
+                Piece::Pawn => pawn_attacks(color, from).has(to),
+                Piece::Knight => knight_attacks(color, from).has(to),
+                Piece::Silver => silver_attacks(color, from).has(to),
+                Piece::Lance => lance_pseudo_attacks(color, from).has(to) && unblocked(),
+                Piece::Rook => rook_pseudo_attacks(from).has(to) && unblocked(),
+                Piece::Bishop => bishop_pseudo_attacks(from).has(to) && unblocked(),
+                Piece::PRook => {
+                    king_attacks(color, from).has(to)
+                        || (rook_pseudo_attacks(from).has(to) && unblocked())
+                }
+                Piece::PBishop => {
+                    king_attacks(color, from).has(to)
+                        || (bishop_pseudo_attacks(from).has(to) && unblocked())
+                }
+                // Gold or promoted small pieces
+                _ => gold_attacks(color, from).has(to),
+            };
+            if !reachable {
+                return false;
+            }
+
             // pinned piece are not allowed to move off the attack ray
             // but are allowed to move along that ray (when not in check)
             if self.pinned.has(from) && !line_ray(self.king(color), from).has(to) {
                 return false;
             }
 
-            // get permitted to-squares depending on checkers
-            let target_squares: BitBoard = match self.checkers.len() {
-                0 => self.target_squares::<false>(),
-                1 => self.target_squares::<true>(),
-                _ => return false, // if there are 2 checkers, King needed to move
+            // when in check, the piece must capture or block the checker
+            // (if there are 2 checkers, the King needs to move)
+            return match self.checkers.len() {
+                0 => true,
+                1 => self.target_squares::<true>().has(to),
+                _ => false,
             };
+        }
+        false
+    }
 
-            // piece needs to move to a target square
-            let attacks: BitBoard;
-            match piece {
-                Piece::Pawn => {
-                    return (target_squares & pawn_attacks(color, from)).has(to);
-                }
-                Piece::Knight => {
-                    return (target_squares & knight_attacks(color, from)).has(to);
-                }
-                Piece::Silver => {
-                    return (target_squares & silver_attacks(color, from)).has(to);
-                }
-                Piece::Lance => {
-                    attacks = lance_pseudo_attacks(color, from);
-                    return (target_squares & attacks).has(to)
-                        && (get_between_rays(from, to) & self.occupied()).is_empty();
-                }
-                Piece::Rook => {
-                    attacks = rook_pseudo_attacks(from);
-                    return (target_squares & attacks).has(to)
-                        && (get_between_rays(from, to) & self.occupied()).is_empty();
-                }
-                Piece::PRook => {
-                    attacks = rook_pseudo_attacks(from) | king_attacks(color, from);
-                    return (target_squares & attacks).has(to)
-                        && (get_between_rays(from, to) & self.occupied()).is_empty();
-                }
-                Piece::Bishop => {
-                    attacks = bishop_pseudo_attacks(from);
-                    return (target_squares & attacks).has(to)
-                        && (get_between_rays(from, to) & self.occupied()).is_empty();
-                }
-                Piece::PBishop => {
-                    attacks = bishop_pseudo_attacks(from) | king_attacks(color, from);
-                    return (target_squares & attacks).has(to)
-                        && (get_between_rays(from, to) & self.occupied()).is_empty();
-                }
-                Piece::King => {
-                    return false; // cannot happen
-                }
-                _ => {
-                    // Gold or promoted small pieces
-                    return (target_squares & gold_attacks(color, from)).has(to);
+    /// Resolve the promotion flag of `mv` and return the canonical legal move, if any.
+    ///
+    /// Real-world game records are not always strict about promotion markers. This
+    /// clears the promotion flag if the moved piece can not promote on this move (for
+    /// instance a `+` on a Gold, a King or an already promoted piece, or a move outside
+    /// the promotion zone) and sets it if the piece must promote (a Pawn, Lance or Knight
+    /// moving to a square from where it could never move again). An optional promotion
+    /// is left as given. Drops are returned unchanged.
+    ///
+    /// Returns `None` if the resulting move is not legal.
+    /// See also [`Move::parse_with_policy`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let mv = Move::parse_with_policy("7g7f", PromotionPolicy::PreferPromotion).unwrap();
+    /// assert_eq!(board.normalize_move(mv), Some("7g7f".parse().unwrap()));
+    /// // Golds can't promote
+    /// assert_eq!(board.normalize_move("6i5h+".parse().unwrap()), Some("6i5h".parse().unwrap()));
+    /// assert_eq!(board.normalize_move("7g7e".parse().unwrap()), None);
+    ///
+    /// // A Pawn moving to the last rank must promote
+    /// let board: Board = "4k4/2P6/9/9/9/9/9/9/4K4 b - 1".parse().unwrap();
+    /// assert_eq!(board.normalize_move("7b7a".parse().unwrap()), Some("7b7a+".parse().unwrap()));
+    /// ```
+    pub fn normalize_move(&self, mv: Move) -> Option<Move> {
+        let mv = match mv {
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => {
+                let piece = self.piece_on(from)?;
+                let promotion = match PromotionStatus::new(self.side_to_move(), piece, from, to) {
+                    PromotionStatus::MustPromote => true,
+                    PromotionStatus::MayPromote => promotion,
+                    _ => false,
+                };
+                Move::BoardMove {
+                    from,
+                    to,
+                    promotion,
                 }
             }
-        }
-        false
+            Move::Drop { .. } => mv,
+        };
+        self.is_legal(mv).then_some(mv)
     }
 
     fn king_is_legal(&self, color: Color, from: Square, to: Square) -> bool {
@@ -598,15 +901,22 @@ impl Board {
         }
     }
 
-    /// Generate all legal board moves and drops given a position in no particular order.
+    /// Generate all legal board moves and drops given a position.
     ///
     /// To retrieve moves, a `listener` callback must be passed that receives [`PieceMoves`].
     /// If you use the listener to update local state, then please remember that it may be
     /// called back several times by this function.
     ///
+    /// All drops are generated before all board moves ([`MoveGenOrder::DropsFirst`]).
+    /// This is guaranteed, but the order within the drops and within the board moves is
+    /// not. Use [`Board::generate_moves_ordered`] to get the board moves first.
+    ///
     /// The listener will be called max 1 time for the King of the side that is to move,
-    /// max 2 times for every other piece on the board, and max 1 time for every piece type
-    /// in hand. So, it will never be called more than 38 x 2 times.
+    /// max 3 times for every other piece on the board (once for each [`PromotionStatus`]
+    /// of its target squares), and max 1 time for every piece type in hand. The bounds on the
+    /// number of calls and moves are given by [`MAX_LISTENER_CALLS`], [`MAX_LEGAL_MOVES`],
+    /// [`MAX_BOARD_MOVES_PER_PIECE`] and [`MAX_DROPS_PER_PIECE`]. They are checked with debug
+    /// assertions.
     ///
     /// If the side_to_move is in check, and has no legal-moves, the listener will not be
     /// called. Normally this means the side_to_move has been checkmated. There is no stalemate
@@ -638,14 +948,312 @@ impl Board {
     /// });
     /// assert_eq!(total_moves, 30);
     /// ```
-    pub fn generate_moves(&self, mut listener: impl FnMut(PieceMoves) -> bool) -> bool {
-        abort_if! {
-            self.generate_drops(&mut listener),
-            self.generate_board_moves(&mut listener)
+    pub fn generate_moves(&self, listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        self.generate_moves_ordered(MoveGenOrder::DropsFirst, listener)
+    }
+
+    /// Generate all legal board moves and drops, with drops and board moves in the given
+    /// order.
+    ///
+    /// This works like [`Board::generate_moves`], which is the same as calling this
+    /// with [`MoveGenOrder::DropsFirst`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+    ///     .parse()
+    ///     .unwrap();
+    /// // After the Bishop exchange, Black has a Bishop in hand
+    /// let board = board.after("8h2b+".parse().unwrap()).unwrap();
+    /// let board = board.after("3a2b".parse().unwrap()).unwrap();
+    /// let mut drops_seen = false;
+    /// board.generate_moves_ordered(MoveGenOrder::BoardFirst, |moves| {
+    ///     let is_drops = matches!(moves, PieceMoves::Drops { .. });
+    ///     // no board moves after the drops
+    ///     assert!(!drops_seen || is_drops);
+    ///     drops_seen |= is_drops;
+    ///     false
+    /// });
+    /// assert!(drops_seen);
+    /// ```
+    pub fn generate_moves_ordered(
+        &self,
+        order: MoveGenOrder,
+        listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
+        #[cfg(any(test, feature = "verify-checks"))]
+        let listener = validated(self, listener);
+        #[cfg(debug_assertions)]
+        let mut listener = bounds_checked(listener);
+        #[cfg(not(debug_assertions))]
+        let mut listener = listener;
+        match order {
+            MoveGenOrder::DropsFirst => {
+                abort_if! {
+                    self.generate_drops(&mut listener),
+                    self.generate_board_moves(&mut listener)
+                }
+            }
+            MoveGenOrder::BoardFirst => {
+                abort_if! {
+                    self.generate_board_moves(&mut listener),
+                    self.generate_drops(&mut listener)
+                }
+            }
+        }
+        false
+    }
+
+    /// Generate all legal board moves and drops, polling `deadline` between piece classes.
+    ///
+    /// This works like [`Board::generate_moves`], but `deadline` is called before the moves
+    /// of every piece type (in hand or on the board) are generated. Once it returns `true`,
+    /// move generation stops. This allows time-based aborts that do not come from the
+    /// listener, for instance in GUIs or under strict time controls.
+    ///
+    /// Returns `true` if move generation was interrupted, either by `deadline` or by the
+    /// listener. Otherwise, returns `false` after all moves have been generated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let mut total_moves = 0;
+    /// let aborted = board.generate_moves_until(|| false, |moves| {
+    ///     total_moves += moves.into_iter().len();
+    ///     false
+    /// });
+    /// assert!(!aborted);
+    /// assert_eq!(total_moves, 30);
+    ///
+    /// // Stop after the Pawn moves
+    /// let mut polls = 0;
+    /// let mut total_moves = 0;
+    /// let aborted = board.generate_moves_until(
+    ///     || {
+    ///         polls += 1;
+    ///         polls > 1
+    ///     },
+    ///     |moves| {
+    ///         total_moves += moves.into_iter().len();
+    ///         false
+    ///     },
+    /// );
+    /// assert!(aborted);
+    /// assert_eq!(total_moves, 9);
+    /// ```
+    pub fn generate_moves_until(
+        &self,
+        mut deadline: impl FnMut() -> bool,
+        mut listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
+        let color = self.side_to_move();
+
+        for piece in [
+            Piece::Pawn,
+            Piece::Lance,
+            Piece::Knight,
+            Piece::Silver,
+            Piece::Gold,
+            Piece::Rook,
+            Piece::Bishop,
+        ] {
+            if self.has_in_hand(color, piece) {
+                abort_if!(deadline(), self.generate_drops_for(piece, &mut listener));
+            }
+        }
+
+        for piece in [
+            Piece::Pawn,
+            Piece::Lance,
+            Piece::Knight,
+            Piece::Silver,
+            Piece::Gold,
+            Piece::Tokin,
+            Piece::PLance,
+            Piece::PKnight,
+            Piece::PSilver,
+            Piece::Bishop,
+            Piece::Rook,
+            Piece::PBishop,
+            Piece::PRook,
+            Piece::King,
+        ] {
+            let mask = self.colored_pieces(color, piece);
+            if !mask.is_empty() {
+                abort_if!(
+                    deadline(),
+                    self.generate_board_moves_for(mask, &mut listener)
+                );
+            }
+        }
+        false
+    }
+
+    /// Generate all legal moves, visiting moves to earlier `tiers` first.
+    ///
+    /// This works like [`Board::generate_moves`], but the listener first receives the
+    /// moves whose destination is in `tiers[0]`, then the remaining moves with a
+    /// destination in `tiers[1]` and so on. Moves with a destination outside all tiers
+    /// are visited last, so no moves are lost. Within one tier, the moves are given
+    /// in no particular order. For instance, the tiers could be the squares around the
+    /// opponent's King, followed by the promotion zone.
+    ///
+    /// The moves are generated once and then split per tier, so this gives a cheap
+    /// plausible ordering without scoring individual moves. The listener may be called
+    /// for every piece once per tier (plus once for the remaining moves).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let center = Rank::E.bitboard() | Rank::F.bitboard();
+    /// let mut moves = Vec::new();
+    /// board.generate_moves_tiered(&[center], |piece_moves| {
+    ///     moves.extend(piece_moves);
+    ///     false
+    /// });
+    /// assert_eq!(moves.len(), 30);
+    /// // the 9 Pawn pushes come first
+    /// assert!(moves[..9].iter().all(|mv| center.has(mv.to())));
+    /// assert!(moves[9..].iter().all(|mv| !center.has(mv.to())));
+    /// ```
+    pub fn generate_moves_tiered(
+        &self,
+        tiers: &[BitBoard],
+        mut listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
+        let mut buffer = [None; MAX_LISTENER_CALLS];
+        let mut len = 0;
+        self.generate_moves(|moves| {
+            buffer[len] = Some(moves);
+            len += 1;
+            false
+        });
+
+        let mut visited = BitBoard::EMPTY;
+        for &tier in tiers.iter().chain(&[BitBoard::FULL]) {
+            let mask = tier & !visited;
+            visited |= tier;
+            if mask.is_empty() {
+                continue;
+            }
+            for moves in buffer[..len].iter().flatten() {
+                let moves = match *moves {
+                    PieceMoves::Drops { color, piece, to } => PieceMoves::Drops {
+                        color,
+                        piece,
+                        to: to & mask,
+                    },
+                    PieceMoves::BoardMoves {
+                        color,
+                        piece,
+                        from,
+                        to,
+                        prom_status,
+                    } => PieceMoves::BoardMoves {
+                        color,
+                        piece,
+                        from,
+                        to: to & mask,
+                        prom_status,
+                    },
+                };
+                abort_if!(!moves.is_empty() && listener(moves));
+            }
         }
         false
     }
 
+    /// Generate the legal moves, with only the non-promotions of `non_promotions`.
+    ///
+    /// This works like [`Board::generate_moves`], which generates all legal moves. With
+    /// [`NonPromotions::Useful`], the promotion status of the moves that should always
+    /// promote is [`PromotionStatus::MustPromote`], so that only their promotions are
+    /// generated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka::*;
+    /// // The Black Bishop can take the Bishop on 2b, and the Lance can go to 1b
+    /// let board: Board = "lnsgkgsn1/1r5bl/pppppp1p1/6p2/9/2P5L/PP1PPPPP1/1B5R1/LNSGKGSN1 b - 9"
+    ///     .parse()
+    ///     .unwrap();
+    /// let count = |non_promotions| {
+    ///     let mut moves = Vec::new();
+    ///     board.generate_moves_with_non_promotions(non_promotions, |piece_moves| {
+    ///         moves.extend(piece_moves);
+    ///         false
+    ///     });
+    ///     moves
+    /// };
+    /// let all = count(NonPromotions::All);
+    /// let useful = count(NonPromotions::Useful);
+    /// assert!(all.contains(&"8h2b".parse().unwrap()));
+    /// assert!(!useful.contains(&"8h2b".parse().unwrap()));
+    /// assert!(useful.contains(&"8h2b+".parse().unwrap()));
+    /// assert!(all.contains(&"1f1b".parse().unwrap()));
+    /// assert!(!useful.contains(&"1f1b".parse().unwrap()));
+    /// // The non-promotion to the third rank can be useful
+    /// assert!(useful.contains(&"1f1c".parse().unwrap()));
+    /// ```
+    pub fn generate_moves_with_non_promotions(
+        &self,
+        non_promotions: NonPromotions,
+        mut listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
+        if non_promotions == NonPromotions::All {
+            return self.generate_moves(listener);
+        }
+        self.generate_moves(|moves| match moves {
+            PieceMoves::BoardMoves {
+                color,
+                piece,
+                from,
+                to,
+                prom_status: PromotionStatus::MayPromote,
+            } => match piece {
+                Piece::Pawn | Piece::Bishop | Piece::Rook => listener(PieceMoves::BoardMoves {
+                    color,
+                    piece,
+                    from,
+                    to,
+                    prom_status: PromotionStatus::MustPromote,
+                }),
+                Piece::Lance => {
+                    // the last two ranks, where Lances must promote on the last one
+                    let second_rank = to & no_fly_zone(color, Piece::Knight);
+                    abort_if!(
+                        !second_rank.is_empty()
+                            && listener(PieceMoves::BoardMoves {
+                                color,
+                                piece,
+                                from,
+                                to: second_rank,
+                                prom_status: PromotionStatus::MustPromote,
+                            }),
+                        !(to & !second_rank).is_empty()
+                            && listener(PieceMoves::BoardMoves {
+                                color,
+                                piece,
+                                from,
+                                to: to & !second_rank,
+                                prom_status: PromotionStatus::MayPromote,
+                            })
+                    );
+                    false
+                }
+                _ => listener(moves),
+            },
+            _ => listener(moves),
+        })
+    }
+
     /// Generate all legal board moves.
     pub fn generate_board_moves(&self, listener: impl FnMut(PieceMoves) -> bool) -> bool {
         debug_assert!(self.inner.hash() != 0);
@@ -677,16 +1285,28 @@ impl Board {
         &self,
         mask: BitBoard,
         mut listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
+        self.add_board_legals(mask, BitBoard::FULL, &mut listener)
+    }
+
+    // Generate the legal board moves of the pieces on `mask` to the squares of `targets`.
+    fn add_board_legals<F: FnMut(PieceMoves) -> bool>(
+        &self,
+        mask: BitBoard,
+        targets: BitBoard,
+        listener: &mut F,
     ) -> bool {
         match self.checkers.len() {
-            0 => self.add_all_legals::<_, false>(mask, &mut listener),
-            1 => self.add_all_legals::<_, true>(mask, &mut listener),
-            _ => self.add_king_legals::<_, true>(mask, &mut listener),
+            0 => self.add_all_legals::<_, false>(mask, targets, listener),
+            1 => self.add_all_legals::<_, true>(mask, targets, listener),
+            _ => self.add_king_legals::<_, true>(mask, targets, listener),
         }
     }
 
     /// Generate all drops in no particular order.
     ///
+    /// See [`Board::generate_drops_in_order`] to control the order of the pieces.
+    ///
     /// # Examples
     ///
     /// ```
@@ -713,20 +1333,92 @@ impl Board {
     /// });
     /// assert_eq!(num_drops, empty_squares.len());
     /// ```
-    pub fn generate_drops(&self, mut listener: impl FnMut(PieceMoves) -> bool) -> bool {
+    pub fn generate_drops(&self, listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        self.generate_drops_in_order(&DEFAULT_DROP_ORDER, listener)
+    }
+
+    /// Generate the drops of the pieces in `order`, one piece type at a time in that order.
+    ///
+    /// Pieces that are not in `order` are not generated, so this can also be used for
+    /// staged drop generation. Pieces that can't be dropped (the King and promoted pieces)
+    /// are ignored. The order should not contain duplicates, otherwise the same drops
+    /// are generated more than once. [`Piece::DROP_ORDER`] is a heuristic order for search.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b RGSP 1".parse().unwrap();
+    /// let mut pieces = Vec::new();
+    /// board.generate_drops_in_order(&Piece::DROP_ORDER, |moves| {
+    ///     if let PieceMoves::Drops { piece, .. } = moves {
+    ///         pieces.push(piece);
+    ///     }
+    ///     false
+    /// });
+    /// assert_eq!(pieces, [Piece::Gold, Piece::Silver, Piece::Rook, Piece::Pawn]);
+    ///
+    /// // Only the Pawn drops: all empty squares but those on the first rank
+    /// let mut num_drops = 0;
+    /// board.generate_drops_in_order(&[Piece::Pawn], |moves| {
+    ///     num_drops += moves.len();
+    ///     false
+    /// });
+    /// assert_eq!(num_drops, 79 - 8);
+    /// ```
+    pub fn generate_drops_in_order(
+        &self,
+        order: &[Piece],
+        mut listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
         match self.checkers.len() {
             0 => {
                 let targets = !self.occupied();
-                self.add_all_drops::<_, false>(&mut listener, targets)
+                self.add_all_drops(&mut listener, targets, order)
             }
             1 => {
                 let targets = self.target_drops::<true>();
-                self.add_all_drops::<_, true>(&mut listener, targets)
+                self.add_all_drops(&mut listener, targets, order)
             }
             _ => false,
         }
     }
 
+    /// Get the squares on which each piece in hand could be dropped, indexed by piece.
+    ///
+    /// When the side to move is in check by a slider, these are the interposition squares
+    /// between the checker and the King, limited to the ranks where each piece may be
+    /// dropped. When the side to move is not in check, all empty squares are used instead.
+    /// When in check by a non-slider or in double check, all masks are empty. For the Pawn,
+    /// files that already have a Pawn of the side to move are excluded as well.
+    ///
+    /// The masks are computed regardless of the pieces in hand, and the Pawn mask may
+    /// include a drop that gives an illegal mate. This
+    /// is meant for engines that implement their own staged drop generation, so that the
+    /// targets are computed only once per position.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// // The Rook on 5a checks the King on 5i
+    /// let board: Board = "k3r4/9/9/9/9/9/9/9/4K4 b GLPr 1".parse().unwrap();
+    /// let masks = board.drop_interpose_masks();
+    /// let between = get_between_rays(Square::A5, Square::I5);
+    /// assert_eq!(masks[Piece::Gold as usize], between);
+    /// assert_eq!(masks[Piece::Lance as usize], between & !Rank::A.bitboard());
+    /// assert_eq!(masks[Piece::Knight as usize], between & !(Rank::A.bitboard() | Rank::B.bitboard()));
+    ///
+    /// let masks = Board::startpos().drop_interpose_masks();
+    /// assert_eq!(masks[Piece::Pawn as usize], BitBoard::EMPTY);
+    /// assert_eq!(masks[Piece::Rook as usize], !Board::startpos().occupied());
+    /// ```
+    pub fn drop_interpose_masks(&self) -> [BitBoard; Piece::HAND_NUM] {
+        match self.checkers.len() {
+            0 => self.drop_masks(!self.occupied()),
+            1 => self.drop_masks(self.target_drops::<true>()),
+            _ => [BitBoard::EMPTY; Piece::HAND_NUM],
+        }
+    }
+
     /// Generate all drops for a particular piece.
     pub fn generate_drops_for(
         &self,
@@ -737,25 +1429,28 @@ impl Board {
         if num_checkers == 0 {
             let dst = !self.occupied();
             match piece {
-                Piece::Pawn => self.add_drops::<commoner::Pawn, _, false>(&mut listener, dst),
-                Piece::Lance => self.add_drops::<commoner::Lance, _, false>(&mut listener, dst),
-                Piece::Knight => self.add_drops::<commoner::Knight, _, false>(&mut listener, dst),
-                Piece::Silver => self.add_drops::<commoner::Silver, _, false>(&mut listener, dst),
-                Piece::Gold => self.add_drops::<commoner::Gold, _, false>(&mut listener, dst),
-                Piece::Rook => self.add_drops::<commoner::Rook, _, false>(&mut listener, dst),
-                Piece::Bishop => self.add_drops::<commoner::Bishop, _, false>(&mut listener, dst),
+                Piece::Pawn => self.add_drops::<commoner::Pawn, _>(&mut listener, dst),
+                Piece::Lance => self.add_drops::<commoner::Lance, _>(&mut listener, dst),
+                Piece::Knight => self.add_drops::<commoner::Knight, _>(&mut listener, dst),
+                Piece::Silver => self.add_drops::<commoner::Silver, _>(&mut listener, dst),
+                Piece::Gold => self.add_drops::<commoner::Gold, _>(&mut listener, dst),
+                Piece::Rook => self.add_drops::<commoner::Rook, _>(&mut listener, dst),
+                Piece::Bishop => self.add_drops::<commoner::Bishop, _>(&mut listener, dst),
                 _ => false, // Other pieces cannot be dropped
             }
         } else if num_checkers == 1 {
             let dst = self.target_drops::<true>();
+            if dst.is_empty() {
+                return false;
+            }
             match piece {
-                Piece::Pawn => self.add_drops::<commoner::Pawn, _, true>(&mut listener, dst),
-                Piece::Lance => self.add_drops::<commoner::Lance, _, true>(&mut listener, dst),
-                Piece::Knight => self.add_drops::<commoner::Knight, _, true>(&mut listener, dst),
-                Piece::Silver => self.add_drops::<commoner::Silver, _, true>(&mut listener, dst),
-                Piece::Gold => self.add_drops::<commoner::Gold, _, true>(&mut listener, dst),
-                Piece::Rook => self.add_drops::<commoner::Rook, _, true>(&mut listener, dst),
-                Piece::Bishop => self.add_drops::<commoner::Bishop, _, true>(&mut listener, dst),
+                Piece::Pawn => self.add_drops::<commoner::Pawn, _>(&mut listener, dst),
+                Piece::Lance => self.add_drops::<commoner::Lance, _>(&mut listener, dst),
+                Piece::Knight => self.add_drops::<commoner::Knight, _>(&mut listener, dst),
+                Piece::Silver => self.add_drops::<commoner::Silver, _>(&mut listener, dst),
+                Piece::Gold => self.add_drops::<commoner::Gold, _>(&mut listener, dst),
+                Piece::Rook => self.add_drops::<commoner::Rook, _>(&mut listener, dst),
+                Piece::Bishop => self.add_drops::<commoner::Bishop, _>(&mut listener, dst),
                 _ => false, // Other pieces cannot be dropped
             }
         } else {
@@ -764,27 +1459,118 @@ impl Board {
         }
     }
 
+    /// Would `mv` give check to the opponent?
+    ///
+    /// This is determined without playing the move. Both direct checks by the moved or
+    /// dropped piece and discovered checks by a slider behind the moving piece are
+    /// detected. The move is assumed to be legal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/9/9/4S4/9/9/9/4L3K b G 1".parse().unwrap();
+    /// assert!(board.gives_check("G*5b".parse().unwrap()));
+    /// assert!(!board.gives_check("G*5c".parse().unwrap()));
+    /// // the Silver uncovers the Lance
+    /// assert!(board.gives_check("5e4d".parse().unwrap()));
+    /// assert!(!board.gives_check("5e5d".parse().unwrap()));
+    /// ```
+    pub fn gives_check(&self, mv: Move) -> bool {
+        let color = self.side_to_move();
+        let their_color = !color;
+        if !self.has_king(their_color) {
+            return false;
+        }
+        let their_king = self.king(their_color);
+        let ours = self.colors(color);
+        let to = mv.to();
+
+        let (piece, occ) = match mv {
+            Move::Drop { piece, to } => (piece, self.occupied() | to.bitboard()),
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => {
+                let piece = self.piece_on(from).unwrap();
+                let occ = (self.occupied() ^ from.bitboard()) | to.bitboard();
+
+                // discovered checks: only possible if `from` is on a line with their King
+                if !line_ray(from, their_king).is_empty() {
+                    let sliders = ours & !from.bitboard();
+                    let rooks = (self.pieces(Piece::Rook) | self.pieces(Piece::PRook)) & sliders;
+                    let bishops =
+                        (self.pieces(Piece::Bishop) | self.pieces(Piece::PBishop)) & sliders;
+                    let lances = self.pieces(Piece::Lance) & sliders;
+                    let discovered = (lazy_rook_moves(their_color, their_king, occ) & rooks)
+                        | (lazy_bishop_moves(their_color, their_king, occ) & bishops)
+                        | (get_lance_moves(their_color, their_king, occ) & lances);
+                    if !discovered.is_empty() {
+                        return true;
+                    }
+                }
+                (if promotion { piece.promote() } else { piece }, occ)
+            }
+        };
+
+        // direct checks: does the piece on `to` attack their King?
+        let attacks = match piece {
+            Piece::Pawn => pawn_attacks(their_color, their_king),
+            Piece::Knight => knight_attacks(their_color, their_king),
+            Piece::Silver => silver_attacks(their_color, their_king),
+            Piece::Gold | Piece::Tokin | Piece::PLance | Piece::PKnight | Piece::PSilver => {
+                gold_attacks(their_color, their_king)
+            }
+            Piece::Lance => get_lance_moves(their_color, their_king, occ),
+            Piece::Rook => lazy_rook_moves(their_color, their_king, occ),
+            Piece::Bishop => lazy_bishop_moves(their_color, their_king, occ),
+            Piece::PRook => {
+                lazy_rook_moves(their_color, their_king, occ) | king_attacks(color, their_king)
+            }
+            Piece::PBishop => {
+                lazy_bishop_moves(their_color, their_king, occ) | king_attacks(color, their_king)
+            }
+            Piece::King => BitBoard::EMPTY,
+        };
+        attacks.has(to)
+    }
+
     /// Generate checks for side-to-move.
     ///
     /// This function will call the `listener` callback multiple times. The listener can interrupt
     /// further processing by returning true. Otherwise, the function will generate all remaining
     /// checks and eventually return false.
-    pub fn generate_checks(&self, mut listener: impl FnMut(PieceMoves) -> bool) -> bool {
+    ///
+    /// With the internal `verify-checks` feature, every generated move is checked with
+    /// [`Board::is_legal`] and by playing it, and the function panics with the SFEN of the
+    /// position and the move if the move is illegal or does not give check.
+    pub fn generate_checks(&self, listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        #[cfg(feature = "verify-checks")]
+        let mut listener = verified_checks(self, listener);
+        #[cfg(not(feature = "verify-checks"))]
+        let mut listener = listener;
+
         let color = self.side_to_move();
         let their_color = !color;
-        if !self.has(their_color, Piece::King) {
+        if !self.has_king(their_color) {
             return false;
         }
 
         let occ = self.occupied();
         let ours = self.colors(color);
-        let empty = !occ;
+        // drops must go to an empty square, and if we are in check, they must interpose
+        let drop_targets = match self.checkers.len() {
+            0 => self.target_drops::<false>(),
+            1 => self.target_drops::<true>(),
+            _ => BitBoard::EMPTY,
+        };
 
         let their_king = self.king(their_color);
         let their_ring = king_attacks(color, their_king);
 
-        let rook_attacks = get_rook_moves(their_color, their_king, occ);
-        let bishop_attacks = get_bishop_moves(their_color, their_king, occ);
+        let rook_attacks = lazy_rook_moves(their_color, their_king, occ);
+        let bishop_attacks = lazy_bishop_moves(their_color, their_king, occ);
 
         //
         // get all squares from which their King could be put in check
@@ -814,19 +1600,14 @@ impl Board {
         for index in 0..Piece::HAND_NUM {
             if hand[index] > 0 {
                 let piece = Piece::index_const(index);
-                let mut to = attacks[index] & empty;
+                let mut to = attacks[index] & drop_targets;
 
                 if piece == Piece::Pawn {
                     // avoid nifu
                     to &= self.pawnless_files[color as usize];
 
                     // avoid illegal mate by pawn drop
-                    if !to.is_empty() {
-                        let to_square = to.next_square().unwrap();
-                        if self.is_illegal_mate_by_pawn_drop(to_square) {
-                            to = to.rm(to_square);
-                        }
-                    }
+                    to = self.without_pawn_drop_mate(to);
                 }
 
                 if !to.is_empty() && listener(PieceMoves::Drops { color, piece, to }) {
@@ -841,15 +1622,20 @@ impl Board {
         let rooks = self.pieces(Piece::Rook) | self.pieces(Piece::PRook);
         let bishops = self.pieces(Piece::Bishop) | self.pieces(Piece::PBishop);
         let lances = self.pieces(Piece::Lance);
-        let our_sliders = ours & (rooks | bishops | lances);
+
+        // only sliders that attack along the line to their King can give discovered check
+        let our_sliders = ours
+            & ((rook_pseudo_attacks(their_king) & rooks)
+                | (bishop_pseudo_attacks(their_king) & bishops)
+                | (lance_pseudo_attacks(their_color, their_king) & lances));
 
         let mut off_the_x_ray: [Option<BitBoard>; 81] = [None; 81];
 
         for slider in our_sliders {
             let between = get_between_rays(slider, their_king);
-            let pinners = between & ours;
-            if pinners.len() == 1 {
-                let from = pinners.next_square().unwrap();
+            let blockers = between & occ;
+            if blockers.len() == 1 && !(blockers & ours).is_empty() {
+                let from = blockers.next_square().unwrap();
                 off_the_x_ray[from as usize] = Some(!between);
             }
         }
@@ -911,6 +1697,131 @@ impl Board {
         false
     }
 
+    /// Generate all legal captures of the side to move.
+    ///
+    /// This only reports the board moves that land on a piece of the opponent. Drops are
+    /// never captures. As with [`Board::generate_moves`], the listener can stop move
+    /// generation by returning `true`.
+    ///
+    /// The move generators are only given the squares of the opponent's pieces as
+    /// targets, so the other moves are never generated (and King moves to other squares
+    /// are never checked for safety). This is cheaper than generating all moves and
+    /// testing every move for a capture, which makes it suitable for quiescence search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka::*;
+    /// // After 7g7f 3c3d the Bishops face each other
+    /// let sfen = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3";
+    /// let board = Board::from_sfen(sfen).unwrap();
+    /// let mut captures = Vec::new();
+    /// board.generate_captures(|moves| {
+    ///     captures.extend(moves);
+    ///     false
+    /// });
+    /// captures.sort_by_key(|mv| mv.is_promotion());
+    /// assert_eq!(captures, ["8h2b".parse().unwrap(), "8h2b+".parse().unwrap()]);
+    /// ```
+    pub fn generate_captures(&self, listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        self.add_captures::<false>(listener)
+    }
+
+    /// Generate all legal captures and promotions of the side to move.
+    ///
+    /// This works like [`Board::generate_captures`], but also reports the promotions
+    /// that don't capture anything. For such moves, only the promotion is reported and
+    /// not the move without promotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::from_sfen("4k4/9/9/9/9/9/9/9/4K3R b - 1").unwrap();
+    /// let mut moves = Vec::new();
+    /// board.generate_captures_and_promotions(|piece_moves| {
+    ///     moves.extend(piece_moves);
+    ///     false
+    /// });
+    /// assert_eq!(moves.len(), 3); // 1a, 1b and 1c
+    /// assert!(moves.iter().all(|mv| mv.is_promotion()));
+    /// ```
+    pub fn generate_captures_and_promotions(
+        &self,
+        listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
+        self.add_captures::<true>(listener)
+    }
+
+    fn add_captures<const PROMOTIONS: bool>(
+        &self,
+        mut listener: impl FnMut(PieceMoves) -> bool,
+    ) -> bool {
+        let color = self.side_to_move();
+        let theirs = self.colors(!color);
+        let mut filter = |moves| {
+            let PieceMoves::BoardMoves {
+                color,
+                piece,
+                from,
+                to,
+                prom_status,
+            } = moves
+            else {
+                return false;
+            };
+            let captures = to & theirs;
+            if !captures.is_empty()
+                && listener(PieceMoves::BoardMoves {
+                    color,
+                    piece,
+                    from,
+                    to: captures,
+                    prom_status,
+                })
+            {
+                return true;
+            }
+            if PROMOTIONS
+                && matches!(
+                    prom_status,
+                    PromotionStatus::MayPromote | PromotionStatus::MustPromote
+                )
+            {
+                // board moves always have a decided promotion status, so that
+                // MustPromote only reports the promotions of the quiet moves
+                let quiet = to & !theirs;
+                if !quiet.is_empty() {
+                    return listener(PieceMoves::BoardMoves {
+                        color,
+                        piece,
+                        from,
+                        to: quiet,
+                        prom_status: PromotionStatus::MustPromote,
+                    });
+                }
+            }
+            false
+        };
+
+        // The generators only look at the target squares. A piece can promote when it
+        // moves into, out of or within the promotion zone, so if a promotable piece is
+        // in the zone all moves are needed, and otherwise only the captures and the
+        // moves into the zone.
+        let targets = if !PROMOTIONS {
+            theirs
+        } else {
+            let zone = prom_zone(color);
+            let promotable = self.occupied() ^ self.pseudo_golds() ^ self.pieces(Piece::King);
+            if (self.colors(color) & zone & promotable).is_empty() {
+                theirs | zone
+            } else {
+                BitBoard::FULL
+            }
+        };
+        self.add_board_legals(BitBoard::FULL, targets, &mut filter)
+    }
+
     // Helper function to handle all PromotionStatus variants
     fn filter_checks_by_promotion_status(
         color: Color,
@@ -937,7 +1848,8 @@ impl Board {
                 }
             }
             PromotionStatus::Undecided | PromotionStatus::MayPromote => {
-                let checks = to & attacks[piece as usize];
+                // Never emit a non-promotion onto a square where the piece must promote
+                let checks = to & attacks[piece as usize] & !must_prom_zone(color, piece);
                 if !checks.is_empty()
                     && listener(PieceMoves::BoardMoves {
                         color,
@@ -0,0 +1,278 @@
+use core::ops::{Deref, DerefMut};
+
+use crate::*;
+
+/// A fixed-capacity list of moves, stored inline.
+///
+/// A `MoveList` can hold [`MAX_LEGAL_MOVES`] moves, which is enough for all legal
+/// moves of any position. It does not allocate, so it can be kept on the stack in a
+/// search. It dereferences to a slice of moves, so it can be sorted, searched and
+/// iterated like one.
+///
+/// Use [`Board::legal_moves`] or [`Board::legal_drops`] to collect the moves of a
+/// position, or fill a list from a listener of [`Board::generate_moves`] with
+/// [`Extend`].
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// let mut moves = board.legal_moves();
+/// assert_eq!(moves.len(), 30);
+///
+/// // Score the moves and sort them, best first
+/// moves.sort_by_key(|mv| if mv.is_promotion() { 0 } else { 1 });
+/// assert!(moves.contains(&"7g7f".parse().unwrap()));
+///
+/// let mut checks = MoveList::new();
+/// board.generate_checks(|mvs| {
+///     checks.extend(mvs);
+///     false
+/// });
+/// assert!(checks.is_empty());
+/// ```
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_LEGAL_MOVES],
+    len: usize,
+}
+
+// Fills the unused part of the buffer.
+const NO_MOVE: Move = Move::Drop {
+    piece: Piece::Pawn,
+    to: Square::A1,
+};
+
+impl MoveList {
+    /// The number of moves the list can hold.
+    pub const CAPACITY: usize = MAX_LEGAL_MOVES;
+
+    /// Create an empty list.
+    pub const fn new() -> Self {
+        Self {
+            moves: [NO_MOVE; MAX_LEGAL_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Add a move to the end of the list.
+    ///
+    /// # Panics
+    /// This panics if the list is full. This can't happen when the list only holds
+    /// the legal moves of one position.
+    #[inline(always)]
+    pub fn push(&mut self, mv: Move) {
+        assert!(self.len < Self::CAPACITY, "MoveList is full");
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    /// Remove the last move and return it, or None if the list is empty.
+    pub fn pop(&mut self) -> Option<Move> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.moves[self.len])
+    }
+
+    /// Remove all moves.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Get the moves as a slice.
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+
+    /// Get the moves as a mutable slice.
+    #[inline(always)]
+    pub fn as_mut_slice(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        self.as_mut_slice()
+    }
+}
+
+impl core::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for MoveList {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for MoveList {}
+
+impl Extend<Move> for MoveList {
+    fn extend<T: IntoIterator<Item = Move>>(&mut self, iter: T) {
+        for mv in iter {
+            self.push(mv);
+        }
+    }
+}
+
+impl FromIterator<Move> for MoveList {
+    fn from_iter<T: IntoIterator<Item = Move>>(iter: T) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = core::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The order of the moves collected by [`Board::generate_moves_into`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum MoveOrdering {
+    /// The order of [`Board::generate_moves`].
+    #[default]
+    None,
+    /// Captures first, then the other moves.
+    CapturesFirst,
+    /// Promotions first, then the other moves.
+    PromotionsFirst,
+}
+
+impl Board {
+    /// Append all legal moves to `moves`, in the given order.
+    ///
+    /// The moves are appended, so the caller can reuse one buffer for a whole search by
+    /// clearing it (or truncating it) between positions. The ordering only applies to the
+    /// appended moves. Within each group, the moves keep the order of [`Board::generate_moves`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+    ///     .parse()
+    ///     .unwrap();
+    /// let mut moves = Vec::new();
+    /// board.generate_moves_into(&mut moves, MoveOrdering::CapturesFirst);
+    /// // the Bishop exchange, with and without promotion, is the only capture
+    /// assert!(moves[..2].iter().all(|mv| mv.to() == Square::B2));
+    /// assert!(!board.occupied().has(moves[2].to()));
+    ///
+    /// moves.clear();
+    /// board.generate_moves_into(&mut moves, MoveOrdering::PromotionsFirst);
+    /// assert_eq!(moves[0], "8h2b+".parse().unwrap());
+    /// assert_eq!(moves.len(), board.legal_moves().len());
+    /// ```
+    pub fn generate_moves_into(&self, moves: &mut Vec<Move>, ordering: MoveOrdering) {
+        let start = moves.len();
+        self.generate_moves(|mvs| {
+            moves.extend(mvs);
+            false
+        });
+        let new_moves = &mut moves[start..];
+        match ordering {
+            MoveOrdering::None => {}
+            MoveOrdering::CapturesFirst => {
+                let occupied = self.occupied();
+                new_moves.sort_by_key(|mv| mv.is_drop() || !occupied.has(mv.to()));
+            }
+            MoveOrdering::PromotionsFirst => new_moves.sort_by_key(|mv| !mv.is_promotion()),
+        }
+    }
+
+    /// Collect all legal moves into a [`MoveList`].
+    ///
+    /// The moves are in the order of [`Board::generate_moves`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+    ///     .parse()
+    ///     .unwrap();
+    /// let moves = board.legal_moves();
+    /// assert!(moves.iter().all(|&mv| board.is_legal(mv)));
+    /// assert_eq!(moves.len(), board.legal_drops().len() + moves.iter().filter(|mv| !mv.is_drop()).count());
+    /// ```
+    pub fn legal_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        self.generate_moves(|mvs| {
+            moves.extend(mvs);
+            false
+        });
+        moves
+    }
+
+    /// Apply `f` to every legal move and the position after it, in parallel.
+    ///
+    /// The moves are split across the threads of the rayon thread pool. The results are
+    /// in the order of [`Board::generate_moves`]. This is meant for work at the root of
+    /// a tree, such as deep perft runs or analysing every move of a position.
+    ///
+    /// This requires the `rayon` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let replies = board.par_map_moves(|_, child| child.legal_moves().len());
+    /// assert_eq!(replies.len(), 30);
+    /// assert_eq!(replies.iter().sum::<usize>(), 900);
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_map_moves<T, F>(&self, f: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(Move, &Board) -> T + Sync + Send,
+    {
+        use rayon::prelude::*;
+
+        self.legal_moves()
+            .par_iter()
+            .map(|&mv| f(mv, &self.after_unchecked(mv)))
+            .collect()
+    }
+
+    /// Collect all legal drops into a [`MoveList`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert!(Board::startpos().legal_drops().is_empty());
+    ///
+    /// let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+    /// assert_eq!(board.legal_drops().len(), 79);
+    /// ```
+    pub fn legal_drops(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        self.generate_drops(|mvs| {
+            moves.extend(mvs);
+            false
+        });
+        moves
+    }
+}
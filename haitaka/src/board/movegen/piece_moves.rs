@@ -3,17 +3,63 @@ use std::ops::BitAnd;
 use crate::*;
 
 /// Simple structure to represent the promotability of a piece.
+///
+/// The move generator splits the target squares of a piece so that the status of a
+/// [`PieceMoves::BoardMoves`] instance holds for all its target squares. The `Undecided`
+/// status means the promotability is determined separately for every target square.
+///
+/// The status of a single move is given by [`PromotionStatus::for_move`]. This follows
+/// the promotion rules: a piece may promote if it moves into, out of, or within the
+/// promotion zone, and must promote if it could not move any further otherwise.
+///
+/// These are all the cases of the rules, so this enum is not `#[non_exhaustive]` and
+/// can be matched without a wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PromotionStatus {
+    /// The status is determined separately for every target square.
     Undecided,
+    /// The piece may promote (both the promotion and non-promotion are legal).
     MayPromote,
+    /// The piece cannot promote.
     CannotPromote,
+    /// The piece must promote.
     MustPromote,
 }
 
+helpers::simple_error! {
+    /// The piece cannot stand on the from-square or the move does not change squares.
+    pub struct PromotionStatusError = "The piece cannot make this move.";
+}
+
 impl PromotionStatus {
     /// Check promotability of the given piece, moving from `from` to `to`.
+    ///
+    /// This is the same as [`PromotionStatus::for_move`].
     pub const fn new(color: Color, piece: Piece, from: Square, to: Square) -> Self {
+        Self::for_move(color, piece, from, to)
+    }
+
+    /// Get the promotion status of the given piece, moving from `from` to `to`.
+    ///
+    /// The result is never `Undecided`. Pieces that cannot promote (Golds, Kings and
+    /// promoted pieces) always get `CannotPromote`. This only looks at the squares,
+    /// so it does not check that the piece can actually reach `to`. Use the
+    /// [`TryFrom`] implementation to reject pieces that cannot stand on `from`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let status = PromotionStatus::for_move(Color::Black, Piece::Pawn, Square::D3, Square::C3);
+    /// assert_eq!(status, PromotionStatus::MayPromote);
+    /// let status = PromotionStatus::for_move(Color::Black, Piece::Pawn, Square::B3, Square::A3);
+    /// assert_eq!(status, PromotionStatus::MustPromote);
+    /// // Moving out of the promotion zone
+    /// let status = PromotionStatus::for_move(Color::Black, Piece::Silver, Square::C3, Square::D3);
+    /// assert_eq!(status, PromotionStatus::MayPromote);
+    /// let status = PromotionStatus::for_move(Color::Black, Piece::Gold, Square::D3, Square::C3);
+    /// assert_eq!(status, PromotionStatus::CannotPromote);
+    /// ```
+    pub const fn for_move(color: Color, piece: Piece, from: Square, to: Square) -> Self {
         if piece.must_promote(color, to) {
             Self::MustPromote
         } else if piece.can_promote(color, to) || piece.can_promote(color, from) {
@@ -22,14 +68,74 @@ impl PromotionStatus {
             Self::CannotPromote
         }
     }
+
+    /// Is a move with the given promotion flag allowed under this status?
+    ///
+    /// `Undecided` allows both.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert!(PromotionStatus::MayPromote.allows(true));
+    /// assert!(PromotionStatus::MayPromote.allows(false));
+    /// assert!(!PromotionStatus::MustPromote.allows(false));
+    /// assert!(!PromotionStatus::CannotPromote.allows(true));
+    /// ```
+    pub const fn allows(self, promotion: bool) -> bool {
+        match self {
+            Self::Undecided | Self::MayPromote => true,
+            Self::CannotPromote => !promotion,
+            Self::MustPromote => promotion,
+        }
+    }
+}
+
+impl TryFrom<(Color, Piece, Square, Square)> for PromotionStatus {
+    type Error = PromotionStatusError;
+
+    /// Get the promotion status of a move, given as `(color, piece, from, to)`.
+    ///
+    /// Unlike [`PromotionStatus::for_move`] this fails if `from` and `to` are the same
+    /// square, or if the piece could never stand on `from` (such as a Black Pawn on
+    /// the first rank).
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let status = PromotionStatus::try_from((Color::White, Piece::Knight, Square::F2, Square::H3));
+    /// assert_eq!(status.unwrap(), PromotionStatus::MustPromote);
+    /// assert!(PromotionStatus::try_from((Color::White, Piece::Knight, Square::H2, Square::F3)).is_err());
+    /// assert!(PromotionStatus::try_from((Color::White, Piece::Gold, Square::H2, Square::H2)).is_err());
+    /// ```
+    fn try_from(
+        (color, piece, from, to): (Color, Piece, Square, Square),
+    ) -> Result<Self, Self::Error> {
+        if from == to || piece.must_promote(color, from) {
+            return Err(PromotionStatusError);
+        }
+        Ok(Self::for_move(color, piece, from, to))
+    }
 }
 
 impl BitAnd for PromotionStatus {
     type Output = Self;
 
-    // Implicit assumptions is that self and rhs are at compatible
-    // so we don't try to add CannotPromote & MustPromote.
-    // I'm not checking for this, but simply giving priority to `self`!
+    /// Combine two statuses for the same move.
+    ///
+    /// `Undecided` is neutral: `Undecided & x` and `x & Undecided` are `x`. Otherwise
+    /// the statuses are expected to agree, which is the case for the status of a
+    /// [`PieceMoves::BoardMoves`] and the [`PromotionStatus::for_move`] of any of its moves.
+    /// This is not checked. If they do not agree, `CannotPromote` and `MustPromote` on the
+    /// left win, while `MayPromote` on the left gives the right-hand status.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let may = PromotionStatus::MayPromote;
+    /// assert_eq!(PromotionStatus::Undecided & may, may);
+    /// assert_eq!(may & PromotionStatus::Undecided, may);
+    /// assert_eq!(may & may, may);
+    /// ```
     fn bitand(self, rhs: Self) -> Self {
         match (self, rhs) {
             (PromotionStatus::Undecided, _) => rhs,
@@ -41,6 +147,19 @@ impl BitAnd for PromotionStatus {
     }
 }
 
+helpers::simple_error! {
+    /// A [`PieceMoves`] instance does not hold legal moves for the given position.
+    #[non_exhaustive]
+    pub enum PieceMovesInvariantError {
+        WrongColor = "The moves are not for the side to move",
+        NotInHand = "The dropped piece is not in hand",
+        InvalidDropTarget = "A drop target is occupied, forbidden for the piece or on a file with a Pawn",
+        WrongPiece = "The piece is not on the from-square",
+        InconsistentPromotion = "The promotion status does not match a target square",
+        IllegalMove = "A move is not legal"
+    }
+}
+
 /// A compact enum representing all the moves for one particular piece.
 ///
 /// Iterate over the PieceMoves instance to unpack the moves.
@@ -64,6 +183,60 @@ pub enum PieceMoves {
 }
 
 impl PieceMoves {
+    /// Safely build a set of board moves for the piece on `from`.
+    ///
+    /// The color is taken from the side to move and `to` is masked against the legal
+    /// target squares of the piece in the given position. The promotion status is left
+    /// `Undecided` for promotable pieces, so promotions are resolved per target square.
+    ///
+    /// Returns `None` if `from` is not occupied by `piece` of the side to move.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// // 2h is the Black Rook; 2a and 2d are not legal targets
+    /// let to = Square::H1.bitboard() | Square::A2.bitboard() | Square::D2.bitboard();
+    /// let moves = PieceMoves::new_board_moves(&board, Piece::Rook, Square::H2, to).unwrap();
+    /// assert_eq!(moves.len(), 1);
+    /// assert!(moves.has("2h1h".parse().unwrap()));
+    /// // 2h does not hold a Gold
+    /// assert!(PieceMoves::new_board_moves(&board, Piece::Gold, Square::H2, to).is_none());
+    /// ```
+    pub fn new_board_moves(
+        board: &Board,
+        piece: Piece,
+        from: Square,
+        to: BitBoard,
+    ) -> Option<Self> {
+        let color = board.side_to_move();
+        if board.colored_piece_on(from) != Some(ColoredPiece { piece, color }) {
+            return None;
+        }
+
+        let mut legal = BitBoard::EMPTY;
+        board.generate_board_moves_for(from.bitboard(), |moves| {
+            if let PieceMoves::BoardMoves { to, .. } = moves {
+                legal |= to;
+            }
+            false
+        });
+
+        let prom_status = if piece.is_promotable() {
+            PromotionStatus::Undecided
+        } else {
+            PromotionStatus::CannotPromote
+        };
+
+        Some(PieceMoves::BoardMoves {
+            color,
+            piece,
+            from,
+            to: to & legal,
+            prom_status,
+        })
+    }
+
     /// Get the number of generated to-squares.
     ///
     /// The PieceMovesIter will generate _at least_ this number of moves,
@@ -83,6 +256,82 @@ impl PieceMoves {
         }
     }
 
+    /// Check that these moves are legal moves in the given position.
+    ///
+    /// This checks the invariants that the move generator maintains: the moves are for the
+    /// side to move, drops are of a piece in hand and only target empty squares where the
+    /// piece may be dropped (and for a Pawn, only files without a Pawn), board moves are
+    /// made by the piece on the from-square, and the promotion status agrees with
+    /// [`PromotionStatus::for_move`] for every target square unless it is `Undecided`.
+    /// Finally, every move is checked with [`Board::is_legal`].
+    ///
+    /// The move generator checks its output with this function in its unit tests and with
+    /// the internal `verify-checks` feature. It can also be used to test move generators
+    /// built on top of this crate.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// board.generate_moves(|moves| {
+    ///     assert!(moves.validate(&board).is_ok());
+    ///     false
+    /// });
+    ///
+    /// // A Black Pawn may not be dropped on the first rank (and Black has no Pawn in hand)
+    /// let moves = PieceMoves::Drops {
+    ///     color: Color::Black,
+    ///     piece: Piece::Pawn,
+    ///     to: Square::A5.bitboard(),
+    /// };
+    /// assert!(moves.validate(&board).is_err());
+    /// ```
+    pub fn validate(&self, board: &Board) -> Result<(), PieceMovesInvariantError> {
+        match *self {
+            PieceMoves::Drops { color, piece, to } => {
+                if color != board.side_to_move() {
+                    return Err(PieceMovesInvariantError::WrongColor);
+                }
+                if piece as usize >= Piece::HAND_NUM || !board.has_in_hand(color, piece) {
+                    return Err(PieceMovesInvariantError::NotInHand);
+                }
+                let mut forbidden = board.occupied() | no_fly_zone(color, piece);
+                if piece == Piece::Pawn {
+                    forbidden |= !board.pawnless_files(color);
+                }
+                if !(to & forbidden).is_empty() {
+                    return Err(PieceMovesInvariantError::InvalidDropTarget);
+                }
+            }
+            PieceMoves::BoardMoves {
+                color,
+                piece,
+                from,
+                to,
+                prom_status,
+            } => {
+                if color != board.side_to_move() {
+                    return Err(PieceMovesInvariantError::WrongColor);
+                }
+                if board.colored_piece_on(from) != Some(ColoredPiece { piece, color }) {
+                    return Err(PieceMovesInvariantError::WrongPiece);
+                }
+                if prom_status != PromotionStatus::Undecided
+                    && to.into_iter().any(|square| {
+                        PromotionStatus::for_move(color, piece, from, square) != prom_status
+                    })
+                {
+                    return Err(PieceMovesInvariantError::InconsistentPromotion);
+                }
+            }
+        }
+        if self.into_iter().all(|mv| board.is_legal(mv)) {
+            Ok(())
+        } else {
+            Err(PieceMovesInvariantError::IllegalMove)
+        }
+    }
+
     /// Check if this set of moves contains a given [`Move`].
     /// The given move can either be a [`Move::Drop`] or [`Move::BoardMove`].
     pub fn has(&self, mv: Move) -> bool {
@@ -105,12 +354,9 @@ impl PieceMoves {
                 if *from != mv_from || !to.has(mv_to) {
                     return false;
                 }
-                match *prom_status & PromotionStatus::new(*color, *piece, *from, mv_to) {
-                    PromotionStatus::CannotPromote => !promotion,
-                    PromotionStatus::MustPromote => promotion,
-                    PromotionStatus::MayPromote => true,
-                    _ => unreachable!(), // by design of `&` and `new` this is really unreachable
-                }
+                // by design of `&` and `for_move` the status is never `Undecided`
+                (*prom_status & PromotionStatus::for_move(*color, *piece, *from, mv_to))
+                    .allows(promotion)
             }
             // Handle Drops
             (
@@ -270,9 +516,15 @@ impl Iterator for PieceMovesIter {
                     let to_square = to.next_square()?;
                     *to ^= to_square.bitboard(); // eat `to` bit
 
-                    let promotion = match *prom_status
-                        & PromotionStatus::new(*color, *piece, from, to_square)
-                    {
+                    // Move generation always hands out a fixed status for all `to` squares.
+                    // Only user-constructed instances may still need a per-square check.
+                    let status = match *prom_status {
+                        PromotionStatus::Undecided => {
+                            PromotionStatus::new(*color, *piece, from, to_square)
+                        }
+                        status => status,
+                    };
+                    let promotion = match status {
                         PromotionStatus::CannotPromote => false,
                         PromotionStatus::MayPromote => {
                             // set `self.to` to generate non-promotion in next step
@@ -280,7 +532,7 @@ impl Iterator for PieceMovesIter {
                             true
                         }
                         PromotionStatus::MustPromote => true,
-                        _ => unreachable!(),
+                        PromotionStatus::Undecided => unreachable!(),
                     };
 
                     Some(Move::BoardMove {
@@ -330,8 +582,10 @@ impl ExactSizeIterator for PieceMovesIter {
                 {
                     debug_assert!(pending_non_promotion == 0);
                     num_targets
+                } else if prom_status == PromotionStatus::MayPromote {
+                    2 * num_targets + pending_non_promotion
                 } else {
-                    // Undecided or MayPromote
+                    // Undecided
                     let remaining_moves = match piece {
                         Piece::Pawn => self.len_for_pawn(color, from, to, num_targets),
                         Piece::Lance => self.len_for_lance(color, to, num_targets),
@@ -361,6 +615,33 @@ impl ExactSizeIterator for PieceMovesIter {
 mod tests {
     use super::*;
 
+    #[test]
+    fn new_board_moves_matches_generated_moves() {
+        let board: Board =
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+                .parse()
+                .unwrap();
+        let color = board.side_to_move();
+        for from in board.colors(color) {
+            let piece = board.piece_on(from).unwrap();
+            let moves = PieceMoves::new_board_moves(&board, piece, from, BitBoard::FULL).unwrap();
+
+            let mut expected = Vec::new();
+            board.generate_board_moves_for(from.bitboard(), |mvs| {
+                expected.extend(mvs);
+                false
+            });
+            let mut actual: Vec<Move> = moves.into_iter().collect();
+            expected.sort_by_key(|mv| format!("{}", mv));
+            actual.sort_by_key(|mv| format!("{}", mv));
+            assert_eq!(actual, expected);
+        }
+        // Opponent pieces are rejected
+        let from = board.colors(!color).next_square().unwrap();
+        let piece = board.piece_on(from).unwrap();
+        assert!(PieceMoves::new_board_moves(&board, piece, from, BitBoard::FULL).is_none());
+    }
+
     #[test]
     fn len_works_with_non_promotions() {
         let mv = PieceMoves::BoardMoves {
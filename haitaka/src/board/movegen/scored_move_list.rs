@@ -0,0 +1,290 @@
+use core::ops::{Deref, DerefMut};
+
+use crate::*;
+
+/// A move with a score, as used for move ordering, killer tables and principal
+/// variations.
+///
+/// The move is stored in its [16-bit encoding](Move::to_u16) and the score is an
+/// `i16`, which is enough for search scores and history counters. A scored move takes
+/// 4 bytes. Higher scores are better.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScoredMove {
+    mv: u16,
+    /// The score of the move.
+    pub score: i16,
+}
+
+const _: () = assert!(core::mem::size_of::<ScoredMove>() == 4);
+
+impl ScoredMove {
+    /// Create a scored move.
+    pub const fn new(mv: Move, score: i16) -> Self {
+        Self {
+            mv: mv.to_u16(),
+            score,
+        }
+    }
+
+    /// The move.
+    #[inline(always)]
+    pub const fn mv(&self) -> Move {
+        match Move::from_u16(self.mv) {
+            Some(mv) => mv,
+            None => unreachable!(),
+        }
+    }
+}
+
+impl core::fmt::Debug for ScoredMove {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScoredMove")
+            .field("mv", &self.mv())
+            .field("score", &self.score)
+            .finish()
+    }
+}
+
+/// A fixed-capacity list of scored moves, stored inline.
+///
+/// This is the scored counterpart of [`MoveList`]: it holds up to [`MAX_LEGAL_MOVES`]
+/// moves, does not allocate, and dereferences to a slice of [`ScoredMove`]s. It adds
+/// helpers to visit the moves best first without sorting the whole list:
+/// [`ScoredMoveList::pick_best`] for incremental selection in a search loop, and
+/// [`ScoredMoveList::select_top_k`] for the best few moves.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// // Score the Pawn moves first
+/// let mut moves = ScoredMoveList::from_moves(&board.legal_moves(), |mv| {
+///     if board.piece_on(mv.from().unwrap()) == Some(Piece::Pawn) { 10 } else { 0 }
+/// });
+/// assert_eq!(moves.len(), 30);
+///
+/// let mut i = 0;
+/// while let Some(best) = moves.pick_best(i) {
+///     assert_eq!(best.score, if i < 9 { 10 } else { 0 });
+///     i += 1;
+/// }
+/// assert_eq!(i, 30);
+/// ```
+#[derive(Clone)]
+pub struct ScoredMoveList {
+    moves: [ScoredMove; MAX_LEGAL_MOVES],
+    len: usize,
+}
+
+// Fills the unused part of the buffer.
+const NO_MOVE: ScoredMove = ScoredMove::new(
+    Move::Drop {
+        piece: Piece::Pawn,
+        to: Square::A1,
+    },
+    0,
+);
+
+impl ScoredMoveList {
+    /// The number of moves the list can hold.
+    pub const CAPACITY: usize = MAX_LEGAL_MOVES;
+
+    /// Create an empty list.
+    pub const fn new() -> Self {
+        Self {
+            moves: [NO_MOVE; MAX_LEGAL_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Create a list from moves, scoring each move with `score`.
+    ///
+    /// # Panics
+    /// This panics if there are more than [`ScoredMoveList::CAPACITY`] moves.
+    pub fn from_moves(moves: &[Move], mut score: impl FnMut(Move) -> i16) -> Self {
+        let mut list = Self::new();
+        for &mv in moves {
+            list.push(mv, score(mv));
+        }
+        list
+    }
+
+    /// Add a move with its score to the end of the list.
+    ///
+    /// # Panics
+    /// This panics if the list is full.
+    #[inline(always)]
+    pub fn push(&mut self, mv: Move, score: i16) {
+        assert!(self.len < Self::CAPACITY, "ScoredMoveList is full");
+        self.moves[self.len] = ScoredMove::new(mv, score);
+        self.len += 1;
+    }
+
+    /// Remove the last move and return it, or None if the list is empty.
+    pub fn pop(&mut self) -> Option<ScoredMove> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.moves[self.len])
+    }
+
+    /// Remove all moves.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Get the scored moves as a slice.
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[ScoredMove] {
+        &self.moves[..self.len]
+    }
+
+    /// Get the scored moves as a mutable slice.
+    #[inline(always)]
+    pub fn as_mut_slice(&mut self) -> &mut [ScoredMove] {
+        &mut self.moves[..self.len]
+    }
+
+    /// Iterate over the moves, without their scores.
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.iter().map(|scored| scored.mv())
+    }
+
+    /// Get the move with the highest score, or None if the list is empty.
+    ///
+    /// Of several moves with the highest score, this returns the first.
+    pub fn best(&self) -> Option<ScoredMove> {
+        self.iter().copied().reduce(|best, scored| {
+            if scored.score > best.score {
+                scored
+            } else {
+                best
+            }
+        })
+    }
+
+    /// Move the best of the moves from `index` on to `index`, and return it.
+    ///
+    /// Returns None if `index` is past the end of the list. Calling this with
+    /// `0, 1, 2, ...` visits the moves best first, like a selection sort that stops as
+    /// soon as the search gets a cutoff. The moves after `index` may be reordered.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut moves = ScoredMoveList::new();
+    /// moves.push("7g7f".parse().unwrap(), 5);
+    /// moves.push("2g2f".parse().unwrap(), 20);
+    /// moves.push("5i5h".parse().unwrap(), -3);
+    ///
+    /// assert_eq!(moves.pick_best(0).unwrap().score, 20);
+    /// assert_eq!(moves.pick_best(1).unwrap().score, 5);
+    /// assert_eq!(moves.pick_best(2).unwrap().score, -3);
+    /// assert_eq!(moves.pick_best(3), None);
+    /// ```
+    pub fn pick_best(&mut self, index: usize) -> Option<ScoredMove> {
+        let rest = self.as_mut_slice().get_mut(index..)?;
+        let mut best_score = rest.first()?.score;
+        let mut best = 0;
+        for (i, scored) in rest.iter().enumerate().skip(1) {
+            if scored.score > best_score {
+                best = i;
+                best_score = scored.score;
+            }
+        }
+        rest.swap(0, best);
+        Some(rest[0])
+    }
+
+    /// Sort the moves by score, best first.
+    ///
+    /// The sort is stable, so moves with equal scores keep their order.
+    pub fn sort_best_first(&mut self) {
+        self.sort_by_key(|scored| core::cmp::Reverse(scored.score));
+    }
+
+    /// Move the `k` best moves to the front of the list, sorted best first, and return
+    /// them.
+    ///
+    /// This is faster than sorting the whole list when `k` is small. The order of the
+    /// other moves is unspecified, and so is the order of moves with equal scores. If
+    /// the list has fewer than `k` moves, all moves are sorted.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let mut moves = ScoredMoveList::from_moves(&board.legal_moves(), |mv| {
+    ///     mv.to().rank() as i16
+    /// });
+    /// let best = moves.select_top_k(2);
+    /// assert_eq!(best.len(), 2);
+    /// assert!(best.iter().all(|scored| scored.score == Rank::H as i16));
+    /// ```
+    pub fn select_top_k(&mut self, k: usize) -> &[ScoredMove] {
+        let k = k.min(self.len);
+        let by_score = |a: &ScoredMove, b: &ScoredMove| b.score.cmp(&a.score);
+        if k > 0 && k < self.len {
+            self.select_nth_unstable_by(k - 1, by_score);
+        }
+        self[..k].sort_unstable_by(by_score);
+        &self[..k]
+    }
+}
+
+impl Default for ScoredMoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for ScoredMoveList {
+    type Target = [ScoredMove];
+
+    fn deref(&self) -> &[ScoredMove] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for ScoredMoveList {
+    fn deref_mut(&mut self) -> &mut [ScoredMove] {
+        self.as_mut_slice()
+    }
+}
+
+impl core::fmt::Debug for ScoredMoveList {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for ScoredMoveList {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for ScoredMoveList {}
+
+impl Extend<ScoredMove> for ScoredMoveList {
+    fn extend<T: IntoIterator<Item = ScoredMove>>(&mut self, iter: T) {
+        for scored in iter {
+            self.push(scored.mv(), scored.score);
+        }
+    }
+}
+
+impl FromIterator<ScoredMove> for ScoredMoveList {
+    fn from_iter<T: IntoIterator<Item = ScoredMove>>(iter: T) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<'a> IntoIterator for &'a ScoredMoveList {
+    type Item = &'a ScoredMove;
+    type IntoIter = core::slice::Iter<'a, ScoredMove>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -1,10 +1,34 @@
+use rand::SeedableRng;
 use rand::rng;
-use rand::rngs::ThreadRng;
+use rand::rngs::{StdRng, ThreadRng};
 use rand::seq::IndexedRandom;
 
 // Movegenerator tests
 use super::*;
 
+/// The positions of `games` games of random legal moves from the start position.
+///
+/// Each game stops after `plies` moves or when the side to move has no legal moves.
+/// The positions include the start position and the last position of each game, and
+/// are the same for the same `seed`. Shared by the tests of the other board modules.
+pub(crate) fn random_positions(seed: u64, games: usize, plies: usize) -> Vec<Board> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut positions = Vec::new();
+    for _ in 0..games {
+        let mut board = Board::startpos();
+        for _ in 0..plies {
+            let moves = board.legal_moves();
+            let Some(&mv) = moves.choose(&mut rng) else {
+                break;
+            };
+            positions.push(board.clone());
+            board.play_unchecked(mv);
+        }
+        positions.push(board);
+    }
+    positions
+}
+
 // Tests the generation of board moves based on giving a subset of squares
 #[test]
 fn subset_movegen_habu_position() {
@@ -142,6 +166,30 @@ fn legality_simple() {
     );
 }
 
+#[test]
+fn legality_random_positions() {
+    // random games reach positions with promoted pieces, pins and checks
+    let mut rng = rng();
+    for sfen in [
+        SFEN_STARTPOS,
+        "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+    ] {
+        let mut board: Board = sfen.parse().unwrap();
+        for _ in 0..60 {
+            test_is_legal(board.clone());
+            let mut v: Vec<Move> = Vec::new();
+            board.generate_moves(|mvs| {
+                v.extend(mvs);
+                false
+            });
+            match v.choose(&mut rng) {
+                Some(&mv) => board.play(mv),
+                None => break,
+            }
+        }
+    }
+}
+
 #[test]
 fn legality_drops() {
     let board: Board = "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
@@ -151,6 +199,18 @@ fn legality_drops() {
     test_nifu(&board);
 }
 
+#[test]
+fn legality_drops_need_piece_in_hand() {
+    let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+    assert!(board.is_legal("G*5e".parse().unwrap()));
+    for piece in [Piece::Pawn, Piece::Lance, Piece::Rook, Piece::Tokin] {
+        assert!(!board.is_legal(Move::Drop {
+            piece,
+            to: Square::E5
+        }));
+    }
+}
+
 #[test]
 fn non_check() {
     let sfen: &str = "lnsgk1snl/1r4gb1/p1pppp2p/6pR1/1p7/2P6/PP1PPPP1P/1BG6/LNS1KGSNL w Pp 12";
@@ -211,6 +271,38 @@ fn discount_pawn_drop_mate_in_perft() {
     assert_eq!(num_moves, 85);
 }
 
+#[test]
+fn pawn_drop_mate_on_any_file() {
+    // The Pawn drop mate on 5b is not the first Pawn drop square
+    let board: Board = "3nkn3/3l1l3/4G4/9/9/9/9/9/4K4 b P 1".parse().unwrap();
+    // The White Rook on 4b checks the Black King and is pinned by the Bishop on 2d,
+    // so P*5b interposes and mates
+    let in_check: Board = "3nkn3/K4r3/4G4/7B1/9/9/9/9/9 b P 1".parse().unwrap();
+    assert_eq!(in_check.checkers(), Square::B4.bitboard());
+
+    let mate: Move = "P*5b".parse().unwrap();
+    for board in [board, in_check] {
+        let mut pawn_drops = BitBoard::EMPTY;
+        board.generate_moves(|mvs| {
+            for mv in mvs {
+                if mv.is_drop() {
+                    pawn_drops |= mv.to().bitboard();
+                }
+            }
+            false
+        });
+        assert!(!pawn_drops.is_empty());
+        assert!(!pawn_drops.has(Square::B5));
+        assert!(!board.is_legal(mate));
+        for square in pawn_drops {
+            assert!(board.is_legal(Move::Drop {
+                piece: Piece::Pawn,
+                to: square
+            }));
+        }
+    }
+}
+
 #[test]
 fn donot_move_into_check() {
     let sfen: &str = "7lk/9/8S/9/9/9/9/7L1/8K b P 1";
@@ -317,7 +409,7 @@ fn tsume() {
     // from_sfen will fail - since there is only one King on board
     assert!(matches!(
         Board::from_sfen(sfen),
-        Err(SFENParseError::InvalidBoard)
+        Err(SfenError::InvalidPosition(PositionError::KingCount { .. }))
     ));
     // tsume will succeed
     let board = Board::tsume(sfen).unwrap();
@@ -618,6 +710,127 @@ fn discovered_checks3() {
     assert_eq!(checks.len(), 8);
 }
 
+// Board moves are handed out with a fixed promotion status for all target squares
+#[test]
+fn board_moves_have_decided_promotion_status() {
+    fn visit(board: &Board, depth: u8) {
+        board.generate_board_moves(|moves| {
+            if let PieceMoves::BoardMoves {
+                color,
+                piece,
+                from,
+                to,
+                prom_status,
+            } = moves
+            {
+                assert_ne!(prom_status, PromotionStatus::Undecided);
+                for square in to {
+                    let status = PromotionStatus::new(color, piece, from, square);
+                    if piece.is_promotable() {
+                        assert_eq!(prom_status, status);
+                    } else {
+                        assert_eq!(prom_status, PromotionStatus::CannotPromote);
+                    }
+                }
+                assert_eq!(moves.into_iter().len(), moves.into_iter().count());
+            }
+            false
+        });
+
+        if depth > 0 {
+            board.generate_moves(|moves| {
+                for mv in moves {
+                    let mut board = board.clone();
+                    board.play_unchecked(mv);
+                    visit(&board, depth - 1);
+                }
+                false
+            });
+        }
+    }
+    let board = "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+        .parse()
+        .unwrap();
+    visit(&board, 2);
+}
+
+#[test]
+fn generate_moves_until_matches_generate_moves() {
+    fn visit(board: &Board, depth: u8) {
+        let mut expected = 0;
+        board.generate_moves(|moves| {
+            expected += moves.into_iter().len();
+            false
+        });
+        let mut actual = 0;
+        assert!(!board.generate_moves_until(
+            || false,
+            |moves| {
+                actual += moves.into_iter().len();
+                false
+            }
+        ));
+        assert_eq!(actual, expected);
+        assert!(board.generate_moves_until(|| true, |_| panic!("deadline ignored")));
+
+        if depth > 0 {
+            board.generate_moves(|moves| {
+                for mv in moves {
+                    let mut board = board.clone();
+                    board.play_unchecked(mv);
+                    visit(&board, depth - 1);
+                }
+                false
+            });
+        }
+    }
+    let board = "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+        .parse()
+        .unwrap();
+    visit(&board, 1);
+}
+
+#[test]
+fn generate_moves_ordered_keeps_drops_and_board_moves_apart() {
+    let mut boards = vec![
+        // no pieces in hand, so no drops
+        Board::startpos(),
+        // in check from the Rook: the Gold can be dropped in between
+        "4k4/9/9/9/4r4/9/9/9/4K4 b G 1".parse().unwrap(),
+        // in check from an adjacent Gold: no drops at all
+        "4k4/9/9/9/9/9/9/4g4/4K4 b G 1".parse().unwrap(),
+        // Black has no pieces on the board, so only drops
+        Board::tsume("4k4/9/9/9/9/9/9/9/9 b G 1").unwrap(),
+    ];
+    boards.extend(random_positions(0x2767, 5, 120));
+    for board in boards {
+        let mut expected: Vec<Move> = board.legal_moves().to_vec();
+        expected.sort();
+        let has_drops = expected.iter().any(|mv| mv.is_drop());
+        let has_board_moves = expected.iter().any(|mv| !mv.is_drop());
+        for (order, drops_first) in [
+            (MoveGenOrder::DropsFirst, true),
+            (MoveGenOrder::BoardFirst, false),
+        ] {
+            let mut moves = Vec::new();
+            board.generate_moves_ordered(order, |mvs| {
+                moves.extend(mvs);
+                false
+            });
+            let switches = moves
+                .windows(2)
+                .filter(|w| w[0].is_drop() != w[1].is_drop())
+                .count();
+            assert_eq!(switches, (has_drops && has_board_moves) as usize, "{board}");
+            if has_drops && has_board_moves {
+                assert_eq!(moves[0].is_drop(), drops_first, "{board}");
+            }
+            moves.sort();
+            assert_eq!(moves, expected, "{board}");
+        }
+    }
+}
+
 #[test]
 fn fuzzing_generate_moves() {
     let mut rng = rng();
@@ -683,6 +896,52 @@ fn fuzzing_checks() {
     }
 }
 
+#[test]
+fn generate_checks_ignores_sliders_off_their_line() {
+    // The Lance on 3f is on a diagonal with the White King, so moving the Lance
+    // on 2e does not discover a check
+    let sfen = "+P+n1g1+Pp+P1/2gg+p+s+pLn/1gppP1S+P1/1+s+PPSPP1k/N1L2N+PL1/6L1+P/9/9/9 b P2r2bp 5";
+    let board = Board::tsume(sfen).unwrap();
+    let mut checks = Vec::new();
+    board.generate_checks(|mvs| {
+        checks.extend(mvs);
+        false
+    });
+    assert!(!checks.contains(&"2e2d".parse().unwrap()));
+    for mv in checks {
+        let mut child = board.clone();
+        child.play(mv);
+        assert!(!child.checkers().is_empty(), "{mv}");
+    }
+}
+
+#[test]
+fn generate_checks_in_check_only_interposes() {
+    for sfen in [
+        // in check by a Knight: no drops at all
+        "4k4/9/9/9/9/9/5n3/9/4K4 b G 1",
+        // in check by a Rook: the Rook drop on 6i blocks, but gives no check
+        "4k4/9/9/9/9/9/9/9/r3K4 b GR 1",
+        // the Lance drop on 4a blocks the Rook and checks the King
+        "4k3R/9/9/9/9/9/9/9/5K3 w l 1",
+    ] {
+        let board: Board = sfen.parse().unwrap();
+        let mut checks = Vec::new();
+        board.generate_checks(|mvs| {
+            checks.extend(mvs);
+            false
+        });
+        let mut expected = Vec::new();
+        board.generate_moves(|mvs| {
+            expected.extend(mvs.into_iter().filter(|&mv| board.gives_check(mv)));
+            false
+        });
+        checks.sort_by_key(|mv| mv.to_string());
+        expected.sort_by_key(|mv| mv.to_string());
+        assert_eq!(checks, expected, "{sfen}");
+    }
+}
+
 #[test]
 fn board_hash_trait_works() {
     use std::collections::hash_map::DefaultHasher;
@@ -708,3 +967,425 @@ fn board_hash_trait_works() {
     assert_eq!(hash1, hash2, "Hashes of identical boards should match");
     assert_ne!(hash1, hash3, "Hashes of different boards should differ");
 }
+
+// The position with the maximum number of legal moves
+const SFEN_MAX_MOVES: &str = "R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1";
+
+#[test]
+fn max_legal_moves_position() {
+    let board: Board = SFEN_MAX_MOVES.parse().unwrap();
+    let mut calls = 0;
+    let mut moves = Vec::new();
+    board.generate_moves(|piece_moves| {
+        calls += 1;
+        let len = piece_moves.into_iter().len();
+        match piece_moves {
+            PieceMoves::BoardMoves { .. } => assert!(len <= MAX_BOARD_MOVES_PER_PIECE),
+            PieceMoves::Drops { .. } => assert!(len <= MAX_DROPS_PER_PIECE),
+        }
+        moves.extend(piece_moves);
+        false
+    });
+    assert!(calls <= MAX_LISTENER_CALLS);
+    assert_eq!(moves.len(), MAX_LEGAL_MOVES);
+    assert!(moves.iter().all(|&mv| board.is_legal(mv)));
+    moves.sort_by_key(|mv| mv.to_string());
+    moves.dedup();
+    assert_eq!(moves.len(), MAX_LEGAL_MOVES);
+
+    // the drops and board moves add up as well
+    let mut drops = 0;
+    board.generate_drops(|piece_moves| {
+        drops += piece_moves.into_iter().len();
+        false
+    });
+    let mut board_moves = 0;
+    board.generate_board_moves(|piece_moves| {
+        board_moves += piece_moves.into_iter().len();
+        false
+    });
+    assert_eq!(drops + board_moves, MAX_LEGAL_MOVES);
+}
+
+#[test]
+fn move_list_holds_the_max_legal_moves() {
+    let board: Board = SFEN_MAX_MOVES.parse().unwrap();
+    let moves = board.legal_moves();
+    assert_eq!(moves.len(), MoveList::CAPACITY);
+
+    let mut expected = Vec::new();
+    board.generate_moves(|mvs| {
+        expected.extend(mvs);
+        false
+    });
+    assert_eq!(moves.as_slice(), expected);
+
+    let drops: Vec<Move> = expected.into_iter().filter(|mv| mv.is_drop()).collect();
+    assert_eq!(board.legal_drops().as_slice(), drops);
+}
+
+#[test]
+fn scored_move_list_selects_the_best_moves() {
+    use rand::Rng;
+
+    let mut rng = StdRng::seed_from_u64(0x5c0e);
+    for board in random_positions(0x5c0e, 2, 100) {
+        let moves = board.legal_moves();
+        let list = ScoredMoveList::from_moves(&moves, |_| rng.random_range(-50..50));
+        assert!(list.iter().map(ScoredMove::mv).eq(moves.iter().copied()));
+        let mut sorted = list.clone();
+        sorted.sort_best_first();
+        assert!(sorted.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(
+            list.best().map(|best| best.score),
+            sorted.first().map(|first| first.score)
+        );
+
+        let mut picked = list.clone();
+        for i in 0..picked.len() {
+            assert_eq!(picked.pick_best(i).unwrap().score, sorted[i].score);
+        }
+        for k in [0, 1, 5, moves.len(), moves.len() + 1] {
+            let mut top = list.clone();
+            let scores: Vec<i16> = top.select_top_k(k).iter().map(|s| s.score).collect();
+            let expected: Vec<i16> = sorted.iter().take(k).map(|s| s.score).collect();
+            assert_eq!(scores, expected);
+            let mut all: Vec<Move> = top.moves().collect();
+            all.sort();
+            let mut original: Vec<Move> = list.moves().collect();
+            original.sort();
+            assert_eq!(all, original);
+        }
+    }
+}
+
+#[test]
+fn scored_move_list_edge_cases() {
+    let mut empty = ScoredMoveList::new();
+    assert_eq!(empty.best(), None);
+    assert_eq!(empty.pick_best(0), None);
+    assert!(empty.select_top_k(3).is_empty());
+    assert_eq!(empty.pop(), None);
+
+    // equal scores keep their order when sorting, and the first one is the best
+    let board = Board::startpos();
+    let moves = board.legal_moves();
+    let mut list = ScoredMoveList::from_moves(&moves, |_| 7);
+    assert_eq!(list.best().map(|best| best.mv()), Some(moves[0]));
+    list.sort_best_first();
+    assert!(list.moves().eq(moves.iter().copied()));
+    assert_eq!(list.pick_best(3).map(|best| best.mv()), Some(moves[3]));
+
+    // the extreme scores, and a drop, survive the packing
+    let drop: Move = "G*5e".parse().unwrap();
+    let mut list = ScoredMoveList::new();
+    list.push(moves[0], i16::MIN);
+    list.push(drop, i16::MAX);
+    list.push(moves[1], 0);
+    assert_eq!(list.best(), Some(ScoredMove::new(drop, i16::MAX)));
+    let top: Vec<(Move, i16)> = list
+        .select_top_k(5)
+        .iter()
+        .map(|scored| (scored.mv(), scored.score))
+        .collect();
+    assert_eq!(top, [(drop, i16::MAX), (moves[1], 0), (moves[0], i16::MIN)]);
+    assert_eq!(list.pop(), Some(ScoredMove::new(moves[0], i16::MIN)));
+}
+
+#[test]
+fn generate_moves_into_orders_stably() {
+    for sfen in [
+        SFEN_STARTPOS,
+        "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+        "8l/1+R5pk/6sp1/5pP1p/9/6P2/PP1pP+bN1P/5SGK1/L6NL b RSNL2P2g 1",
+    ] {
+        let board: Board = sfen.parse().unwrap();
+        let expected = board.legal_moves();
+        let is_capture = |mv: &Move| !mv.is_drop() && board.occupied().has(mv.to());
+
+        // moves are appended after the existing ones
+        let mut moves = vec![expected[0]];
+        board.generate_moves_into(&mut moves, MoveOrdering::None);
+        assert_eq!(moves[1..], expected[..]);
+
+        for (ordering, first) in [
+            (
+                MoveOrdering::CapturesFirst,
+                &is_capture as &dyn Fn(&Move) -> bool,
+            ),
+            (MoveOrdering::PromotionsFirst, &|mv: &Move| {
+                mv.is_promotion()
+            }),
+        ] {
+            let mut moves = Vec::new();
+            board.generate_moves_into(&mut moves, ordering);
+            let split = moves
+                .iter()
+                .position(|mv| !first(mv))
+                .unwrap_or(moves.len());
+            assert!(moves[split..].iter().all(|mv| !first(mv)), "{sfen}");
+
+            // each group keeps the generation order
+            let firsts: Vec<Move> = expected.iter().copied().filter(|mv| first(mv)).collect();
+            let rest: Vec<Move> = expected.iter().copied().filter(|mv| !first(mv)).collect();
+            assert_eq!(moves[..split], firsts[..]);
+            assert_eq!(moves[split..], rest[..]);
+        }
+    }
+}
+
+#[test]
+fn max_legal_moves_bounds_hold_after_one_move() {
+    // the debug assertions in generate_moves check the bounds for every reply
+    let board: Board = SFEN_MAX_MOVES.parse().unwrap();
+    board.generate_moves(|moves| {
+        for mv in moves {
+            let mut board = board.clone();
+            board.play_unchecked(mv);
+            let mut total = 0;
+            board.generate_moves(|moves| {
+                total += moves.into_iter().len();
+                false
+            });
+            assert!(total <= MAX_LEGAL_MOVES);
+        }
+        false
+    });
+}
+
+#[test]
+fn slider_attacks_match_attackers() {
+    for sfen in [
+        SFEN_STARTPOS,
+        SFEN_MAX_MOVES,
+        "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+        "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+        "8l/1+R5pk/6sp1/5pP1p/9/6P2/PP1pP+bN1P/5SGK1/L6NL b RSNL2P2g 1",
+    ] {
+        let board: Board = sfen.parse().unwrap();
+        for color in Color::ALL {
+            let attacks = board.slider_attacks(color, board.occupied());
+            for square in Square::ALL {
+                let attackers = board.attackers(square, color) & board.sliders(color);
+                assert_eq!(
+                    attacks.has(square),
+                    !attackers.is_empty(),
+                    "{sfen} {square}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn generate_moves_tiered_visits_tiers_in_order() {
+    for sfen in [
+        SFEN_STARTPOS,
+        SFEN_MAX_MOVES,
+        "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+    ] {
+        let board: Board = sfen.parse().unwrap();
+        let color = board.side_to_move();
+        let king_zone = king_attacks(!color, board.king(!color));
+        let tiers = [king_zone, prom_zone(color), king_zone];
+
+        let mut moves = Vec::new();
+        board.generate_moves_tiered(&tiers, |piece_moves| {
+            moves.extend(piece_moves);
+            false
+        });
+        let mut expected = Vec::new();
+        board.generate_moves(|piece_moves| {
+            expected.extend(piece_moves);
+            false
+        });
+        assert_eq!(moves.len(), expected.len(), "{sfen}");
+
+        let tier = |mv: &Move| {
+            tiers
+                .iter()
+                .position(|tier| tier.has(mv.to()))
+                .unwrap_or(tiers.len())
+        };
+        assert!(
+            moves.windows(2).all(|w| tier(&w[0]) <= tier(&w[1])),
+            "{sfen}"
+        );
+        moves.sort_by_key(|mv| mv.to_string());
+        expected.sort_by_key(|mv| mv.to_string());
+        assert_eq!(moves, expected, "{sfen}");
+
+        // the listener can stop early
+        let mut calls = 0;
+        assert!(board.generate_moves_tiered(&tiers, |_| {
+            calls += 1;
+            true
+        }));
+        assert_eq!(calls, 1);
+    }
+}
+
+#[test]
+fn generate_captures_matches_generate_moves() {
+    fn visit(board: &Board, depth: u8) {
+        let theirs = board.colors(!board.side_to_move());
+        let mut all = Vec::new();
+        board.generate_moves(|moves| {
+            all.extend(moves);
+            false
+        });
+
+        let mut captures = Vec::new();
+        assert!(!board.generate_captures(|moves| {
+            captures.extend(moves);
+            false
+        }));
+        let expected: Vec<Move> = all
+            .iter()
+            .copied()
+            .filter(|mv| theirs.has(mv.to()))
+            .collect();
+        assert_eq!(captures, expected);
+
+        let mut tactical = Vec::new();
+        board.generate_captures_and_promotions(|moves| {
+            tactical.extend(moves);
+            false
+        });
+        let mut expected: Vec<Move> = all
+            .iter()
+            .copied()
+            .filter(|mv| theirs.has(mv.to()) || mv.is_promotion())
+            .collect();
+        tactical.sort_by_key(|mv| (mv.from(), mv.to(), mv.is_promotion()));
+        expected.sort_by_key(|mv| (mv.from(), mv.to(), mv.is_promotion()));
+        assert_eq!(tactical, expected);
+
+        if depth > 0 {
+            for mv in all {
+                visit(&board.after_unchecked(mv), depth - 1);
+            }
+        }
+    }
+    let board = "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PP1PPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+        .parse()
+        .unwrap();
+    visit(&board, 1);
+    visit(&Board::startpos(), 2);
+    // the Silver promotes when it leaves the zone, and the Rook checks the King
+    let board = "4k4/9/2S6/9/9/9/9/9/r3K4 b - 1".parse().unwrap();
+    visit(&board, 2);
+}
+
+#[test]
+fn gives_check_matches_playing_the_move() {
+    let mut rng = rng();
+    for sfen in [
+        SFEN_STARTPOS,
+        "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+        "+P+n1g1+Pp+P1/2gg+p+s+pLn/1gppP1S+Pp/1+s+PPSPPPk/N1L2N+PL1/6L1+P/9/9/9 b - 1",
+    ] {
+        let mut board = Board::tsume(sfen)
+            .or_else(|_| Board::from_sfen(sfen))
+            .unwrap();
+        for _ in 0..100 {
+            let mut moves = Vec::new();
+            board.generate_moves(|mvs| {
+                moves.extend(mvs);
+                false
+            });
+            for &mv in &moves {
+                let gives_check = !board.after_unchecked(mv).checkers().is_empty();
+                assert_eq!(board.gives_check(mv), gives_check, "{board} {mv}");
+            }
+            let Some(&mv) = moves.choose(&mut rng) else {
+                break;
+            };
+            board.play_unchecked(mv);
+        }
+    }
+}
+
+#[test]
+fn useful_non_promotions() {
+    // Returns the number of non-promotions left out
+    fn check(board: &Board) -> usize {
+        let mut all = Vec::new();
+        board.generate_moves(|mvs| {
+            all.extend(mvs);
+            false
+        });
+        let mut useful = Vec::new();
+        board.generate_moves_with_non_promotions(NonPromotions::Useful, |mvs| {
+            useful.extend(mvs);
+            false
+        });
+        let color = board.side_to_move();
+        let mut expected: Vec<Move> = all
+            .iter()
+            .copied()
+            .filter(|&mv| {
+                let Move::BoardMove {
+                    from,
+                    to,
+                    promotion: false,
+                } = mv
+                else {
+                    return true;
+                };
+                let useless = match board.piece_on(from).unwrap() {
+                    Piece::Pawn | Piece::Bishop | Piece::Rook => true,
+                    Piece::Lance => to.rank() == Rank::H.relative_to(color),
+                    _ => false,
+                };
+                !(useless
+                    && all.contains(&Move::BoardMove {
+                        from,
+                        to,
+                        promotion: true,
+                    }))
+            })
+            .collect();
+        useful.sort();
+        expected.sort();
+        assert_eq!(useful, expected, "{board}");
+        all.len() - useful.len()
+    }
+
+    for (sfen, useless) in [
+        // Pawns and Lances to the third, second and last rank
+        ("4k4/9/P5L2/1P5L1/9/9/9/9/4K4 b - 1", true),
+        ("4k4/9/9/9/9/1p5l1/p5l2/9/4K4 w - 1", true),
+        // a Rook and a Bishop entering, inside and leaving the zone
+        ("4k4/1R7/9/9/9/6B2/9/9/4K4 b - 1", true),
+        ("4k4/9/9/2b6/9/9/9/1r7/4K4 w - 1", true),
+        // Knights and Silvers, whose non-promotions are always useful
+        ("4k4/9/9/2N1S4/9/9/9/9/4K4 b - 1", false),
+    ] {
+        assert_eq!(check(&sfen.parse().unwrap()) > 0, useless, "{sfen}");
+    }
+    for board in random_positions(0x2783, 20, 150) {
+        check(&board);
+    }
+}
+
+#[test]
+fn piece_on_matches_the_bitboards() {
+    let mut rng = rng();
+    for _ in 0..10 {
+        let mut board = Board::startpos();
+        for _ in 0..200 {
+            for square in Square::ALL {
+                let expected = Piece::ALL
+                    .into_iter()
+                    .find(|&piece| board.pieces(piece).has(square));
+                assert_eq!(board.piece_on(square), expected, "{board} {square}");
+            }
+            let moves = board.legal_moves();
+            let Some(&mv) = moves.choose(&mut rng) else {
+                break;
+            };
+            board.play_unchecked(mv);
+        }
+    }
+}
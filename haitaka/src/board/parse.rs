@@ -4,51 +4,208 @@ use core::str::FromStr;
 use super::{Piece, ZobristBoard};
 use crate::*;
 
-helpers::simple_error! {
-    /// An error while parsing the SFEN string.
-    pub enum SFENParseError {
-        InvalidBoard = "The board representation is invalid.",
-        InvalidHands = "The hands representation is invalid",
-        InvalidSideToMove = "The side to move is invalid.",
-        InvalidMoveNumber = "The move number is invalid.",
-        MissingField = "The SFEN string is missing a field.",
-        TooManyFields = "The SFEN string has too many fields."
+/// A field of a SFEN string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SfenField {
+    /// The board.
+    Board,
+    /// The side to move.
+    SideToMove,
+    /// The pieces in hand.
+    Hands,
+    /// The move number.
+    MoveNumber,
+}
+
+impl Display for SfenField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Board => "board",
+            Self::SideToMove => "side to move",
+            Self::Hands => "hands",
+            Self::MoveNumber => "move number",
+        })
+    }
+}
+
+/// An error while parsing a SFEN string.
+///
+/// Syntax errors tell which field failed, and at which byte offset in the SFEN string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SfenError {
+    /// A field is invalid.
+    InvalidField {
+        /// The invalid field.
+        field: SfenField,
+        /// The byte offset of the first character that could not be parsed, or of the
+        /// field if the field as a whole is invalid.
+        offset: usize,
+    },
+    /// A field is missing.
+    MissingField {
+        /// The first missing field.
+        field: SfenField,
+        /// The length of the SFEN string.
+        offset: usize,
+    },
+    /// The SFEN string has more than four fields.
+    TooManyFields {
+        /// The byte offset of the first extra field.
+        offset: usize,
+    },
+    /// The SFEN string is well-formed, but the position is not valid.
+    InvalidPosition(PositionError),
+}
+
+impl SfenError {
+    /// The field of a syntax error.
+    pub fn field(&self) -> Option<SfenField> {
+        match *self {
+            Self::InvalidField { field, .. } | Self::MissingField { field, .. } => Some(field),
+            _ => None,
+        }
+    }
+
+    /// The byte offset of a syntax error in the SFEN string.
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            Self::InvalidField { offset, .. }
+            | Self::MissingField { offset, .. }
+            | Self::TooManyFields { offset } => Some(offset),
+            Self::InvalidPosition(_) => None,
+        }
     }
 }
 
+impl Display for SfenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            Self::InvalidField { field, offset } => {
+                write!(f, "The {field} field is invalid (at byte {offset}).")
+            }
+            Self::MissingField { field, .. } => write!(f, "The {field} field is missing."),
+            Self::TooManyFields { offset } => {
+                write!(f, "The SFEN string has too many fields (at byte {offset}).")
+            }
+            Self::InvalidPosition(error) => write!(f, "The position is invalid: {error}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SfenError {}
+
+/// The old name of [`SfenError`].
+#[deprecated(since = "0.4.0", note = "use `SfenError` instead")]
+pub type SFENParseError = SfenError;
+
 impl Board {
     /// Parse a SFEN string. You can also parse the board with [`FromStr`].
     ///
+    /// Apart from the syntax, this checks that
+    /// - both sides have exactly one King and the Kings are not next to each other,
+    /// - no Pawn, Lance or Knight stands on a square from which it can never move,
+    /// - the side that is not to move is not in check,
+    /// - the side to move is not in check by more than two pieces,
+    /// - no side has more than 39 pieces on the board, and there are not more pieces of
+    ///   a type on the board and in both hands than in a game set (fewer is fine, for
+    ///   handicap games and problems),
+    /// - the move number is not zero.
+    ///
+    /// It does _not_ check that neither side has two unpromoted Pawns on one file (nifu),
+    /// since such positions occur in game records that ended with the illegal move. Use
+    /// [`Board::from_sfen_validated`] to reject them as well.
+    ///
+    /// The move number may be left out. It is then 1 if Black is to move, and 2 if White
+    /// is to move. See [`Board::from_sfen_lenient`] for other deviations from the format.
+    ///
+    /// # Errors
+    /// Errors with the field and the byte offset of a syntax error, or with the
+    /// [`PositionError`] of an invalid position.
+    ///
     /// # Examples
     /// ```
     /// # use haitaka::*;
     /// const STARTPOS: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1";
     /// let board = Board::from_sfen(STARTPOS).unwrap();
     /// assert_eq!(format!("{}", board), STARTPOS);
+    ///
+    /// let error = Board::from_sfen("lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b 2X 1");
+    /// assert_eq!(
+    ///     error,
+    ///     Err(SfenError::InvalidField { field: SfenField::Hands, offset: 61 })
+    /// );
     /// ```
-    pub fn from_sfen(sfen: &str) -> Result<Self, SFENParseError> {
-        let mut board = Self::parse(sfen)?;
+    pub fn from_sfen(sfen: &str) -> Result<Self, SfenError> {
+        let mut board = Self::parse_unvalidated(sfen, false)?;
         board.validate_after_parse(false)?;
         Ok(board)
     }
 
-    fn validate_after_parse(&mut self, tsume: bool) -> Result<(), SFENParseError> {
-        use SFENParseError::*;
-        if !self.move_number_is_valid() {
-            return Err(InvalidMoveNumber);
-        }
-        if !self.is_valid(tsume) {
-            return Err(InvalidBoard);
-        }
+    /// Parse a SFEN string and reject every position that can't occur in a legal game.
+    ///
+    /// This runs all checks of [`Board::from_sfen`] and also rejects positions where a
+    /// side has two unpromoted Pawns on one file (nifu). Use this for SFEN strings from
+    /// untrusted sources, such as users of a service.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::from_sfen_validated(SFEN_STARTPOS).unwrap();
+    /// assert_eq!(board, Board::startpos());
+    ///
+    /// let nifu = "lnsgkgsnl/1r5b1/ppppppppp/9/9/P8/P1PPPPPPP/1B5R1/LNSGKGSNL b - 1";
+    /// assert!(Board::from_sfen(nifu).is_ok());
+    /// assert!(matches!(
+    ///     Board::from_sfen_validated(nifu),
+    ///     Err(SfenError::InvalidPosition(PositionError::DoublePawn { .. }))
+    /// ));
+    /// ```
+    pub fn from_sfen_validated(sfen: &str) -> Result<Self, SfenError> {
+        let board = Self::from_sfen(sfen)?;
+        board
+            .validate_pawn_files()
+            .map_err(SfenError::InvalidPosition)?;
+        Ok(board)
+    }
+
+    /// Parse a SFEN string, accepting common deviations from the format.
+    ///
+    /// SFEN strings from other tools and databases do not always follow the format to
+    /// the letter. On top of [`Board::from_sfen`], this accepts
+    /// - any whitespace between the fields, and before and after them,
+    /// - a missing hands field (no pieces in hand) and a missing move number,
+    /// - a move number of 0 (which becomes 1),
+    /// - a count of 0 in the hands (no pieces), and a piece that is listed more than once
+    ///   (the counts add up).
+    ///
+    /// Both functions accept the pieces in hand in any order. The position is checked as
+    /// with [`Board::from_sfen`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let sfen = "  lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL\tb  0G \n";
+    /// assert!(Board::from_sfen(sfen).is_err());
+    /// assert_eq!(Board::from_sfen_lenient(sfen).unwrap(), Board::startpos());
+    ///
+    /// let sfen = "4k4/9/4P4/9/9/9/9/9/4K4 b PGP 0";
+    /// let board = Board::from_sfen_lenient(sfen).unwrap();
+    /// assert_eq!(board.to_string(), "4k4/9/4P4/9/9/9/9/9/4K4 b G2P 1");
+    /// ```
+    pub fn from_sfen_lenient(sfen: &str) -> Result<Self, SfenError> {
+        let mut board = Self::parse_unvalidated(sfen, true)?;
+        board.validate_after_parse(false)?;
+        Ok(board)
+    }
+
+    pub(super) fn validate_after_parse(&mut self, tsume: bool) -> Result<(), SfenError> {
+        self.validate_position(tsume)
+            .map_err(SfenError::InvalidPosition)?;
         let (checkers, pinned) = self.calculate_checkers_and_pins(self.side_to_move());
         self.checkers = checkers;
         self.pinned = pinned;
-        if !self.checkers_and_pins_are_valid() {
-            return Err(InvalidBoard);
-        }
-        if !self.piece_counts_are_valid() {
-            return Err(InvalidBoard);
-        }
         Ok(())
     }
 
@@ -57,10 +214,12 @@ impl Board {
     /// This function supports a custom SFEN format in which (1) the Black King is
     /// not required to be present and (2) all remaining pieces that are
     /// not on the board and not in Black's hand are automatically assigned to White's
-    /// hand.
+    /// hand. White must have a King. See [`BoardMode`] for the rules of positions without
+    /// a Black King. Pieces that the SFEN already puts in White's hand are kept, so the
+    /// SFEN of a Tsume Shogi board parses to the same board again.
     ///
     /// By convention we require Black to be the side-to-move, otherwise it returns a
-    /// SFENParseError::InvalidSideToMove.
+    /// [`SfenError::InvalidField`] for the side to move.
     ///
     /// # Examples
     ///
@@ -68,18 +227,25 @@ impl Board {
     /// use haitaka::*;
     /// let sfen = "lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1";
     /// // from_sfen will fail - since there is only one King on board
-    /// assert!(matches!(Board::from_sfen(sfen), Err(SFENParseError::InvalidBoard)));
+    /// assert!(matches!(
+    ///     Board::from_sfen(sfen),
+    ///     Err(SfenError::InvalidPosition(PositionError::KingCount { .. }))
+    /// ));
     /// // tsume will succeed
     /// let board = Board::tsume(sfen).unwrap();
     /// assert!(board.has(Color::White, Piece::King));
     /// assert!(!board.has(Color::Black, Piece::King));
     /// assert_eq!(board.num_in_hand(Color::White, Piece::Gold), 2);
     /// assert_eq!(board.num_in_hand(Color::White, Piece::Silver), 3);
+    /// assert_eq!(Board::tsume(&board.to_string()).unwrap(), board);
     /// ```
-    pub fn tsume(sfen: &str) -> Result<Self, SFENParseError> {
-        let mut board = Self::parse(sfen)?;
+    pub fn tsume(sfen: &str) -> Result<Self, SfenError> {
+        let mut board = Self::parse_unvalidated(sfen, false)?;
         if board.side_to_move() != Color::Black {
-            Err(SFENParseError::InvalidSideToMove)
+            Err(SfenError::InvalidField {
+                field: SfenField::SideToMove,
+                offset: sfen.find(' ').map_or(0, |offset| offset + 1),
+            })
         } else {
             board.piece_counts_make_valid();
             board.validate_after_parse(true)?;
@@ -87,9 +253,11 @@ impl Board {
         }
     }
 
-    fn parse(sfen: &str) -> Result<Self, SFENParseError> {
-        use SFENParseError::*;
-
+    // Parse a SFEN string without validating the position.
+    //
+    // This doesn't compute checkers and pins, so the result can only be used
+    // after `validate_after_parse` (or for its hash).
+    pub(crate) fn parse_unvalidated(sfen: &str, lenient: bool) -> Result<Self, SfenError> {
         let mut board = Self {
             inner: ZobristBoard::empty(),
             pinned: BitBoard::EMPTY,
@@ -98,16 +266,36 @@ impl Board {
             move_number: 0,
         };
 
-        let mut parts = sfen.split(' ');
-        let mut next = || parts.next().ok_or(MissingField);
+        // the fields are slices of `sfen`
+        let offset = |field: &str| field.as_ptr() as usize - sfen.as_ptr() as usize;
+        let invalid = |field, s: &str, at: usize| SfenError::InvalidField {
+            field,
+            offset: offset(s) + at,
+        };
+        let missing = |field| SfenError::MissingField {
+            field,
+            offset: sfen.len(),
+        };
+        let mut parts = sfen
+            .split(|c: char| if lenient { c.is_whitespace() } else { c == ' ' })
+            .filter(|part| !lenient || !part.is_empty());
 
-        Self::parse_board(&mut board, next()?, true).map_err(|_| InvalidBoard)?;
-        Self::parse_side_to_move(&mut board, next()?).map_err(|_| InvalidSideToMove)?;
-        Self::parse_hands(&mut board, next()?).map_err(|_| InvalidHands)?;
+        let s = parts.next().ok_or(missing(SfenField::Board))?;
+        Self::parse_board(&mut board, s, true).map_err(|at| invalid(SfenField::Board, s, at))?;
+        let s = parts.next().ok_or(missing(SfenField::SideToMove))?;
+        Self::parse_side_to_move(&mut board, s)
+            .map_err(|_| invalid(SfenField::SideToMove, s, 0))?;
+        match parts.next() {
+            Some(s) => Self::parse_hands(&mut board, s, lenient)
+                .map_err(|at| invalid(SfenField::Hands, s, at))?,
+            None if lenient => {}
+            None => return Err(missing(SfenField::Hands)),
+        }
 
         // Parse the move number if it exists, otherwise set a default value
-        if let Some(move_number_str) = parts.next() {
-            Self::parse_move_number(&mut board, move_number_str).map_err(|_| InvalidMoveNumber)?;
+        if let Some(s) = parts.next() {
+            Self::parse_move_number(&mut board, s, lenient)
+                .map_err(|_| invalid(SfenField::MoveNumber, s, 0))?;
         } else {
             // Default move number: 1 if Black to move, 2 if White to move
             board.move_number = if board.side_to_move() == Color::Black {
@@ -117,96 +305,103 @@ impl Board {
             };
         }
 
-        if parts.next().is_some() {
-            return Err(TooManyFields);
+        if let Some(s) = parts.next() {
+            return Err(SfenError::TooManyFields { offset: offset(s) });
         }
 
         Ok(board)
     }
 
     /// Parse the board representation of a SFEN string.
-    fn parse_board(board: &mut Board, s: &str, strict: bool) -> Result<(), ()> {
-        let mut last_rank: Option<usize> = None;
-        for (rank, row) in s.split('/').enumerate() {
-            last_rank = Some(rank);
-            let rank = Rank::try_index(rank).ok_or(())?;
+    ///
+    /// Errors with the offset of the error in `s`.
+    fn parse_board(board: &mut Board, s: &str, strict: bool) -> Result<(), usize> {
+        let mut ranks = 0;
+        let mut start = 0;
+        for row in s.split('/') {
+            let rank = Rank::try_index(ranks).ok_or(start)?;
+            ranks += 1;
             let mut file = File::NUM;
             let mut prom: bool = false;
 
-            for c in row.chars() {
+            for (i, c) in row.char_indices() {
+                let at = start + i;
                 if let Some(offset) = c.to_digit(10) {
                     if prom {
-                        return Err(());
+                        return Err(at);
                     };
-                    file -= offset as usize; // let it panic!
+                    file = file.checked_sub(offset as usize).ok_or(at)?;
                 } else if c == '+' {
                     if prom {
-                        return Err(());
+                        return Err(at);
                     };
                     prom = true;
                 } else if let Some((piece, color)) = Piece::try_from_char(c) {
-                    file -= 1; // let it panic
+                    file = file.checked_sub(1).ok_or(at)?;
                     let piece = if prom { piece.promote() } else { piece };
-                    let square = Square::new(File::try_index(file).ok_or(())?, rank);
+                    if prom && !piece.is_promoted() {
+                        // Golds and Kings don't promote
+                        return Err(at);
+                    }
+                    let square = Square::new(File::try_index(file).ok_or(at)?, rank);
                     board.unchecked_put(color, piece, square);
                     prom = false;
                 } else {
-                    return Err(());
+                    return Err(at);
                 }
             }
-            if file != 0 {
-                return Err(());
+            if file != 0 || prom {
+                return Err(start + row.len());
             }
+            start += row.len() + 1;
         }
-        if let Some(last_rank) = last_rank {
-            if last_rank == 8 || !strict {
-                return Ok(());
-            }
+        if ranks == Rank::NUM || !strict {
+            return Ok(());
         }
-        // If we didn't see any ranks, it's unconditionally an error
-        Err(())
+        Err(s.len())
     }
 
     /// Parse the SFEN hands.
-    fn parse_hands(board: &mut Board, s: &str) -> Result<(), ()> {
-        let mut empty = false;
-        let mut found: bool = false;
-        let mut count: u32 = 0;
-
-        for c in s.chars() {
-            if !empty {
-                if c == '-' {
-                    empty = true;
-                } else if let Some(num) = c.to_digit(10) {
-                    count = 10 * count + num;
-                } else if let Some((piece, color)) = Piece::try_from_char(c) {
-                    if count > u8::MAX as u32 {
-                        return Err(()); // way... too large
-                    }
-                    board.unchecked_set_hand(
-                        color,
-                        piece,
-                        if count > 0 { count as u8 } else { 1u8 },
-                    );
-                    count = 0;
-                    found = true;
-                } else {
-                    return Err(());
+    ///
+    /// Errors with the offset of the error in `s`. In lenient mode, counts of 0 and
+    /// pieces that are listed more than once are accepted.
+    fn parse_hands(board: &mut Board, s: &str, lenient: bool) -> Result<(), usize> {
+        if s == "-" {
+            return Ok(());
+        }
+        if s.is_empty() {
+            return Err(0);
+        }
+
+        // the count, and the offset where it starts
+        let mut count: Option<(u32, usize)> = None;
+        for (i, c) in s.char_indices() {
+            if let Some(digit) = c.to_digit(10) {
+                let (n, start) = count.unwrap_or((0, i));
+                let n = n.checked_mul(10).and_then(|n| n.checked_add(digit));
+                count = Some((n.ok_or(start)?, start));
+            } else if let Some((piece, color)) = Piece::try_from_char(c) {
+                if piece == Piece::King {
+                    return Err(i);
+                }
+                let (n, start) = count.take().unwrap_or((1, i));
+                let held = board.num_in_hand(color, piece) as u32;
+                if !lenient && (n == 0 || held > 0) {
+                    // a count of 0, or a piece listed twice
+                    return Err(start);
+                }
+                let total = held + n;
+                if total > Piece::MAX_HAND[piece as usize] as u32 {
+                    return Err(start);
                 }
+                board.unchecked_set_hand(color, piece, total as u8);
             } else {
-                // we read another '-'
-                return Err(());
+                return Err(i);
             }
         }
-
-        if empty == found {
-            // both are false should not be possible, given non-empty input string;
-            // both true, implies an ill-formatted input string (containing pieces and '-')
-            return Err(());
-        }
-        if count > 0 {
-            // we read a dangling number without associated piece
-            return Err(());
+        if let Some((_, start)) = count {
+            // a dangling number without associated piece
+            return Err(start);
         }
 
         Ok(())
@@ -220,17 +415,20 @@ impl Board {
         Ok(())
     }
 
-    fn parse_move_number(board: &mut Board, s: &str) -> Result<(), ()> {
+    fn parse_move_number(board: &mut Board, s: &str, lenient: bool) -> Result<(), ()> {
         board.move_number = s.parse().map_err(|_| ())?;
         if board.move_number == 0 {
-            return Err(());
+            if !lenient {
+                return Err(());
+            }
+            board.move_number = 1;
         }
         Ok(())
     }
 }
 
 impl FromStr for Board {
-    type Err = SFENParseError;
+    type Err = SfenError;
 
     /// Parse a SFEN string.
     ///
@@ -244,10 +442,7 @@ impl FromStr for Board {
     /// assert_eq!(format!("{}", board), STARTPOS);
     /// ```
     fn from_str(sfen: &str) -> Result<Self, Self::Err> {
-        match Self::from_sfen(sfen) {
-            Ok(board) => Ok(board),
-            Err(error) => Err(error),
-        }
+        Self::from_sfen(sfen)
     }
 }
 
@@ -257,15 +452,55 @@ impl Display for Board {
     /// # Examples
     /// ```
     /// # use haitaka::*;
-    /// let mut board: Board = SFEN_6PIECE_HANDICAP.parse().unwrap();
-    /// assert_eq!(format!("{}", board), SFEN_6PIECE_HANDICAP);
-    /// board = SFEN_4PIECE_HANDICAP.parse().unwrap();
-    /// assert_eq!(format!("{}", board), SFEN_4PIECE_HANDICAP);
-    /// board = SFEN_2PIECE_HANDICAP.parse().unwrap();
-    /// assert_eq!(format!("{}", board), SFEN_2PIECE_HANDICAP);
+    /// for handicap in Handicap::ALL {
+    ///     let board: Board = handicap.sfen().parse().unwrap();
+    ///     assert_eq!(format!("{}", board), handicap.sfen());
+    /// }
     /// ```
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        // BOARD
+        self.write_board_field(f)?;
+        write!(f, " {} ", self.side_to_move())?;
+        self.write_hands_field(f)?;
+        write!(f, " {}", self.move_number)
+    }
+}
+
+impl Board {
+    /// The board field of the SFEN string, without the side to move, the hands and
+    /// the move number.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert_eq!(board.sfen_board_field(), "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL");
+    /// ```
+    pub fn sfen_board_field(&self) -> String {
+        let mut s = String::new();
+        self.write_board_field(&mut s).unwrap();
+        s
+    }
+
+    /// The hands field of the SFEN string, or `-` if both hands are empty.
+    ///
+    /// The pieces are listed in USI order, with all Black pieces before all White pieces.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().sfen_hands_field(), "-");
+    /// let board: Board = "lnsgkgsnl/1r7/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 5"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(board.sfen_hands_field(), "Bb");
+    /// ```
+    pub fn sfen_hands_field(&self) -> String {
+        let mut s = String::new();
+        self.write_hands_field(&mut s).unwrap();
+        s
+    }
+
+    fn write_board_field(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result {
         for &rank in Rank::ALL.iter() {
             let mut empty = 0;
             for &file in File::ALL.iter().rev() {
@@ -287,47 +522,40 @@ impl Display for Board {
                 write!(f, "/")?;
             }
         }
+        Ok(())
+    }
 
-        // STM
-        write!(f, " {}", self.side_to_move())?;
-
-        // HANDS
+    fn write_hands_field(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result {
         if self.is_hand_empty(Color::White) && self.is_hand_empty(Color::Black) {
-            write!(f, " -")?;
-        } else {
-            write!(f, " ")?;
-            // http://hgm.nubati.net/usi.html
-            // "The pieces are always listed in the order rook, bishop, gold, silver, knight, lance, pawn;
-            // and with all black pieces before all white pieces."
-            let pieces: [Piece; 7] = [
-                Piece::Rook,
-                Piece::Bishop,
-                Piece::Gold,
-                Piece::Silver,
-                Piece::Knight,
-                Piece::Lance,
-                Piece::Pawn,
-            ];
-
-            for color in [Color::Black, Color::White] {
-                let hand = self.hand(color);
-                for piece in pieces {
-                    let count = hand[piece as usize];
-                    if count > 0 {
-                        let piece_str = piece.to_str(color);
-                        if count > 1 {
-                            write!(f, "{}{}", count, piece_str)?;
-                        } else {
-                            write!(f, "{}", piece_str)?;
-                        }
+            return write!(f, "-");
+        }
+        // http://hgm.nubati.net/usi.html
+        // "The pieces are always listed in the order rook, bishop, gold, silver, knight, lance, pawn;
+        // and with all black pieces before all white pieces."
+        let pieces: [Piece; 7] = [
+            Piece::Rook,
+            Piece::Bishop,
+            Piece::Gold,
+            Piece::Silver,
+            Piece::Knight,
+            Piece::Lance,
+            Piece::Pawn,
+        ];
+
+        for color in [Color::Black, Color::White] {
+            let hand = self.hand(color);
+            for piece in pieces {
+                let count = hand[piece as usize];
+                if count > 0 {
+                    let piece_str = piece.to_str(color);
+                    if count > 1 {
+                        write!(f, "{}{}", count, piece_str)?;
+                    } else {
+                        write!(f, "{}", piece_str)?;
                     }
                 }
             }
         }
-
-        // MOVE_NUMBER
-        write!(f, " {}", self.move_number)?;
-
         Ok(())
     }
 }
@@ -354,4 +582,143 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn sfen_fields_match_display() {
+        for sfen in include_str!("test_data/valid.sfens").lines() {
+            let board = Board::from_sfen(sfen).unwrap();
+            let fields = format!(
+                "{} {} {} {}",
+                board.sfen_board_field(),
+                board.side_to_move(),
+                board.sfen_hands_field(),
+                board.move_number()
+            );
+            assert_eq!(fields, board.to_string());
+        }
+    }
+
+    #[test]
+    fn validated_sfens() {
+        for sfen in include_str!("test_data/valid.sfens").lines() {
+            assert_eq!(
+                Board::from_sfen_validated(sfen).unwrap(),
+                Board::from_sfen(sfen).unwrap()
+            );
+        }
+        for sfen in include_str!("test_data/invalid.sfens").lines() {
+            assert!(Board::from_sfen_validated(sfen).is_err());
+        }
+        // a Pawn on the same file as a promoted Pawn is fine
+        let sfen = "lnsgkgsnl/1r5b1/ppppppppp/9/9/+P8/1PPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+        assert!(Board::from_sfen_validated(sfen).is_ok());
+        // but not two Pawns, of either side
+        let sfen = "lnsgkgsnl/1r5b1/p1ppppppp/p8/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+        assert!(matches!(
+            Board::from_sfen_validated(sfen),
+            Err(SfenError::InvalidPosition(PositionError::DoublePawn { .. }))
+        ));
+    }
+
+    #[test]
+    fn errors_have_locations() {
+        let board = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL";
+        let cases = [
+            // a tenth rank, and a rank with ten files
+            (format!("{board}/9 b - 1"), Some(SfenField::Board), Some(58)),
+            (
+                "lnsgkgsnl1/9 b - 1".to_string(),
+                Some(SfenField::Board),
+                Some(9),
+            ),
+            // a short rank, and a missing rank
+            (
+                "lnsgkgsn/9 b - 1".to_string(),
+                Some(SfenField::Board),
+                Some(8),
+            ),
+            (
+                "9/9/9/9/9/9/9/9 b - 1".to_string(),
+                Some(SfenField::Board),
+                Some(15),
+            ),
+            (
+                format!("{board} x - 1"),
+                Some(SfenField::SideToMove),
+                Some(58),
+            ),
+            (format!("{board} b"), Some(SfenField::Hands), Some(59)),
+            (format!("{board} b 0P 1"), Some(SfenField::Hands), Some(60)),
+            (format!("{board} b P2P 1"), Some(SfenField::Hands), Some(61)),
+            (format!("{board} b 19P 1"), Some(SfenField::Hands), Some(60)),
+            (format!("{board} b P2 1"), Some(SfenField::Hands), Some(61)),
+            (
+                format!("{board} b - 0"),
+                Some(SfenField::MoveNumber),
+                Some(62),
+            ),
+            (format!("{board} b - 1 "), None, Some(64)),
+        ];
+        for (sfen, field, offset) in cases {
+            let error = Board::from_sfen(&sfen).unwrap_err();
+            assert_eq!((error.field(), error.offset()), (field, offset), "{sfen}");
+        }
+
+        let error = Board::from_sfen(&format!("{board} b - 1 1")).unwrap_err();
+        assert_eq!(error, SfenError::TooManyFields { offset: 64 });
+        let error = Board::from_sfen("4k4/9/9/9/9/9/9/9/4K4 b 19P 1").unwrap_err();
+        assert!(matches!(error, SfenError::InvalidField { .. }));
+        let error = Board::from_sfen("4k4/9/9/9/9/9/9/9/R3K3R b R 1").unwrap_err();
+        assert!(matches!(
+            error,
+            SfenError::InvalidPosition(PositionError::PieceCount { .. })
+        ));
+    }
+
+    #[test]
+    fn lenient_sfens() {
+        for sfen in include_str!("test_data/valid.sfens").lines() {
+            assert_eq!(
+                Board::from_sfen_lenient(sfen).unwrap(),
+                Board::from_sfen(sfen).unwrap()
+            );
+        }
+        for sfen in include_str!("test_data/invalid.sfens").lines() {
+            let lenient = Board::from_sfen_lenient(sfen);
+            // the only invalid SFEN that is accepted has move number 0
+            assert!(lenient.is_err() || sfen.ends_with(" 0"), "{sfen}");
+        }
+
+        let expected = Board::from_sfen("4k4/9/9/9/9/9/9/9/4K4 w 2P3p 1").unwrap();
+        for sfen in [
+            "4k4/9/9/9/9/9/9/9/4K4 w 2P3p 1",
+            "4k4/9/9/9/9/9/9/9/4K4 w 2P3p 0",
+            "4k4/9/9/9/9/9/9/9/4K4 w P0Gp2pP 1",
+            "4k4/9/9/9/9/9/9/9/4K4\tw 2P3p\r\n1",
+        ] {
+            assert_eq!(Board::from_sfen_lenient(sfen).unwrap(), expected, "{sfen}");
+        }
+        // without a move number, White's first move is move 2
+        let board = Board::from_sfen_lenient(" 4k4/9/9/9/9/9/9/9/4K4 w").unwrap();
+        assert_eq!(board.move_number(), 2);
+        assert!(board.is_hand_empty(Color::Black));
+
+        // offsets are still those in the original string
+        let error = Board::from_sfen_lenient("  4k4/9/9/9/9/9/9/9/4K4 w 2Q").unwrap_err();
+        assert_eq!(error.offset(), Some(27));
+        let error = Board::from_sfen_lenient("4k4/9/9/9/9/9/9/9/4K4 w 2p 1 1").unwrap_err();
+        assert_eq!(error, SfenError::TooManyFields { offset: 29 });
+    }
+
+    #[test]
+    fn tsume_keeps_whites_hand() {
+        let board = Board::tsume("8k/9/9/9/9/9/9/9/9 b 2G2p 1").unwrap();
+        assert_eq!(board.num_in_hand(Color::White, Piece::Pawn), 18);
+        assert_eq!(board.num_in_hand(Color::White, Piece::Gold), 2);
+        assert_eq!(Board::tsume(&board.to_string()).unwrap(), board);
+
+        // too many pieces are an error, not an overflow
+        assert!(Board::tsume("8k/9/9/9/9/9/9/9/9 b 2G19p 1").is_err());
+        assert!(Board::tsume("8k/9/9/9/9/9/9/9/9 b 5G 1").is_err());
+    }
 }
@@ -0,0 +1,116 @@
+//! Game phase estimation
+use crate::*;
+
+/// Weights of the pieces in hand for [`Board::phase_value`], indexed by [`Piece`].
+///
+/// Only the unpromoted pieces (other than the King) can be held in hand.
+pub const PHASE_WEIGHTS: [u8; Piece::NUM] = [
+    1, // Pawn
+    2, // Lance
+    2, // Knight
+    3, // Silver
+    5, // Bishop
+    5, // Rook
+    3, // Gold
+    0, // King
+    0, 0, 0, 0, 0, 0,
+];
+
+// Weight of a promoted piece on the board.
+const PROMOTED_WEIGHT: u32 = 2;
+
+// Total weight at which the endgame is fully reached.
+const ENDGAME_WEIGHT: u32 = 24;
+
+/// The phase of a game, as estimated by [`Board::phase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Phase {
+    /// Few or no pieces have been exchanged.
+    Opening,
+    /// Some pieces have been exchanged.
+    MiddleGame,
+    /// Many pieces have been exchanged or promoted, or a King has entered the opponent's camp.
+    EndGame,
+}
+
+impl Phase {
+    /// Positions with a [`Board::phase_value`] below this value are in the opening.
+    pub const MIDDLE_GAME_START: f32 = 0.25;
+    /// Positions with a [`Board::phase_value`] of at least this value are in the endgame.
+    pub const ENDGAME_START: f32 = 0.75;
+}
+
+impl Board {
+    /// Estimate the game phase as a value from 0.0 (opening) to 1.0 (endgame).
+    ///
+    /// Pieces only leave the board when they are captured, so the pieces in hand are a
+    /// good measure of how far the game has progressed. The pieces in hand of both sides
+    /// are weighted by [`PHASE_WEIGHTS`] (1 for a Pawn up to 5 for a Bishop or Rook) and
+    /// every promoted piece on the board adds 2. The sum is scaled so that 24 (for
+    /// instance both Bishops, two Silvers and a few Pawns) gives 1.0, and the value is
+    /// capped at 1.0. If a King has entered the opponent's camp, the value is 1.0.
+    ///
+    /// The value can decrease, since pieces in hand are dropped back on the board.
+    /// It is meant for interpolating evaluation terms between the opening and the endgame.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().phase_value(), 0.0);
+    ///
+    /// // After a Bishop exchange
+    /// let board: Board = "lnsgkgsnl/1r7/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 5"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(board.phase_value(), 10.0 / 24.0);
+    /// ```
+    pub fn phase_value(&self) -> f32 {
+        if self.king_entered(Color::Black) || self.king_entered(Color::White) {
+            return 1.0;
+        }
+
+        let mut weight = 0;
+        for hand in self.hands() {
+            for (&count, &piece_weight) in hand.iter().zip(&PHASE_WEIGHTS) {
+                weight += count as u32 * piece_weight as u32;
+            }
+        }
+        for piece in Piece::ALL {
+            if piece.is_promoted() {
+                weight += self.pieces(piece).len() * PROMOTED_WEIGHT;
+            }
+        }
+        (weight.min(ENDGAME_WEIGHT) as f32) / ENDGAME_WEIGHT as f32
+    }
+
+    /// Estimate the game phase.
+    ///
+    /// The phase is derived from [`Board::phase_value`] with the thresholds
+    /// [`Phase::MIDDLE_GAME_START`] and [`Phase::ENDGAME_START`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().phase(), Phase::Opening);
+    ///
+    /// let board: Board = "lnsgkgsnl/1r7/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 5"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(board.phase(), Phase::MiddleGame);
+    ///
+    /// // The Black King has entered White's camp
+    /// let board: Board = "2K6/9/9/9/4k4/9/9/9/9 b - 1".parse().unwrap();
+    /// assert_eq!(board.phase(), Phase::EndGame);
+    /// ```
+    pub fn phase(&self) -> Phase {
+        let value = self.phase_value();
+        if value < Phase::MIDDLE_GAME_START {
+            Phase::Opening
+        } else if value < Phase::ENDGAME_START {
+            Phase::MiddleGame
+        } else {
+            Phase::EndGame
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Pins of either side
+use crate::*;
+
+/// A piece that is pinned to its King by a slider of the opponent.
+///
+/// The pinned piece can only move on `ray`, which has the squares between the King and
+/// the pinner, and the square of the pinner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pin {
+    /// The square of the pinned piece.
+    pub pinned: Square,
+    /// The square of the slider that pins it.
+    pub pinner: Square,
+    /// The squares the pinned piece can move to without exposing its King.
+    pub ray: BitBoard,
+}
+
+impl Board {
+    /// Iterate over the pins of the pieces of `color` to the King of `color`.
+    ///
+    /// Unlike [`Board::pinned`], this only has pieces of `color`, and it works for both
+    /// sides, not only for the side to move. There are no pins if `color` has no King.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let sfen = "ln3gsn1/7kl/3+B1p1p1/p4s2p/2P6/P2B3PP/1PNP+rPP2/2G3SK1/L4G1NL b G3Prs3p 65";
+    /// let board = Board::from_sfen(sfen).unwrap();
+    /// // The White Silver on 4d is pinned by the Black Bishop on 6f
+    /// let pins: Vec<Pin> = board.pins(Color::White).collect();
+    /// assert_eq!(pins.len(), 1);
+    /// assert_eq!(pins[0].pinned, Square::D4);
+    /// assert_eq!(pins[0].pinner, Square::F6);
+    /// let ray = [Square::F6, Square::E5, Square::D4, Square::C3];
+    /// assert_eq!(pins[0].ray, ray.into_iter().collect());
+    /// // It's Black's turn, so `pinned` does not have the Silver yet
+    /// assert_eq!(board.pinned(), BitBoard::EMPTY);
+    /// ```
+    pub fn pins(&self, color: Color) -> impl Iterator<Item = Pin> + '_ {
+        let Some(king) = self.colored_pieces(color, Piece::King).next_square() else {
+            return None.into_iter().flatten();
+        };
+        let bishops = self.pieces(Piece::Bishop) | self.pieces(Piece::PBishop);
+        let rooks = self.pieces(Piece::Rook) | self.pieces(Piece::PRook);
+        let lances = self.pieces(Piece::Lance);
+        let sliders = (bishop_pseudo_attacks(king) & bishops)
+            | (rook_pseudo_attacks(king) & rooks)
+            | (lance_pseudo_attacks(color, king) & lances);
+
+        let occupied = self.occupied();
+        let ours = self.colors(color);
+        let pins = (sliders & self.colors(!color))
+            .into_iter()
+            .filter_map(move |pinner| {
+                let ray = get_between_rays(pinner, king);
+                let between = ray & occupied;
+                if between.len() != 1 || (between & ours).is_empty() {
+                    return None;
+                }
+                Some(Pin {
+                    pinned: between.next_square()?,
+                    pinner,
+                    ray: ray | pinner.bitboard(),
+                })
+            });
+        Some(pins).into_iter().flatten()
+    }
+
+    /// Get the pieces of `color` that are pinned to the King of `color`.
+    ///
+    /// See [`Board::pins`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/4r4/9/9/9/4G4/4S4/4K4 b - 1".parse().unwrap();
+    /// assert_eq!(board.pinned_for(Color::Black), BitBoard::EMPTY);
+    /// let board: Board = "4k4/9/4r4/9/9/9/9/4S4/4K4 b - 1".parse().unwrap();
+    /// assert_eq!(board.pinned_for(Color::Black), Square::H5.bitboard());
+    /// assert_eq!(board.pinners(Color::Black), Square::C5.bitboard());
+    /// ```
+    pub fn pinned_for(&self, color: Color) -> BitBoard {
+        self.pins(color).fold(BitBoard::EMPTY, |pinned, pin| {
+            pinned | pin.pinned.bitboard()
+        })
+    }
+
+    /// Get the sliders of the opponent of `color` that pin a piece of `color` to the King
+    /// of `color`.
+    ///
+    /// See [`Board::pins`].
+    pub fn pinners(&self, color: Color) -> BitBoard {
+        self.pins(color).fold(BitBoard::EMPTY, |pinners, pin| {
+            pinners | pin.pinner.bitboard()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_of_both_sides() {
+        use Square::*;
+
+        for (board, color, expected) in [
+            // a pin of each side on the same file
+            (
+                "4k4/4s4/9/4R4/9/4r4/9/4G4/4K4 b - 1",
+                Color::Black,
+                &[(H5, F5)][..],
+            ),
+            (
+                "4k4/4s4/9/4R4/9/4r4/9/4G4/4K4 b - 1",
+                Color::White,
+                &[(B5, D5)],
+            ),
+            // two pins of one King
+            (
+                "4k4/9/4r4/9/b8/9/2S6/4G4/4K4 b - 1",
+                Color::Black,
+                &[(H5, C5), (G7, E9)],
+            ),
+            // a Lance pins in front of the King, but not behind it
+            ("4k4/9/4l4/9/4G4/4K4/9/9/9 b - 1", Color::Black, &[(E5, C5)]),
+            ("4k4/9/9/9/4K4/4G4/9/4l4/9 b - 1", Color::Black, &[]),
+            // two pieces between the King and the slider
+            ("4k4/9/4r4/4p4/9/9/9/4S4/4K4 b - 1", Color::Black, &[]),
+            ("4k4/9/4r4/9/9/9/4G4/4S4/4K4 b - 1", Color::Black, &[]),
+        ] {
+            let board: Board = board.parse().unwrap();
+            let mut pins: Vec<(Square, Square)> = board
+                .pins(color)
+                .map(|pin| (pin.pinned, pin.pinner))
+                .collect();
+            pins.sort();
+            assert_eq!(pins, expected, "{board} {color:?}");
+            for pin in board.pins(color) {
+                assert!(pin.ray.has(pin.pinned) && pin.ray.has(pin.pinner));
+                assert!(!pin.ray.has(board.king(color)));
+            }
+            let pinned: BitBoard = expected.iter().map(|&(pinned, _)| pinned).collect();
+            let pinners: BitBoard = expected.iter().map(|&(_, pinner)| pinner).collect();
+            assert_eq!(board.pinned_for(color), pinned);
+            assert_eq!(board.pinners(color), pinners);
+        }
+
+        // without a King, nothing is pinned
+        let board = Board::tsume("4k4/9/4r4/9/9/9/9/4S4/9 b G 1").unwrap();
+        assert_eq!(board.pins(Color::Black).count(), 0);
+    }
+
+    #[test]
+    fn pins_match_the_pins_of_the_side_to_move() {
+        for board in random_positions(0x919, 10, 150) {
+            let color = board.side_to_move();
+            assert_eq!(
+                board.pinned_for(color),
+                board.pinned() & board.colors(color),
+                "{board}"
+            );
+            for pin in board.pins(color) {
+                // the pinned piece can only move on the ray
+                board.generate_board_moves_for(pin.pinned.bitboard(), |moves| {
+                    assert!(moves.into_iter().all(|mv| pin.ray.has(mv.to())));
+                    false
+                });
+            }
+            // the pins of the other side are the pins after a null move
+            if let Some(null) = board.null_move() {
+                assert_eq!(
+                    board.pinned_for(!color),
+                    null.pinned() & board.colors(!color),
+                    "{board}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Move generation restricted to a region of the board
+use crate::*;
+
+/// A view of a [`Board`] that only generates moves within a region.
+///
+/// Created by [`Board::restrict_region`]. Board moves are only generated for pieces on
+/// squares in the region, and only to squares in the region. Drops are only generated
+/// on squares in the region. All generated moves are legal moves of the underlying board:
+/// the region only selects a subset of them, it does not remove the pieces outside the
+/// region (so those pieces still block, pin and give check).
+///
+/// This is meant for solving Tsume Shogi problems or mining patterns in a local area,
+/// for instance the squares around a King.
+#[derive(Debug, Clone, Copy)]
+pub struct RegionView<'a> {
+    board: &'a Board,
+    region: BitBoard,
+}
+
+// Limit the moves to target squares in the region.
+fn restrict(moves: PieceMoves, region: BitBoard) -> Option<PieceMoves> {
+    let moves = match moves {
+        PieceMoves::BoardMoves {
+            color,
+            piece,
+            from,
+            to,
+            prom_status,
+        } => PieceMoves::BoardMoves {
+            color,
+            piece,
+            from,
+            to: to & region,
+            prom_status,
+        },
+        PieceMoves::Drops { color, piece, to } => PieceMoves::Drops {
+            color,
+            piece,
+            to: to & region,
+        },
+    };
+    (!moves.is_empty()).then_some(moves)
+}
+
+impl<'a> RegionView<'a> {
+    /// The underlying board.
+    pub fn board(&self) -> &'a Board {
+        self.board
+    }
+
+    /// The region.
+    pub fn region(&self) -> BitBoard {
+        self.region
+    }
+
+    /// Generate all legal moves within the region.
+    ///
+    /// This works like [`Board::generate_moves`]: the listener can interrupt the
+    /// generation by returning true.
+    pub fn generate_moves(&self, mut listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        self.generate_drops(&mut listener) || self.generate_board_moves(&mut listener)
+    }
+
+    /// Generate the legal board moves within the region.
+    pub fn generate_board_moves(&self, mut listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        let region = self.region;
+        self.board.generate_board_moves_for(region, |moves| {
+            restrict(moves, region).is_some_and(&mut listener)
+        })
+    }
+
+    /// Generate the legal drops within the region.
+    pub fn generate_drops(&self, mut listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        if (self.region & !self.board.occupied()).is_empty() {
+            return false;
+        }
+        let region = self.region;
+        self.board
+            .generate_drops(|moves| restrict(moves, region).is_some_and(&mut listener))
+    }
+
+    /// Generate the checks within the region.
+    ///
+    /// See [`Board::generate_checks`].
+    pub fn generate_checks(&self, mut listener: impl FnMut(PieceMoves) -> bool) -> bool {
+        let region = self.region;
+        self.board.generate_checks(|moves| {
+            let from_region = match moves {
+                PieceMoves::BoardMoves { from, .. } => region.has(from),
+                PieceMoves::Drops { .. } => true,
+            };
+            from_region && restrict(moves, region).is_some_and(&mut listener)
+        })
+    }
+}
+
+impl Board {
+    /// Restrict move generation to a region of the board.
+    ///
+    /// The returned [`RegionView`] only generates moves of pieces in `region` (and
+    /// drops from the hand) to squares in `region`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+    ///
+    /// // The squares around the White King
+    /// let region = king_attacks(Color::White, Square::A5);
+    /// let view = board.restrict_region(region);
+    /// let mut moves = Vec::new();
+    /// view.generate_moves(|mvs| {
+    ///     moves.extend(mvs);
+    ///     false
+    /// });
+    /// // Only Gold drops: the Pawn on 5c and the King on 5i are outside the region
+    /// assert_eq!(moves.len(), 5);
+    /// assert!(moves.iter().all(|mv| mv.is_drop() && region.has(mv.to())));
+    ///
+    /// // G*5b is mate
+    /// let mut checks = Vec::new();
+    /// view.generate_checks(|mvs| {
+    ///     checks.extend(mvs);
+    ///     false
+    /// });
+    /// assert!(checks.contains(&"G*5b".parse().unwrap()));
+    /// ```
+    pub fn restrict_region(&self, region: BitBoard) -> RegionView<'_> {
+        RegionView {
+            board: self,
+            region,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_moves_are_the_legal_moves_in_the_region() {
+        let board: Board =
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+                .parse()
+                .unwrap();
+        for region in [
+            BitBoard::FULL,
+            BitBoard::EMPTY,
+            king_attacks(Color::White, board.king(Color::White)),
+            Rank::A.bitboard() | Rank::B.bitboard() | Rank::C.bitboard(),
+            File::Five.bitboard(),
+        ] {
+            let mut expected = Vec::new();
+            board.generate_moves(|mvs| {
+                expected.extend(mvs.into_iter().filter(|mv| {
+                    region.has(mv.to()) && mv.from().is_none_or(|from| region.has(from))
+                }));
+                false
+            });
+            let mut actual = Vec::new();
+            board.restrict_region(region).generate_moves(|mvs| {
+                actual.extend(mvs);
+                false
+            });
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+    }
+}
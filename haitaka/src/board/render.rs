@@ -0,0 +1,187 @@
+//! Text diagrams of positions
+use core::fmt::Write;
+
+use super::bod::{HAND_ORDER, NUMBERS, PIECE_CHARS};
+use crate::*;
+
+/// How [`Board::render`] shows the pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum PieceStyle {
+    /// The SFEN letters: upper case for Black, lower case for White, and a `+` in front
+    /// of promoted pieces.
+    #[default]
+    Ascii,
+    /// The kanji of printed diagrams, with a `v` in front of White's pieces.
+    Kanji,
+}
+
+/// Options for [`Board::render`].
+///
+/// By default, the pieces are shown as letters and no squares are highlighted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct RenderOptions {
+    /// How the pieces are shown.
+    pub pieces: PieceStyle,
+    /// The squares to mark with a `*`, such as the target squares of a piece or the
+    /// squares of a bitboard.
+    pub highlights: BitBoard,
+}
+
+impl RenderOptions {
+    /// Kanji pieces and no highlights.
+    pub const KANJI: Self = Self {
+        pieces: PieceStyle::Kanji,
+        highlights: BitBoard::EMPTY,
+    };
+}
+
+impl Board {
+    /// Render the position as a text diagram, for debugging and for terminals.
+    ///
+    /// The diagram shows the board as seen by Black, with the files on top and the
+    /// ranks on the right, White's hand above the board, Black's hand below it, and the
+    /// move number and side to move at the bottom. Every square takes four columns (in
+    /// a monospace font where kanji are two columns wide), with the piece or `.` (`・`
+    /// for kanji) for an empty square, and a `*` in front if the square is highlighted.
+    ///
+    /// Use [`Display`](core::fmt::Display) to get the SFEN of the position instead.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "lnsgk2nl/1r4gs1/p1pppp1pp/1p4p2/7P1/2P6/PP1PPPP1P/1SG4R1/LN2KGSNL b Bb 1"
+    ///     .parse()
+    ///     .unwrap();
+    /// let options = RenderOptions {
+    ///     highlights: board.checkers() | Square::E2.bitboard(),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(board.render(options), "\
+    /// White hand: B
+    ///    9   8   7   6   5   4   3   2   1
+    ///    l   n   s   g   k   .   .   n   l  a
+    ///    .   r   .   .   .   .   g   s   .  b
+    ///    p   .   p   p   p   p   .   p   p  c
+    ///    .   p   .   .   .   .   p   .   .  d
+    ///    .   .   .   .   .   .   .  *P   .  e
+    ///    .   .   P   .   .   .   .   .   .  f
+    ///    P   P   .   P   P   P   P   .   P  g
+    ///    .   S   G   .   .   .   .   R   .  h
+    ///    L   N   .   .   K   G   S   N   L  i
+    /// Black hand: B
+    /// Move 1, Black to move
+    /// ");
+    ///
+    /// let board = Board::startpos();
+    /// assert!(board.render(RenderOptions::KANJI).contains(" v香 v桂 v銀 v金 v玉 v金 v銀 v桂 v香 一"));
+    /// ```
+    pub fn render(&self, options: RenderOptions) -> String {
+        let kanji = options.pieces == PieceStyle::Kanji;
+        let mut text = String::new();
+
+        text.push_str("White hand: ");
+        self.write_rendered_hand(&mut text, Color::White, kanji);
+        text.push('\n');
+        for file in File::ALL.into_iter().rev() {
+            if kanji {
+                let _ = write!(
+                    text,
+                    "  {}",
+                    char::from_u32('１' as u32 + file as u32).unwrap()
+                );
+            } else {
+                let _ = write!(text, "   {}", file as usize + 1);
+            }
+        }
+        text.push('\n');
+
+        for rank in Rank::ALL {
+            for file in File::ALL.into_iter().rev() {
+                let square = Square::new(file, rank);
+                let mut cell = String::new();
+                if options.highlights.has(square) {
+                    cell.push('*');
+                }
+                match self.color_on(square).zip(self.piece_on(square)) {
+                    Some((color, piece)) if kanji => {
+                        if color == Color::White {
+                            cell.push('v');
+                        }
+                        cell.push(PIECE_CHARS[piece as usize]);
+                    }
+                    Some((color, piece)) => cell.push_str(&piece.to_str(color)),
+                    None if kanji => cell.push('・'),
+                    None => cell.push('.'),
+                }
+                // kanji are two columns wide
+                let width: usize = cell.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum();
+                for _ in width..4 {
+                    text.push(' ');
+                }
+                text.push_str(&cell);
+            }
+            if kanji {
+                text.push(' ');
+                text.push(NUMBERS[rank as usize]);
+            } else {
+                let _ = write!(text, "  {}", rank);
+            }
+            text.push('\n');
+        }
+
+        text.push_str("Black hand: ");
+        self.write_rendered_hand(&mut text, Color::Black, kanji);
+        text.push('\n');
+        let _ = writeln!(
+            text,
+            "Move {}, {:?} to move",
+            self.move_number,
+            self.side_to_move()
+        );
+        text
+    }
+
+    fn write_rendered_hand(&self, text: &mut String, color: Color, kanji: bool) {
+        if kanji {
+            self.write_kanji_hand(text, color);
+            return;
+        }
+        let mut empty = true;
+        for piece in HAND_ORDER {
+            let count = self.num_in_hand(color, piece);
+            if count > 0 {
+                if !empty {
+                    text.push(' ');
+                }
+                if count > 1 {
+                    let _ = write!(text, "{}", count);
+                }
+                text.push_str(&piece.to_str(Color::Black));
+                empty = false;
+            }
+        }
+        if empty {
+            text.push('-');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_hands_and_side_to_move() {
+        let board: Board = "4k4/9/9/9/9/9/9/9/4K4 w RG2Pb16p 10".parse().unwrap();
+        let text = board.render(RenderOptions::default());
+        assert!(text.starts_with("White hand: B 16P\n"));
+        assert!(text.ends_with("Black hand: R G 2P\nMove 10, White to move\n"));
+        assert_eq!(text.lines().count(), 13);
+
+        let text = board.render(RenderOptions::KANJI);
+        assert!(text.starts_with("White hand: 角　歩十六\n"));
+        assert!(text.contains("\nBlack hand: 飛　金　歩二\n"));
+        assert!(text.contains("\n  ・  ・  ・  ・  玉  ・  ・  ・  ・ 九\n"));
+    }
+}
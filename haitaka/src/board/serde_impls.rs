@@ -0,0 +1,79 @@
+// Serde support for `Board` (with the `serde` feature).
+//
+// A board is stored as its SFEN string. Tsume Shogi boards without a Black King are
+// read back with `Board::tsume`.
+use core::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Unexpected, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::*;
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct BoardVisitor;
+
+impl Visitor<'_> for BoardVisitor {
+    type Value = Board;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a SFEN string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Board, E> {
+        Board::from_sfen(v)
+            .or_else(|error| Board::tsume(v).map_err(|_| error))
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(BoardVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trips() {
+        let board: Board = "lnsgkg1nl/1r5s1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 5"
+            .parse()
+            .unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, format!("\"{}\"", board));
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+
+        let tsume = Board::tsume("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1").unwrap();
+        let json = serde_json::to_string(&tsume).unwrap();
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), tsume);
+        assert!(serde_json::from_str::<Board>("\"9/9 b - 1\"").is_err());
+
+        let mv: Move = "P*5e".parse().unwrap();
+        let json = serde_json::to_string(&(mv, Piece::PRook, Color::White, Square::E5)).unwrap();
+        assert_eq!(json, r#"["P*5e","+R","w","5e"]"#);
+        assert_eq!(
+            serde_json::from_str::<(Move, Piece, Color, Square)>(&json).unwrap(),
+            (mv, Piece::PRook, Color::White, Square::E5)
+        );
+
+        let json = serde_json::to_string(&(board.pieces(Piece::Rook), board.hands())).unwrap();
+        assert_eq!(
+            json,
+            r#"["0x10000000000010000",[[0,0,0,0,1,0,0,0,0,0,0,0,0,0],[0,0,0,0,1,0,0,0,0,0,0,0,0,0]]]"#
+        );
+        let (rooks, hands): (BitBoard, [[u8; Piece::NUM]; Color::NUM]) =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(rooks, board.pieces(Piece::Rook));
+        assert_eq!(&hands, board.hands());
+
+        assert!(serde_json::from_str::<BitBoard>("\"0x1ffffffffffffffffffffffff\"").is_err());
+        assert!(serde_json::from_str::<Move>("\"7g7x\"").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+// Conversions between `Board` and `shogi_core::PartialPosition` (with the
+// `interop-shogi-core` feature).
+//
+// The move number of a board is the ply of a `PartialPosition`: both count from 1, as
+// the last field of a SFEN string. The last move of a `PartialPosition` is not kept.
+// The conversions of the basic types are in haitaka-types.
+use shogi_core::PartialPosition;
+
+use crate::*;
+
+impl From<&Board> for PartialPosition {
+    fn from(board: &Board) -> Self {
+        let mut position = PartialPosition::empty();
+        for (color, piece, square) in board.iter_pieces() {
+            position.piece_set(
+                square.into(),
+                Some(shogi_core::Piece::new(piece.into(), color.into())),
+            );
+        }
+        for color in Color::ALL {
+            let hand = position.hand_of_a_player_mut(color.into());
+            for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+                for _ in 0..board.num_in_hand(color, piece) {
+                    *hand = hand.added(piece.into()).unwrap();
+                }
+            }
+        }
+        position.side_to_move_set(board.side_to_move().into());
+        // The move number of a board is never 0
+        let _ = position.ply_set(board.move_number());
+        position
+    }
+}
+
+impl TryFrom<&PartialPosition> for Board {
+    type Error = PositionError;
+
+    /// Convert a position, which is checked as with [`Board::from_sfen`].
+    fn try_from(position: &PartialPosition) -> Result<Self, Self::Error> {
+        let mut builder = BoardBuilder::new();
+        for &square in &Square::ALL {
+            if let Some(piece) = position.piece_at(square.into()) {
+                let (kind, color) = piece.to_parts();
+                builder.put(color.into(), kind.into(), square);
+            }
+        }
+        for color in Color::ALL {
+            let hand = position.hand_of_a_player(color.into());
+            for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+                builder.set_hand(color, piece, hand.count(piece.into()).unwrap());
+            }
+        }
+        builder
+            .side_to_move(position.side_to_move().into())
+            .move_number(position.ply());
+        builder.build(Validation::Lenient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::ToUsi;
+
+    #[test]
+    fn basic_types_round_trip() {
+        for square in Square::ALL {
+            let converted = shogi_core::Square::from(square);
+            assert_eq!(converted.file(), square.file() as u8 + 1);
+            assert_eq!(converted.rank(), square.rank() as u8 + 1);
+            assert_eq!(Square::from(converted), square);
+        }
+        for piece in Piece::ALL {
+            let kind = shogi_core::PieceKind::from(piece);
+            assert_eq!(Piece::from(kind), piece);
+            let promoted = piece.is_promotable().then(|| piece.promote());
+            assert_eq!(kind.promote().map(Piece::from), promoted);
+        }
+        for color in Color::ALL {
+            assert_eq!(Color::from(shogi_core::Color::from(color)), color);
+        }
+        assert_eq!(
+            shogi_core::Piece::new(Piece::PRook.into(), Color::White.into()),
+            shogi_core::Piece::W_PR
+        );
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        let sfens = [
+            SFEN_STARTPOS,
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+            "ln3gsn1/7kl/3+B1p1p1/p4s2p/2P6/P2B3PP/1PNP+rPP2/2G3SK1/L4G1NL b G3Prs3p 65",
+        ];
+        for sfen in sfens {
+            let board: Board = sfen.parse().unwrap();
+            let position = PartialPosition::from(&board);
+            assert_eq!(position.to_sfen_owned(), sfen);
+            assert_eq!(Board::try_from(&position).unwrap(), board);
+        }
+        for board in random_positions(0x5c0, 4, 100) {
+            let mut position = PartialPosition::from(&board);
+            assert_eq!(Board::try_from(&position).unwrap(), board);
+            for &mv in board.legal_moves().iter().take(20) {
+                let converted = mv.to_shogi_core(board.side_to_move());
+                assert_eq!(converted.to_usi_owned(), mv.to_string());
+                let back: Move = converted.into();
+                assert_eq!(back, mv);
+            }
+            if let Some(&mv) = board.legal_moves().iter().next() {
+                let mut played = board.clone();
+                played.play(mv);
+                position
+                    .make_move(mv.to_shogi_core(board.side_to_move()))
+                    .unwrap();
+                assert_eq!(Board::try_from(&position).unwrap(), played);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_positions_are_rejected() {
+        let mut position = PartialPosition::startpos();
+        position.piece_set(shogi_core::Square::SQ_5I, None);
+        assert_eq!(
+            Board::try_from(&position),
+            Err(PositionError::KingCount {
+                color: Color::Black,
+                count: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Rotated and mirrored positions
+use crate::*;
+
+impl Board {
+    fn transformed(&self, swap_colors: bool, map: impl Fn(Square) -> Square) -> Self {
+        let swap = |color: Color| if swap_colors { !color } else { color };
+        let mut builder = BoardBuilder::new();
+        for (color, piece, square) in self.iter_pieces() {
+            builder.put(swap(color), piece, map(square));
+        }
+        for color in Color::ALL {
+            for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+                builder.set_hand(swap(color), piece, self.num_in_hand(color, piece));
+            }
+        }
+        builder
+            .side_to_move(swap(self.side_to_move()))
+            .move_number(self.move_number());
+        // The position is as valid as `self`: mirroring keeps the colors, so it keeps
+        // the mode, and `rotate_180` only swaps them when both sides have a King.
+        builder.build(Validation::None).unwrap()
+    }
+
+    /// Get the position seen from the other side: the board is rotated by 180 degrees,
+    /// and the colors of all pieces, the hands and the side to move are swapped.
+    ///
+    /// The rotated position is equivalent to the original one, with the roles of Black
+    /// and White reversed. A move `mv` of the original position corresponds to the move
+    /// with both squares [flipped](Square::flip) in the rotated position. Rotating twice
+    /// gives back the original position. The move number is not changed.
+    ///
+    /// # Panics
+    /// This panics if Black has no King (see [`BoardMode`]). Only White may be without
+    /// a King in the rotated position, which a [`Board`] can not represent.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+    ///     .parse()
+    ///     .unwrap();
+    /// let rotated = board.rotate_180();
+    /// assert_eq!(
+    ///     rotated.to_string(),
+    ///     "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL w - 3"
+    /// );
+    /// assert_eq!(rotated.rotate_180(), board);
+    /// ```
+    pub fn rotate_180(&self) -> Self {
+        assert!(
+            self.mode() == BoardMode::Standard,
+            "Can not rotate a board without a Black King"
+        );
+        self.transformed(true, Square::flip)
+    }
+
+    /// Get the position mirrored in the central file, so that file 1 becomes file 9.
+    ///
+    /// The colors, the hands and the side to move are not changed, and neither is the
+    /// [`BoardMode`]. A move `mv` of the original position corresponds to the move with
+    /// both files [flipped](Square::flip_file) in the mirrored position.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mirrored = Board::startpos().mirror_files();
+    /// assert_eq!(
+    ///     mirrored.to_string(),
+    ///     "lnsgkgsnl/1b5r1/ppppppppp/9/9/9/PPPPPPPPP/1R5B1/LNSGKGSNL b - 1"
+    /// );
+    /// assert_eq!(mirrored.mirror_files(), Board::startpos());
+    /// ```
+    pub fn mirror_files(&self) -> Self {
+        self.transformed(false, Square::flip_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_move(mv: Move, map: fn(Square) -> Square) -> Move {
+        match mv {
+            Move::Drop { piece, to } => Move::Drop { piece, to: map(to) },
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => Move::BoardMove {
+                from: map(from),
+                to: map(to),
+                promotion,
+            },
+        }
+    }
+
+    fn sorted_moves(board: &Board) -> Vec<String> {
+        let mut moves: Vec<String> = board.legal_moves().iter().map(Move::to_string).collect();
+        moves.sort();
+        moves
+    }
+
+    fn check_transformed(board: &Board) {
+        for (transformed, map) in [
+            (board.rotate_180(), Square::flip as fn(Square) -> Square),
+            (board.mirror_files(), Square::flip_file),
+        ] {
+            let mut expected: Vec<String> = board
+                .legal_moves()
+                .iter()
+                .map(|&mv| map_move(mv, map).to_string())
+                .collect();
+            expected.sort();
+            assert_eq!(sorted_moves(&transformed), expected, "{board}");
+            assert_eq!(transformed.checkers().len(), board.checkers().len());
+            assert_eq!(transformed.status(), board.status());
+            assert_eq!(transformed.move_number(), board.move_number());
+            let reparsed: Board = transformed.to_string().parse().unwrap();
+            assert_eq!(reparsed, transformed);
+            assert_eq!(reparsed.hash(), transformed.hash());
+        }
+        assert_eq!(board.rotate_180().rotate_180(), *board);
+        assert_eq!(board.mirror_files().mirror_files(), *board);
+    }
+
+    #[test]
+    fn transformed_positions_have_the_same_moves() {
+        for sfen in [
+            // Pawns in hand, with a Pawn already on some files
+            "4k4/9/4P4/9/9/9/2P6/9/4K4 b G2Pp 5",
+            // Knights and Lances that must promote near the edge
+            "4k4/P1L6/2N6/9/9/9/6n2/6l1p/4K4 w - 20",
+            // in check from a Horse, with promoted pieces of both sides
+            "4k4/9/9/9/+b8/9/1+P7/6+s2/4K4 b r 7",
+            // mated
+            "4k4/9/9/9/9/9/9/3+r+r4/4K4 b - 30",
+        ] {
+            check_transformed(&sfen.parse().unwrap());
+        }
+        for board in random_positions(0x180, 10, 150) {
+            check_transformed(&board);
+        }
+    }
+
+    #[test]
+    fn tsume_boards_keep_their_mode_when_mirrored() {
+        let board = Board::tsume("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1").unwrap();
+        let mirrored = board.mirror_files();
+        assert_eq!(mirrored.mode(), BoardMode::TsumeAttackerNoKing);
+        assert_eq!(Board::tsume(&mirrored.to_string()).unwrap(), mirrored);
+        assert_eq!(mirrored.mirror_files(), board);
+        let mut expected: Vec<String> = board
+            .legal_moves()
+            .iter()
+            .map(|&mv| map_move(mv, Square::flip_file).to_string())
+            .collect();
+        expected.sort();
+        assert_eq!(sorted_moves(&mirrored), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "without a Black King")]
+    fn tsume_boards_can_not_be_rotated() {
+        let board = Board::tsume("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1").unwrap();
+        board.rotate_180();
+    }
+}
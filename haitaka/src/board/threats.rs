@@ -0,0 +1,114 @@
+//! Attacked squares and hanging pieces
+use crate::*;
+
+impl Board {
+    /// Get a [`BitBoard`] of all squares attacked by the pieces of `color`.
+    ///
+    /// This includes the squares of the pieces of `color` that are defended by another
+    /// piece of `color`. Pins are ignored, and so is whether a piece could actually move
+    /// to an attacked square (for instance a Pawn that must promote).
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// let threats = board.threats(Color::Black);
+    /// assert!(threats.has(Square::F7)); // by the Pawn on 7g
+    /// assert!(threats.has(Square::H5)); // defended by the Golds, the King and the Rook
+    /// assert!(!threats.has(Square::E5));
+    /// assert_eq!(threats & board.colors(Color::White), BitBoard::EMPTY);
+    /// ```
+    pub fn threats(&self, color: Color) -> BitBoard {
+        let ours = self.colors(color);
+        let mut threats = self.slider_attacks(color, self.occupied());
+        for square in self.pieces(Piece::Pawn) & ours {
+            threats |= pawn_attacks(color, square);
+        }
+        for square in self.pieces(Piece::Knight) & ours {
+            threats |= knight_attacks(color, square);
+        }
+        for square in self.pieces(Piece::Silver) & ours {
+            threats |= silver_attacks(color, square);
+        }
+        for square in self.pseudo_tokins() & ours {
+            threats |= gold_attacks(color, square);
+        }
+        for square in self.pieces(Piece::King) & ours {
+            threats |= king_attacks(color, square);
+        }
+        threats
+    }
+
+    /// Get a [`BitBoard`] of the pieces of `color` that are attacked by the opponent and
+    /// not defended.
+    ///
+    /// The King is never included. Like [`Board::threats`], this ignores pins, so a
+    /// piece that is only defended by a pinned piece is not hanging.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert!(Board::startpos().hanging_pieces(Color::Black).is_empty());
+    ///
+    /// // After Bx2b+, the Black Horse is attacked by the Silver on 3a
+    /// let board: Board = "lnsgkgsnl/1r5+B1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL w B 4"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(board.hanging_pieces(Color::Black), Square::B2.bitboard());
+    /// // ... and the Horse attacks the undefended Lance, Knight and Pawn next to it
+    /// let hanging = Square::A1.bitboard() | Square::A2.bitboard() | Square::C2.bitboard();
+    /// assert_eq!(board.hanging_pieces(Color::White), hanging);
+    /// ```
+    pub fn hanging_pieces(&self, color: Color) -> BitBoard {
+        let pieces = self.colors(color) & !self.pieces(Piece::King);
+        pieces & self.threats(!color) & !self.threats(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threats_match_the_attackers() {
+        let mut boards = vec![
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1"
+                .parse()
+                .unwrap(),
+            // no Black King
+            Board::tsume("4k4/9/4r4/9/9/9/9/4S4/9 b G 1").unwrap(),
+        ];
+        boards.extend(random_positions(0x2787, 5, 100));
+        for board in boards {
+            for color in Color::ALL {
+                let expected: BitBoard = Square::ALL
+                    .into_iter()
+                    .filter(|&square| !board.attackers(square, color).is_empty())
+                    .collect();
+                assert_eq!(board.threats(color), expected, "{board} {color:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn hanging_pieces() {
+        use Square::*;
+
+        for (sfen, black, white) in [
+            // two Pawns that attack each other
+            ("4k4/9/9/9/4p4/4P4/9/9/4K4 b - 1", &[F5][..], &[E5][..]),
+            // a Pawn defended only by its King
+            ("4k4/4p4/4P4/9/9/9/9/9/4K4 b - 1", &[C5], &[]),
+            // a King is never hanging, even in check
+            ("4k4/9/9/9/9/9/9/4r4/4K4 b - 1", &[], &[H5]),
+            // a pinned Gold still defends the Silver
+            ("4k4/9/4r4/9/9/3p5/3S5/4G4/4K4 b - 1", &[], &[F6]),
+        ] {
+            let board: Board = sfen.parse().unwrap();
+            let black: BitBoard = black.iter().copied().collect();
+            let white: BitBoard = white.iter().copied().collect();
+            assert_eq!(board.hanging_pieces(Color::Black), black, "{sfen}");
+            assert_eq!(board.hanging_pieces(Color::White), white, "{sfen}");
+        }
+    }
+}
@@ -0,0 +1,453 @@
+//! Taking back moves
+use crate::*;
+
+/// The state needed to take back a move with [`Board::unplay`].
+///
+/// Returned by [`Board::play_unchecked_with_undo`]. This holds the move, the captured
+/// piece (if any) and the checkers and pins from before the move. Everything else is
+/// restored by reversing the move, including the Zobrist hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoState {
+    mv: Move,
+    captured: Option<Piece>,
+    pinned: BitBoard,
+    checkers: BitBoard,
+}
+
+impl UndoState {
+    /// The move that was played.
+    pub fn mv(&self) -> Move {
+        self.mv
+    }
+
+    /// The piece that was captured by the move (as it was on the board, so possibly
+    /// promoted), or None if the move was not a capture.
+    pub fn captured(&self) -> Option<Piece> {
+        self.captured
+    }
+}
+
+impl Board {
+    /// Play a move and return the state needed to take it back.
+    ///
+    /// This is [`Board::play_unchecked`] for make/unmake search: instead of cloning the
+    /// board before every move, play the move and later pass the returned [`UndoState`]
+    /// to [`Board::unplay`].
+    ///
+    /// As with [`Board::play_unchecked`], only legal moves should ever be passed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+    ///     .parse()
+    ///     .unwrap();
+    /// let before = board.clone();
+    ///
+    /// let undo = board.play_unchecked_with_undo("8h2b+".parse().unwrap());
+    /// assert_eq!(undo.captured(), Some(Piece::Bishop));
+    /// assert_eq!(board.num_in_hand(Color::Black, Piece::Bishop), 1);
+    ///
+    /// board.unplay(undo);
+    /// assert_eq!(board, before);
+    /// assert_eq!(board.hash(), before.hash());
+    /// ```
+    pub fn play_unchecked_with_undo(&mut self, mv: Move) -> UndoState {
+        self.play_unchecked_with_undo_and_listener(mv, &mut ())
+    }
+
+    /// Like [`Board::play_unchecked_with_undo`], but reports every change to the board
+    /// to `listener`. See [`BoardListener`].
+    pub fn play_unchecked_with_undo_and_listener(
+        &mut self,
+        mv: Move,
+        listener: &mut impl BoardListener,
+    ) -> UndoState {
+        let captured = match mv {
+            Move::BoardMove { to, .. } => self.piece_on(to),
+            Move::Drop { .. } => None,
+        };
+        let undo = UndoState {
+            mv,
+            captured,
+            pinned: self.pinned,
+            checkers: self.checkers,
+        };
+        self.play_unchecked_with_listener(mv, listener);
+        undo
+    }
+
+    /// Take back the last move played with [`Board::play_unchecked_with_undo`].
+    ///
+    /// The board is restored to exactly the position before the move, including the
+    /// move number and the Zobrist hash. Moves must be taken back in the reverse order
+    /// in which they were played.
+    ///
+    /// # Panics
+    /// This may panic if `undo` does not belong to the last move played on this board.
+    /// Even if it does not panic, the board state is then corrupted.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut board = Board::startpos();
+    /// let mut undos = Vec::new();
+    /// for mv in ["7g7f", "3c3d", "8h2b+", "3a2b", "B*4e"] {
+    ///     undos.push(board.play_unchecked_with_undo(mv.parse().unwrap()));
+    /// }
+    /// while let Some(undo) = undos.pop() {
+    ///     board.unplay(undo);
+    /// }
+    /// assert_eq!(board, Board::startpos());
+    /// ```
+    pub fn unplay(&mut self, undo: UndoState) {
+        self.unplay_with_listener(undo, &mut ());
+    }
+
+    /// Like [`Board::unplay`], but reports every change to the board to `listener`.
+    ///
+    /// The changes are reported in the order in which they are undone, so the
+    /// callbacks are the reverse of those of the move.
+    pub fn unplay_with_listener(&mut self, undo: UndoState, listener: &mut impl BoardListener) {
+        self.inner.toggle_side_to_move();
+        self.move_number -= 1;
+        let color = self.inner.side_to_move();
+
+        match undo.mv {
+            Move::Drop { piece, to } => {
+                // lift the piece and put it back in hand
+                self.inner.clear_square(piece, color, to);
+                listener.piece_removed(color, piece, to);
+                self.inner.take_in_hand(color, piece);
+                let count = self.num_in_hand(color, piece);
+                listener.hand_changed(color, piece, count - 1, count);
+
+                if piece == Piece::Pawn {
+                    self.pawnless_files[color as usize] |= to.file().bitboard();
+                }
+            }
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => {
+                // move the piece back (unpromoted if it promoted)
+                let final_piece = self
+                    .piece_on(to)
+                    .expect("Missing piece on move's `to` square");
+                let piece = if promotion {
+                    final_piece.unpromote()
+                } else {
+                    final_piece
+                };
+                self.inner.clear_square(final_piece, color, to);
+                listener.piece_removed(color, final_piece, to);
+                self.inner.set_square(piece, color, from);
+                listener.piece_added(color, piece, from);
+
+                if piece == Piece::Pawn && promotion {
+                    self.pawnless_files[color as usize] &= !to.file().bitboard();
+                }
+
+                // put back the captured piece
+                if let Some(capture) = undo.captured {
+                    let captured = capture.unpromote();
+                    self.inner.take_from_hand(color, captured);
+                    let count = self.num_in_hand(color, captured);
+                    listener.hand_changed(color, captured, count + 1, count);
+                    self.inner.set_square(capture, !color, to);
+                    listener.piece_added(!color, capture, to);
+
+                    if capture == Piece::Pawn {
+                        self.pawnless_files[!color as usize] &= !to.file().bitboard();
+                    }
+                }
+            }
+        }
+
+        self.pinned = undo.pinned;
+        self.checkers = undo.checkers;
+    }
+}
+
+/// The state needed to take back a null move with [`Board::unmake_null`].
+///
+/// Returned by [`Board::make_null`]. A null move can only be made when the side to move
+/// is not in check, so only the pins from before the null move need to be kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NullMoveUndo {
+    pinned: BitBoard,
+}
+
+impl Board {
+    /// Make a null move in place and return the state needed to take it back.
+    ///
+    /// This is [`Board::null_move`] for make/unmake search, without cloning the board.
+    /// The side to move passes: the move number is increased and the side to move
+    /// (and so the Zobrist hash) changes. Returns None, without changing the board, if
+    /// the side to move is in check.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut board = Board::startpos();
+    /// let undo = board.make_null().unwrap();
+    /// assert_eq!(board.side_to_move(), Color::White);
+    /// assert_eq!(board.move_number(), 2);
+    /// assert_eq!(board, Board::startpos().null_move().unwrap());
+    ///
+    /// board.unmake_null(undo);
+    /// assert_eq!(board, Board::startpos());
+    /// assert_eq!(board.hash(), Board::startpos().hash());
+    /// ```
+    pub fn make_null(&mut self) -> Option<NullMoveUndo> {
+        if !self.checkers.is_empty() {
+            return None;
+        }
+        let undo = NullMoveUndo {
+            pinned: self.pinned,
+        };
+        self.move_number += 1;
+        self.inner.toggle_side_to_move();
+
+        // the opponent was not in check, so there are no checkers
+        let (_, pinned) = self.calculate_checkers_and_pins(self.side_to_move());
+        self.pinned = pinned;
+        Some(undo)
+    }
+
+    /// Take back a null move made with [`Board::make_null`].
+    ///
+    /// As with [`Board::unplay`], moves and null moves must be taken back in the
+    /// reverse order in which they were made.
+    pub fn unmake_null(&mut self, undo: NullMoveUndo) {
+        self.move_number -= 1;
+        self.inner.toggle_side_to_move();
+        self.pinned = undo.pinned;
+        self.checkers = BitBoard::EMPTY;
+    }
+}
+
+/// A move played speculatively, which is taken back when the guard is dropped.
+///
+/// Returned by [`Board::speculate`]. The guard derefs to the board after the move, so
+/// that the position can be inspected or searched. Further moves can be tried with
+/// [`Speculation::speculate`]. Since the board is restored on drop, it is also restored
+/// when a search returns early with `?` or unwinds from a panic.
+///
+/// The guard also derefs mutably, so that it can be passed to a search that plays and
+/// takes back its own moves. Such code must leave the board as it found it, or the
+/// board is corrupted when the guard is dropped.
+///
+/// Use [`Speculation::commit`] to keep the move instead.
+#[derive(Debug)]
+pub struct Speculation<'a> {
+    board: &'a mut Board,
+    undo: UndoState,
+}
+
+impl Speculation<'_> {
+    /// The move that was played.
+    pub fn mv(&self) -> Move {
+        self.undo.mv
+    }
+
+    /// The state that will be used to take back the move.
+    pub fn undo_state(&self) -> UndoState {
+        self.undo
+    }
+
+    /// Play another move on top of this one.
+    ///
+    /// The new guard borrows this one, so the moves are always taken back in the
+    /// right order.
+    pub fn speculate(&mut self, mv: Move) -> Speculation<'_> {
+        self.board.speculate(mv)
+    }
+
+    /// Keep the move on the board, instead of taking it back.
+    pub fn commit(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl core::ops::Deref for Speculation<'_> {
+    type Target = Board;
+
+    fn deref(&self) -> &Board {
+        self.board
+    }
+}
+
+impl core::ops::DerefMut for Speculation<'_> {
+    fn deref_mut(&mut self) -> &mut Board {
+        self.board
+    }
+}
+
+impl Drop for Speculation<'_> {
+    fn drop(&mut self) {
+        self.board.unplay(self.undo);
+    }
+}
+
+impl Board {
+    /// Play a move until the returned guard is dropped.
+    ///
+    /// This is [`Board::play_unchecked_with_undo`] with automatic rollback. It is useful
+    /// for code paths that try a move and must always leave the board as they found it,
+    /// such as pondering or verification searches.
+    ///
+    /// As with [`Board::play_unchecked`], only legal moves should ever be passed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let mut board = Board::startpos();
+    /// {
+    ///     let mut guard = board.speculate("7g7f".parse().unwrap());
+    ///     assert_eq!(guard.side_to_move(), Color::White);
+    ///     let reply = guard.speculate("3c3d".parse().unwrap());
+    ///     assert_eq!(reply.move_number(), 3);
+    /// }
+    /// assert_eq!(board, Board::startpos());
+    ///
+    /// // Keep the move
+    /// board.speculate("7g7f".parse().unwrap()).commit();
+    /// assert_eq!(board.side_to_move(), Color::White);
+    /// ```
+    pub fn speculate(&mut self, mv: Move) -> Speculation<'_> {
+        let undo = self.play_unchecked_with_undo(mv);
+        Speculation { board: self, undo }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rng;
+    use rand::seq::IndexedRandom;
+
+    fn perft_clone(board: &Board, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        board.generate_moves(|moves| {
+            for mv in moves {
+                let mut board = board.clone();
+                board.play_unchecked(mv);
+                nodes += perft_clone(&board, depth - 1);
+            }
+            false
+        });
+        nodes
+    }
+
+    fn perft_unplay(board: &mut Board, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut moves = Vec::new();
+        board.generate_moves(|mvs| {
+            moves.extend(mvs);
+            false
+        });
+        let mut nodes = 0;
+        for mv in moves {
+            let undo = board.play_unchecked_with_undo(mv);
+            nodes += perft_unplay(board, depth - 1);
+            board.unplay(undo);
+        }
+        nodes
+    }
+
+    #[test]
+    fn unplay_restores_the_board() {
+        let mut rng = rng();
+        for sfen in [
+            SFEN_STARTPOS,
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+        ] {
+            let mut board: Board = sfen.parse().unwrap();
+            let mut history = Vec::new();
+            for _ in 0..80 {
+                let mut moves = Vec::new();
+                board.generate_moves(|mvs| {
+                    moves.extend(mvs);
+                    false
+                });
+                let Some(&mv) = moves.choose(&mut rng) else {
+                    break;
+                };
+                let before = board.clone();
+                let undo = board.play_unchecked_with_undo(mv);
+                history.push((before, undo));
+            }
+            while let Some((before, undo)) = history.pop() {
+                board.unplay(undo);
+                assert_eq!(board, before, "after taking back {}", undo.mv());
+            }
+        }
+    }
+
+    #[test]
+    fn null_moves_compute_the_pins() {
+        for sfen in [
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+            "ln3gsn1/7kl/3+B1p1p1/p4s2p/2P6/P2B3PP/1PNP+rPP2/2G3SK1/L4G1NL b G3Prs3p 65",
+        ] {
+            let mut board: Board = sfen.parse().unwrap();
+            let before = board.clone();
+            let undo = board.make_null().unwrap();
+            let reparsed: Board = board.to_string().parse().unwrap();
+            assert_eq!(board.pinned(), reparsed.pinned(), "{sfen}");
+            assert_eq!(board, reparsed);
+            assert_eq!(board.hash(), reparsed.hash());
+
+            board.unmake_null(undo);
+            assert_eq!(board, before);
+        }
+
+        // no null move when in check
+        let mut board: Board = "4k4/9/9/9/9/9/9/4r4/4K4 b - 1".parse().unwrap();
+        let before = board.clone();
+        assert!(board.make_null().is_none());
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn perft_with_unplay() {
+        for (sfen, depth) in [
+            (SFEN_STARTPOS, 3),
+            (
+                "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+                2,
+            ),
+        ] {
+            let mut board: Board = sfen.parse().unwrap();
+            let expected = perft_clone(&board, depth);
+            assert_eq!(perft_unplay(&mut board, depth), expected);
+        }
+    }
+
+    #[test]
+    fn speculation_rolls_back_on_early_return() {
+        fn search(board: &mut Board, moves: &[&str]) -> Result<(), Move> {
+            let Some((first, rest)) = moves.split_first() else {
+                return Ok(());
+            };
+            let mv: Move = first.parse().unwrap();
+            let mut guard = board.speculate(mv);
+            if guard.undo_state().captured().is_some() {
+                return Err(mv);
+            }
+            search(&mut guard, rest)
+        }
+
+        let mut board = Board::startpos();
+        let moves = ["7g7f", "3c3d", "8h2b+", "3a2b", "B*4e"];
+        assert_eq!(search(&mut board, &moves), Err("8h2b+".parse().unwrap()));
+        assert_eq!(board, Board::startpos());
+    }
+}
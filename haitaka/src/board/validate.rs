@@ -1,89 +1,235 @@
 use crate::*;
 
-macro_rules! soft_assert {
-    ($expr:expr) => {
-        if !$expr {
-            return false;
+/// The reason why a position is invalid, as returned by [`Board::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PositionError {
+    /// The move number is 0.
+    InvalidMoveNumber,
+    /// The bitboards of the board are inconsistent: a square holds more than one piece.
+    /// This can only happen after misuse of the unchecked methods.
+    OverlappingPieces,
+    /// A side does not have exactly one King. In Tsume Shogi positions (see
+    /// [`BoardMode`]), Black may have no King.
+    KingCount {
+        /// The side with the wrong number of Kings.
+        color: Color,
+        /// The number of Kings of that side.
+        count: u32,
+    },
+    /// A side has more than 39 pieces on the board.
+    TooManyPieces {
+        /// The side with too many pieces.
+        color: Color,
+    },
+    /// There are more pieces of a type on the board and in both hands than in a game
+    /// set (such as more than 18 Pawns). Promoted pieces count as their unpromoted type.
+    PieceCount {
+        /// The (unpromoted) piece type.
+        piece: Piece,
+        /// The number of pieces of that type.
+        count: u32,
+    },
+    /// A Pawn, Lance or Knight stands on a square from which it can never move.
+    DeadPiece {
+        /// The color of the piece.
+        color: Color,
+        /// The piece.
+        piece: Piece,
+        /// The square of the piece.
+        square: Square,
+    },
+    /// The Kings are next to each other.
+    KingsAdjacent,
+    /// The side that is not to move is in check.
+    OpponentInCheck,
+    /// The side to move is in check by more than two pieces.
+    TooManyCheckers {
+        /// The number of checking pieces.
+        count: u32,
+    },
+    /// A side has two unpromoted Pawns on one file (nifu).
+    DoublePawn {
+        /// The side with two Pawns on the file.
+        color: Color,
+        /// The file.
+        file: File,
+    },
+    /// The checkers or pins stored in the board are not those of the position. This can
+    /// only happen after misuse of the unchecked methods.
+    StaleCheckersOrPins,
+}
+
+impl core::fmt::Display for PositionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            Self::InvalidMoveNumber => write!(f, "The move number is invalid."),
+            Self::OverlappingPieces => write!(f, "A square holds more than one piece."),
+            Self::KingCount { color, count } => {
+                write!(f, "{color:?} has {count} Kings.")
+            }
+            Self::TooManyPieces { color } => {
+                write!(f, "{color:?} has too many pieces on the board.")
+            }
+            Self::PieceCount { piece, count } => {
+                write!(f, "There are {count} pieces of type {piece:?}.")
+            }
+            Self::DeadPiece {
+                color,
+                piece,
+                square,
+            } => write!(f, "The {color:?} {piece:?} on {square} can never move."),
+            Self::KingsAdjacent => write!(f, "The Kings are next to each other."),
+            Self::OpponentInCheck => write!(f, "The side that is not to move is in check."),
+            Self::TooManyCheckers { count } => {
+                write!(f, "The side to move is in check by {count} pieces.")
+            }
+            Self::DoublePawn { color, file } => {
+                write!(
+                    f,
+                    "{color:?} has two unpromoted Pawns on file {file:?} (nifu)."
+                )
+            }
+            Self::StaleCheckersOrPins => {
+                write!(f, "The checkers or pins do not match the position.")
+            }
         }
-    };
+    }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for PositionError {}
+
 impl Board {
+    /// Check that the position could occur in a legal game, and if not, tell why.
+    ///
+    /// This runs all checks of [`Board::from_sfen_validated`]: the counts of Kings and
+    /// other pieces, Pawns, Lances and Knights that can never move, the Kings standing
+    /// next to each other, the side that is not to move being in check, and two
+    /// unpromoted Pawns of one side on a file (nifu). It also checks that the stored
+    /// checkers and pins are up to date. Positions without a Black King are checked as
+    /// Tsume Shogi positions (see [`BoardMode`]).
+    ///
+    /// Boards from the parsers are always valid, apart from nifu. This is meant for
+    /// boards that were changed with the unchecked methods, or to explain why a
+    /// position was rejected.
+    ///
+    /// # Errors
+    /// Returns the first problem found as a [`PositionError`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().validate(), Ok(()));
+    ///
+    /// let nifu = "lnsgkgsnl/1r5b1/ppppppppp/9/9/P8/P1PPPPPPP/1B5R1/LNSGKGSNL b - 1";
+    /// let board = Board::from_sfen(nifu).unwrap();
+    /// assert_eq!(
+    ///     board.validate(),
+    ///     Err(PositionError::DoublePawn { color: Color::Black, file: File::Nine })
+    /// );
+    ///
+    /// let mut board: Board = "4k4/9/9/9/9/9/9/9/4K4 b - 1".parse().unwrap();
+    /// board.unchecked_put(Color::Black, Piece::Pawn, Square::A1);
+    /// assert_eq!(
+    ///     board.validate(),
+    ///     Err(PositionError::DeadPiece { color: Color::Black, piece: Piece::Pawn, square: Square::A1 })
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), PositionError> {
+        self.validate_position(self.mode() == BoardMode::TsumeAttackerNoKing)?;
+        self.validate_checkers_and_pins()?;
+        self.validate_pawn_files()
+    }
+
     /// Canonical implementation of board validity. Used for debugging.
     #[cfg(test)]
     #[allow(dead_code)]
     pub(crate) fn validity_check(&self, for_tsume: bool) -> bool {
-        soft_assert!(self.is_valid(for_tsume));
-        soft_assert!(self.piece_counts_are_valid());
-        soft_assert!(self.checkers_and_pins_are_valid());
-        soft_assert!(self.move_number_is_valid());
-        true
+        self.validate_position(for_tsume).is_ok()
+            && self.validate_checkers_and_pins().is_ok()
+            && self.validate_pawn_files().is_ok()
     }
 
     /// Check if the board position is valid.
     ///
-    /// This does not validate checkers and pins, but does verify that the opponent's
-    /// King is not in check.
+    /// This does not validate the stored checkers and pins, or nifu, but does verify that
+    /// the opponent's King is not in check.
     ///
     /// If the `for_tsume` flag is set, we check the validity the position for a
-    /// Tsume Shogi problem. In this case, we do not require the presence of Sente's King.
-    pub(super) fn is_valid(&self, for_tsume: bool) -> bool {
+    /// Tsume Shogi problem. In this case, we do not require the presence of Sente's King
+    /// (see [`BoardMode`]).
+    pub(super) fn validate_position(&self, for_tsume: bool) -> Result<(), PositionError> {
+        use PositionError::*;
+
+        if self.move_number == 0 {
+            return Err(InvalidMoveNumber);
+        }
+
         // Piece bitboards should not overlap.
         let mut occupied = BitBoard::EMPTY;
         for &piece in &Piece::ALL {
             let pieces = self.pieces(piece);
-            soft_assert!((pieces & occupied).is_empty());
+            if !(pieces & occupied).is_empty() {
+                return Err(OverlappingPieces);
+            }
             occupied |= pieces;
         }
 
-        // Colors should not overlap
+        // Colors should not overlap, and occupied should match
         let whites = self.colors(Color::White);
         let blacks = self.colors(Color::Black);
-        soft_assert!((whites & blacks).is_empty());
-
-        // Occupied should match
-        soft_assert!(occupied == self.occupied());
+        if !(whites & blacks).is_empty() || occupied != self.occupied() {
+            return Err(OverlappingPieces);
+        }
 
         for &color in &Color::ALL {
             let pieces = self.colors(color);
-            soft_assert!(pieces.len() <= 39);
-            if !for_tsume || self.has(color, Piece::King) {
-                soft_assert!((pieces & self.pieces(Piece::King)).len() == 1);
+            // the defender (White) always needs a King
+            let count = (pieces & self.pieces(Piece::King)).len();
+            if count > 1 || (count == 0 && (!for_tsume || color == Color::White)) {
+                return Err(KingCount { color, count });
+            }
+            if pieces.len() > 39 {
+                return Err(TooManyPieces { color });
             }
 
-            let pawns = pieces & self.pieces(Piece::Pawn);
-            let knights = pieces & self.pieces(Piece::Knight);
-            let lances = pieces & self.pieces(Piece::Lance);
-
-            let badlands_pawns = no_fly_zone(color, Piece::Pawn);
-            let badlands_knights = no_fly_zone(color, Piece::Knight);
-
-            soft_assert!((pawns & badlands_pawns).is_empty());
-            soft_assert!((lances & badlands_pawns).is_empty());
-            soft_assert!((knights & badlands_knights).is_empty());
-        }
-
-        // make sure we have two Kings on board, unless this is a Tsume position
-        if !for_tsume {
-            soft_assert!(self.pieces(Piece::King).len() == 2);
-        } else {
-            soft_assert!(self.pieces(Piece::King).len() <= 2);
+            for piece in [Piece::Pawn, Piece::Lance, Piece::Knight] {
+                let dead = pieces & self.pieces(piece) & no_fly_zone(color, piece);
+                if let Some(square) = dead.next_square() {
+                    return Err(DeadPiece {
+                        color,
+                        piece,
+                        square,
+                    });
+                }
+            }
         }
 
         // make sure that the Kings are not touching each other
-        if !for_tsume || self.has(Color::Black, Piece::King) {
+        if self.mode() == BoardMode::Standard {
             let white_king_square = self.king(Color::White);
             let black_king_square = self.king(Color::Black);
             let white_king_moves = king_attacks(Color::White, white_king_square);
-            soft_assert!(!white_king_moves.has(black_king_square));
+            if white_king_moves.has(black_king_square) {
+                return Err(KingsAdjacent);
+            }
         }
 
         // opponent should not be in check
         // their_checkers is set to the BitBoard of all opponent's pieces attacking our King
         let (their_checkers, _) = self.calculate_checkers_and_pins(!self.side_to_move());
-        soft_assert!(their_checkers.is_empty());
+        if !their_checkers.is_empty() {
+            return Err(OpponentInCheck);
+        }
+        let (checkers, _) = self.calculate_checkers_and_pins(self.side_to_move());
+        if checkers.len() > 2 {
+            return Err(TooManyCheckers {
+                count: checkers.len(),
+            });
+        }
 
-        true
+        self.validate_piece_counts()
     }
 
     /// Are the piece counts valid?
@@ -93,43 +239,58 @@ impl Board {
     /// in both hands and on the board and comparing that to the expected number.
     /// In order to also support handicap games (without too much fuss), we
     /// only check that the piece count does not exceed the expected maximum.
-    pub(super) fn piece_counts_are_valid(&self) -> bool {
+    #[allow(clippy::needless_range_loop)]
+    fn validate_piece_counts(&self) -> Result<(), PositionError> {
         let &hands = self.hands();
         for index in 0..Piece::HAND_NUM {
             let piece = Piece::index_const(index);
             debug_assert!(piece != Piece::King);
 
-            let num_board_pieces = (self.pieces(piece) | self.pieces(piece.promote())).len() as u8;
-            let max_num = Piece::MAX_HAND[index];
-            let sum = hands[0][index] + hands[1][index] + num_board_pieces;
+            let num_board_pieces = (self.pieces(piece) | self.pieces(piece.promote())).len();
+            let max_num = Piece::MAX_HAND[index] as u32;
+            let count = hands[0][index] as u32 + hands[1][index] as u32 + num_board_pieces;
 
-            soft_assert!(sum <= max_num);
+            if count > max_num {
+                return Err(PositionError::PieceCount { piece, count });
+            }
         }
-        true
+        Ok(())
     }
 
     /// Assign all remaining pieces to White's hand. Used in setting up Tsume Shogi positions.
+    #[allow(clippy::needless_range_loop)]
     pub(super) fn piece_counts_make_valid(&mut self) {
         let &hands = self.hands();
         for index in 0..7 {
             let piece = Piece::index_const(index);
             let num = (self.pieces(piece) | self.pieces(piece.promote())).len() as u8;
             let sum = hands[0][index] + hands[1][index] + num;
-            let missing = Piece::MAX_HAND[index] - sum;
-            self.unchecked_set_hand(Color::White, piece, missing);
+            // too many pieces are left for the validation to reject
+            let missing = Piece::MAX_HAND[index].saturating_sub(sum);
+            let white = hands[Color::White as usize][index];
+            self.unchecked_set_hand(Color::White, piece, white + missing);
         }
     }
 
-    pub(super) fn checkers_and_pins_are_valid(&self) -> bool {
+    fn validate_checkers_and_pins(&self) -> Result<(), PositionError> {
         let (checkers, pinned) = self.calculate_checkers_and_pins(self.side_to_move());
-        soft_assert!(self.checkers == checkers);
-        soft_assert!(self.pinned() == pinned);
-        soft_assert!(self.checkers.len() < 3);
-        true
+        if self.checkers != checkers || self.pinned != pinned {
+            return Err(PositionError::StaleCheckersOrPins);
+        }
+        Ok(())
     }
 
-    pub(super) fn move_number_is_valid(&self) -> bool {
-        self.move_number > 0
+    /// Does neither side have two unpromoted Pawns on the same file (nifu)?
+    pub(super) fn validate_pawn_files(&self) -> Result<(), PositionError> {
+        for &color in &Color::ALL {
+            let pawns = self.colored_pieces(color, Piece::Pawn);
+            for &file in &File::ALL {
+                if (pawns & file.bitboard()).len() > 1 {
+                    return Err(PositionError::DoublePawn { color, file });
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Calculate checkers and pins for color.
@@ -142,6 +303,7 @@ impl Board {
         let mut checkers = BitBoard::EMPTY;
         let mut pinned = BitBoard::EMPTY;
 
+        // this also runs on unvalidated boards, which may lack either King
         if !self.has(color, Piece::King) {
             return (checkers, pinned);
         }
@@ -180,3 +342,149 @@ impl Board {
         (checkers, pinned)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kings() -> BoardBuilder {
+        let mut builder = BoardBuilder::new();
+        builder.put(Color::White, Piece::King, Square::A5).put(
+            Color::Black,
+            Piece::King,
+            Square::I5,
+        );
+        builder
+    }
+
+    fn validate(builder: &BoardBuilder) -> Result<(), PositionError> {
+        builder.build(Validation::None).unwrap().validate()
+    }
+
+    #[test]
+    fn valid_positions() {
+        for sfen in include_str!("test_data/valid.sfens").lines() {
+            let board = Board::from_sfen(sfen).unwrap();
+            let nifu = Board::from_sfen_validated(sfen).is_err();
+            assert_eq!(
+                board.validate().is_err(),
+                nifu,
+                "{sfen}: {:?}",
+                board.validate()
+            );
+        }
+    }
+
+    #[test]
+    fn reports_the_problem() {
+        use PositionError::*;
+
+        assert_eq!(validate(&kings()), Ok(()));
+        assert_eq!(validate(kings().move_number(0)), Err(InvalidMoveNumber));
+        assert_eq!(
+            validate(kings().put(Color::White, Piece::King, Square::A1)),
+            Err(KingCount {
+                color: Color::White,
+                count: 2
+            })
+        );
+        assert_eq!(
+            validate(kings().remove(Square::A5)),
+            Err(KingCount {
+                color: Color::White,
+                count: 0
+            })
+        );
+        // without a Black King, the position is a Tsume Shogi problem
+        assert_eq!(validate(kings().remove(Square::I5)), Ok(()));
+        assert_eq!(
+            validate(kings().put(Color::Black, Piece::Knight, Square::B1)),
+            Err(DeadPiece {
+                color: Color::Black,
+                piece: Piece::Knight,
+                square: Square::B1
+            })
+        );
+        assert_eq!(
+            validate(kings().put(Color::White, Piece::Lance, Square::I1)),
+            Err(DeadPiece {
+                color: Color::White,
+                piece: Piece::Lance,
+                square: Square::I1
+            })
+        );
+        assert_eq!(
+            validate(kings().put(Color::Black, Piece::King, Square::B4)),
+            Err(KingCount {
+                color: Color::Black,
+                count: 2
+            })
+        );
+        assert_eq!(
+            validate(
+                kings()
+                    .remove(Square::I5)
+                    .put(Color::Black, Piece::King, Square::B4)
+            ),
+            Err(KingsAdjacent)
+        );
+        assert_eq!(
+            validate(kings().put(Color::Black, Piece::Rook, Square::E5)),
+            Err(OpponentInCheck)
+        );
+        assert_eq!(
+            validate(
+                kings()
+                    .put(Color::White, Piece::Rook, Square::I1)
+                    .put(Color::White, Piece::Gold, Square::H5)
+                    .put(Color::White, Piece::Knight, Square::G4)
+            ),
+            Err(TooManyCheckers { count: 3 })
+        );
+        assert_eq!(
+            validate(kings().set_hand(Color::Black, Piece::Bishop, 2).set_hand(
+                Color::White,
+                Piece::Bishop,
+                1
+            )),
+            Err(PieceCount {
+                piece: Piece::Bishop,
+                count: 3
+            })
+        );
+        let mut builder = kings();
+        for &square in &Square::ALL[..40] {
+            if builder.piece_on(square).is_none() {
+                builder.put(Color::Black, Piece::Tokin, square);
+            }
+        }
+        assert_eq!(
+            validate(&builder),
+            Err(TooManyPieces {
+                color: Color::Black
+            })
+        );
+        assert_eq!(
+            validate(kings().put(Color::White, Piece::Pawn, Square::C3).put(
+                Color::White,
+                Piece::Pawn,
+                Square::F3
+            )),
+            Err(DoublePawn {
+                color: Color::White,
+                file: File::Three
+            })
+        );
+    }
+
+    #[test]
+    fn reports_broken_boards() {
+        let mut board = Board::startpos();
+        board.unchecked_put(Color::Black, Piece::Pawn, Square::A3);
+        assert_eq!(board.validate(), Err(PositionError::OverlappingPieces));
+
+        let mut board: Board = "4k4/9/9/9/9/9/9/9/4K4 b - 1".parse().unwrap();
+        board.unchecked_put(Color::White, Piece::Rook, Square::G5);
+        assert_eq!(board.validate(), Err(PositionError::StaleCheckersOrPins));
+    }
+}
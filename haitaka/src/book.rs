@@ -0,0 +1,471 @@
+//! Binary opening books
+//!
+//! A binary book is a list of 16-byte entries, in the style of the Polyglot books of
+//! chess engines. Each entry gives a move in a position, with a weight (how often the
+//! move should be played, relative to the other moves of the position) and a `learn`
+//! value that is free for the engine to use. All numbers are big-endian:
+//!
+//! | Bytes | Field                                      |
+//! |-------|--------------------------------------------|
+//! | 0-7   | The [`Board::hash`] of the position        |
+//! | 8-9   | The move, see [`Move::to_u16`]             |
+//! | 10-11 | The weight                                 |
+//! | 12-15 | The learn value                            |
+//!
+//! The entries are sorted by hash and then by move, and there is no header. A book can
+//! therefore be probed with a binary search directly in the bytes of the file, for
+//! instance in a memory-mapped file: [`Book`] borrows the bytes and does not copy them.
+//!
+//! [`BookBuilder`] makes a book from positions and moves, or from whole games. Since the
+//! positions are keyed by their hash, a position that is reached by different move
+//! orders (a transposition) has only one set of entries. [`build_from_games`] makes a
+//! book from game records (such as read with the [`kif`](crate::kif) or
+//! [`csa`](crate::csa) modules), weighting the moves by how often they were played and
+//! by the results of the games.
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! use haitaka::book::*;
+//!
+//! let moves: Vec<Move> = ["7g7f", "3c3d", "2g2f"].iter().map(|mv| mv.parse().unwrap()).collect();
+//! let mut builder = BookBuilder::new();
+//! builder.add_game(&Board::startpos(), &moves, 1).unwrap();
+//! builder.add(&Board::startpos(), "2g2f".parse().unwrap(), 3);
+//! let bytes = builder.to_bytes();
+//! assert_eq!(bytes.len(), 4 * ENTRY_SIZE);
+//!
+//! let book = Book::new(&bytes).unwrap();
+//! let entries = book.probe(&Board::startpos());
+//! assert_eq!(entries.len(), 2);
+//! assert_eq!(entries[0].mv, "2g2f".parse().unwrap());
+//! assert_eq!(entries[0].weight, 3);
+//! assert_eq!(entries[1].mv, "7g7f".parse().unwrap());
+//! assert_eq!(entries[1].weight, 1);
+//! ```
+use std::collections::BTreeMap;
+
+use crate::record::*;
+use crate::*;
+
+/// The size of a book entry in bytes.
+pub const ENTRY_SIZE: usize = 16;
+
+helpers::simple_error! {
+    /// The bytes are not a valid binary book.
+    pub enum BookError {
+        InvalidLength = "The length of the book is not a multiple of the entry size",
+        InvalidMove = "A book entry has an invalid move",
+        Unsorted = "The book entries are not sorted, or not unique"
+    }
+}
+
+/// A move of a book position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BookEntry {
+    /// The move.
+    pub mv: Move,
+    /// The weight of the move, relative to the other moves of the position.
+    pub weight: u16,
+    /// A value that is free for the engine to use (0 in books made by [`BookBuilder`]).
+    pub learn: u32,
+}
+
+/// A binary book, borrowing its bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct Book<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Book<'a> {
+    /// Read a book from its bytes.
+    ///
+    /// This checks all entries once, so that probing the book can't fail.
+    ///
+    /// # Errors
+    /// Errors with a [`BookError`] if the length of `data` is not a multiple of
+    /// [`ENTRY_SIZE`], if an entry has an invalid move, or if the entries are not sorted
+    /// by hash and then by move, with no entry repeated.
+    ///
+    /// # Examples
+    /// ```
+    /// use haitaka::book::*;
+    ///
+    /// assert!(Book::new(&[]).unwrap().is_empty());
+    /// assert!(matches!(Book::new(&[0; 15]), Err(BookError::InvalidLength)));
+    /// // A move from 1a to 1a
+    /// assert!(matches!(Book::new(&[0; 16]), Err(BookError::InvalidMove)));
+    /// ```
+    pub fn new(data: &'a [u8]) -> Result<Self, BookError> {
+        if !data.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::InvalidLength);
+        }
+        let book = Self { data };
+        let mut previous = None;
+        for index in 0..book.len() {
+            let key = (book.hash(index), book.move_bits(index));
+            if Move::from_u16(key.1).is_none() {
+                return Err(BookError::InvalidMove);
+            }
+            if previous.is_some_and(|previous| previous >= key) {
+                return Err(BookError::Unsorted);
+            }
+            previous = Some(key);
+        }
+        Ok(book)
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.data.len() / ENTRY_SIZE
+    }
+
+    /// Does the book have no entries?
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn bytes<const N: usize>(&self, index: usize, offset: usize) -> [u8; N] {
+        let start = index * ENTRY_SIZE + offset;
+        self.data[start..start + N].try_into().unwrap()
+    }
+
+    fn hash(&self, index: usize) -> u64 {
+        u64::from_be_bytes(self.bytes(index, 0))
+    }
+
+    fn move_bits(&self, index: usize) -> u16 {
+        u16::from_be_bytes(self.bytes(index, 8))
+    }
+
+    fn entry(&self, index: usize) -> BookEntry {
+        BookEntry {
+            mv: Move::from_u16(self.move_bits(index)).unwrap(),
+            weight: u16::from_be_bytes(self.bytes(index, 10)),
+            learn: u32::from_be_bytes(self.bytes(index, 12)),
+        }
+    }
+
+    /// Get the entries of the position with the given [`Board::hash`], in the order of
+    /// the moves in the book.
+    pub fn probe_hash(&self, hash: u64) -> impl Iterator<Item = BookEntry> + use<'a> {
+        let book = *self;
+        // binary search for the first entry with this hash
+        let (mut low, mut high) = (0, book.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if book.hash(mid) < hash {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        (low..book.len())
+            .take_while(move |&index| book.hash(index) == hash)
+            .map(move |index| book.entry(index))
+    }
+
+    /// Get the entries of `board`, in the order of the moves in the book.
+    ///
+    /// Entries with moves that are not legal on `board` are left out. These can only
+    /// come from another position with the same hash.
+    pub fn probe(&self, board: &Board) -> Vec<BookEntry> {
+        self.probe_hash(board.hash())
+            .filter(|entry| board.is_legal(entry.mv))
+            .collect()
+    }
+}
+
+/// A builder for binary books.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone, Default)]
+pub struct BookBuilder {
+    // (hash, move) => weight
+    entries: BTreeMap<(u64, u16), u16>,
+}
+
+impl BookBuilder {
+    /// Start an empty book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Does the book have no entries?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add `weight` to the weight of `mv` in the position of `board`.
+    ///
+    /// Weights of the same move in the same position add up, saturating at `u16::MAX`.
+    /// The move is not checked.
+    pub fn add(&mut self, board: &Board, mv: Move, weight: u16) -> &mut Self {
+        self.add_hash(board.hash(), mv.to_u16(), weight);
+        self
+    }
+
+    fn add_hash(&mut self, hash: u64, mv: u16, weight: u16) {
+        let total = self.entries.entry((hash, mv)).or_default();
+        *total = total.saturating_add(weight);
+    }
+
+    /// Add every move of a game that starts from `start`, with the same `weight`.
+    ///
+    /// # Errors
+    /// Errors if a move is illegal. The moves before it are added.
+    pub fn add_game(
+        &mut self,
+        start: &Board,
+        moves: &[Move],
+        weight: u16,
+    ) -> Result<&mut Self, IllegalMoveError> {
+        let mut board = start.clone();
+        for &mv in moves {
+            if !board.is_legal(mv) {
+                return Err(IllegalMoveError::new(mv));
+            }
+            self.add(&board, mv, weight);
+            board.play_unchecked(mv);
+        }
+        Ok(self)
+    }
+
+    /// Write the book, with a learn value of 0 in every entry.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_SIZE);
+        for (&(hash, mv), &weight) in &self.entries {
+            bytes.extend(hash.to_be_bytes());
+            bytes.extend(mv.to_be_bytes());
+            bytes.extend(weight.to_be_bytes());
+            bytes.extend(0u32.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+/// Options for [`build_from_games`].
+///
+/// Every time a move is played in a game, it scores the weight of the result of the game
+/// for the player of the move. By default, a win scores 2, a draw (or a game without a
+/// result) 1 and a loss 0, and only the first 40 moves of each game are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BookOptions {
+    /// The number of moves from the start of each game that are added.
+    pub max_ply: usize,
+    /// Moves that were played fewer times than this (in the same position) are left out.
+    pub min_count: u32,
+    /// The score of a move of the winner.
+    pub win_weight: u16,
+    /// The score of a move in a drawn game, or in a game without a result.
+    pub draw_weight: u16,
+    /// The score of a move of the loser.
+    pub loss_weight: u16,
+}
+
+impl Default for BookOptions {
+    fn default() -> Self {
+        Self {
+            max_ply: 40,
+            min_count: 1,
+            win_weight: 2,
+            draw_weight: 1,
+            loss_weight: 0,
+        }
+    }
+}
+
+/// Make a book from game records.
+///
+/// The moves of all games are merged, so that transpositions share their entries, and
+/// the weight of each move is the sum of its scores (see [`BookOptions`]), saturating at
+/// `u16::MAX`. Moves with a total weight of 0 are left out. The result of a game is
+/// taken from [`GameRecord::winner`]. A game is only used up to its first illegal move.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::book::*;
+/// use haitaka::record::*;
+///
+/// let mut games = Vec::new();
+/// for (moves, end) in [
+///     ("7g7f 3c3d 2g2f", GameEnd::Resign),
+///     ("7g7f 8c8d", GameEnd::Resign),
+///     ("2g2f 8c8d", GameEnd::Repetition),
+/// ] {
+///     let mut record = GameRecord::new(Board::startpos());
+///     for mv in moves.split_whitespace() {
+///         record.push(mv.parse().unwrap());
+///     }
+///     record.end = Some(end);
+///     games.push(record);
+/// }
+///
+/// let bytes = build_from_games(games, &BookOptions::default()).to_bytes();
+/// let book = Book::new(&bytes).unwrap();
+/// let weights: Vec<_> = book
+///     .probe(&Board::startpos())
+///     .iter()
+///     .map(|entry| (entry.mv.to_string(), entry.weight))
+///     .collect();
+/// // 7g7f won once and lost once, 2g2f was drawn
+/// assert_eq!(weights.len(), 2);
+/// assert!(weights.contains(&("7g7f".to_string(), 2)));
+/// assert!(weights.contains(&("2g2f".to_string(), 1)));
+/// ```
+pub fn build_from_games(
+    games: impl IntoIterator<Item = GameRecord>,
+    options: &BookOptions,
+) -> BookBuilder {
+    // (hash, move) => (times played, total score)
+    let mut stats: BTreeMap<(u64, u16), (u32, u32)> = BTreeMap::new();
+    for game in games {
+        let winner = game.winner();
+        let mut board = game.start;
+        for &mv in game.moves.iter().take(options.max_ply) {
+            if !board.is_legal(mv) {
+                break;
+            }
+            let score = match winner {
+                Some(color) if color == board.side_to_move() => options.win_weight,
+                Some(_) => options.loss_weight,
+                None => options.draw_weight,
+            };
+            let (count, total) = stats.entry((board.hash(), mv.to_u16())).or_default();
+            *count += 1;
+            *total = total.saturating_add(score as u32);
+            board.play_unchecked(mv);
+        }
+    }
+
+    let mut builder = BookBuilder::new();
+    for ((hash, mv), (count, total)) in stats {
+        if count >= options.min_count && total > 0 {
+            builder.add_hash(hash, mv, total.min(u16::MAX as u32) as u16);
+        }
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(usi: &str) -> Vec<Move> {
+        usi.split_whitespace()
+            .map(|mv| mv.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn transpositions_share_entries() {
+        let start = Board::startpos();
+        let mut builder = BookBuilder::new();
+        builder
+            .add_game(&start, &moves("7g7f 3c3d 2g2f 8c8d"), 1)
+            .unwrap()
+            .add_game(&start, &moves("2g2f 3c3d 7g7f 8c8d"), 1)
+            .unwrap()
+            .add_game(&start, &moves("2g2f 3c3d 7g7f 4a3b"), u16::MAX)
+            .unwrap();
+        assert!(builder.add_game(&start, &moves("7g7f 7g7f"), 1).is_err());
+        // the first move of the illegal game is added
+        assert_eq!(builder.len(), 8);
+
+        let bytes = builder.to_bytes();
+        let book = Book::new(&bytes).unwrap();
+        assert_eq!(book.len(), 8);
+
+        let mut board = start.clone();
+        for mv in moves("7g7f 3c3d 2g2f") {
+            board.play(mv);
+        }
+        let entries = book.probe(&board);
+        assert_eq!(entries.len(), 2);
+        let weights: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.mv, entry.weight))
+            .collect();
+        assert!(weights.contains(&("8c8d".parse().unwrap(), 2)));
+        assert!(weights.contains(&("4a3b".parse().unwrap(), u16::MAX)));
+        assert!(entries.iter().all(|entry| entry.learn == 0));
+
+        let mut weights: Vec<_> = book.probe(&start).iter().map(|e| e.weight).collect();
+        weights.sort();
+        assert_eq!(weights, [2, u16::MAX]);
+        assert_eq!(book.probe_hash(0).count(), 0);
+    }
+
+    #[test]
+    fn rejects_unsorted_books() {
+        let mut builder = BookBuilder::new();
+        builder
+            .add_game(&Board::startpos(), &moves("7g7f 3c3d"), 1)
+            .unwrap();
+        let bytes = builder.to_bytes();
+        let swapped = [&bytes[ENTRY_SIZE..], &bytes[..ENTRY_SIZE]].concat();
+        assert!(matches!(Book::new(&swapped), Err(BookError::Unsorted)));
+        let repeated = [&bytes[..ENTRY_SIZE], &bytes[..ENTRY_SIZE]].concat();
+        assert!(matches!(Book::new(&repeated), Err(BookError::Unsorted)));
+    }
+
+    fn record(usi: &str, end: Option<GameEnd>) -> GameRecord {
+        let mut record = GameRecord::new(Board::startpos());
+        for mv in moves(usi) {
+            record.push(mv);
+        }
+        record.end = end;
+        record
+    }
+
+    #[test]
+    fn books_from_games() {
+        let games = [
+            // White resigns
+            record("7g7f 3c3d 2g2f", Some(GameEnd::Resign)),
+            // Black resigns
+            record("2g2f 3c3d 7g7f 8c8d", Some(GameEnd::Resign)),
+            record("7g7f 8c8d", Some(GameEnd::Repetition)),
+            // only used up to the illegal move
+            record("7g7f 8c8d 7g7f 8d8e", None),
+        ];
+        let bytes = build_from_games(games.clone(), &BookOptions::default()).to_bytes();
+        let book = Book::new(&bytes).unwrap();
+        let weights = |usi: &str| {
+            let mut board = Board::startpos();
+            for mv in moves(usi) {
+                board.play(mv);
+            }
+            let mut weights: Vec<_> = book
+                .probe(&board)
+                .iter()
+                .map(|entry| (entry.mv.to_string(), entry.weight))
+                .collect();
+            weights.sort();
+            weights
+        };
+
+        // 2g2f only lost, so its weight is 0 and it is left out
+        assert_eq!(weights(""), [("7g7f".to_string(), 2 + 1 + 1)]);
+        assert_eq!(weights("7g7f"), [("8c8d".to_string(), 1 + 1)]);
+        assert_eq!(weights("7g7f 3c3d"), [("2g2f".to_string(), 2)]);
+        // a transposition of the first game, reached in the second
+        assert_eq!(weights("7g7f 3c3d 2g2f"), [("8c8d".to_string(), 2)]);
+
+        let options = BookOptions {
+            max_ply: 1,
+            min_count: 2,
+            win_weight: 1,
+            draw_weight: 1,
+            loss_weight: 1,
+        };
+        // only 7g7f was played more than once
+        assert_eq!(build_from_games(games, &options).len(), 1);
+    }
+}
@@ -0,0 +1,145 @@
+//! Runtime discovery of compile-time features
+use core::fmt;
+
+/// The implementation used for slider attacks (see [`get_rook_moves`](crate::get_rook_moves)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SliderBackend {
+    /// Magic bitboard lookup tables, generated at build time (the default).
+    Magic,
+    /// The Qugiy algorithm, computing attacks with bit tricks instead of tables
+    /// (`qugiy` feature).
+    Qugiy,
+}
+
+/// The compile-time features of this build of the crate.
+///
+/// Returned by [`capabilities`]. Applications and test harnesses can use this to
+/// report the configuration, for instance in bug reports, or to adapt to it.
+/// The [`Display`](fmt::Display) implementation gives a one-line summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The crate version.
+    pub version: &'static str,
+    /// The slider attack implementation.
+    pub slider_backend: SliderBackend,
+    /// Whether the `std` feature is enabled. It adds the `std::error::Error` impls of
+    /// the error types.
+    /// The crate links `std` either way: it does not support `no_std` yet.
+    pub std: bool,
+    /// Whether the slider move tables are built on first use instead of by the build
+    /// script (`lazy-tables` feature).
+    pub lazy_tables: bool,
+    /// Whether the board uses the color-major storage layout (`color-major-storage` feature).
+    pub color_major_storage: bool,
+    /// Whether SVG rendering is available (`viz` feature).
+    pub viz: bool,
+    /// Whether the reference evaluation is available (`reference-eval` feature).
+    pub reference_eval: bool,
+    /// Whether Shift-JIS records can be decoded (`encoding` feature).
+    pub encoding: bool,
+    /// Whether the basic types implement `Serialize` and `Deserialize` (`serde` feature).
+    pub serde: bool,
+    /// Whether the parallel perft and move mapping are available (`rayon` feature).
+    pub rayon: bool,
+    /// Whether the conversions to and from the `shogi_core` types are available
+    /// (`interop-shogi-core` feature).
+    pub interop_shogi_core: bool,
+    /// Whether every check generated by [`Board::generate_checks`](crate::Board::generate_checks)
+    /// and every move generated by [`Board::generate_moves`](crate::Board::generate_moves) is
+    /// verified (`verify-checks` feature).
+    pub verify_checks: bool,
+}
+
+impl Capabilities {
+    // The Cargo features other than the slider backend, and whether they are enabled.
+    fn features(&self) -> [(&'static str, bool); 10] {
+        [
+            ("std", self.std),
+            ("lazy-tables", self.lazy_tables),
+            ("color-major-storage", self.color_major_storage),
+            ("viz", self.viz),
+            ("reference-eval", self.reference_eval),
+            ("encoding", self.encoding),
+            ("serde", self.serde),
+            ("rayon", self.rayon),
+            ("interop-shogi-core", self.interop_shogi_core),
+            ("verify-checks", self.verify_checks),
+        ]
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sliders = match self.slider_backend {
+            SliderBackend::Magic => "magic",
+            SliderBackend::Qugiy => "qugiy",
+        };
+        write!(f, "haitaka {} (sliders: {}", self.version, sliders)?;
+        for (name, enabled) in self.features() {
+            if enabled {
+                write!(f, ", {}", name)?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// Get the compile-time features of this build.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let caps = capabilities();
+/// assert_eq!(caps.version, env!("CARGO_PKG_VERSION"));
+/// assert_eq!(caps.std, cfg!(feature = "std"));
+/// println!("{}", caps);
+/// ```
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        slider_backend: if cfg!(feature = "qugiy") {
+            SliderBackend::Qugiy
+        } else {
+            SliderBackend::Magic
+        },
+        std: cfg!(feature = "std"),
+        lazy_tables: cfg!(feature = "lazy-tables"),
+        color_major_storage: cfg!(feature = "color-major-storage"),
+        viz: cfg!(feature = "viz"),
+        reference_eval: cfg!(feature = "reference-eval"),
+        encoding: cfg!(feature = "encoding"),
+        serde: cfg!(feature = "serde"),
+        rayon: cfg!(feature = "rayon"),
+        interop_shogi_core: cfg!(feature = "interop-shogi-core"),
+        verify_checks: cfg!(feature = "verify-checks"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_match_the_build() {
+        let caps = capabilities();
+        assert_eq!(
+            caps.slider_backend == SliderBackend::Qugiy,
+            cfg!(feature = "qugiy")
+        );
+        assert_eq!(
+            caps.color_major_storage,
+            cfg!(feature = "color-major-storage")
+        );
+
+        assert_eq!(caps.lazy_tables, cfg!(feature = "lazy-tables"));
+        assert_eq!(caps.rayon, cfg!(feature = "rayon"));
+
+        let summary = caps.to_string();
+        assert!(summary.starts_with(&format!("haitaka {} (sliders: ", caps.version)));
+        for (name, enabled) in caps.features() {
+            assert_eq!(summary.contains(&format!(", {name}")), enabled, "{summary}");
+        }
+    }
+}
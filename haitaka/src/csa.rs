@@ -0,0 +1,815 @@
+//! Reading and writing CSA game records
+//!
+//! CSA is the record format of the Computer Shogi Association. It is used by computer
+//! Shogi servers such as floodgate. A CSA record consists of the names of the players
+//! (`N+`, `N-`), other header fields (`$KEY:value`), the start position, the side to
+//! move, and one line per move (`+7776FU`), optionally followed by the time used in
+//! seconds (`T3`). The game end is marked with a special move such as `%TORYO`, and
+//! comment lines start with `'`:
+//!
+//! ```text
+//! V2.2
+//! N+Alice
+//! N-Bob
+//! $START_TIME:2024/01/01 10:00:00
+//! PI
+//! +
+//! +7776FU
+//! T3
+//! 'The usual opening move
+//! -3334FU
+//! T5
+//! %TORYO
+//! ```
+//!
+//! The start position is either `PI` (the standard start position, optionally with
+//! pieces removed for a handicap) or a board given row by row (`P1` to `P9`), with the
+//! pieces in hand given by `P+` and `P-` lines. Several statements can be put on one
+//! line, separated by commas.
+//!
+//! The header fields are stored with the keys of the [`kif`](crate::kif) module, so
+//! that records can be converted between the formats: the players are `先手` and `後手`,
+//! and `$EVENT`, `$SITE`, `$START_TIME`, `$END_TIME`, `$TIME_LIMIT` and `$OPENING` are
+//! `棋戦`, `場所`, `開始日時`, `終了日時`, `持ち時間` and `戦型`. Other `$` fields keep
+//! their key.
+//!
+//! With the `encoding` feature, [`parse_bytes`] reads Shift-JIS records as well.
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::record::*;
+use crate::*;
+
+helpers::simple_error! {
+    /// The kind of a [`CsaError`].
+    pub enum CsaErrorKind {
+        InvalidPosition = "Invalid start position",
+        InvalidMove = "Invalid move",
+        IllegalMove = "Illegal move",
+        InvalidTime = "Invalid time",
+        InvalidEncoding = "The record is neither valid UTF-8 nor valid Shift-JIS"
+    }
+}
+
+/// An error in a CSA record.
+#[derive(Debug, Clone, Copy)]
+pub struct CsaError {
+    line: usize,
+    kind: CsaErrorKind,
+}
+
+impl CsaError {
+    /// The line of the error (starting at 1), or 0 if the error is not on a line.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The kind of error.
+    pub fn kind(&self) -> CsaErrorKind {
+        self.kind
+    }
+}
+
+impl core::fmt::Display for CsaError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} (line {})", self.kind, self.line)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CsaError {}
+
+helpers::simple_error! {
+    /// A record that can't be written in CSA.
+    pub struct CsaWriteError = "The record holds an illegal move.";
+}
+
+// The piece names, in the order of `Piece::ALL`.
+const PIECE_NAMES: [&str; Piece::NUM] = [
+    "FU", "KY", "KE", "GI", "KA", "HI", "KI", "OU", "TO", "NY", "NK", "NG", "UM", "RY",
+];
+
+// The order in which the pieces in hand are written.
+const HAND_ORDER: [Piece; Piece::HAND_NUM] = [
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Gold,
+    Piece::Silver,
+    Piece::Knight,
+    Piece::Lance,
+    Piece::Pawn,
+];
+
+// The `$` header fields with a KIF equivalent.
+const HEADER_KEYS: [(&str, &str); 6] = [
+    ("EVENT", "棋戦"),
+    ("SITE", "場所"),
+    ("START_TIME", "開始日時"),
+    ("END_TIME", "終了日時"),
+    ("TIME_LIMIT", "持ち時間"),
+    ("OPENING", "戦型"),
+];
+
+// The KIF keys of the player names, by color.
+const PLAYER_KEYS: [&str; Color::NUM] = ["後手", "先手"];
+
+// The special moves that end a game. `%+ILLEGAL_ACTION` and `%-ILLEGAL_ACTION` are
+// handled separately.
+const GAME_ENDS: [(&str, GameEnd); 9] = [
+    ("%TORYO", GameEnd::Resign),
+    ("%CHUDAN", GameEnd::Abort),
+    ("%SENNICHITE", GameEnd::Repetition),
+    ("%JISHOGI", GameEnd::Impasse),
+    ("%TIME_UP", GameEnd::Timeout),
+    ("%TSUMI", GameEnd::Mate),
+    ("%FUZUMI", GameEnd::NoMate),
+    ("%ILLEGAL_MOVE", GameEnd::IllegalMoveLoss),
+    ("%KACHI", GameEnd::EnteringKingWin),
+];
+
+fn color_sign(color: Color) -> char {
+    match color {
+        Color::Black => '+',
+        Color::White => '-',
+    }
+}
+
+fn parse_color(c: char) -> Option<Color> {
+    match c {
+        '+' => Some(Color::Black),
+        '-' => Some(Color::White),
+        _ => None,
+    }
+}
+
+fn parse_piece(name: &str) -> Option<Piece> {
+    PIECE_NAMES
+        .iter()
+        .position(|&n| n == name)
+        .map(Piece::index_const)
+}
+
+// Parse a square such as `77`, or `00` for a piece in hand.
+fn parse_square(digits: &str) -> Option<Option<Square>> {
+    let mut digits = digits.chars().map(|c| c.to_digit(10));
+    match (digits.next()??, digits.next()??) {
+        (0, 0) => Some(None),
+        (file @ 1..=9, rank @ 1..=9) => Some(Some(Square::new(
+            File::index_const(file as usize - 1),
+            Rank::index_const(rank as usize - 1),
+        ))),
+        _ => None,
+    }
+}
+
+// The start position while it is read.
+struct Setup {
+    board: [Option<(Color, Piece)>; Square::NUM],
+    hands: [[u8; Piece::HAND_NUM]; Color::NUM],
+}
+
+impl Setup {
+    fn new() -> Self {
+        Self {
+            board: [None; Square::NUM],
+            hands: [[0; Piece::HAND_NUM]; Color::NUM],
+        }
+    }
+
+    fn startpos() -> Self {
+        let mut setup = Self::new();
+        for (color, piece, square) in Board::startpos().iter_pieces() {
+            setup.board[square as usize] = Some((color, piece));
+        }
+        setup
+    }
+
+    // Parse a row such as `P1-KY-KE-GI-KI-OU-KI-GI-KE-KY`.
+    fn parse_row(&mut self, rank: Rank, text: &str) -> Option<()> {
+        if !text.is_ascii() || text.len() > 3 * File::NUM {
+            return None;
+        }
+        // trailing empty squares may be left out
+        let text = format!("{:<width$}", text, width = 3 * File::NUM);
+        for (index, cell) in text.as_bytes().chunks(3).enumerate() {
+            let cell = core::str::from_utf8(cell).ok()?;
+            let square = Square::new(File::index_const(File::NUM - 1 - index), rank);
+            self.board[square as usize] = match cell {
+                " * " | "   " => None,
+                _ => {
+                    let color = parse_color(cell.chars().next()?)?;
+                    Some((color, parse_piece(&cell[1..])?))
+                }
+            };
+        }
+        Some(())
+    }
+
+    // Parse the pieces of a `P+` or `P-` line, such as `00KA00FU` or `55OU`.
+    fn parse_pieces(&mut self, color: Color, text: &str) -> Option<()> {
+        if !text.is_ascii() || !text.len().is_multiple_of(4) {
+            return None;
+        }
+        for chunk in text.as_bytes().chunks(4) {
+            let chunk = core::str::from_utf8(chunk).ok()?;
+            if chunk == "00AL" {
+                self.add_remaining(color);
+                continue;
+            }
+            let piece = parse_piece(&chunk[2..])?;
+            match parse_square(&chunk[..2])? {
+                Some(square) => self.board[square as usize] = Some((color, piece)),
+                None if (piece as usize) < Piece::HAND_NUM => {
+                    let hand = &mut self.hands[color as usize][piece as usize];
+                    *hand = hand.saturating_add(1);
+                }
+                None => return None,
+            }
+        }
+        Some(())
+    }
+
+    // Put all pieces that are not on the board or in a hand into the hand of `color`.
+    fn add_remaining(&mut self, color: Color) {
+        for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+            let on_board = self
+                .board
+                .iter()
+                .flatten()
+                .filter(|(_, p)| p.unpromote() == piece)
+                .count();
+            let in_hands: usize = self
+                .hands
+                .iter()
+                .map(|hand| hand[piece as usize] as usize)
+                .sum();
+            let max = Piece::MAX_HAND[piece as usize] as usize;
+            let hand = &mut self.hands[color as usize][piece as usize];
+            *hand += max.saturating_sub(on_board + in_hands) as u8;
+        }
+    }
+
+    fn build(&self, side_to_move: Color) -> Option<Board> {
+        let mut builder = BoardBuilder::new();
+        for (square, cell) in Square::ALL.into_iter().zip(self.board) {
+            if let Some((color, piece)) = cell {
+                builder.put(color, piece, square);
+            }
+        }
+        for color in Color::ALL {
+            for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+                builder.set_hand(color, piece, self.hands[color as usize][piece as usize]);
+            }
+        }
+        builder.side_to_move(side_to_move);
+        // a board without the Black King is a Tsume Shogi problem
+        let sfen = builder.build(Validation::None).ok()?.to_string();
+        builder
+            .build(Validation::Strict)
+            .or_else(|_| Board::tsume(&sfen))
+            .ok()
+    }
+}
+
+// Parse a move such as `+7776FU` for the side to move.
+fn parse_move(board: &Board, text: &str) -> Option<Move> {
+    if parse_color(text.chars().next()?)? != board.side_to_move() {
+        return None;
+    }
+    Move::parse_csa(text, |square| board.piece_on(square)).ok()
+}
+
+/// Parse a CSA record.
+///
+/// The moves are checked to be legal.
+///
+/// # Errors
+/// Errors with a [`CsaError`] holding the line of the error.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::csa;
+/// use haitaka::record::*;
+/// let csa = "\
+/// N+Alice
+/// N-Bob
+/// PI
+/// +
+/// +7776FU,T3
+/// -3334FU,T5
+/// +8822UM
+/// -3122GI
+/// +0045KA
+/// %TORYO
+/// ";
+/// let record = csa::parse(csa).unwrap();
+/// assert_eq!(record.header("先手"), Some("Alice"));
+/// assert_eq!(record.moves.len(), 5);
+/// assert_eq!(record.moves[3], "3a2b".parse().unwrap());
+/// assert_eq!(record.end, Some(GameEnd::Resign));
+/// assert_eq!(record.final_board().num_in_hand(Color::White, Piece::Bishop), 1);
+/// ```
+pub fn parse(text: &str) -> Result<GameRecord, CsaError> {
+    use CsaErrorKind::*;
+
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut record = GameRecord::new(Board::startpos());
+    let mut setup = None;
+    let mut board = None;
+    let mut last_line = 0;
+    for (line_no, line) in text.lines().enumerate() {
+        let line_no = line_no + 1;
+        last_line = line_no;
+        let error = |kind| CsaError {
+            line: line_no,
+            kind,
+        };
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('\'') {
+            record
+                .comments
+                .push((record.moves.len(), comment.to_string()));
+            continue;
+        }
+        // position lines can't be split with commas
+        if let Some(rest) = line.strip_prefix('P') {
+            if board.is_some() {
+                return Err(error(InvalidPosition));
+            }
+            let setup = setup.get_or_insert_with(Setup::new);
+            let valid = match rest.chars().next() {
+                Some('I') => {
+                    *setup = Setup::startpos();
+                    // the pieces removed for a handicap, such as `82HI22KA`
+                    let removed = &rest[1..];
+                    removed.len().is_multiple_of(4)
+                        && removed.is_ascii()
+                        && removed.as_bytes().chunks(4).all(|chunk| {
+                            let chunk = core::str::from_utf8(chunk).unwrap_or_default();
+                            match (parse_square(&chunk[..2]), parse_piece(&chunk[2..])) {
+                                (Some(Some(square)), Some(piece)) => {
+                                    let cell = &mut setup.board[square as usize];
+                                    let matches = cell.map(|(_, piece)| piece) == Some(piece);
+                                    *cell = None;
+                                    matches
+                                }
+                                _ => false,
+                            }
+                        })
+                }
+                Some(c @ ('+' | '-')) => setup
+                    .parse_pieces(parse_color(c).unwrap(), &rest[1..])
+                    .is_some(),
+                Some(c @ '1'..='9') => {
+                    let rank = Rank::index_const(c as usize - '1' as usize);
+                    setup.parse_row(rank, &rest[1..]).is_some()
+                }
+                _ => false,
+            };
+            if !valid {
+                return Err(error(InvalidPosition));
+            }
+            continue;
+        }
+
+        for statement in line.split(',') {
+            if let Some(name) = statement.strip_prefix("N+") {
+                record.headers.push((
+                    PLAYER_KEYS[Color::Black as usize].to_string(),
+                    name.to_string(),
+                ));
+            } else if let Some(name) = statement.strip_prefix("N-") {
+                record.headers.push((
+                    PLAYER_KEYS[Color::White as usize].to_string(),
+                    name.to_string(),
+                ));
+            } else if let Some(field) = statement.strip_prefix('$') {
+                let (key, value) = field.split_once(':').unwrap_or((field, ""));
+                let key = HEADER_KEYS
+                    .iter()
+                    .find(|(csa_key, _)| *csa_key == key)
+                    .map_or(key, |(_, kif_key)| kif_key);
+                record.headers.push((key.to_string(), value.to_string()));
+            } else if statement == "+" || statement == "-" {
+                if board.is_some() {
+                    return Err(error(InvalidPosition));
+                }
+                let color = parse_color(statement.chars().next().unwrap()).unwrap();
+                let start = match &setup {
+                    Some(setup) => setup.build(color).ok_or(error(InvalidPosition))?,
+                    None => return Err(error(InvalidPosition)),
+                };
+                record.start = start.clone();
+                board = Some(start);
+            } else if let Some(secs) = statement.strip_prefix('T') {
+                let secs = secs.parse().map_err(|_| error(InvalidTime))?;
+                match record.times.last_mut() {
+                    Some(time) => *time = Some(Duration::from_secs(secs)),
+                    None => return Err(error(InvalidTime)),
+                }
+            } else if let Some(end) = statement.strip_prefix('%') {
+                let board = board.as_ref().ok_or(error(InvalidPosition))?;
+                let color = board.side_to_move();
+                record.end = match end {
+                    "+ILLEGAL_ACTION" | "-ILLEGAL_ACTION" => {
+                        if parse_color(end.chars().next().unwrap()) == Some(color) {
+                            Some(GameEnd::IllegalMoveLoss)
+                        } else {
+                            Some(GameEnd::IllegalMoveWin)
+                        }
+                    }
+                    _ => GAME_ENDS
+                        .iter()
+                        .find(|(name, _)| name[1..] == *end)
+                        .map(|(_, end)| *end),
+                };
+                // other special moves (such as `%MATTA`) are skipped
+            } else if statement.starts_with(['+', '-']) {
+                let board = board.as_mut().ok_or(error(InvalidPosition))?;
+                if record.end.is_some() {
+                    continue;
+                }
+                let mv = parse_move(board, statement).ok_or(error(InvalidMove))?;
+                if !board.is_legal(mv) {
+                    return Err(error(IllegalMove));
+                }
+                board.play_unchecked(mv);
+                record.push(mv);
+            }
+            // other lines (such as the version `V2.2`) are skipped
+        }
+    }
+    if board.is_none() {
+        return Err(CsaError {
+            line: last_line,
+            kind: InvalidPosition,
+        });
+    }
+    Ok(record)
+}
+
+/// Parse a CSA record in UTF-8 or Shift-JIS.
+///
+/// The encoding is detected with [`encoding::detect`](crate::encoding::detect).
+/// This requires the `encoding` feature.
+///
+/// # Errors
+/// Errors with a [`CsaError`] holding the line of the error. If the record can't be
+/// decoded, the kind is [`CsaErrorKind::InvalidEncoding`] and the line is 0.
+#[cfg(feature = "encoding")]
+pub fn parse_bytes(bytes: &[u8]) -> Result<GameRecord, CsaError> {
+    let text = encoding::decode(bytes).map_err(|_| CsaError {
+        line: 0,
+        kind: CsaErrorKind::InvalidEncoding,
+    })?;
+    parse(&text)
+}
+
+fn write_comments(text: &mut String, record: &GameRecord, ply: usize) {
+    for (_, comment) in record.comments.iter().filter(|(at, _)| *at == ply) {
+        for line in comment.lines() {
+            text.push('\'');
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+}
+
+fn write_position(text: &mut String, board: &Board) {
+    if board.same_position(&Board::startpos()) {
+        text.push_str("PI\n");
+    } else {
+        for rank in Rank::ALL {
+            let _ = write!(text, "P{}", rank as usize + 1);
+            for &file in File::ALL.iter().rev() {
+                match board.color_on(Square::new(file, rank)) {
+                    Some(color) => {
+                        let piece = board.piece_on(Square::new(file, rank)).unwrap();
+                        text.push(color_sign(color));
+                        text.push_str(PIECE_NAMES[piece as usize]);
+                    }
+                    None => text.push_str(" * "),
+                }
+            }
+            text.push('\n');
+        }
+        for color in [Color::Black, Color::White] {
+            let _ = write!(text, "P{}", color_sign(color));
+            for piece in HAND_ORDER {
+                for _ in 0..board.num_in_hand(color, piece) {
+                    let _ = write!(text, "00{}", PIECE_NAMES[piece as usize]);
+                }
+            }
+            text.push('\n');
+        }
+    }
+    text.push(color_sign(board.side_to_move()));
+    text.push('\n');
+}
+
+/// Write a record in CSA (version 2.2).
+///
+/// The players (`先手` and `後手`) and the header fields listed in the
+/// [module documentation](self) are written as `N+`, `N-` and `$` fields. Other header
+/// fields with an ASCII key are written with their key, and the rest is left out. The
+/// start position is written as `PI` if it is the standard start position, and row by
+/// row otherwise.
+///
+/// # Errors
+/// Errors with [`CsaWriteError`] if a move of the record is illegal.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::csa;
+/// use haitaka::record::*;
+/// let mut record = GameRecord::new(Board::startpos());
+/// record.headers.push(("先手".to_string(), "Alice".to_string()));
+/// record.headers.push(("開始日時".to_string(), "2024/01/01".to_string()));
+/// for mv in ["7g7f", "3c3d", "8h2b+", "3a2b", "B*4e"] {
+///     record.push(mv.parse().unwrap());
+/// }
+/// record.end = Some(GameEnd::Resign);
+///
+/// let csa = csa::write(&record).unwrap();
+/// assert_eq!(csa, "\
+/// V2.2
+/// N+Alice
+/// $START_TIME:2024/01/01
+/// PI
+/// +
+/// +7776FU
+/// -3334FU
+/// +8822UM
+/// -3122GI
+/// +0045KA
+/// %TORYO
+/// ");
+/// assert_eq!(csa::parse(&csa).unwrap(), record);
+/// ```
+pub fn write(record: &GameRecord) -> Result<String, CsaWriteError> {
+    let mut text = String::from("V2.2\n");
+    for color in [Color::Black, Color::White] {
+        if let Some(name) = record.header(PLAYER_KEYS[color as usize]) {
+            let _ = writeln!(text, "N{}{}", color_sign(color), name);
+        }
+    }
+    for (key, value) in &record.headers {
+        if PLAYER_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let key = match HEADER_KEYS
+            .iter()
+            .find(|(_, kif_key)| *kif_key == key.as_str())
+        {
+            Some((csa_key, _)) => *csa_key,
+            None if key.is_ascii() && !key.contains(':') => key.as_str(),
+            None => continue,
+        };
+        let _ = writeln!(text, "${}:{}", key, value);
+    }
+    write_position(&mut text, &record.start);
+    write_comments(&mut text, record, 0);
+
+    let mut board = record.start.clone();
+    for (ply, &mv) in record.moves.iter().enumerate() {
+        if !board.is_legal(mv) {
+            return Err(CsaWriteError);
+        }
+        text.push(color_sign(board.side_to_move()));
+        match mv {
+            Move::Drop { piece, to } => {
+                let _ = write!(
+                    text,
+                    "00{}{}",
+                    to.file() as usize + 1,
+                    to.rank() as usize + 1
+                );
+                text.push_str(PIECE_NAMES[piece as usize]);
+            }
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => {
+                let piece = board.piece_on(from).unwrap();
+                let piece = if promotion { piece.promote() } else { piece };
+                let _ = write!(
+                    text,
+                    "{}{}{}{}{}",
+                    from.file() as usize + 1,
+                    from.rank() as usize + 1,
+                    to.file() as usize + 1,
+                    to.rank() as usize + 1,
+                    PIECE_NAMES[piece as usize]
+                );
+            }
+        }
+        text.push('\n');
+        if let Some(time) = record.times.get(ply).copied().flatten() {
+            let _ = writeln!(text, "T{}", time.as_secs());
+        }
+        board.play_unchecked(mv);
+        write_comments(&mut text, record, ply + 1);
+    }
+    match record.end {
+        Some(GameEnd::IllegalMoveWin) => {
+            // the player who moved last made the illegal move
+            let _ = writeln!(text, "%{}ILLEGAL_ACTION", color_sign(!board.side_to_move()));
+        }
+        Some(end) => {
+            if let Some((name, _)) = GAME_ENDS.iter().find(|(_, e)| *e == end) {
+                let _ = writeln!(text, "{}", name);
+            }
+        }
+        None => {}
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSA: &str = "\
+'---- a floodgate record ----
+V2.2
+N+Alice
+N-Bob
+$EVENT:practice
+$START_TIME:2024/01/01 10:00:00
+$ROUND:3
+PI
++
+'Start
++7776FU,T3
+-3334FU
+T5
+'A quiet reply
+'on two lines
++8822UM,T1
+-3122GI,T62
++0045KA,T10
+-5152OU,T0
++4563KA,T1
+%CHUDAN
+";
+
+    #[test]
+    fn parse_record() {
+        let record = parse(CSA).unwrap();
+        assert_eq!(
+            record.headers,
+            [
+                ("先手".to_string(), "Alice".to_string()),
+                ("後手".to_string(), "Bob".to_string()),
+                ("棋戦".to_string(), "practice".to_string()),
+                ("開始日時".to_string(), "2024/01/01 10:00:00".to_string()),
+                ("ROUND".to_string(), "3".to_string()),
+            ]
+        );
+        assert_eq!(record.moves.len(), 7);
+        assert_eq!(record.moves[2], "8h2b+".parse().unwrap());
+        assert_eq!(record.moves[4], "B*4e".parse().unwrap());
+        assert_eq!(record.moves[6], "4e6c".parse().unwrap());
+        assert_eq!(record.times[1], Some(Duration::from_secs(5)));
+        assert_eq!(record.times[3], Some(Duration::from_secs(62)));
+        assert_eq!(
+            record.comments,
+            [
+                (0, "---- a floodgate record ----".to_string()),
+                (0, "Start".to_string()),
+                (2, "A quiet reply".to_string()),
+                (2, "on two lines".to_string())
+            ]
+        );
+        assert_eq!(record.end, Some(GameEnd::Abort));
+    }
+
+    #[test]
+    fn write_and_parse_again() {
+        let record = parse(CSA).unwrap();
+        let csa = write(&record).unwrap();
+        assert!(csa.contains("-3122GI\nT62\n"));
+        assert!(csa.contains("'A quiet reply\n'on two lines\n+8822UM\n"));
+        assert_eq!(parse(&csa).unwrap(), record);
+    }
+
+    #[test]
+    fn convert_to_kif_and_back() {
+        let record = parse(CSA).unwrap();
+        let kif = kif::write(&record).unwrap();
+        let mut from_kif = kif::parse(&kif).unwrap();
+        // KIF adds the handicap header
+        assert_eq!(
+            from_kif.headers.remove(0),
+            ("手合割".to_string(), "平手".to_string())
+        );
+        assert_eq!(from_kif, record);
+        assert_eq!(write(&from_kif).unwrap(), write(&record).unwrap());
+    }
+
+    #[test]
+    fn board_positions() {
+        let csa = "\
+P1 *  *  *  * -OU *  *  *  * 
+P2
+P3 *  *  *  * +FU
+P4
+P5
+P6
+P7
+P8
+P9 *  *  *  *  *  *  *  * +OU
+P+00KI
+P-00AL
++
++0052KI
+%TSUMI
+";
+        let record = parse(csa).unwrap();
+        assert_eq!(
+            record.start.to_string(),
+            "4k4/9/4P4/9/9/9/9/9/8K b G2r2b3g4s4n4l17p 1"
+        );
+        assert_eq!(record.end, Some(GameEnd::Mate));
+        assert_eq!(parse(&write(&record).unwrap()).unwrap(), record);
+
+        // a handicap, and a Tsume problem without the attacking King
+        let record = parse("PI82HI22KA\n-\n-5142OU\n").unwrap();
+        assert_eq!(record.start, Board::handicap(Handicap::TwoPiece));
+        assert_eq!(parse(&write(&record).unwrap()).unwrap(), record);
+
+        let record = parse("P1 *  *  *  * -OU\nP3 *  *  *  * +FU\nP+00KI\n+\n").unwrap();
+        assert_eq!(
+            record.start,
+            Board::tsume("4k4/9/4P4/9/9/9/9/9/9 b G 1").unwrap()
+        );
+        // White holds all other pieces
+        assert!(
+            write(&record)
+                .unwrap()
+                .contains("P9 *  *  *  *  *  *  *  *  * \nP+00KI\nP-00HI00HI00KA")
+        );
+        assert_eq!(parse(&write(&record).unwrap()).unwrap(), record);
+    }
+
+    #[test]
+    fn write_illegal_moves() {
+        let mut record = GameRecord::new(Board::startpos());
+        record.push("7g7f".parse().unwrap());
+        record.push("7f7e".parse().unwrap());
+        assert!(write(&record).is_err());
+    }
+
+    #[test]
+    fn illegal_actions() {
+        let record = parse("PI\n+\n+7776FU\n%+ILLEGAL_ACTION\n").unwrap();
+        assert_eq!(record.end, Some(GameEnd::IllegalMoveWin));
+        assert!(write(&record).unwrap().ends_with("%+ILLEGAL_ACTION\n"));
+        let record = parse("PI\n+\n+7776FU\n%-ILLEGAL_ACTION\n").unwrap();
+        assert_eq!(record.end, Some(GameEnd::IllegalMoveLoss));
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let error = parse("PI\n+\n+7776FU\n+3334FU\n").unwrap_err();
+        assert!(matches!(error.kind(), CsaErrorKind::InvalidMove));
+        assert_eq!(error.line(), 4);
+
+        let error = parse("PI\n+\n+7775FU\n").unwrap_err();
+        assert!(matches!(error.kind(), CsaErrorKind::IllegalMove));
+        assert_eq!(error.line(), 3);
+
+        let error = parse("PI\n+\n+7776FU,Tx\n").unwrap_err();
+        assert!(matches!(error.kind(), CsaErrorKind::InvalidTime));
+        assert_eq!(error.line(), 3);
+
+        let error = parse("V2.2\nPI82KA\n+\n").unwrap_err();
+        assert!(matches!(error.kind(), CsaErrorKind::InvalidPosition));
+        assert_eq!(error.line(), 2);
+
+        // an unknown piece on an empty square
+        let error = parse("V2.2\nPI55XX\n+\n").unwrap_err();
+        assert!(matches!(error.kind(), CsaErrorKind::InvalidPosition));
+        assert_eq!(error.line(), 2);
+
+        let error = parse("N+Alice\n+7776FU\n").unwrap_err();
+        assert!(matches!(error.kind(), CsaErrorKind::InvalidPosition));
+        assert_eq!(error.line(), 2);
+
+        let error = parse("P1+FU *  * -OU\n+\n").unwrap_err();
+        assert!(matches!(error.kind(), CsaErrorKind::InvalidPosition));
+        assert_eq!(error.line(), 2);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn parse_shift_jis() {
+        let csa = CSA.replace("Alice", "先手さん");
+        let (bytes, _, had_errors) = encoding_rs::SHIFT_JIS.encode(&csa);
+        assert!(!had_errors);
+        assert_eq!(parse_bytes(&bytes).unwrap(), parse(&csa).unwrap());
+    }
+}
@@ -0,0 +1,93 @@
+//! Text decoding of Japanese game records
+//!
+//! Most legacy KIF files (and many BOD diagrams) are encoded in Shift-JIS, while newer
+//! files (such as `.kifu` files) use UTF-8. The functions in this module detect the
+//! encoding of a record and decode it, so that record parsers can accept raw bytes
+//! without every caller having to deal with encodings.
+//!
+//! This module requires the `encoding` feature.
+use std::borrow::Cow;
+
+use encoding_rs::{SHIFT_JIS, UTF_8};
+
+use crate::*;
+
+/// The text encoding of a game record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextEncoding {
+    /// UTF-8, with or without a byte order mark.
+    Utf8,
+    /// Shift-JIS (more precisely, the Windows code page 932 variant).
+    ShiftJis,
+}
+
+helpers::simple_error! {
+    /// The bytes are neither valid UTF-8 nor valid Shift-JIS.
+    pub struct DecodeError = "The text is neither valid UTF-8 nor valid Shift-JIS.";
+}
+
+/// Detect the encoding of a game record.
+///
+/// Text with a UTF-8 byte order mark or text that is valid UTF-8 is taken to be UTF-8.
+/// Anything else is taken to be Shift-JIS. Pure ASCII text is valid in both encodings,
+/// so this returns [`TextEncoding::Utf8`] for it.
+///
+/// # Examples
+/// ```
+/// # use haitaka::encoding::*;
+/// assert_eq!(detect("手合割：平手".as_bytes()), TextEncoding::Utf8);
+/// assert_eq!(detect(b"\x8e\xe8\x8d\x87\x8a\x84\x81F\x95\xbd\x8e\xe8"), TextEncoding::ShiftJis);
+/// assert_eq!(detect(b"7g7f"), TextEncoding::Utf8);
+/// ```
+pub fn detect(bytes: &[u8]) -> TextEncoding {
+    if bytes.starts_with(b"\xef\xbb\xbf") || core::str::from_utf8(bytes).is_ok() {
+        TextEncoding::Utf8
+    } else {
+        TextEncoding::ShiftJis
+    }
+}
+
+/// Decode a game record with the given encoding.
+///
+/// A UTF-8 byte order mark is removed. Malformed input is an error, rather than being
+/// replaced with replacement characters, so that a corrupted record is not silently
+/// misread.
+///
+/// # Examples
+/// ```
+/// # use haitaka::encoding::*;
+/// let bytes = b"\x8e\xe8\x8d\x87\x8a\x84\x81F\x95\xbd\x8e\xe8";
+/// assert_eq!(decode_as(bytes, TextEncoding::ShiftJis).unwrap(), "手合割：平手");
+/// assert!(decode_as(bytes, TextEncoding::Utf8).is_err());
+/// ```
+pub fn decode_as(bytes: &[u8], encoding: TextEncoding) -> Result<Cow<'_, str>, DecodeError> {
+    let decoded = match encoding {
+        TextEncoding::Utf8 => {
+            let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+            UTF_8.decode_without_bom_handling_and_without_replacement(bytes)
+        }
+        TextEncoding::ShiftJis => {
+            SHIFT_JIS.decode_without_bom_handling_and_without_replacement(bytes)
+        }
+    };
+    decoded.ok_or(DecodeError)
+}
+
+/// Detect the encoding of a game record and decode it.
+///
+/// This is [`decode_as`] with the encoding returned by [`detect`]. UTF-8 input is
+/// borrowed, not copied.
+///
+/// # Examples
+/// ```
+/// # use haitaka::encoding::*;
+/// let utf8 = "手合割：平手".as_bytes();
+/// let sjis = b"\x8e\xe8\x8d\x87\x8a\x84\x81F\x95\xbd\x8e\xe8";
+/// assert_eq!(decode(utf8).unwrap(), decode(sjis).unwrap());
+///
+/// // A lone lead byte is not valid in either encoding
+/// assert!(decode(b"\x8e").is_err());
+/// ```
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, DecodeError> {
+    decode_as(bytes, detect(bytes))
+}
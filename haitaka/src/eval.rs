@@ -0,0 +1,172 @@
+//! A reference evaluation function
+//!
+//! [`Board::quick_eval`] is a small, hand-tuned evaluation: material, piece-square
+//! tables (by rank only) and a simple King safety term. It is good enough to get a
+//! working engine off the ground and to benchmark move generation with evaluation,
+//! but it is not meant to be strong. It also shows how to use the material values
+//! ([`SEE_VALUES`]) and the attack queries of the [`Board`].
+//!
+//! This module requires the `reference-eval` feature.
+use crate::*;
+
+/// Bonus for a piece in hand, in percent of its [`SEE_VALUES`] value.
+///
+/// A piece in hand can be dropped almost anywhere, so it is worth more than the
+/// same piece on the board.
+pub const HAND_BONUS_PERCENT: i32 = 10;
+
+/// Piece-square tables, indexed by [`Piece`] and by the rank counted from the
+/// player's own back rank (0) to the opponent's back rank (8).
+///
+/// The tables only depend on the rank. They encourage advancing the Pawns, Silvers and
+/// promoted pieces, and keeping the King and the Golds at home.
+pub const PSQT: [[i16; Rank::NUM]; Piece::NUM] = [
+    [0, 0, 0, 5, 10, 20, 35, 50, 0],        // Pawn
+    [0, 0, 0, 0, 5, 10, 20, 30, 0],         // Lance
+    [0, 0, 0, 5, 10, 20, 30, 0, 0],         // Knight
+    [0, 5, 10, 15, 20, 25, 30, 30, 20],     // Silver
+    [0, 5, 5, 5, 10, 10, 15, 15, 10],       // Bishop
+    [0, 0, 0, 5, 5, 10, 15, 20, 20],        // Rook
+    [10, 15, 10, 5, 0, 0, 5, 10, 10],       // Gold
+    [30, 20, 0, -20, -40, -50, -40, 0, 50], // King
+    [0, 0, 5, 10, 20, 30, 40, 40, 30],      // Tokin
+    [0, 0, 5, 10, 20, 30, 40, 40, 30],      // PLance
+    [0, 0, 5, 10, 20, 30, 40, 40, 30],      // PKnight
+    [0, 0, 5, 10, 20, 30, 40, 40, 30],      // PSilver
+    [0, 5, 10, 15, 20, 25, 30, 30, 30],     // PBishop
+    [0, 5, 10, 15, 20, 25, 30, 30, 30],     // PRook
+];
+
+/// Bonus for every Gold, Silver or small promoted piece next to its own King.
+pub const KING_DEFENDER_BONUS: i32 = 20;
+
+/// Penalty for every square next to the King that is attacked by the opponent.
+pub const KING_ZONE_ATTACK_PENALTY: i32 = 15;
+
+// The rank of `square`, counted from the back rank of `color`.
+const fn relative_rank(color: Color, square: Square) -> usize {
+    match color {
+        Color::Black => Rank::NUM - 1 - square.rank() as usize,
+        Color::White => square.rank() as usize,
+    }
+}
+
+impl Board {
+    /// Evaluate the position from the point of view of the side to move.
+    ///
+    /// The score is in centipawns (a Pawn on the board is worth 100) and is the sum of:
+    /// - the material on the board ([`SEE_VALUES`]) and in hand (with [`HAND_BONUS_PERCENT`]),
+    /// - the piece-square tables ([`PSQT`]),
+    /// - for each King, [`KING_DEFENDER_BONUS`] for every adjacent Gold-like or Silver
+    ///   defender and [`KING_ZONE_ATTACK_PENALTY`] for every adjacent square attacked
+    ///   by the opponent.
+    ///
+    /// Each term is computed for both players and the opponent's score is subtracted.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// assert_eq!(Board::startpos().quick_eval(), 0);
+    ///
+    /// // Black is a Rook up
+    /// let board: Board = "lnsgkgsnl/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b R 1"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert!(board.quick_eval() > 1000);
+    /// assert!(board.null_move().unwrap().quick_eval() < -1000);
+    /// ```
+    pub fn quick_eval(&self) -> i32 {
+        let us = self.side_to_move();
+        self.quick_eval_for(us) - self.quick_eval_for(!us)
+    }
+
+    fn quick_eval_for(&self, color: Color) -> i32 {
+        let mut score = 0;
+
+        for square in self.colors(color) {
+            let piece = self.piece_on(square).unwrap();
+            score += SEE_VALUES[piece as usize] as i32;
+            score += PSQT[piece as usize][relative_rank(color, square)] as i32;
+        }
+
+        for (piece, &count) in Piece::ALL.iter().zip(self.hand(color)) {
+            let value = SEE_VALUES[*piece as usize] as i32;
+            score += count as i32 * value * (100 + HAND_BONUS_PERCENT) / 100;
+        }
+
+        if self.has_king(color) {
+            let king = self.king(color);
+            let zone = king_attacks(color, king);
+            let defenders = zone
+                & self.colors(color)
+                & (self.pseudo_golds() | self.pieces(Piece::Silver))
+                & !(self.pieces(Piece::PBishop) | self.pieces(Piece::PRook));
+            score += defenders.len() as i32 * KING_DEFENDER_BONUS;
+            let attacked = zone
+                .into_iter()
+                .filter(|&square| !self.attackers(square, !color).is_empty())
+                .count();
+            score -= attacked as i32 * KING_ZONE_ATTACK_PENALTY;
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rotate the board by 180 degrees and swap the colors of all pieces
+    fn flip_sfen(sfen: &str) -> String {
+        let fields: Vec<&str> = sfen.split(' ').collect();
+        let mut tokens: Vec<String> = Vec::new();
+        let mut promoted = false;
+        for c in fields[0].chars() {
+            if c == '+' {
+                promoted = true;
+                continue;
+            }
+            let c = if c.is_ascii_uppercase() {
+                c.to_ascii_lowercase()
+            } else {
+                c.to_ascii_uppercase()
+            };
+            tokens.push(if promoted {
+                format!("+{c}")
+            } else {
+                c.to_string()
+            });
+            promoted = false;
+        }
+        tokens.reverse();
+        let hands: String = fields[2]
+            .chars()
+            .map(|c| {
+                if c.is_ascii_uppercase() {
+                    c.to_ascii_lowercase()
+                } else {
+                    c.to_ascii_uppercase()
+                }
+            })
+            .collect();
+        let side = if fields[1] == "b" { "w" } else { "b" };
+        format!("{} {} {} {}", tokens.concat(), side, hands, fields[3])
+    }
+
+    #[test]
+    fn quick_eval_is_symmetric() {
+        for sfen in [
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+        ] {
+            let board: Board = sfen.parse().unwrap();
+            let flipped: Board = flip_sfen(sfen).parse().unwrap();
+            assert_eq!(board.quick_eval(), flipped.quick_eval(), "{sfen}");
+            assert_eq!(
+                board.quick_eval(),
+                -board.null_move().unwrap().quick_eval(),
+                "{sfen}"
+            );
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! NNUE input features
+//!
+//! This module computes the input feature indices of HalfKP and HalfKA networks, with
+//! the same encoding as YaneuraOu, so that the indices can be used with nets trained
+//! for that engine.
+//!
+//! A feature combines the square of the King of the perspective (the side whose half of
+//! the network is evaluated) with a piece, given as a `BonaPiece`: a piece of a color on
+//! a square, or the n-th piece of a type in the hand of a color. Both are seen from the
+//! perspective: for White, the board is rotated by 180 degrees (see
+//! [`Square::relative_to`]), and "friend" and "enemy" pieces are swapped.
+//!
+//! `index = king_square * pieces + bona_piece`
+//!
+//! The `BonaPiece` numbers are:
+//!
+//! | BonaPiece   | Piece                                                        |
+//! |-------------|--------------------------------------------------------------|
+//! | 1 - 89      | Pieces in hand, see [`HAND_BASE`]                            |
+//! | 90 - 1547   | Pieces on the board, see [`BOARD_BASE`]                      |
+//! | 1548 - 1709 | Friend and enemy King (HalfKA only)                          |
+//!
+//! Promoted Pawns, Lances, Knights and Silvers count as Golds. HalfKP leaves out both
+//! Kings, so that there are 38 active features. HalfKA includes them, for 40 active
+//! features. Square indices are the same as in YaneuraOu (`1a` is 0, `1b` is 1 and so
+//! on), which is the order of [`Square::ALL`].
+//!
+//! Playing a move changes only a few features, unless the King of the perspective
+//! moves. [`feature_diff`] returns these changes, for accumulators that are updated
+//! incrementally.
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! use haitaka::features::*;
+//!
+//! let board = Board::startpos();
+//! let features = active_features(&board, Color::Black, FeatureSet::HalfKP);
+//! assert_eq!(features.len(), 38);
+//! // The Pawn on 7g, seen from the Black King on 5i
+//! let king = Square::I5 as u32;
+//! let pawn = BOARD_BASE[Piece::Pawn as usize] + Square::G7 as u32;
+//! assert!(features.contains(&(king * FeatureSet::HalfKP.pieces() + pawn)));
+//! ```
+use crate::*;
+
+/// The feature sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FeatureSet {
+    /// King square times all pieces but the Kings.
+    HalfKP,
+    /// King square times all pieces, including both Kings.
+    HalfKA,
+}
+
+impl FeatureSet {
+    /// The number of `BonaPiece`s, which is the number of features per King square.
+    pub const fn pieces(self) -> u32 {
+        match self {
+            Self::HalfKP => FE_END,
+            Self::HalfKA => FE_END + 2 * Square::NUM as u32,
+        }
+    }
+
+    /// The number of features.
+    pub const fn dimensions(self) -> u32 {
+        Square::NUM as u32 * self.pieces()
+    }
+
+    /// The maximum number of active features in a position with both Kings.
+    pub const fn max_active(self) -> usize {
+        match self {
+            Self::HalfKP => 38,
+            Self::HalfKA => 40,
+        }
+    }
+}
+
+/// The end of the `BonaPiece`s of HalfKP, and the start of the friend King in HalfKA.
+pub const FE_END: u32 = 1548;
+
+/// The `BonaPiece` of the first friend piece in hand of each type, indexed by
+/// [`Piece`]. The enemy pieces follow the friend pieces. The n-th piece in hand
+/// (counting from 0) has the base plus n.
+///
+/// | Piece  | Friend | Enemy |
+/// |--------|--------|-------|
+/// | Pawn   | 1      | 20    |
+/// | Lance  | 39     | 44    |
+/// | Knight | 49     | 54    |
+/// | Silver | 59     | 64    |
+/// | Gold   | 69     | 74    |
+/// | Bishop | 79     | 82    |
+/// | Rook   | 85     | 88    |
+pub const HAND_BASE: [u32; Piece::HAND_NUM] = [1, 39, 49, 59, 79, 85, 69];
+
+/// The `BonaPiece` of the first friend piece on the board of each type, indexed by
+/// [`Piece`]. The enemy pieces follow 81 later. Add the square index to get the
+/// `BonaPiece` of a piece on a square.
+///
+/// | Piece                        | Friend | Enemy |
+/// |------------------------------|--------|-------|
+/// | Pawn                         | 90     | 171   |
+/// | Lance                        | 252    | 333   |
+/// | Knight                       | 414    | 495   |
+/// | Silver                       | 576    | 657   |
+/// | Gold (and promoted minors)   | 738    | 819   |
+/// | Bishop                       | 900    | 981   |
+/// | Horse (promoted Bishop)      | 1062   | 1143  |
+/// | Rook                         | 1224   | 1305  |
+/// | Dragon (promoted Rook)       | 1386   | 1467  |
+/// | King (HalfKA only)           | 1548   | 1629  |
+pub const BOARD_BASE: [u32; Piece::NUM] = [
+    90, 252, 414, 576, 900, 1224, 738, 1548, 738, 738, 738, 738, 1062, 1386,
+];
+
+// The enemy bases of the hand pieces, relative to the friend bases.
+const HAND_ENEMY_OFFSET: [u32; Piece::HAND_NUM] = [19, 5, 5, 5, 3, 3, 5];
+
+/// Get the `BonaPiece` of a piece of `color` on `square`, seen from `perspective`.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::features::*;
+///
+/// assert_eq!(board_piece(Color::Black, Color::Black, Piece::Pawn, Square::G7), 150);
+/// // The same Pawn, seen from White, is an enemy Pawn on 3c
+/// assert_eq!(board_piece(Color::White, Color::Black, Piece::Pawn, Square::G7), 171 + 20);
+/// ```
+pub const fn board_piece(perspective: Color, color: Color, piece: Piece, square: Square) -> u32 {
+    let square = square.relative_to(perspective) as u32;
+    let base = BOARD_BASE[piece as usize];
+    if color as usize == perspective as usize {
+        base + square
+    } else {
+        base + Square::NUM as u32 + square
+    }
+}
+
+/// Get the `BonaPiece` of the `index`-th (counting from 0) `piece` in the hand of
+/// `color`, seen from `perspective`.
+///
+/// # Panics
+/// This panics if `piece` can't be in hand.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::features::*;
+///
+/// assert_eq!(hand_piece(Color::Black, Color::Black, Piece::Pawn, 0), 1);
+/// assert_eq!(hand_piece(Color::Black, Color::White, Piece::Rook, 1), 89);
+/// ```
+pub const fn hand_piece(perspective: Color, color: Color, piece: Piece, index: u8) -> u32 {
+    assert!((piece as usize) < Piece::HAND_NUM, "Not a piece in hand");
+    let base = HAND_BASE[piece as usize] + index as u32;
+    if color as usize == perspective as usize {
+        base
+    } else {
+        base + HAND_ENEMY_OFFSET[piece as usize]
+    }
+}
+
+// The feature of `bona_piece` for a King on `king` (already seen from the perspective).
+fn feature(set: FeatureSet, king: Square, bona_piece: u32) -> u32 {
+    king as u32 * set.pieces() + bona_piece
+}
+
+/// Call `f` with the index of every active feature of `board`, seen from `perspective`.
+///
+/// The features are given in no particular order.
+///
+/// # Panics
+/// This panics if `perspective` has no King.
+pub fn for_each_active_feature(
+    board: &Board,
+    perspective: Color,
+    set: FeatureSet,
+    mut f: impl FnMut(u32),
+) {
+    let king = board.king(perspective).relative_to(perspective);
+    for (color, piece, square) in board.iter_pieces() {
+        if piece == Piece::King && set == FeatureSet::HalfKP {
+            continue;
+        }
+        f(feature(
+            set,
+            king,
+            board_piece(perspective, color, piece, square),
+        ));
+    }
+    for color in Color::ALL {
+        for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+            for index in 0..board.num_in_hand(color, piece) {
+                f(feature(
+                    set,
+                    king,
+                    hand_piece(perspective, color, piece, index),
+                ));
+            }
+        }
+    }
+}
+
+/// Collect the indices of the active features of `board`, seen from `perspective`.
+///
+/// See [`for_each_active_feature`].
+///
+/// # Panics
+/// This panics if `perspective` has no King.
+pub fn active_features(board: &Board, perspective: Color, set: FeatureSet) -> Vec<u32> {
+    let mut features = Vec::with_capacity(set.max_active());
+    for_each_active_feature(board, perspective, set, |index| features.push(index));
+    features
+}
+
+/// The features that a move removes and adds.
+///
+/// A move removes and adds at most two features each: a board move removes the moving
+/// piece from its square and adds it (perhaps promoted) on the target square, and a
+/// capture also removes the captured piece and adds it to the hand. A drop removes a
+/// piece from the hand and adds it on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FeatureDiff {
+    removed: [u32; 2],
+    added: [u32; 2],
+    num_removed: u8,
+    num_added: u8,
+}
+
+impl FeatureDiff {
+    /// The features that are no longer active after the move.
+    pub fn removed(&self) -> &[u32] {
+        &self.removed[..self.num_removed as usize]
+    }
+
+    /// The features that are active after the move, but were not before it.
+    pub fn added(&self) -> &[u32] {
+        &self.added[..self.num_added as usize]
+    }
+
+    fn remove(&mut self, index: u32) {
+        self.removed[self.num_removed as usize] = index;
+        self.num_removed += 1;
+    }
+
+    fn add(&mut self, index: u32) {
+        self.added[self.num_added as usize] = index;
+        self.num_added += 1;
+    }
+}
+
+/// Get the changes in the active features of `perspective` when `mv` is played on
+/// `board`.
+///
+/// Returns None if the move is a move of the King of `perspective`. All features change
+/// then, so they have to be computed again (a refresh) with [`active_features`].
+///
+/// The move must be legal.
+///
+/// # Panics
+/// This panics if `perspective` has no King.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::features::*;
+///
+/// let board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+///     .parse()
+///     .unwrap();
+/// let mv = "8h2b+".parse().unwrap();
+/// let diff = feature_diff(&board, mv, Color::Black, FeatureSet::HalfKP).unwrap();
+/// assert_eq!(diff.removed().len(), 2);
+/// assert_eq!(diff.added().len(), 2);
+///
+/// let mut features = active_features(&board, Color::Black, FeatureSet::HalfKP);
+/// features.retain(|index| !diff.removed().contains(index));
+/// features.extend(diff.added());
+/// features.sort();
+/// let mut expected = active_features(&board.after(mv).unwrap(), Color::Black, FeatureSet::HalfKP);
+/// expected.sort();
+/// assert_eq!(features, expected);
+///
+/// // The King moves: refresh
+/// assert!(feature_diff(&board, "5i4h".parse().unwrap(), Color::Black, FeatureSet::HalfKP).is_none());
+/// ```
+pub fn feature_diff(
+    board: &Board,
+    mv: Move,
+    perspective: Color,
+    set: FeatureSet,
+) -> Option<FeatureDiff> {
+    let color = board.side_to_move();
+    let king = board.king(perspective).relative_to(perspective);
+    let mut diff = FeatureDiff::default();
+
+    match mv {
+        Move::Drop { piece, to } => {
+            let index = board.num_in_hand(color, piece) - 1;
+            diff.remove(feature(
+                set,
+                king,
+                hand_piece(perspective, color, piece, index),
+            ));
+            diff.add(feature(
+                set,
+                king,
+                board_piece(perspective, color, piece, to),
+            ));
+        }
+        Move::BoardMove {
+            from,
+            to,
+            promotion,
+        } => {
+            let piece = board
+                .piece_on(from)
+                .expect("Missing piece on move's `from` square");
+            if piece == Piece::King && color == perspective {
+                return None;
+            }
+            if let Some(capture) = board.piece_on(to) {
+                let captured = capture.unpromote();
+                let index = board.num_in_hand(color, captured);
+                diff.remove(feature(
+                    set,
+                    king,
+                    board_piece(perspective, !color, capture, to),
+                ));
+                diff.add(feature(
+                    set,
+                    king,
+                    hand_piece(perspective, color, captured, index),
+                ));
+            }
+            if piece != Piece::King || set == FeatureSet::HalfKA {
+                let final_piece = if promotion { piece.promote() } else { piece };
+                diff.remove(feature(
+                    set,
+                    king,
+                    board_piece(perspective, color, piece, from),
+                ));
+                diff.add(feature(
+                    set,
+                    king,
+                    board_piece(perspective, color, final_piece, to),
+                ));
+            }
+        }
+    }
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bona_pieces_are_unique() {
+        for set in [FeatureSet::HalfKP, FeatureSet::HalfKA] {
+            let mut seen = vec![false; set.pieces() as usize];
+            let mut mark = |bona_piece: u32| {
+                assert!(!seen[bona_piece as usize], "{bona_piece}");
+                seen[bona_piece as usize] = true;
+            };
+            for color in Color::ALL {
+                for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+                    let max = match piece {
+                        Piece::Pawn => 18,
+                        Piece::Bishop | Piece::Rook => 2,
+                        _ => 4,
+                    };
+                    for index in 0..max {
+                        mark(hand_piece(Color::Black, color, piece, index));
+                    }
+                }
+                for piece in Piece::ALL {
+                    let gold_like =
+                        piece.is_promoted() && !matches!(piece, Piece::PBishop | Piece::PRook);
+                    if gold_like || (piece == Piece::King && set == FeatureSet::HalfKP) {
+                        continue;
+                    }
+                    for square in Square::ALL {
+                        mark(board_piece(Color::Black, color, piece, square));
+                    }
+                }
+            }
+            // All but BonaPiece 0 and the gaps after the hand pieces are taken
+            let unused = seen.iter().filter(|&&seen| !seen).count();
+            assert_eq!(unused, 1 + 13);
+        }
+    }
+
+    fn check_diffs(board: &Board) {
+        for &mv in board.legal_moves().iter() {
+            let after = board.after_unchecked(mv);
+            for set in [FeatureSet::HalfKP, FeatureSet::HalfKA] {
+                for perspective in Color::ALL {
+                    let mut expected = active_features(&after, perspective, set);
+                    assert!(expected.len() <= set.max_active());
+                    assert!(expected.iter().all(|&index| index < set.dimensions()));
+                    expected.sort();
+                    let Some(diff) = feature_diff(board, mv, perspective, set) else {
+                        assert_eq!(board.king(perspective), mv.from().unwrap());
+                        continue;
+                    };
+                    let mut features = active_features(board, perspective, set);
+                    for removed in diff.removed() {
+                        let i = features.iter().position(|f| f == removed).unwrap();
+                        features.swap_remove(i);
+                    }
+                    features.extend(diff.added());
+                    features.sort();
+                    assert_eq!(features, expected, "{board} {mv}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn diffs_match_active_features() {
+        for sfen in [
+            // the Bishop captures the checking Dragon, which goes to the hand as the
+            // second Rook
+            "4k4/9/4+r4/9/9/9/B8/9/4K4 b Rp 1",
+            // the King captures, and a second Silver is dropped from the hand
+            "4k4/9/9/9/9/9/9/4g4/4K4 b 2S 1",
+            // the last of many Pawns in hand is dropped
+            "4k4/9/9/9/9/9/9/9/4K4 b 17P 1",
+            // Pawns, a Lance and a Knight that may or must promote
+            "4k4/P8/1L7/2N6/9/9/9/9/4K4 b - 1",
+        ] {
+            check_diffs(&sfen.parse().unwrap());
+        }
+        for board in random_positions(0xfea7, 4, 80) {
+            check_diffs(&board);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! Games and their outcome
+//!
+//! [`Board::status`] only looks at the current position, so it can't tell a Sennichite
+//! or an illegal checkmate by Pawn drop from an ongoing game. A [`Game`] keeps the
+//! history of the game (see [`GameHistory`]) and applies all the rules for ending a
+//! game with [`Game::outcome`]:
+//!
+//! - A player with no legal moves loses (normally by checkmate).
+//! - A player who checkmates with a Pawn drop (打ち歩詰め) loses.
+//! - When the same position occurs for the fourth time, the game is a draw by
+//!   Sennichite, unless one side gave check with every move since the first of the
+//!   four occurrences. That side loses.
+//! - A player can declare an entering King win (入玉宣言), see
+//!   [`Board::try_declare_win`].
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! use haitaka::game::*;
+//!
+//! let mut game = Game::new(Board::startpos());
+//! for _ in 0..3 {
+//!     for mv in ["2h3h", "8b7b", "3h2h", "7b8b"] {
+//!         assert_eq!(game.outcome(), GameOutcome::Ongoing);
+//!         game.play(mv.parse().unwrap());
+//!     }
+//! }
+//! assert_eq!(game.outcome(), GameOutcome::Sennichite);
+//! assert_eq!(game.outcome().winner(), None);
+//! assert!(game.outcome().is_over());
+//! ```
+use crate::repetition::*;
+use crate::*;
+
+/// How a game ended, or that it did not end yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GameOutcome {
+    /// The game is still ongoing.
+    Ongoing,
+    /// The side to move has no legal moves, so the other side wins.
+    ///
+    /// This is nearly always checkmate, but it also covers the (never seen in actual
+    /// games) case of a player who is not in check and has no legal moves.
+    Checkmate {
+        /// The side that made the last move.
+        winner: Color,
+    },
+    /// The last move was a Pawn drop that checkmates, which is illegal: the side that
+    /// dropped the Pawn loses.
+    IllegalPawnDropMate {
+        /// The side to move.
+        winner: Color,
+    },
+    /// The position occurred for the fourth time, without perpetual check: a draw.
+    Sennichite,
+    /// The position occurred for the fourth time, and one side gave check with every
+    /// move of the repetition: that side loses.
+    PerpetualCheck {
+        /// The side that did not give perpetual check.
+        winner: Color,
+    },
+    /// The side to move declared an entering King win.
+    Impasse {
+        /// The winner, or None for a draw.
+        winner: Option<Color>,
+    },
+}
+
+impl GameOutcome {
+    /// The winner, or None if the game is a draw or still ongoing.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// use haitaka::game::*;
+    /// let outcome = GameOutcome::Checkmate { winner: Color::White };
+    /// assert_eq!(outcome.winner(), Some(Color::White));
+    /// assert_eq!(GameOutcome::Ongoing.winner(), None);
+    /// ```
+    pub const fn winner(self) -> Option<Color> {
+        match self {
+            Self::Ongoing | Self::Sennichite => None,
+            Self::Checkmate { winner }
+            | Self::IllegalPawnDropMate { winner }
+            | Self::PerpetualCheck { winner } => Some(winner),
+            Self::Impasse { winner } => winner,
+        }
+    }
+
+    /// Whether the game ended.
+    pub const fn is_over(self) -> bool {
+        !matches!(self, Self::Ongoing)
+    }
+}
+
+/// A game: a board with the history of its positions.
+///
+/// Moves are played and taken back as with [`GameHistory`], which this wraps.
+/// [`Game::outcome`] tells whether and how the game ended. Moves can still be played
+/// after the game ended; it is up to the caller to stop.
+#[derive(Debug, Clone)]
+pub struct Game {
+    history: GameHistory,
+    declaration: Option<GameOutcome>,
+}
+
+impl Game {
+    /// Start a game from the given position.
+    pub fn new(board: Board) -> Self {
+        Self {
+            history: GameHistory::new(board),
+            declaration: None,
+        }
+    }
+
+    /// The current position.
+    pub fn board(&self) -> &Board {
+        self.history.board()
+    }
+
+    /// The history of the game.
+    pub fn history(&self) -> &GameHistory {
+        &self.history
+    }
+
+    /// The moves played since the start position.
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.history.moves()
+    }
+
+    /// The number of moves played since the start position.
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Whether no moves have been played yet.
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    /// Play a move.
+    ///
+    /// # Panics
+    /// This panics if the move is illegal.
+    pub fn play(&mut self, mv: Move) {
+        assert!(self.try_play(mv).is_ok(), "Illegal move {}!", mv);
+    }
+
+    /// Non-panicking version of [`Game::play`].
+    ///
+    /// # Errors
+    /// Errors with [`IllegalMoveError`] if the move was illegal. The game is not
+    /// changed then.
+    pub fn try_play(&mut self, mv: Move) -> Result<(), IllegalMoveError> {
+        self.history.try_play(mv)?;
+        self.declaration = None;
+        Ok(())
+    }
+
+    /// Play a move without checking whether it is legal.
+    ///
+    /// This is the only way to play a checkmate by Pawn drop, which
+    /// [`Game::outcome`] then reports as [`GameOutcome::IllegalPawnDropMate`]. See
+    /// [`Board::play_unchecked`] for what happens with other illegal moves.
+    pub fn play_unchecked(&mut self, mv: Move) {
+        self.history.play_unchecked(mv);
+        self.declaration = None;
+    }
+
+    /// Take back the last move, returning it, or None if no moves were played.
+    ///
+    /// This also takes back a declaration made after the move.
+    pub fn undo(&mut self) -> Option<Move> {
+        self.declaration = None;
+        self.history.undo()
+    }
+
+    /// Declare an entering King win for the side to move, under the given rule.
+    ///
+    /// This ends the game with a [`GameOutcome::Impasse`], which is also returned: a
+    /// valid declaration wins (or draws), an invalid one loses. See
+    /// [`Board::try_declare_win`] for the conditions. Playing or taking back a move
+    /// discards the declaration.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// use haitaka::game::*;
+    /// let board: Board = "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b RB 1".parse().unwrap();
+    /// let mut game = Game::new(board);
+    /// let outcome = game.declare_win(ImpasseRule::Points27);
+    /// assert_eq!(outcome, GameOutcome::Impasse { winner: Some(Color::Black) });
+    /// assert_eq!(game.outcome(), outcome);
+    ///
+    /// let outcome = game.declare_win(ImpasseRule::Points24);
+    /// assert_eq!(outcome, GameOutcome::Impasse { winner: None });
+    /// ```
+    pub fn declare_win(&mut self, rule: ImpasseRule) -> GameOutcome {
+        let board = self.board();
+        let color = board.side_to_move();
+        let winner = match board.try_declare_win(color, rule) {
+            Declaration::Win => Some(color),
+            Declaration::Draw => None,
+            Declaration::Loss => Some(!color),
+        };
+        let outcome = GameOutcome::Impasse { winner };
+        self.declaration = Some(outcome);
+        outcome
+    }
+
+    /// Get the outcome of the game in the current position.
+    ///
+    /// Unlike [`Board::status`], this applies all rules: see the
+    /// [module documentation](self).
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// use haitaka::game::*;
+    /// let mut game = Game::new("4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap());
+    /// assert_eq!(game.outcome(), GameOutcome::Ongoing);
+    /// game.play("G*5b".parse().unwrap());
+    /// assert_eq!(game.outcome(), GameOutcome::Checkmate { winner: Color::Black });
+    /// ```
+    pub fn outcome(&self) -> GameOutcome {
+        if let Some(outcome) = self.declaration {
+            return outcome;
+        }
+
+        let board = self.board();
+        let color = board.side_to_move();
+        if !board.generate_moves(|_| true) {
+            let pawn_drop = matches!(
+                self.history.last_move(),
+                Some(Move::Drop {
+                    piece: Piece::Pawn,
+                    ..
+                })
+            );
+            return if pawn_drop && !board.checkers().is_empty() {
+                GameOutcome::IllegalPawnDropMate { winner: color }
+            } else {
+                GameOutcome::Checkmate { winner: !color }
+            };
+        }
+
+        match self.history.repetition() {
+            RepetitionOutcome::Draw => GameOutcome::Sennichite,
+            RepetitionOutcome::LastMoverLoses => GameOutcome::PerpetualCheck { winner: color },
+            RepetitionOutcome::SideToMoveLoses => GameOutcome::PerpetualCheck { winner: !color },
+            _ => GameOutcome::Ongoing,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pawn_drop_mate_loses() {
+        // The White Knights block the King, the Gold protects the Pawn
+        let mut game = Game::new("3nkn3/9/4G4/9/9/9/9/9/4K4 b P 1".parse().unwrap());
+        let mv: Move = "P*5b".parse().unwrap();
+        assert!(game.try_play(mv).is_err());
+        game.play_unchecked(mv);
+        assert_eq!(
+            game.outcome(),
+            GameOutcome::IllegalPawnDropMate {
+                winner: Color::White
+            }
+        );
+        assert_eq!(game.undo(), Some(mv));
+        assert_eq!(game.outcome(), GameOutcome::Ongoing);
+    }
+
+    #[test]
+    fn perpetual_check_loses() {
+        let moves = ["9b9a", "1a2b", "9a9b", "2b1a"];
+        let mut game = Game::new("8k/R8/9/9/9/9/9/9/4K4 b - 1".parse().unwrap());
+        for mv in moves.repeat(3) {
+            assert_eq!(game.outcome(), GameOutcome::Ongoing);
+            game.play(mv.parse().unwrap());
+        }
+        // Black gave check with every move
+        let outcome = game.outcome();
+        assert_eq!(
+            outcome,
+            GameOutcome::PerpetualCheck {
+                winner: Color::White
+            }
+        );
+        assert_eq!(outcome.winner(), Some(Color::White));
+
+        // The same cycle, but ending with a move by Black
+        let moves = ["1a2b", "9a9b", "2b1a", "9b9a"];
+        let mut game = Game::new("R7k/9/9/9/9/9/9/9/4K4 w - 1".parse().unwrap());
+        for mv in moves.repeat(3) {
+            game.play(mv.parse().unwrap());
+        }
+        assert_eq!(
+            game.outcome(),
+            GameOutcome::PerpetualCheck {
+                winner: Color::White
+            }
+        );
+    }
+
+    #[test]
+    fn moves_discard_declarations() {
+        let board: Board = "+R+B+P+P+P+P+P+P+P/4G4/4K4/9/9/9/9/9/4k4 b RB 1"
+            .parse()
+            .unwrap();
+        let mut game = Game::new(board);
+        game.play("5c4c".parse().unwrap());
+        // White's declaration is invalid
+        assert_eq!(
+            game.declare_win(ImpasseRule::Points27),
+            GameOutcome::Impasse {
+                winner: Some(Color::Black)
+            }
+        );
+        assert!(game.outcome().is_over());
+        game.undo();
+        assert_eq!(game.outcome(), GameOutcome::Ongoing);
+        assert_eq!(
+            game.declare_win(ImpasseRule::Try),
+            GameOutcome::Impasse {
+                winner: Some(Color::White)
+            }
+        );
+        game.play("5c4c".parse().unwrap());
+        assert_eq!(game.outcome(), GameOutcome::Ongoing);
+        assert_eq!(game.len(), 1);
+    }
+}
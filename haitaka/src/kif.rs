@@ -0,0 +1,680 @@
+//! Reading and writing KIF game records
+//!
+//! KIF (棋譜ファイル) is the most common format for Japanese game records. It is used by
+//! Kifu for Windows and exported by most Shogi servers. A KIF record consists of header
+//! lines (`key：value`, such as `先手：` for the name of the Black player), followed by one
+//! line per move with the move number (手数), the move and optionally the time used
+//! (消費時間). Comment lines start with `*`:
+//!
+//! ```text
+//! 手合割：平手
+//! 先手：Alice
+//! 後手：Bob
+//! 手数----指手---------消費時間--
+//!    1 ７六歩(77)   ( 0:03/00:00:03)
+//! *The usual opening move
+//!    2 ３四歩(33)   ( 0:05/00:00:05)
+//!    3 投了         ( 0:10/00:00:13)
+//! ```
+//!
+//! Only the main line is read: variations (`変化：`) are skipped. The start position is
+//! given by the handicap (`手合割`) header, or by a board diagram (see
+//! [`Board::from_bod`]). Records are always written with the handicap header.
+//!
+//! With the `encoding` feature, [`parse_bytes`] reads Shift-JIS records as well.
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::record::*;
+use crate::*;
+
+helpers::simple_error! {
+    /// The kind of a [`KifError`].
+    pub enum KifErrorKind {
+        UnsupportedStartPosition = "The start position is not supported",
+        InvalidDiagram = "Invalid board diagram",
+        InvalidMove = "Invalid move",
+        IllegalMove = "Illegal move",
+        InvalidMoveNumber = "Invalid move number",
+        InvalidTime = "Invalid time",
+        InvalidEncoding = "The record is neither valid UTF-8 nor valid Shift-JIS"
+    }
+}
+
+/// An error in a KIF record.
+#[derive(Debug, Clone, Copy)]
+pub struct KifError {
+    line: usize,
+    kind: KifErrorKind,
+}
+
+impl KifError {
+    /// The line of the error (starting at 1), or 0 if the error is not on a line.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The kind of error.
+    pub fn kind(&self) -> KifErrorKind {
+        self.kind
+    }
+}
+
+impl core::fmt::Display for KifError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} (line {})", self.kind, self.line)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KifError {}
+
+helpers::simple_error! {
+    /// A record that can't be written in KIF.
+    pub enum KifWriteError {
+        UnsupportedStartPosition = "Only the standard and the handicap start positions can be written in KIF.",
+        IllegalMove = "The record holds an illegal move."
+    }
+}
+
+// The handicap (手合割) of the standard start position.
+const EVEN: &str = "平手";
+
+// The special moves that end a game.
+const GAME_ENDS: [(&str, GameEnd); 10] = [
+    ("投了", GameEnd::Resign),
+    ("中断", GameEnd::Abort),
+    ("千日手", GameEnd::Repetition),
+    ("持将棋", GameEnd::Impasse),
+    ("切れ負け", GameEnd::Timeout),
+    ("詰み", GameEnd::Mate),
+    ("不詰", GameEnd::NoMate),
+    ("反則勝ち", GameEnd::IllegalMoveWin),
+    ("反則負け", GameEnd::IllegalMoveLoss),
+    ("入玉勝ち", GameEnd::EnteringKingWin),
+];
+
+// The piece names, in the order of `Piece::ALL`, as they are written.
+pub(crate) const PIECE_NAMES: [&str; Piece::NUM] = [
+    "歩", "香", "桂", "銀", "角", "飛", "金", "玉", "と", "成香", "成桂", "成銀", "馬", "龍",
+];
+
+// Other names that are accepted when reading.
+const PIECE_ALIASES: [(&str, Piece); 5] = [
+    ("王", Piece::King),
+    ("杏", Piece::PLance),
+    ("圭", Piece::PKnight),
+    ("全", Piece::PSilver),
+    ("竜", Piece::PRook),
+];
+
+pub(crate) const FILE_CHARS: [char; File::NUM] =
+    ['１', '２', '３', '４', '５', '６', '７', '８', '９'];
+pub(crate) const RANK_CHARS: [char; Rank::NUM] =
+    ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+// The width of the move column (full-width characters count as two).
+const MOVE_WIDTH: usize = 13;
+
+pub(crate) fn parse_file(c: char) -> Option<File> {
+    match FILE_CHARS.iter().position(|&f| f == c) {
+        Some(index) => File::try_index(index),
+        None => File::try_index(c.to_digit(10)?.checked_sub(1)? as usize),
+    }
+}
+
+pub(crate) fn parse_rank(c: char) -> Option<Rank> {
+    match RANK_CHARS.iter().position(|&r| r == c) {
+        Some(index) => Rank::try_index(index),
+        None => Rank::try_index(c.to_digit(10)?.checked_sub(1)? as usize),
+    }
+}
+
+pub(crate) fn parse_piece(text: &str) -> Option<(Piece, &str)> {
+    let names = PIECE_NAMES.iter().zip(Piece::ALL);
+    let aliases = PIECE_ALIASES.iter().map(|(name, piece)| (name, *piece));
+    names
+        .chain(aliases)
+        .find_map(|(name, piece)| Some((piece, text.strip_prefix(name)?)))
+}
+
+// Parse a move such as `７六歩(77)`, `同　角成(88)` or `５五角打`.
+//
+// This checks that the piece matches the board, but not that the move is legal.
+fn parse_move(board: &Board, text: &str, last_to: Option<Square>) -> Option<Move> {
+    let (to, rest) = match text.strip_prefix('同') {
+        Some(rest) => (last_to?, rest.trim_start_matches('　')),
+        None => {
+            let mut chars = text.chars();
+            let file = parse_file(chars.next()?)?;
+            let rank = parse_rank(chars.next()?)?;
+            (Square::new(file, rank), chars.as_str())
+        }
+    };
+    let (piece, rest) = parse_piece(rest)?;
+
+    let (promotion, rest) = if let Some(rest) = rest.strip_prefix("不成") {
+        (false, rest)
+    } else if let Some(rest) = rest.strip_prefix('成') {
+        (true, rest)
+    } else {
+        (false, rest)
+    };
+
+    // drops are marked with 打, but some writers leave it out
+    if let Some(rest) = rest.strip_prefix('打') {
+        return (!promotion && rest.is_empty()).then_some(Move::Drop { piece, to });
+    }
+    if rest.is_empty() {
+        return (!promotion).then_some(Move::Drop { piece, to });
+    }
+
+    let from = rest.strip_prefix('(')?.strip_suffix(')')?;
+    let mut digits = from.chars();
+    let file = File::try_index(digits.next()?.to_digit(10)?.checked_sub(1)? as usize)?;
+    let rank = Rank::try_index(digits.next()?.to_digit(10)?.checked_sub(1)? as usize)?;
+    if digits.next().is_some() {
+        return None;
+    }
+    let from = Square::new(file, rank);
+    if board.piece_on(from) != Some(piece) {
+        return None;
+    }
+    Some(Move::BoardMove {
+        from,
+        to,
+        promotion,
+    })
+}
+
+// Parse a time such as `( 0:03/00:00:03)`. Only the time of the move is used.
+fn parse_time(text: &str) -> Option<Duration> {
+    let text = text.strip_prefix('(')?.strip_suffix(')')?;
+    let (time, _total) = text.split_once('/')?;
+    let (minutes, seconds) = time.trim().split_once(':')?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: u64 = seconds.parse().ok()?;
+    let secs = minutes.checked_mul(60)?.checked_add(seconds)?;
+    Some(Duration::from_secs(secs))
+}
+
+fn is_move_line(line: &str) -> bool {
+    line.trim_start().starts_with(|c: char| c.is_ascii_digit())
+}
+
+// Is this a line of a board diagram that `Board::from_bod` reads?
+fn is_diagram_line(line: &str) -> bool {
+    line.starts_with('|')
+        || line.starts_with("手数＝")
+        || matches!(line, "先手番" | "後手番" | "下手番" | "上手番")
+        || line
+            .split_once('：')
+            .is_some_and(|(key, _)| key.trim().ends_with("の持駒"))
+}
+
+/// Parse a KIF record.
+///
+/// See the [module documentation](self) for what is supported.
+///
+/// # Errors
+/// Errors with a [`KifError`] holding the line of the error.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::record::*;
+/// let kif = "\
+/// 手合割：平手
+/// 先手：Alice
+/// 後手：Bob
+/// 手数----指手---------消費時間--
+///    1 ７六歩(77)   ( 0:03/00:00:03)
+///    2 ３四歩(33)   ( 0:05/00:00:05)
+///    3 ２二角成(88) ( 0:01/00:00:04)
+///    4 同　銀(31)   ( 0:02/00:00:07)
+///    5 投了
+/// ";
+/// let record = kif::parse(kif).unwrap();
+/// assert_eq!(record.header("先手"), Some("Alice"));
+/// assert_eq!(record.moves.len(), 4);
+/// assert_eq!(record.moves[3], "3a2b".parse().unwrap());
+/// assert_eq!(record.end, Some(GameEnd::Resign));
+/// assert_eq!(record.final_board().num_in_hand(Color::White, Piece::Bishop), 1);
+/// ```
+pub fn parse(text: &str) -> Result<GameRecord, KifError> {
+    use KifErrorKind::*;
+
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim_end()));
+
+    // the header fields, up to the first move or comment
+    let mut headers = Vec::new();
+    let mut start = Board::startpos();
+    let mut first_line = None;
+    let mut diagram = String::new();
+    let mut diagram_line = 0;
+    for (line_no, line) in lines.by_ref() {
+        let error = |kind| KifError {
+            line: line_no,
+            kind,
+        };
+        if line.starts_with('*') || is_move_line(line) {
+            first_line = Some((line_no, line));
+            break;
+        }
+        if is_diagram_line(line) {
+            if diagram.is_empty() {
+                diagram_line = line_no;
+            }
+            diagram.push_str(line);
+            diagram.push('\n');
+            continue;
+        }
+        if line.starts_with("手数") {
+            break;
+        }
+        if let Some((key, value)) = line.split_once('：') {
+            let (key, value) = (key.trim(), value.trim());
+            if key == "手合割" {
+                start = if value == EVEN {
+                    Board::startpos()
+                } else {
+                    Handicap::ALL
+                        .into_iter()
+                        .find(|handicap| handicap.japanese_name() == value)
+                        .map(Board::handicap)
+                        .ok_or(error(UnsupportedStartPosition))?
+                };
+            }
+            headers.push((key.to_string(), value.to_string()));
+        }
+        // other lines (such as `#` comments) are skipped
+    }
+    // a board diagram takes precedence over the handicap
+    if !diagram.is_empty() {
+        start = Board::from_bod(&diagram).map_err(|_| KifError {
+            line: diagram_line,
+            kind: InvalidDiagram,
+        })?;
+    }
+
+    let mut record = GameRecord::new(start.clone());
+    record.headers = headers;
+    let mut board = start;
+    let mut last_to = None;
+    for (line_no, line) in first_line.into_iter().chain(lines) {
+        let error = |kind| KifError {
+            line: line_no,
+            kind,
+        };
+        if let Some(comment) = line.strip_prefix('*') {
+            record
+                .comments
+                .push((record.moves.len(), comment.to_string()));
+            continue;
+        }
+        if line.starts_with("変化") {
+            // the variations follow the main line
+            break;
+        }
+        if record.end.is_some() || !is_move_line(line) {
+            continue;
+        }
+
+        let line = line.trim();
+        let digits = line
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(line.len());
+        let (number, rest) = line.split_at(digits);
+        if number.parse() != Ok(record.moves.len() + 1) {
+            return Err(error(InvalidMoveNumber));
+        }
+
+        // a trailing `+` marks a move with variations
+        let rest = rest.trim().trim_end_matches('+').trim_end();
+        let rest = rest.replacen("同 ", "同", 1);
+        let (text, time) = rest.split_once(' ').unwrap_or((&rest, ""));
+        let time = time.replace(' ', "");
+        let time = if time.is_empty() {
+            None
+        } else {
+            Some(parse_time(&time).ok_or(error(InvalidTime))?)
+        };
+
+        if let Some((_, end)) = GAME_ENDS.iter().find(|(name, _)| *name == text) {
+            record.end = Some(*end);
+            continue;
+        }
+        let mv = parse_move(&board, text, last_to).ok_or(error(InvalidMove))?;
+        if !board.is_legal(mv) {
+            return Err(error(IllegalMove));
+        }
+        board.play_unchecked(mv);
+        last_to = Some(mv.to());
+        record.moves.push(mv);
+        record.times.push(time);
+    }
+    Ok(record)
+}
+
+/// Parse a KIF record in UTF-8 or Shift-JIS.
+///
+/// The encoding is detected with [`encoding::detect`](crate::encoding::detect).
+/// This requires the `encoding` feature.
+///
+/// # Errors
+/// Errors with a [`KifError`] holding the line of the error. If the record can't be
+/// decoded, the kind is [`KifErrorKind::InvalidEncoding`] and the line is 0.
+#[cfg(feature = "encoding")]
+pub fn parse_bytes(bytes: &[u8]) -> Result<GameRecord, KifError> {
+    let text = encoding::decode(bytes).map_err(|_| KifError {
+        line: 0,
+        kind: KifErrorKind::InvalidEncoding,
+    })?;
+    parse(&text)
+}
+
+// Write a move such as `７六歩(77)`, `同　角成(88)` or `５五角打`. The move must be
+// legal.
+fn write_move(text: &mut String, board: &Board, mv: Move, last_to: Option<Square>) {
+    let to = mv.to();
+    if last_to == Some(to) {
+        text.push_str("同　");
+    } else {
+        text.push(FILE_CHARS[to.file() as usize]);
+        text.push(RANK_CHARS[to.rank() as usize]);
+    }
+    match mv {
+        Move::Drop { piece, .. } => {
+            text.push_str(PIECE_NAMES[piece as usize]);
+            text.push('打');
+        }
+        Move::BoardMove {
+            from, promotion, ..
+        } => {
+            let piece = board.piece_on(from).unwrap();
+            text.push_str(PIECE_NAMES[piece as usize]);
+            if promotion {
+                text.push('成');
+            } else if PromotionStatus::for_move(board.side_to_move(), piece, from, to)
+                == PromotionStatus::MayPromote
+            {
+                text.push_str("不成");
+            }
+            let _ = write!(
+                text,
+                "({}{})",
+                from.file() as usize + 1,
+                from.rank() as usize + 1
+            );
+        }
+    }
+}
+
+// Pad a move to the width of the move column, with at least one space.
+fn pad_move(text: &mut String, start: usize) {
+    let width: usize = text[start..]
+        .chars()
+        .map(|c| if c.is_ascii() { 1 } else { 2 })
+        .sum();
+    for _ in width..MOVE_WIDTH.max(width + 1) {
+        text.push(' ');
+    }
+}
+
+fn write_comments(text: &mut String, record: &GameRecord, ply: usize) {
+    for (_, comment) in record.comments.iter().filter(|(at, _)| *at == ply) {
+        for line in comment.lines() {
+            text.push('*');
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+}
+
+/// Write a record in KIF.
+///
+/// The handicap (`手合割`) header is set to the start position, and added in front if
+/// it is missing. The other header fields are written as they are. Times are written if any move has a time.
+///
+/// # Errors
+/// Errors with [`KifWriteError::UnsupportedStartPosition`] if the start position is not
+/// the standard start position or a handicap position, and with
+/// [`KifWriteError::IllegalMove`] if a move of the record is illegal.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::record::*;
+/// let mut record = GameRecord::new(Board::startpos());
+/// record.headers.push(("先手".to_string(), "Alice".to_string()));
+/// for mv in ["7g7f", "3c3d", "8h2b+", "3a2b", "B*4e"] {
+///     record.push(mv.parse().unwrap());
+/// }
+/// record.end = Some(GameEnd::Resign);
+///
+/// let kif = kif::write(&record).unwrap();
+/// assert_eq!(kif, "\
+/// 手合割：平手
+/// 先手：Alice
+/// 手数----指手---------消費時間--
+///    1 ７六歩(77)
+///    2 ３四歩(33)
+///    3 ２二角成(88)
+///    4 同　銀(31)
+///    5 ４五角打
+///    6 投了
+/// ");
+/// assert_eq!(kif::parse(&kif).unwrap().moves, record.moves);
+/// ```
+pub fn write(record: &GameRecord) -> Result<String, KifWriteError> {
+    let handicap = if record.start.same_position(&Board::startpos()) {
+        EVEN
+    } else {
+        Handicap::ALL
+            .into_iter()
+            .find(|&handicap| record.start.same_position(&Board::handicap(handicap)))
+            .ok_or(KifWriteError::UnsupportedStartPosition)?
+            .japanese_name()
+    };
+
+    let mut text = String::new();
+    if record.header("手合割").is_none() {
+        let _ = writeln!(text, "手合割：{}", handicap);
+    }
+    for (key, value) in &record.headers {
+        let value = if key == "手合割" {
+            handicap
+        } else {
+            value.as_str()
+        };
+        let _ = writeln!(text, "{}：{}", key, value);
+    }
+    text.push_str("手数----指手---------消費時間--\n");
+    write_comments(&mut text, record, 0);
+
+    let with_times = record.times.iter().any(Option::is_some);
+    let mut totals = [Duration::ZERO; Color::NUM];
+    let mut board = record.start.clone();
+    let mut last_to = None;
+    for (ply, &mv) in record.moves.iter().enumerate() {
+        if !board.is_legal(mv) {
+            return Err(KifWriteError::IllegalMove);
+        }
+        let _ = write!(text, "{:>4} ", ply + 1);
+        let start = text.len();
+        write_move(&mut text, &board, mv, last_to);
+        if with_times {
+            let time = record.times.get(ply).copied().flatten().unwrap_or_default();
+            let total = &mut totals[board.side_to_move() as usize];
+            *total += time;
+            let (secs, total_secs) = (time.as_secs(), total.as_secs());
+            pad_move(&mut text, start);
+            let _ = write!(
+                text,
+                "({:>2}:{:02}/{:02}:{:02}:{:02})",
+                secs / 60,
+                secs % 60,
+                total_secs / 3600,
+                total_secs / 60 % 60,
+                total_secs % 60
+            );
+        }
+        text.push('\n');
+        board.play_unchecked(mv);
+        last_to = Some(mv.to());
+        write_comments(&mut text, record, ply + 1);
+    }
+    if let Some((name, _)) = GAME_ENDS.iter().find(|(_, end)| record.end == Some(*end)) {
+        let _ = writeln!(text, "{:>4} {}", record.moves.len() + 1, name);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIF: &str = "\
+# ---- Kifu for Windows ----
+開始日時：2024/01/01 10:00:00
+手合割：平手
+先手：Alice
+後手：Bob
+手数----指手---------消費時間--
+*Start
+   1 ７六歩(77)   ( 0:03/00:00:03)
+   2 ３四歩(33)   ( 0:05/00:00:05)
+*A quiet reply
+*on two lines
+   3 ２二角成(88) ( 0:01/00:00:04)+
+   4 同　銀(31)   ( 1:02/00:01:07)
+   5 ４五角打     ( 0:10/00:00:14)
+   6 ５二玉(51)   ( 0:00/00:01:07)
+   7 ６三角不成(45) ( 0:01/00:00:15)
+   8 中断         ( 0:00/00:01:07)
+まで7手で中断
+
+変化：3手
+   3 ６六歩(67)   ( 0:01/00:00:04)
+";
+
+    #[test]
+    fn parse_record() {
+        let record = parse(KIF).unwrap();
+        assert_eq!(record.headers.len(), 4);
+        assert_eq!(record.header("後手"), Some("Bob"));
+        assert_eq!(record.moves.len(), 7);
+        assert_eq!(record.moves[2], "8h2b+".parse().unwrap());
+        assert_eq!(record.moves[4], "B*4e".parse().unwrap());
+        assert_eq!(record.moves[6], "4e6c".parse().unwrap());
+        assert_eq!(record.times[3], Some(Duration::from_secs(62)));
+        assert_eq!(
+            record.comments,
+            [
+                (0, "Start".to_string()),
+                (2, "A quiet reply".to_string()),
+                (2, "on two lines".to_string())
+            ]
+        );
+        assert_eq!(record.end, Some(GameEnd::Abort));
+    }
+
+    #[test]
+    fn write_and_parse_again() {
+        let record = parse(KIF).unwrap();
+        let kif = write(&record).unwrap();
+        assert!(kif.contains("   4 同　銀(31)   ( 1:02/00:01:07)\n"));
+        assert!(kif.contains("   7 ６三角不成(45) ( 0:01/00:00:15)\n"));
+        assert!(kif.contains("*A quiet reply\n*on two lines\n   3 "));
+        assert_eq!(parse(&kif).unwrap(), record);
+    }
+
+    #[test]
+    fn write_errors() {
+        let mut record = GameRecord::new(Board::startpos());
+        record.push("7g7f".parse().unwrap());
+        record.push("7f7e".parse().unwrap());
+        assert!(matches!(write(&record), Err(KifWriteError::IllegalMove)));
+
+        let record = GameRecord::new(Board::tsume("4k4/9/4P4/9/9/9/9/9/9 b G 1").unwrap());
+        assert!(matches!(
+            write(&record),
+            Err(KifWriteError::UnsupportedStartPosition)
+        ));
+    }
+
+    #[test]
+    fn handicap_records() {
+        let kif = "手合割：二枚落ち\n   1 ５二玉(51)\n   2 ７六歩(77)\n";
+        let record = parse(kif).unwrap();
+        assert_eq!(record.start, Board::handicap(Handicap::TwoPiece));
+        assert_eq!(record.moves.len(), 2);
+        let kif = write(&record).unwrap();
+        assert!(kif.starts_with("手合割：二枚落ち\n"));
+        assert_eq!(parse(&kif).unwrap(), record);
+
+        let record = GameRecord::new(Board::tsume("4k4/9/4P4/9/9/9/9/9/9 b G 1").unwrap());
+        assert!(write(&record).is_err());
+    }
+
+    #[test]
+    fn parse_board_diagrams() {
+        let board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+            .parse()
+            .unwrap();
+        let kif = format!(
+            "開始日時：2024/01/01\n{}先手：Alice\n手数----指手---------消費時間--\n   1 ２二角成(88)\n",
+            board.to_bod()
+        );
+        let record = parse(&kif).unwrap();
+        assert_eq!(record.start, board);
+        assert_eq!(record.header("先手"), Some("Alice"));
+        assert_eq!(record.headers.len(), 2);
+        assert_eq!(record.moves, ["8h2b+".parse().unwrap()]);
+        assert_eq!(record.final_board().move_number(), 4);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let error = parse("手合割：平手\n   1 ７六歩(77)\n   2 ７六歩(77)\n").unwrap_err();
+        assert!(matches!(error.kind(), KifErrorKind::InvalidMove));
+        assert_eq!(error.line(), 3);
+
+        let error = parse("   1 ７五歩(77)\n").unwrap_err();
+        assert!(matches!(error.kind(), KifErrorKind::IllegalMove));
+        assert_eq!(error.line(), 1);
+
+        let error = parse("   1 ７六歩(77)\n   3 ３四歩(33)\n").unwrap_err();
+        assert!(matches!(error.kind(), KifErrorKind::InvalidMoveNumber));
+        assert_eq!(error.line(), 2);
+
+        let error = parse("   1 ７六歩(77)   ( 0:x3/00:00:03)\n").unwrap_err();
+        assert!(matches!(error.kind(), KifErrorKind::InvalidTime));
+
+        let error = parse("   1 ７六歩(77)   (307445734561825861:00/00:00:03)\n").unwrap_err();
+        assert!(matches!(error.kind(), KifErrorKind::InvalidTime));
+
+        let error = parse("手合割：平手\n後手の持駒：なし\n").unwrap_err();
+        assert!(matches!(error.kind(), KifErrorKind::InvalidDiagram));
+        assert_eq!(error.line(), 2);
+
+        let error = parse("開始日時：2024/01/01\n手合割：九枚落ち\n").unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            KifErrorKind::UnsupportedStartPosition
+        ));
+        assert_eq!(error.line(), 2);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn parse_shift_jis() {
+        let (bytes, _, had_errors) = encoding_rs::SHIFT_JIS.encode(KIF);
+        assert!(!had_errors);
+        assert_eq!(parse_bytes(&bytes).unwrap(), parse(KIF).unwrap());
+    }
+}
@@ -18,6 +18,8 @@
 
 use haitaka_types::*;
 
+mod capabilities;
+
 pub use bitboard::*;
 pub use color::*;
 pub use file::*;
@@ -29,8 +31,31 @@ pub use square::*;
 
 pub mod attacks;
 pub mod board;
+pub mod book;
+pub mod csa;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+#[cfg(feature = "reference-eval")]
+pub mod eval;
+pub mod features;
+pub mod game;
+pub mod kif;
+pub mod notation;
+pub mod packed_sfen;
+pub mod perft;
+pub mod policy;
+pub mod prelude;
+pub mod record;
+pub mod repetition;
 pub mod slider_moves;
+pub mod tables;
+pub mod tsume;
+pub mod usi;
+#[cfg(feature = "viz")]
+pub mod viz;
+pub mod zobrist;
 
 pub use attacks::*;
 pub use board::*;
+pub use capabilities::*;
 pub use slider_moves::*;
@@ -0,0 +1,348 @@
+//! Move notation with check and mate markers
+//!
+//! Game records often mark moves that give check or mate, for instance with `+` and `#`
+//! in western notation. Whether a move gives check or mate depends on the position, so
+//! these markers can't be derived from a [`Move`] alone. This module computes them from
+//! the position before the move, so that move formatters can append them as configured
+//! by [`FormatOptions`].
+//!
+//! Moves can be formatted in USI format with [`format_usi`], and in the western notation
+//! of English Shogi books with [`format_western`], [`Board::san`] or
+//! [`MoveNotation::to_western`].
+use core::fmt;
+
+use crate::*;
+
+/// Options for formatting moves.
+///
+/// By default, no markers are added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FormatOptions {
+    /// Marker appended to moves that give check (but not mate).
+    pub check_marker: Option<&'static str>,
+    /// Marker appended to moves that give mate.
+    ///
+    /// If this is `None`, mating moves get the `check_marker`.
+    pub mate_marker: Option<&'static str>,
+}
+
+impl FormatOptions {
+    /// The western markers: `+` for check and `#` for mate.
+    ///
+    /// Note that `+` also marks promotions in USI, so this is not suitable for USI moves.
+    pub const WESTERN: Self = Self {
+        check_marker: Some("+"),
+        mate_marker: Some("#"),
+    };
+}
+
+/// Does a move give check or mate?
+///
+/// A move either gives no check, check or mate, so this enum is not
+/// `#[non_exhaustive]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckStatus {
+    /// The move does not give check.
+    NoCheck,
+    /// The move gives check, but not mate.
+    Check,
+    /// The move gives mate.
+    Mate,
+}
+
+/// Get the [`CheckStatus`] of `mv`, played in the position `board`.
+///
+/// The move is assumed to be legal.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// # use haitaka::notation::*;
+/// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+/// assert_eq!(check_status(&board, "G*4b".parse().unwrap()), CheckStatus::Check);
+/// assert_eq!(check_status(&board, "G*5b".parse().unwrap()), CheckStatus::Mate);
+/// assert_eq!(check_status(&board, "5i5h".parse().unwrap()), CheckStatus::NoCheck);
+/// ```
+pub fn check_status(board: &Board, mv: Move) -> CheckStatus {
+    if !board.gives_check(mv) {
+        return CheckStatus::NoCheck;
+    }
+    let mut board = board.clone();
+    board.play_unchecked(mv);
+    if board.checkers().is_empty() {
+        CheckStatus::NoCheck
+    } else if board.generate_moves(|_| true) {
+        CheckStatus::Check
+    } else {
+        CheckStatus::Mate
+    }
+}
+
+/// Get the marker for `mv`, played in the position `board`, as configured by `options`.
+///
+/// Returns the empty string if the move needs no marker.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// # use haitaka::notation::*;
+/// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+/// let mv = "G*5b".parse().unwrap();
+/// assert_eq!(marker(&board, mv, &FormatOptions::WESTERN), "#");
+/// assert_eq!(marker(&board, mv, &FormatOptions::default()), "");
+/// ```
+pub fn marker(board: &Board, mv: Move, options: &FormatOptions) -> &'static str {
+    if options.check_marker.is_none() && options.mate_marker.is_none() {
+        return "";
+    }
+    let marker = match check_status(board, mv) {
+        CheckStatus::NoCheck => None,
+        CheckStatus::Check => options.check_marker,
+        CheckStatus::Mate => options.mate_marker.or(options.check_marker),
+    };
+    marker.unwrap_or("")
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Style {
+    Usi,
+    Western,
+}
+
+/// A move followed by its check or mate marker.
+///
+/// Created by [`format_usi`] and [`format_western`].
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedMove<'a> {
+    board: &'a Board,
+    mv: Move,
+    options: FormatOptions,
+    style: Style,
+}
+
+impl fmt::Display for FormattedMove<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Style::Usi => write!(f, "{}", self.mv)?,
+            Style::Western => write_western(f, self.board, self.mv)?,
+        }
+        write!(f, "{}", marker(self.board, self.mv, &self.options))
+    }
+}
+
+// Write a move such as `P-7f`, `Bx2b+`, `G6i-5h` or `S*5e`.
+fn write_western(f: &mut fmt::Formatter<'_>, board: &Board, mv: Move) -> fmt::Result {
+    match mv {
+        Move::Drop { piece, to } => {
+            write!(f, "{}*{}", piece.to_str(Color::Black), to)
+        }
+        Move::BoardMove {
+            from,
+            to,
+            promotion,
+        } => {
+            let piece = board
+                .piece_on(from)
+                .expect("Missing piece on move's `from` square");
+            write!(f, "{}", piece.to_str(Color::Black))?;
+            if board.legal_origins(piece, to).len() > 1 {
+                write!(f, "{}", from)?;
+            }
+            let separator = if board.occupied().has(to) { 'x' } else { '-' };
+            write!(f, "{}{}", separator, to)?;
+            if promotion {
+                write!(f, "+")
+            } else if PromotionStatus::for_move(board.side_to_move(), piece, from, to)
+                == PromotionStatus::MayPromote
+            {
+                write!(f, "=")
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Format `mv`, played in the position `board`, in USI format with markers.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// # use haitaka::notation::*;
+/// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+/// let options = FormatOptions {
+///     check_marker: Some(" (check)"),
+///     mate_marker: Some(" (mate)"),
+/// };
+/// let mv = "G*5b".parse().unwrap();
+/// assert_eq!(format_usi(&board, mv, options).to_string(), "G*5b (mate)");
+/// let mv = "G*4b".parse().unwrap();
+/// assert_eq!(format_usi(&board, mv, options).to_string(), "G*4b (check)");
+/// ```
+pub fn format_usi(board: &Board, mv: Move, options: FormatOptions) -> FormattedMove<'_> {
+    FormattedMove {
+        board,
+        mv,
+        options,
+        style: Style::Usi,
+    }
+}
+
+/// Format `mv`, played in the position `board`, in western notation with markers.
+///
+/// This is the notation of English Shogi books (also known as Hodges notation): the
+/// piece (`P`, `L`, `N`, `S`, `G`, `B`, `R`, `K`, or `+P`, `+L`, `+N`, `+S`, `+B`, `+R`
+/// for promoted pieces), the square the piece comes from if another piece of the same
+/// type could also move to the target square, `-` for a move, `x` for a capture or `*`
+/// for a drop, and the target square. A promotion is marked with `+`, and a move that
+/// could promote but doesn't with `=`.
+///
+/// The move is assumed to be legal.
+///
+/// # Panics
+/// This may panic if the move is not legal.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// # use haitaka::notation::*;
+/// let board: Board = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+///     .parse()
+///     .unwrap();
+/// let western = |mv: &str| format_western(&board, mv.parse().unwrap(), FormatOptions::default()).to_string();
+/// assert_eq!(western("2g2f"), "P-2f");
+/// assert_eq!(western("8h2b+"), "Bx2b+");
+/// assert_eq!(western("8h2b"), "Bx2b=");
+/// // both Golds can move to 5h
+/// assert_eq!(western("6i5h"), "G6i-5h");
+///
+/// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+/// let mv = "G*5b".parse().unwrap();
+/// assert_eq!(format_western(&board, mv, FormatOptions::WESTERN).to_string(), "G*5b#");
+/// ```
+pub fn format_western(board: &Board, mv: Move, options: FormatOptions) -> FormattedMove<'_> {
+    FormattedMove {
+        board,
+        mv,
+        options,
+        style: Style::Western,
+    }
+}
+
+impl Board {
+    /// Format a legal move in western notation, without markers.
+    ///
+    /// This is a shorthand for [`format_western`] with the default options. The name
+    /// follows the "standard algebraic notation" of chess, which this notation is
+    /// modeled on.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let board = Board::startpos();
+    /// assert_eq!(board.san("7g7f".parse().unwrap()), "P-7f");
+    /// ```
+    pub fn san(&self, mv: Move) -> String {
+        format_western(self, mv, FormatOptions::default()).to_string()
+    }
+}
+
+/// Formatting of moves that needs the position they are played in.
+///
+/// [`Move`] is defined in `haitaka_types`, which knows nothing about boards, so the
+/// methods that need a [`Board`] are added by this trait.
+pub trait MoveNotation {
+    /// Format the move, played in the position `board`, in western notation without
+    /// markers. This is the same as [`Board::san`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// use haitaka::notation::MoveNotation;
+    ///
+    /// let board: Board = "4k4/9/9/9/9/9/9/9/4K4 b S 1".parse().unwrap();
+    /// let mv: Move = "S*5e".parse().unwrap();
+    /// assert_eq!(mv.to_western(&board), "S*5e");
+    /// ```
+    fn to_western(&self, board: &Board) -> String;
+}
+
+impl MoveNotation for Move {
+    fn to_western(&self, board: &Board) -> String {
+        board.san(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn western(sfen: &str, mv: &str) -> String {
+        let board: Board = sfen.parse().unwrap();
+        let mv: Move = mv.parse().unwrap();
+        assert!(board.is_legal(mv), "{sfen} {mv}");
+        format_western(&board, mv, FormatOptions::WESTERN).to_string()
+    }
+
+    #[test]
+    fn disambiguation() {
+        // Golds on 6h, 6i, 5i and 4i, and a Tokin on 4h
+        let sfen = "4k4/9/9/9/9/9/9/3G1+P3/K2GGG3 b - 1";
+        assert_eq!(western(sfen, "6i5h"), "G6i-5h");
+        assert_eq!(western(sfen, "5i5h"), "G5i-5h");
+        assert_eq!(western(sfen, "4i5h"), "G4i-5h");
+        assert_eq!(western(sfen, "6h5h"), "G6h-5h");
+        assert_eq!(western(sfen, "6h7g"), "G-7g");
+        // the Tokin moves like a Gold, but it is another piece
+        assert_eq!(western(sfen, "4h5h"), "+P-5h");
+
+        // Dragons on 9e and 1i, which both reach 9i and 1e
+        let sfen = "4k4/9/9/9/+R8/9/9/4K4/8+R b - 1";
+        assert_eq!(western(sfen, "9e9i"), "+R9e-9i");
+        assert_eq!(western(sfen, "1i9i"), "+R1i-9i");
+        assert_eq!(western(sfen, "1i1e"), "+R1i-1e");
+        assert_eq!(western(sfen, "9e7e"), "+R-7e");
+
+        // two Silvers that can capture on the same square
+        let sfen = "4k4/9/9/9/9/4p4/3S1S3/9/4K4 b - 1";
+        assert_eq!(western(sfen, "6g5f"), "S6gx5f");
+        assert_eq!(western(sfen, "4g5f"), "S4gx5f");
+        assert_eq!(western(sfen, "6g6f"), "S-6f");
+    }
+
+    #[test]
+    fn drops() {
+        // a drop is never ambiguous, even if a piece on the board reaches the square
+        let sfen = "4k4/9/9/9/9/9/9/4G4/4K4 b GP 1";
+        assert_eq!(western(sfen, "G*4h"), "G*4h");
+        assert_eq!(western(sfen, "5h4h"), "G-4h");
+        assert_eq!(western(sfen, "P*5e"), "P*5e");
+        // White's pieces have the same letters
+        assert_eq!(western("4k4/9/9/9/9/9/9/9/4K4 w r 1", "R*9e"), "R*9e");
+        // drops that give check and mate
+        let sfen = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1";
+        assert_eq!(western(sfen, "G*4b"), "G*4b+");
+        assert_eq!(western(sfen, "G*5b"), "G*5b#");
+    }
+
+    #[test]
+    fn promotions() {
+        // a Pawn may promote on the third rank and must promote on the last
+        let sfen = "4k4/P8/2P6/9/9/9/9/9/4K4 b - 1";
+        assert_eq!(western(sfen, "7c7b+"), "P-7b+");
+        assert_eq!(western(sfen, "7c7b"), "P-7b=");
+        assert_eq!(western(sfen, "9b9a+"), "P-9a+");
+        // a Silver may promote when it leaves the zone, a Gold never
+        let sfen = "4k4/9/2S3G2/9/9/9/9/9/4K4 b - 1";
+        assert_eq!(western(sfen, "7c6d+"), "S-6d+");
+        assert_eq!(western(sfen, "7c6d"), "S-6d=");
+        assert_eq!(western(sfen, "3c3d"), "G-3d");
+        // a promoted piece can not promote again
+        let sfen = "4k4/9/9/9/9/9/9/1+B7/4K4 b - 1";
+        assert_eq!(western(sfen, "8h2b"), "+B-2b");
+        // White promotes in Black's camp
+        let sfen = "4k4/9/9/9/9/9/9/6b2/4K4 w - 1";
+        assert_eq!(western(sfen, "3h2i+"), "B-2i+");
+        assert_eq!(western(sfen, "3h2i"), "B-2i=");
+    }
+}
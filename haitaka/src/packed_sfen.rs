@@ -0,0 +1,578 @@
+//! Packed SFEN: 32-byte positions, and training data in that format
+//!
+//! Packed SFEN is the binary position format of YaneuraOu, which is used by most shogi
+//! engines for the training data of their evaluation functions. It packs a position
+//! with all 40 pieces in exactly 256 bits, written from the lowest bit of the first byte
+//! on:
+//!
+//! - 1 bit for the side to move (0 for Black, 1 for White);
+//! - 7 bits each for the squares of the Black and the White King;
+//! - the other squares, in the order of [`Square::ALL`] (1a, 1b, ..., 9i), with a
+//!   Huffman code for each piece or empty square;
+//! - the pieces in hand, Black first, in the order of [`Piece::ALL`].
+//!
+//! The move number is not part of the position.
+//!
+//! Training data files (usually with the extension `.bin`) hold 40-byte
+//! [`TrainingRecord`]s. [`TrainingReader`] reads them from any [`std::io::Read`].
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! use haitaka::packed_sfen::*;
+//!
+//! let board = Board::startpos();
+//! let packed = board.to_packed_sfen().unwrap();
+//! assert_eq!(packed.len(), PACKED_SFEN_SIZE);
+//! assert_eq!(Board::from_packed_sfen(&packed).unwrap(), board);
+//! ```
+use std::io::{self, Read};
+
+use crate::*;
+
+/// The size of a packed SFEN in bytes.
+pub const PACKED_SFEN_SIZE: usize = 32;
+
+/// The size of a [`TrainingRecord`] in bytes.
+pub const TRAINING_RECORD_SIZE: usize = 40;
+
+helpers::simple_error! {
+    /// The position can't be packed, or the bytes are not a valid packed SFEN.
+    pub enum PackedSfenError {
+        IncompletePieces = "The position does not have exactly the 40 pieces of a game",
+        InvalidData = "The bytes are not a valid packed SFEN",
+        InvalidPosition = "The packed position is not valid"
+    }
+}
+
+// The Huffman codes (code, bits) of the pieces on the board, indexed by the unpromoted
+// piece. An empty square is a single 0 bit. Pieces in hand drop the first bit, which is
+// always 1.
+const HUFFMAN_CODES: [(u8, u8); Piece::HAND_NUM] = [
+    (0x01, 2), // Pawn
+    (0x03, 4), // Lance
+    (0x0b, 4), // Knight
+    (0x07, 4), // Silver
+    (0x1f, 6), // Bishop
+    (0x3f, 6), // Rook
+    (0x0f, 5), // Gold
+];
+
+// The colors in the order of the format, which numbers Black 0 and White 1.
+const COLORS: [Color; Color::NUM] = [Color::Black, Color::White];
+
+// The number of pieces of a game, indexed by the unpromoted piece (without the King).
+const PIECE_COUNTS: [u32; Piece::HAND_NUM] = [18, 4, 4, 4, 2, 2, 4];
+
+struct BitWriter {
+    data: [u8; PACKED_SFEN_SIZE],
+    cursor: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u8, bits: u8) {
+        for i in 0..bits {
+            if value & (1 << i) != 0 {
+                self.data[self.cursor / 8] |= 1 << (self.cursor % 8);
+            }
+            self.cursor += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8; PACKED_SFEN_SIZE],
+    cursor: usize,
+}
+
+impl BitReader<'_> {
+    fn is_done(&self) -> bool {
+        self.cursor == PACKED_SFEN_SIZE * 8
+    }
+
+    fn read(&mut self, bits: u8) -> Result<u8, PackedSfenError> {
+        let mut value = 0;
+        for i in 0..bits {
+            if self.is_done() {
+                return Err(PackedSfenError::InvalidData);
+            }
+            let bit = (self.data[self.cursor / 8] >> (self.cursor % 8)) & 1;
+            value |= bit << i;
+            self.cursor += 1;
+        }
+        Ok(value)
+    }
+
+    // Read a piece, with `skipped` leading bits of its code left out.
+    fn read_piece(&mut self, skipped: u8) -> Result<Option<(Color, Piece)>, PackedSfenError> {
+        let (mut code, mut bits) = ((1 << skipped) - 1, skipped);
+        let piece = loop {
+            code |= self.read(1)? << bits;
+            bits += 1;
+            if (code, bits) == (0, 1) {
+                return Ok(None);
+            }
+            if let Some(index) = HUFFMAN_CODES.iter().position(|&c| c == (code, bits)) {
+                break Piece::index_const(index);
+            }
+            if bits == 6 {
+                return Err(PackedSfenError::InvalidData);
+            }
+        };
+        let promoted = piece != Piece::Gold && self.read(1)? == 1;
+        let color = COLORS[self.read(1)? as usize];
+        Ok(Some((
+            color,
+            if promoted { piece.promote() } else { piece },
+        )))
+    }
+}
+
+impl Board {
+    /// Pack the position in 32 bytes.
+    ///
+    /// The move number is left out. See the [module documentation](crate::packed_sfen)
+    /// for the format.
+    ///
+    /// # Errors
+    /// Errors with [`PackedSfenError::IncompletePieces`] if the position does not have
+    /// both Kings and exactly the other 38 pieces of a game, on the board or in hand.
+    /// Tsume Shogi positions therefore can't be packed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// let packed = Board::startpos().to_packed_sfen().unwrap();
+    /// // Black to move, with the Kings on 5i and 5a
+    /// assert_eq!(packed[0], (Square::I5 as u8) << 1);
+    /// assert_eq!(packed[1] & 0x7f, Square::A5 as u8);
+    ///
+    /// let board: Board = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1".parse().unwrap();
+    /// assert!(board.to_packed_sfen().is_err());
+    /// ```
+    pub fn to_packed_sfen(&self) -> Result<[u8; PACKED_SFEN_SIZE], PackedSfenError> {
+        let mut counts = [0; Piece::HAND_NUM];
+        for (index, count) in counts.iter_mut().enumerate() {
+            let piece = Piece::index_const(index);
+            *count = (self.pieces(piece) | self.pieces(piece.promote())).len();
+            for color in Color::ALL {
+                *count += self.num_in_hand(color, piece) as u32;
+            }
+        }
+        let kings = COLORS.map(|color| self.colored_pieces(color, Piece::King));
+        if counts != PIECE_COUNTS || kings.iter().any(|king| king.len() != 1) {
+            return Err(PackedSfenError::IncompletePieces);
+        }
+
+        let mut writer = BitWriter {
+            data: [0; PACKED_SFEN_SIZE],
+            cursor: 0,
+        };
+        writer.write((self.side_to_move() == Color::White) as u8, 1);
+        for king in kings {
+            writer.write(king.next_square().unwrap() as u8, 7);
+        }
+        for square in Square::ALL {
+            let Some(piece) = self.piece_on(square) else {
+                writer.write(0, 1);
+                continue;
+            };
+            if piece == Piece::King {
+                continue;
+            }
+            let (code, bits) = HUFFMAN_CODES[piece.unpromote() as usize];
+            writer.write(code, bits);
+            if piece != Piece::Gold {
+                writer.write(piece.is_promoted() as u8, 1);
+            }
+            writer.write((self.color_on(square) == Some(Color::White)) as u8, 1);
+        }
+        for color in COLORS {
+            for (index, &(code, bits)) in HUFFMAN_CODES.iter().enumerate() {
+                let piece = Piece::index_const(index);
+                for _ in 0..self.num_in_hand(color, piece) {
+                    writer.write(code >> 1, bits - 1);
+                    if piece != Piece::Gold {
+                        writer.write(0, 1);
+                    }
+                    writer.write((color == Color::White) as u8, 1);
+                }
+            }
+        }
+        debug_assert_eq!(writer.cursor, PACKED_SFEN_SIZE * 8);
+        Ok(writer.data)
+    }
+
+    /// Unpack a position packed with [`Board::to_packed_sfen`].
+    ///
+    /// The move number is set to 1. The position is checked with
+    /// [`Validation::Lenient`], which runs the same checks as [`Board::from_sfen`]: a
+    /// position with two unpromoted Pawns of one side on a file is accepted.
+    ///
+    /// # Errors
+    /// Errors with [`PackedSfenError::InvalidData`] if the bytes can't be decoded, and
+    /// with [`PackedSfenError::InvalidPosition`] if the decoded position is not valid.
+    pub fn from_packed_sfen(packed: &[u8; PACKED_SFEN_SIZE]) -> Result<Self, PackedSfenError> {
+        Self::unpack(packed, 1)
+    }
+
+    fn unpack(packed: &[u8; PACKED_SFEN_SIZE], move_number: u16) -> Result<Self, PackedSfenError> {
+        let mut reader = BitReader {
+            data: packed,
+            cursor: 0,
+        };
+        let mut builder = BoardBuilder::new();
+        builder
+            .side_to_move(COLORS[reader.read(1)? as usize])
+            .move_number(move_number);
+        let mut kings = [Square::A1; Color::NUM];
+        for (color, king) in COLORS.into_iter().zip(&mut kings) {
+            *king =
+                Square::try_index(reader.read(7)? as usize).ok_or(PackedSfenError::InvalidData)?;
+            builder.put(color, Piece::King, *king);
+        }
+        if kings[0] == kings[1] {
+            return Err(PackedSfenError::InvalidData);
+        }
+        for square in Square::ALL {
+            if kings.contains(&square) {
+                continue;
+            }
+            if let Some((color, piece)) = reader.read_piece(0)? {
+                builder.put(color, piece, square);
+            }
+        }
+        let mut hands = [[0; Piece::HAND_NUM]; Color::NUM];
+        while !reader.is_done() {
+            match reader.read_piece(1)? {
+                Some((color, piece)) if piece.is_unpromoted() => {
+                    hands[color as usize][piece as usize] += 1;
+                }
+                _ => return Err(PackedSfenError::InvalidData),
+            }
+        }
+        for color in Color::ALL {
+            for (index, &count) in hands[color as usize].iter().enumerate() {
+                builder.set_hand(color, Piece::index_const(index), count);
+            }
+        }
+        builder
+            .build(Validation::Lenient)
+            .map_err(|_| PackedSfenError::InvalidPosition)
+    }
+}
+
+// YaneuraOu numbers the pieces of drops from 1, haitaka from 0.
+fn move_to_yaneuraou(mv: Option<Move>) -> u16 {
+    match mv {
+        Some(mv) if mv.is_drop() => mv.to_u16() + (1 << 7),
+        Some(mv) => mv.to_u16(),
+        None => 0,
+    }
+}
+
+fn move_from_yaneuraou(value: u16) -> Option<Move> {
+    if value & (1 << 14) != 0 {
+        Move::from_u16(value.checked_sub(1 << 7)?)
+    } else {
+        Move::from_u16(value)
+    }
+}
+
+/// A position of a training data file, with its evaluation, a move and the result of
+/// the game.
+///
+/// In the file, a record takes 40 little-endian bytes:
+///
+/// | Bytes | Field                                             |
+/// |-------|---------------------------------------------------|
+/// | 0-31  | The position, see [`Board::to_packed_sfen`]       |
+/// | 32-33 | The score                                         |
+/// | 34-35 | The move, in the 16-bit encoding of YaneuraOu     |
+/// | 36-37 | The move number                                   |
+/// | 38    | The result                                        |
+/// | 39    | Padding (0)                                       |
+///
+/// The 16-bit move encoding of YaneuraOu is that of [`Move::to_u16`], except that the
+/// pieces of drops are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrainingRecord {
+    /// The position, with the move number of the record.
+    pub board: Board,
+    /// The score of the position, from the side to move.
+    pub score: i16,
+    /// The move played or searched in the position, if any.
+    pub mv: Option<Move>,
+    /// The result of the game for the side to move: 1 for a win, 0 for a draw and -1
+    /// for a loss.
+    pub result: i8,
+}
+
+impl TrainingRecord {
+    /// Read a record from its bytes.
+    ///
+    /// A move that can't be decoded (such as the null move of YaneuraOu) is read as
+    /// None.
+    ///
+    /// # Errors
+    /// Errors if the position can't be unpacked, see [`Board::from_packed_sfen`].
+    pub fn from_bytes(bytes: &[u8; TRAINING_RECORD_SIZE]) -> Result<Self, PackedSfenError> {
+        let field = |start: usize| [bytes[start], bytes[start + 1]];
+        let packed = bytes[..PACKED_SFEN_SIZE].try_into().unwrap();
+        let move_number = u16::from_le_bytes(field(36));
+        Ok(Self {
+            board: Board::unpack(packed, move_number)?,
+            score: i16::from_le_bytes(field(32)),
+            mv: move_from_yaneuraou(u16::from_le_bytes(field(34))),
+            result: bytes[38] as i8,
+        })
+    }
+
+    /// Write the record.
+    ///
+    /// # Errors
+    /// Errors if the position can't be packed, see [`Board::to_packed_sfen`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// use haitaka::packed_sfen::*;
+    ///
+    /// let record = TrainingRecord {
+    ///     board: Board::startpos(),
+    ///     score: 42,
+    ///     mv: Some("P*5e".parse().unwrap()),
+    ///     result: -1,
+    /// };
+    /// let bytes = record.to_bytes().unwrap();
+    /// assert_eq!(bytes[32..], [42, 0, 0xa8, 0x40, 1, 0, 0xff, 0]);
+    /// assert_eq!(TrainingRecord::from_bytes(&bytes).unwrap(), record);
+    /// ```
+    pub fn to_bytes(&self) -> Result<[u8; TRAINING_RECORD_SIZE], PackedSfenError> {
+        let mut bytes = [0; TRAINING_RECORD_SIZE];
+        bytes[..PACKED_SFEN_SIZE].copy_from_slice(&self.board.to_packed_sfen()?);
+        bytes[32..34].copy_from_slice(&self.score.to_le_bytes());
+        bytes[34..36].copy_from_slice(&move_to_yaneuraou(self.mv).to_le_bytes());
+        bytes[36..38].copy_from_slice(&self.board.move_number().to_le_bytes());
+        bytes[38] = self.result as u8;
+        Ok(bytes)
+    }
+}
+
+/// A reader of training data files, which iterates over their [`TrainingRecord`]s.
+///
+/// The records are read one at a time, so that files that don't fit in memory can be
+/// read as well. Wrap unbuffered readers such as [`std::fs::File`] in a
+/// [`std::io::BufReader`].
+///
+/// A record that can't be unpacked is an error of kind
+/// [`std::io::ErrorKind::InvalidData`], with the message of the [`PackedSfenError`].
+/// Reading continues with the next record. A file that ends in the middle of a record
+/// gives an error of kind [`std::io::ErrorKind::UnexpectedEof`].
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::packed_sfen::*;
+///
+/// let mut data = Vec::new();
+/// let mut board = Board::startpos();
+/// for mv in ["7g7f", "3c3d"] {
+///     let mv = mv.parse().unwrap();
+///     let record = TrainingRecord { board: board.clone(), score: 0, mv: Some(mv), result: 0 };
+///     data.extend(record.to_bytes().unwrap());
+///     board.play(mv);
+/// }
+///
+/// let records: Vec<TrainingRecord> = TrainingReader::new(data.as_slice())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(records.len(), 2);
+/// assert_eq!(records[1].board.side_to_move(), Color::White);
+/// assert_eq!(records[1].mv, Some("3c3d".parse().unwrap()));
+/// ```
+#[derive(Debug)]
+pub struct TrainingReader<R> {
+    reader: R,
+}
+
+impl<R: Read> TrainingReader<R> {
+    /// Read training records from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Get back the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for TrainingReader<R> {
+    type Item = io::Result<TrainingRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = [0; TRAINING_RECORD_SIZE];
+        let mut filled = 0;
+        while filled < TRAINING_RECORD_SIZE {
+            match self.reader.read(&mut bytes[filled..]) {
+                Ok(0) if filled == 0 => return None,
+                Ok(0) => return Some(Err(io::ErrorKind::UnexpectedEof.into())),
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        Some(
+            TrainingRecord::from_bytes(&bytes)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::IndexedRandom;
+
+    #[test]
+    fn packed_sfens_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0x256);
+        for _ in 0..10 {
+            let mut board = Board::startpos();
+            for _ in 0..150 {
+                let packed = board.to_packed_sfen().unwrap();
+                let unpacked = Board::from_packed_sfen(&packed).unwrap();
+                assert!(unpacked.same_position(&board), "{board}");
+                assert_eq!(unpacked.move_number(), 1);
+
+                let moves = board.legal_moves();
+                let Some(&mv) = moves.choose(&mut rng) else {
+                    break;
+                };
+                let record = TrainingRecord {
+                    board: board.clone(),
+                    score: -300,
+                    mv: Some(mv),
+                    result: 1,
+                };
+                let bytes = record.to_bytes().unwrap();
+                assert_eq!(TrainingRecord::from_bytes(&bytes).unwrap(), record);
+                board.play_unchecked(mv);
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_yaneuraou_records() {
+        // Written with the bit layout of `SfenPacker` in YaneuraOu's sfen_packer.cpp
+        let data = include_bytes!("test_data/yaneuraou.bin");
+        let expected = [
+            (
+                "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+                0,
+                "7g7f",
+                0,
+            ),
+            (
+                "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3",
+                38,
+                "8h2b+",
+                1,
+            ),
+            (
+                "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+                -512,
+                "N*5e",
+                -1,
+            ),
+            (
+                "ln3gsn1/7kl/3+B1p1p1/p4s2p/2P6/P2B3PP/1PNP+rPP2/2G3SK1/L4G1NL b G3Prs3p 65",
+                2000,
+                "G*3b",
+                1,
+            ),
+        ];
+        let records: Vec<TrainingRecord> = TrainingReader::new(&data[..])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), expected.len());
+        for ((record, bytes), (sfen, score, mv, result)) in records
+            .iter()
+            .zip(data.chunks(TRAINING_RECORD_SIZE))
+            .zip(expected)
+        {
+            assert_eq!(record.board.to_string(), sfen);
+            assert_eq!(record.score, score);
+            let mv = mv.parse().unwrap();
+            assert_eq!(record.mv, Some(mv));
+            assert!(record.board.is_legal(mv), "{sfen} {mv}");
+            assert_eq!(record.result, result);
+            assert_eq!(record.to_bytes().unwrap()[..], *bytes);
+        }
+    }
+
+    #[test]
+    fn checks_positions_as_from_sfen() {
+        // nifu is accepted, as by `Board::from_sfen`
+        let board = Board::from_sfen("4k4/9/9/9/4P4/9/4P4/9/4K4 b 2r2b4g4s4n4l16p 1").unwrap();
+        let unpacked = Board::from_packed_sfen(&board.to_packed_sfen().unwrap()).unwrap();
+        assert!(unpacked.same_position(&board));
+
+        // White is in check with Black to move
+        let sfen = "4k3R/9/9/9/9/9/9/9/4K4 b r2b4g4s4n4l18p 1";
+        assert!(Board::from_sfen(sfen).is_err());
+        let mut builder = BoardBuilder::new();
+        builder.put(Color::White, Piece::King, Square::A5);
+        builder.put(Color::Black, Piece::Rook, Square::A1);
+        builder.put(Color::Black, Piece::King, Square::I5);
+        for (piece, count) in [
+            (Piece::Pawn, 18),
+            (Piece::Lance, 4),
+            (Piece::Knight, 4),
+            (Piece::Silver, 4),
+            (Piece::Gold, 4),
+            (Piece::Bishop, 2),
+            (Piece::Rook, 1),
+        ] {
+            builder.set_hand(Color::White, piece, count);
+        }
+        let board = builder.build(Validation::None).unwrap();
+        assert_eq!(board.to_string(), sfen);
+        assert!(matches!(
+            Board::from_packed_sfen(&board.to_packed_sfen().unwrap()),
+            Err(PackedSfenError::InvalidPosition)
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let packed = Board::startpos().to_packed_sfen().unwrap();
+        // both Kings on 5i
+        let mut same_kings = packed;
+        same_kings[1] = (packed[1] & 0x80) | 44;
+        assert!(matches!(
+            Board::from_packed_sfen(&same_kings),
+            Err(PackedSfenError::InvalidData)
+        ));
+        // a King square out of range
+        assert!(matches!(
+            Board::from_packed_sfen(&[0xff; PACKED_SFEN_SIZE]),
+            Err(PackedSfenError::InvalidData)
+        ));
+
+        // a partial record
+        let record = TrainingRecord {
+            board: Board::startpos(),
+            score: 0,
+            mv: None,
+            result: 0,
+        };
+        let bytes = record.to_bytes().unwrap();
+        let mut reader = TrainingReader::new(&bytes[..30]);
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,234 @@
+//! Perft: counting the move sequences of the game tree
+//!
+//! Perft (performance test) counts all sequences of legal moves of a given length from
+//! a position. The counts of well-known positions are published, so perft is the
+//! standard way to check a move generator, and [`perft_divide`] breaks a count down by
+//! the first move to find where two move generators disagree.
+//!
+//! The counts for the start position are 30, 900, 25,470, 719,731 and 19,861,490 for
+//! depths 1 to 5.
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! let board = Board::startpos();
+//! assert_eq!(perft::perft(&board, 3), 25_470);
+//! assert_eq!(perft::perft_hashed(&board, 3, 1 << 12), 25_470);
+//! ```
+use crate::*;
+
+/// Count the sequences of legal moves of length `depth` from `board`.
+///
+/// This counts the moves at the last level without playing them (bulk counting), and
+/// is as fast as the move generator allows. Repeated positions are counted every time
+/// they occur.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// assert_eq!(perft::perft(&board, 0), 1);
+/// assert_eq!(perft::perft(&board, 2), 900);
+/// ```
+pub fn perft(board: &Board, depth: u8) -> u64 {
+    match depth {
+        0 => 1,
+        1 => {
+            let mut nodes = 0;
+            board.generate_moves(|moves| {
+                nodes += moves.into_iter().len() as u64;
+                false
+            });
+            nodes
+        }
+        _ => {
+            let mut nodes = 0;
+            board.generate_moves(|moves| {
+                for mv in moves {
+                    nodes += perft(&board.after_unchecked(mv), depth - 1);
+                }
+                false
+            });
+            nodes
+        }
+    }
+}
+
+/// Count the sequences of legal moves of length `depth` from `board` for every first
+/// move.
+///
+/// The counts add up to [`perft`] of the position. Comparing them with the counts of
+/// another move generator shows which first move has the wrong count, and repeating
+/// that after the move narrows the difference down to a single position.
+///
+/// The moves are in the order of [`Board::generate_moves`]. With a `depth` of 0, the
+/// result is empty.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// let divide = perft::perft_divide(&board, 3);
+/// assert_eq!(divide.len(), 30);
+/// assert_eq!(divide.iter().map(|&(_, nodes)| nodes).sum::<u64>(), 25_470);
+///
+/// let mv = "7g7f".parse().unwrap();
+/// let (_, nodes) = divide.iter().find(|&&(m, _)| m == mv).unwrap();
+/// assert_eq!(*nodes, perft::perft(&board.after_unchecked(mv), 2));
+/// ```
+pub fn perft_divide(board: &Board, depth: u8) -> Vec<(Move, u64)> {
+    let mut divide = Vec::new();
+    if depth == 0 {
+        return divide;
+    }
+    board.generate_moves(|moves| {
+        for mv in moves {
+            divide.push((mv, perft(&board.after_unchecked(mv), depth - 1)));
+        }
+        false
+    });
+    divide
+}
+
+/// Count the sequences of legal moves of length `depth` from `board`, using all
+/// threads of the rayon thread pool.
+///
+/// The moves of `board` are split across the threads with [`Board::par_map_moves`],
+/// and each thread runs [`perft`] on its moves. This gives the same counts as
+/// [`perft`].
+///
+/// This requires the `rayon` feature.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// assert_eq!(perft::perft_parallel(&board, 3), 25_470);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn perft_parallel(board: &Board, depth: u8) -> u64 {
+    if depth <= 1 {
+        return perft(board, depth);
+    }
+    board
+        .par_map_moves(|_, child| perft(child, depth - 1))
+        .into_iter()
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    hash: u64,
+    depth: u8,
+    nodes: u64,
+}
+
+/// Count the sequences of legal moves of length `depth` from `board`, with a
+/// transposition table.
+///
+/// The counts of positions that were already seen at the same depth are taken from a
+/// table of `entries` entries (rounded down to a power of two, and at least one), which
+/// is keyed by [`Board::hash`]. This gives the same counts as [`perft`], but is much
+/// faster at larger depths, where many move sequences transpose to the same positions.
+/// Each entry takes 24 bytes.
+///
+/// Two positions with the same hash would share a count, so the result could in
+/// principle be wrong. With 64-bit hashes, this is very unlikely.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// assert_eq!(perft::perft_hashed(&board, 4, 1 << 16), 719_731);
+/// ```
+pub fn perft_hashed(board: &Board, depth: u8, entries: usize) -> u64 {
+    let size = if entries == 0 {
+        1
+    } else {
+        1 << entries.ilog2()
+    };
+    let mut table = vec![Entry::default(); size];
+    perft_with_table(board, depth, &mut table)
+}
+
+fn perft_with_table(board: &Board, depth: u8, table: &mut [Entry]) -> u64 {
+    if depth <= 1 {
+        return perft(board, depth);
+    }
+    let hash = board.hash();
+    let index = hash as usize & (table.len() - 1);
+    let entry = table[index];
+    if entry.hash == hash && entry.depth == depth {
+        return entry.nodes;
+    }
+
+    let mut nodes = 0;
+    board.generate_moves(|moves| {
+        for mv in moves {
+            nodes += perft_with_table(&board.after_unchecked(mv), depth - 1, table);
+        }
+        false
+    });
+    table[index] = Entry { hash, depth, nodes };
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (SFEN, counts for depths 1, 2, 3, ...)
+    // The other positions are those of the perft benchmark.
+    const POSITIONS: &[(&str, &[u64])] = &[
+        (SFEN_STARTPOS, &[30, 900, 25_470, 719_731]),
+        (
+            "ln1g5/1r4k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b b BG2SLPnp 61",
+            &[228, 16_433],
+        ),
+        (
+            "ln1gk1snl/1r5b1/p1ppppgpp/1s4p2/1p7/P1P3R2/1P1PPPP1P/1BG3S2/LNS1KG1NL b P 1",
+            &[42, 1_334, 56_183],
+        ),
+    ];
+
+    #[test]
+    fn reference_counts() {
+        for &(sfen, counts) in POSITIONS {
+            let board = Board::from_sfen(sfen).unwrap();
+            for (depth, &count) in (1..).zip(counts) {
+                assert_eq!(
+                    perft_hashed(&board, depth, 1 << 16),
+                    count,
+                    "{sfen} depth {depth}"
+                );
+                // the larger counts take too long without the table in debug builds
+                if count < 100_000 {
+                    assert_eq!(perft(&board, depth), count, "{sfen} depth {depth}");
+                    let divide = perft_divide(&board, depth);
+                    assert_eq!(divide.iter().map(|&(_, nodes)| nodes).sum::<u64>(), count);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_counts() {
+        for &(sfen, counts) in POSITIONS {
+            let board = Board::from_sfen(sfen).unwrap();
+            for (depth, &count) in (0..).zip([1].iter().chain(counts)) {
+                if count < 100_000 {
+                    assert_eq!(perft_parallel(&board, depth), count, "{sfen} depth {depth}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tiny_tables() {
+        let board = Board::startpos();
+        for entries in [0, 1, 3] {
+            assert_eq!(perft_hashed(&board, 3, entries), 25_470);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Move indices for the policy output of neural networks
+//!
+//! Shogi engines with a neural network usually predict a probability for every move
+//! from a fixed policy vector. This module maps moves to indices in such a vector and
+//! back, using the common scheme of 27 move labels on each of the 81 target squares
+//! (as used by dlshogi and similar engines):
+//!
+//! `index = label * 81 + to`
+//!
+//! The move is seen from the side to move: for White, the board is rotated by 180
+//! degrees (see [`Square::relative_to`]), so that both sides move "up". `to` is the
+//! index of the rotated target square. The labels are:
+//!
+//! | Label   | Move                                                          |
+//! |---------|---------------------------------------------------------------|
+//! | 0 - 9   | Board moves in the directions of [`DIRECTIONS`], no promotion |
+//! | 10 - 19 | The same directions, with promotion                           |
+//! | 20 - 26 | Drops of the pieces in hand, in the order of [`Piece::ALL`]   |
+//!
+//! A direction together with the target square determines the from-square: it is the
+//! first occupied square when walking back from the target square. So every legal move
+//! has its own index, and [`index_to_move`] can recover the move from a position.
+use crate::*;
+
+/// The number of move labels.
+pub const NUM_LABELS: usize = 27;
+
+/// The size of the policy vector.
+pub const POLICY_SIZE: usize = NUM_LABELS * Square::NUM;
+
+/// The directions of board moves, seen from Black, in label order.
+///
+/// Each direction is a `(file, rank)` step, with the same signs as [`Square::offset`]:
+/// a positive file step goes to a higher file (to the left in a diagram) and a negative
+/// rank step goes up. These are up, up-left, up-right, left, right, down, down-left,
+/// down-right and the left and right Knight jumps.
+pub const DIRECTIONS: [(i8, i8); 10] = [
+    (0, -1),
+    (1, -1),
+    (-1, -1),
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (1, 1),
+    (-1, 1),
+    (1, -2),
+    (-1, -2),
+];
+
+// The first drop label.
+const DROP_LABEL: usize = 2 * DIRECTIONS.len();
+
+// Get the label of a board move from `from` to `to`, seen from Black.
+fn direction_label(from: Square, to: Square) -> usize {
+    let df = to.file() as i8 - from.file() as i8;
+    let dr = to.rank() as i8 - from.rank() as i8;
+    let step = if (df, dr) == (1, -2) || (df, dr) == (-1, -2) {
+        (df, dr)
+    } else {
+        assert!(
+            df == 0 || dr == 0 || df.abs() == dr.abs(),
+            "Not a move along a line or a Knight jump: {from}{to}"
+        );
+        (df.signum(), dr.signum())
+    };
+    DIRECTIONS.iter().position(|&d| d == step).unwrap()
+}
+
+/// Get the policy index of a move played by `color`.
+///
+/// See the [module documentation](self) for the scheme.
+///
+/// # Panics
+/// This panics if a board move does not go along a line or make a Knight jump, and if
+/// a drop drops a King or a promoted piece. Legal moves never do.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::policy::*;
+/// // 7g7f moves up to 7f
+/// let index = move_to_index("7g7f".parse().unwrap(), Color::Black);
+/// assert_eq!(index, Square::F7 as usize);
+///
+/// // 3c3d is the same move for White, so it has the same label
+/// let index = move_to_index("3c3d".parse().unwrap(), Color::White);
+/// assert_eq!(index, Square::F7 as usize);
+///
+/// // A Bishop drop
+/// let index = move_to_index("B*5e".parse().unwrap(), Color::Black);
+/// assert_eq!(index, (20 + Piece::Bishop as usize) * 81 + Square::E5 as usize);
+/// ```
+pub fn move_to_index(mv: Move, color: Color) -> usize {
+    let (label, to) = match mv {
+        Move::BoardMove {
+            from,
+            to,
+            promotion,
+        } => {
+            let from = from.relative_to(color);
+            let to = to.relative_to(color);
+            let label = direction_label(from, to);
+            let label = if promotion {
+                label + DIRECTIONS.len()
+            } else {
+                label
+            };
+            (label, to)
+        }
+        Move::Drop { piece, to } => {
+            assert!(
+                (piece as usize) < Piece::HAND_NUM,
+                "{piece:?} can't be dropped"
+            );
+            (DROP_LABEL + piece as usize, to.relative_to(color))
+        }
+    };
+    label * Square::NUM + to as usize
+}
+
+/// Get the legal move with the given policy index, for the side to move.
+///
+/// Returns None if the index is out of range or if there is no legal move with
+/// this index in the position.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::policy::*;
+/// let board = Board::startpos();
+/// let mv = "2g2f".parse().unwrap();
+/// let index = move_to_index(mv, Color::Black);
+/// assert_eq!(index_to_move(&board, index), Some(mv));
+///
+/// // No piece can move up to 5e
+/// assert_eq!(index_to_move(&board, Square::E5 as usize), None);
+/// assert_eq!(index_to_move(&board, POLICY_SIZE), None);
+/// ```
+pub fn index_to_move(board: &Board, index: usize) -> Option<Move> {
+    if index >= POLICY_SIZE {
+        return None;
+    }
+    let color = board.side_to_move();
+    let label = index / Square::NUM;
+    let to = Square::index_const(index % Square::NUM).relative_to(color);
+
+    let mv = if label >= DROP_LABEL {
+        Move::Drop {
+            piece: Piece::index_const(label - DROP_LABEL),
+            to,
+        }
+    } else {
+        let promotion = label >= DIRECTIONS.len();
+        let (df, dr) = DIRECTIONS[label % DIRECTIONS.len()];
+        // walk back from `to`, in board coordinates
+        let (df, dr) = match color {
+            Color::Black => (-df, -dr),
+            Color::White => (df, dr),
+        };
+        let knight = dr.abs() == 2;
+        let occupied = board.occupied();
+        let mut from = to.try_offset(df, dr)?;
+        while !knight && !occupied.has(from) {
+            from = from.try_offset(df, dr)?;
+        }
+        Move::BoardMove {
+            from,
+            to,
+            promotion,
+        }
+    };
+    board.is_legal(mv).then_some(mv)
+}
+
+/// Get the policy indices of generated moves.
+///
+/// This converts the moves passed to a listener of [`Board::generate_moves`] in bulk.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::policy::*;
+/// let board = Board::startpos();
+/// let mut indices = Vec::new();
+/// board.generate_moves(|moves| {
+///     indices.extend(piece_moves_indices(moves));
+///     false
+/// });
+/// assert_eq!(indices.len(), 30);
+/// ```
+pub fn piece_moves_indices(moves: PieceMoves) -> impl Iterator<Item = usize> {
+    let (PieceMoves::BoardMoves { color, .. } | PieceMoves::Drops { color, .. }) = moves;
+    moves.into_iter().map(move |mv| move_to_index(mv, color))
+}
+
+/// Mark the policy indices of all legal moves in `mask`.
+///
+/// The first [`POLICY_SIZE`] entries of `mask` are set to true for legal moves and
+/// to false for all other indices. This can be used to mask the policy output of a
+/// network before taking the softmax.
+///
+/// # Panics
+/// This panics if `mask` is shorter than [`POLICY_SIZE`].
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::policy::*;
+/// let board = Board::startpos();
+/// let mut mask = [false; POLICY_SIZE];
+/// fill_legal_mask(&board, &mut mask);
+/// assert_eq!(mask.iter().filter(|&&legal| legal).count(), 30);
+/// assert!(mask[move_to_index("7g7f".parse().unwrap(), Color::Black)]);
+/// ```
+pub fn fill_legal_mask(board: &Board, mask: &mut [bool]) {
+    let mask = &mut mask[..POLICY_SIZE];
+    mask.fill(false);
+    board.generate_moves(|moves| {
+        for index in piece_moves_indices(moves) {
+            mask[index] = true;
+        }
+        false
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_are_unique_and_reversible() {
+        for sfen in [
+            SFEN_STARTPOS,
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62",
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+            "8l/1+R5pk/6sp1/5pP1p/9/6P2/PP1pP+bN1P/5SGK1/L6NL b RSNL2P2g 1",
+            "R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1",
+        ] {
+            let board: Board = sfen.parse().unwrap();
+            let color = board.side_to_move();
+            let moves = board.legal_moves();
+
+            let mut mask = [false; POLICY_SIZE];
+            fill_legal_mask(&board, &mut mask);
+            assert_eq!(mask.iter().filter(|&&legal| legal).count(), moves.len());
+
+            for &mv in moves.iter() {
+                let index = move_to_index(mv, color);
+                assert!(mask[index]);
+                assert_eq!(index_to_move(&board, index), Some(mv), "{sfen} {mv}");
+            }
+            for (index, &legal) in mask.iter().enumerate() {
+                assert_eq!(index_to_move(&board, index).is_some(), legal);
+            }
+        }
+    }
+
+    #[test]
+    fn both_colors_share_labels() {
+        // a move of Black and the same move rotated for White get the same index
+        for mv in ["7g7f", "2h6h", "8h2b+", "3i4h", "1c1b+", "B*5e", "N*1c"] {
+            let mv: Move = mv.parse().unwrap();
+            let rotated = match mv {
+                Move::BoardMove {
+                    from,
+                    to,
+                    promotion,
+                } => Move::BoardMove {
+                    from: from.flip(),
+                    to: to.flip(),
+                    promotion,
+                },
+                Move::Drop { piece, to } => Move::Drop {
+                    piece,
+                    to: to.flip(),
+                },
+            };
+            assert_eq!(
+                move_to_index(mv, Color::Black),
+                move_to_index(rotated, Color::White)
+            );
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! The `haitaka` prelude
+//!
+//! This module re-exports the types that are needed in almost every program using
+//! this crate. Importing the prelude avoids the blanket `use haitaka::*;` which also
+//! brings all the free attack and slider functions into scope.
+//!
+//! # Examples
+//! ```
+//! use haitaka::prelude::*;
+//!
+//! let board = Board::startpos();
+//! let mut num_moves = 0;
+//! board.generate_moves(|moves: PieceMoves| {
+//!     num_moves += moves.into_iter().len();
+//!     false
+//! });
+//! assert_eq!(num_moves, 30);
+//! assert_eq!(board.status(), GameStatus::Ongoing);
+//! assert_eq!(board.piece_on(Square::I5), Some(Piece::King));
+//! assert_eq!(board.color_on(Square::I5), Some(Color::Black));
+//! ```
+
+pub use crate::{
+    BitBoard, Board, Color, ColoredPiece, File, GameStatus, Move, Piece, PieceMoves, Rank, Square,
+    bitboard,
+};
@@ -0,0 +1,143 @@
+//! Game records
+//!
+//! A [`GameRecord`] holds a game as read from or written to a record format such as KIF
+//! (see the [`kif`](crate::kif) module): the start position, the moves of the main line,
+//! and the metadata that the formats have in common.
+use core::time::Duration;
+
+use crate::*;
+
+/// How a game ended.
+///
+/// These are the terminations that game records commonly mark after the last move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GameEnd {
+    /// The side to move resigned.
+    Resign,
+    /// The game was interrupted.
+    Abort,
+    /// The game ended in Sennichite (repetition).
+    Repetition,
+    /// The game ended in Jishogi (impasse).
+    Impasse,
+    /// The side to move lost on time.
+    Timeout,
+    /// The side to move was checkmated.
+    Mate,
+    /// The side to move has no mate (for Tsume Shogi records).
+    NoMate,
+    /// The side to move won by an illegal move of the opponent.
+    IllegalMoveWin,
+    /// The side to move lost by an illegal move.
+    IllegalMoveLoss,
+    /// The side to move declared a win by entering King (nyuugyoku).
+    EnteringKingWin,
+}
+
+/// A recorded game.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::record::*;
+/// let mut record = GameRecord::new(Board::startpos());
+/// record.headers.push(("先手".to_string(), "Alice".to_string()));
+/// record.push("7g7f".parse().unwrap());
+/// record.push("3c3d".parse().unwrap());
+/// record.end = Some(GameEnd::Abort);
+///
+/// assert_eq!(record.header("先手"), Some("Alice"));
+/// assert_eq!(
+///     record.final_board().to_string(),
+///     "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    /// The header fields (such as the names of the players), in the order of the record.
+    pub headers: Vec<(String, String)>,
+    /// The start position.
+    pub start: Board,
+    /// The moves of the main line.
+    pub moves: Vec<Move>,
+    /// The time used for each move, if recorded. This has the same length as `moves`.
+    pub times: Vec<Option<Duration>>,
+    /// The comments, each with the number of moves played before it. Comments on the
+    /// start position have 0.
+    pub comments: Vec<(usize, String)>,
+    /// How the game ended, if recorded.
+    pub end: Option<GameEnd>,
+}
+
+impl GameRecord {
+    /// Create a record without moves, starting from `start`.
+    pub fn new(start: Board) -> Self {
+        Self {
+            headers: Vec::new(),
+            start,
+            moves: Vec::new(),
+            times: Vec::new(),
+            comments: Vec::new(),
+            end: None,
+        }
+    }
+
+    /// Add a move without a recorded time.
+    ///
+    /// The move is not checked.
+    pub fn push(&mut self, mv: Move) {
+        self.moves.push(mv);
+        self.times.push(None);
+    }
+
+    /// Get the value of the first header field with the given key.
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get the position after all moves.
+    ///
+    /// # Panics
+    /// This may panic if the moves are not legal.
+    pub fn final_board(&self) -> Board {
+        let mut board = self.start.clone();
+        for &mv in &self.moves {
+            board.play_unchecked(mv);
+        }
+        board
+    }
+
+    /// Get the winner from how the game ended, or None for a draw, an interrupted game,
+    /// or a record without an end.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka::*;
+    /// use haitaka::record::*;
+    /// let mut record = GameRecord::new(Board::startpos());
+    /// record.push("7g7f".parse().unwrap());
+    /// assert_eq!(record.winner(), None);
+    /// // White resigns
+    /// record.end = Some(GameEnd::Resign);
+    /// assert_eq!(record.winner(), Some(Color::Black));
+    /// record.end = Some(GameEnd::Repetition);
+    /// assert_eq!(record.winner(), None);
+    /// ```
+    pub fn winner(&self) -> Option<Color> {
+        let mut side_to_move = self.start.side_to_move();
+        if !self.moves.len().is_multiple_of(2) {
+            side_to_move = !side_to_move;
+        }
+        match self.end? {
+            GameEnd::Resign | GameEnd::Timeout | GameEnd::Mate | GameEnd::IllegalMoveLoss => {
+                Some(!side_to_move)
+            }
+            GameEnd::IllegalMoveWin | GameEnd::EnteringKingWin => Some(side_to_move),
+            GameEnd::Abort | GameEnd::Repetition | GameEnd::Impasse | GameEnd::NoMate => None,
+        }
+    }
+}
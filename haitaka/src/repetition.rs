@@ -0,0 +1,333 @@
+//! Repetition (Sennichite) rules
+//!
+//! A game ends in Sennichite (千日手) when the same position, with the same side to move
+//! and the same pieces in hand, occurs for the fourth time. Normally this is a draw.
+//! However, if one side gave check with every one of its moves since the first occurrence
+//! of the repeated position (perpetual check, 連続王手の千日手), then that side loses.
+//!
+//! The [`Board`](crate::Board) does not keep any history, so applying these rules is
+//! left to the caller. The [`classify`] function is a reference implementation that works
+//! on a history of position hashes (see [`Board::hash`](crate::Board::hash)).
+//! [`GameHistory`] wraps a board, records the history as moves are played, and applies
+//! the rules with [`classify`].
+use crate::*;
+
+/// The result of checking a game history for repetitions.
+///
+/// The outcome is expressed in terms of the side that made the last move in the history
+/// and the side that is to move next, since the history itself does not record colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RepetitionOutcome {
+    /// The last position has not (yet) occurred four times.
+    NoRepetition,
+    /// Sennichite without perpetual check: the game is a draw.
+    Draw,
+    /// Perpetual check by the side that made the last move: they lose.
+    LastMoverLoses,
+    /// Perpetual check by the side to move: they lose.
+    SideToMoveLoses,
+}
+
+/// Classify the last position of a game history under the repetition rules.
+///
+/// Each entry of `history` holds the [`Board::hash`](crate::Board::hash) of a position
+/// and whether the move leading to that position gave check. Consecutive entries must be
+/// consecutive positions of one game, so that the entries alternate between both sides.
+/// The first entry may be the start position (its check flag is then ignored).
+///
+/// Only the last position is classified, so this should be called after every move.
+/// The moves counted for perpetual check are all moves after the first of the four
+/// occurrences, up to the last move. If both sides gave check with every move (which
+/// requires a cycle of counter-checks), the result is [`RepetitionOutcome::Draw`].
+///
+/// # Examples
+/// ```
+/// # use haitaka::repetition::*;
+/// // Black checks (a -> b), White escapes (b -> c), Black checks (c -> d),
+/// // White returns (d -> a) and so on.
+/// let (a, b, c, d) = (1, 2, 3, 4);
+/// let mut history = vec![(a, false)];
+/// for _ in 0..3 {
+///     history.extend([(b, true), (c, false), (d, true), (a, false)]);
+/// }
+/// // White made the last move, Black gave perpetual check
+/// assert_eq!(classify(&history), RepetitionOutcome::SideToMoveLoses);
+///
+/// history.pop();
+/// assert_eq!(classify(&history), RepetitionOutcome::NoRepetition);
+///
+/// // Without the checks, it's a draw
+/// let mut history = vec![(a, false)];
+/// for _ in 0..3 {
+///     history.extend([(b, false), (c, false), (d, false), (a, false)]);
+/// }
+/// assert_eq!(classify(&history), RepetitionOutcome::Draw);
+/// ```
+pub fn classify(history: &[(u64, bool)]) -> RepetitionOutcome {
+    let Some(&(last, _)) = history.last() else {
+        return RepetitionOutcome::NoRepetition;
+    };
+
+    // find the first of the (last) four occurrences of the last position
+    let mut occurrences = 0;
+    let mut first = history.len();
+    for (i, &(hash, _)) in history.iter().enumerate().rev() {
+        if hash == last {
+            occurrences += 1;
+            first = i;
+            if occurrences == 4 {
+                break;
+            }
+        }
+    }
+    if occurrences < 4 {
+        return RepetitionOutcome::NoRepetition;
+    }
+
+    // moves by the last mover have an even distance to the last entry
+    let cycle = &history[first + 1..];
+    let all_checks = |parity: usize| {
+        cycle
+            .iter()
+            .rev()
+            .skip(parity)
+            .step_by(2)
+            .all(|&(_, gave_check)| gave_check)
+    };
+    match (all_checks(0), all_checks(1)) {
+        (true, false) => RepetitionOutcome::LastMoverLoses,
+        (false, true) => RepetitionOutcome::SideToMoveLoses,
+        _ => RepetitionOutcome::Draw,
+    }
+}
+
+/// A board that records the positions of the game.
+///
+/// Moves are played through the history, which keeps the hash of every position and
+/// whether the move leading to it gave check. This is what [`classify`] needs to apply
+/// the Sennichite rules. Moves can also be taken back with [`GameHistory::undo`].
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::repetition::*;
+/// let mut game = GameHistory::new(Board::startpos());
+/// for _ in 0..3 {
+///     for mv in ["2h3h", "8b7b", "3h2h", "7b8b"] {
+///         assert!(!game.is_sennichite());
+///         game.play(mv.parse().unwrap());
+///     }
+/// }
+/// // The start position occurs for the fourth time
+/// assert_eq!(game.occurrences(), 4);
+/// assert!(game.is_sennichite());
+/// assert!(!game.is_perpetual_check_loss());
+/// assert_eq!(game.repetition(), RepetitionOutcome::Draw);
+///
+/// game.undo();
+/// assert_eq!(game.occurrences(), 3);
+/// assert!(!game.is_sennichite());
+/// ```
+#[derive(Debug, Clone)]
+pub struct GameHistory {
+    board: Board,
+    history: Vec<(u64, bool)>,
+    undo: Vec<UndoState>,
+}
+
+impl GameHistory {
+    /// Start a history from the given position.
+    pub fn new(board: Board) -> Self {
+        let history = vec![(board.hash(), !board.checkers().is_empty())];
+        Self {
+            board,
+            history,
+            undo: Vec::new(),
+        }
+    }
+
+    /// The current position.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The history in the form used by [`classify`]: the hash of every position,
+    /// starting with the start position, and whether the move leading to it gave check.
+    pub fn entries(&self) -> &[(u64, bool)] {
+        &self.history
+    }
+
+    /// The moves played since the start position.
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.undo.iter().map(|undo| undo.mv())
+    }
+
+    /// The last move played, or None if no moves were played.
+    pub fn last_move(&self) -> Option<Move> {
+        self.undo.last().map(|undo| undo.mv())
+    }
+
+    /// The number of moves played since the start position.
+    pub fn len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Whether no moves have been played yet.
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty()
+    }
+
+    /// Play a move and record the new position.
+    ///
+    /// # Panics
+    /// This panics if the move is illegal.
+    pub fn play(&mut self, mv: Move) {
+        assert!(self.try_play(mv).is_ok(), "Illegal move {}!", mv);
+    }
+
+    /// Non-panicking version of [`GameHistory::play`].
+    ///
+    /// # Errors
+    /// Errors with [`IllegalMoveError`] if the move was illegal. The history is not
+    /// changed then.
+    pub fn try_play(&mut self, mv: Move) -> Result<(), IllegalMoveError> {
+        if !self.board.is_legal(mv) {
+            return Err(IllegalMoveError::new(mv));
+        }
+        self.play_unchecked(mv);
+        Ok(())
+    }
+
+    /// Play a move without checking whether it is legal.
+    ///
+    /// See [`Board::play_unchecked`] for what happens with illegal moves.
+    pub fn play_unchecked(&mut self, mv: Move) {
+        self.undo.push(self.board.play_unchecked_with_undo(mv));
+        self.history
+            .push((self.board.hash(), !self.board.checkers().is_empty()));
+    }
+
+    /// Take back the last move, returning it, or None if no moves were played.
+    pub fn undo(&mut self) -> Option<Move> {
+        let undo = self.undo.pop()?;
+        self.history.pop();
+        self.board.unplay(undo);
+        Some(undo.mv())
+    }
+
+    /// How often the current position occurred, counting the current one.
+    ///
+    /// Positions are compared by [`Board::hash`], so the side to move and the pieces in
+    /// hand have to be the same as well.
+    pub fn occurrences(&self) -> usize {
+        self.occurrences_of(self.board.hash())
+    }
+
+    /// How often the position with the given [`Board::hash`] occurred.
+    pub fn occurrences_of(&self, hash: u64) -> usize {
+        self.history.iter().filter(|&&(h, _)| h == hash).count()
+    }
+
+    /// Classify the current position under the repetition rules, with [`classify`].
+    pub fn repetition(&self) -> RepetitionOutcome {
+        classify(&self.history)
+    }
+
+    /// Whether the game ended in Sennichite: the current position occurred for the
+    /// fourth time. This includes repetitions lost by perpetual check.
+    pub fn is_sennichite(&self) -> bool {
+        self.repetition() != RepetitionOutcome::NoRepetition
+    }
+
+    /// Whether the game ended in Sennichite with perpetual check, so that the checking
+    /// side loses. See [`GameHistory::repetition`] for which side that is.
+    pub fn is_perpetual_check_loss(&self) -> bool {
+        matches!(
+            self.repetition(),
+            RepetitionOutcome::LastMoverLoses | RepetitionOutcome::SideToMoveLoses
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Play the moves repeatedly and record the history
+    fn play_cycle(sfen: &str, moves: &[&str], cycles: usize) -> Vec<(u64, bool)> {
+        let mut board: Board = sfen.parse().unwrap();
+        let mut history = vec![(board.hash(), false)];
+        for _ in 0..cycles {
+            for mv in moves {
+                board.play(mv.parse().unwrap());
+                history.push((board.hash(), !board.checkers().is_empty()));
+            }
+        }
+        history
+    }
+
+    #[test]
+    fn perpetual_check_loses() {
+        // Black's Rook keeps checking the White King from the first and second rank
+        let moves = ["9b9a", "1a2b", "9a9b", "2b1a"];
+        let history = play_cycle("8k/R8/9/9/9/9/9/9/4K4 b - 1", &moves, 3);
+        assert_eq!(classify(&history), RepetitionOutcome::SideToMoveLoses);
+        assert_eq!(
+            classify(&history[..history.len() - 1]),
+            RepetitionOutcome::NoRepetition
+        );
+
+        // The same cycle, but starting with White in check
+        let moves = ["1a2b", "9a9b", "2b1a", "9b9a"];
+        let history = play_cycle("R7k/9/9/9/9/9/9/9/4K4 w - 1", &moves, 3);
+        assert_eq!(classify(&history), RepetitionOutcome::LastMoverLoses);
+    }
+
+    #[test]
+    fn repetition_without_checks_is_a_draw() {
+        let moves = ["2h3h", "8b7b", "3h2h", "7b8b"];
+        let history = play_cycle(SFEN_STARTPOS, &moves, 3);
+        assert_eq!(classify(&history), RepetitionOutcome::Draw);
+        assert_eq!(
+            classify(&history[..history.len() - 1]),
+            RepetitionOutcome::NoRepetition
+        );
+    }
+
+    #[test]
+    fn checks_before_the_cycle_do_not_count() {
+        // Only the last four occurrences count: the quiet moves of the first cycle
+        // don't save the checking side
+        let (a, b, c, d) = (1, 2, 3, 4);
+        let mut history = vec![(a, true), (b, false), (c, false), (d, false), (a, false)];
+        history.extend([(b, true), (c, false), (d, true), (a, false)].repeat(3));
+        assert_eq!(classify(&history), RepetitionOutcome::SideToMoveLoses);
+        let mut history = vec![(a, false)];
+        history.extend([(b, true), (c, false), (d, false), (a, false)].repeat(3));
+        assert_eq!(classify(&history), RepetitionOutcome::Draw);
+    }
+
+    #[test]
+    fn game_history_matches_classify() {
+        let moves = ["9b9a", "1a2b", "9a9b", "2b1a"];
+        let history = play_cycle("8k/R8/9/9/9/9/9/9/4K4 b - 1", &moves, 3);
+        let mut game = GameHistory::new("8k/R8/9/9/9/9/9/9/4K4 b - 1".parse().unwrap());
+        for mv in moves.repeat(3) {
+            assert!(!game.is_perpetual_check_loss());
+            game.play(mv.parse().unwrap());
+        }
+        assert_eq!(game.entries(), history);
+        assert!(game.is_perpetual_check_loss());
+        assert_eq!(game.repetition(), RepetitionOutcome::SideToMoveLoses);
+        assert_eq!(game.len(), 12);
+
+        // taking back all moves restores the start position
+        while game.undo().is_some() {}
+        assert!(game.is_empty());
+        assert_eq!(game.entries(), &history[..1]);
+        assert!(game.try_play("9b9c".parse().unwrap()).is_ok());
+        assert!(game.try_play("9c9d".parse().unwrap()).is_err());
+        assert_eq!(game.len(), 1);
+    }
+}
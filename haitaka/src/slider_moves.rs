@@ -20,12 +20,87 @@
 //! hand, it doesn't need to allocate a huge amount of extra memory for the moves tables (see
 //! `SLIDING_MOVES_TABLE_SIZE` in `haitaka_types/src/sliders/magic.rs`).
 //!
+//! The magic bitboard tables are normally generated by the build script. With the `lazy-tables`
+//! feature flag, the build script skips this and the tables are built on first use instead
+//! (which takes about 10ms in release builds). This is meant for platforms where build scripts
+//! can't run or where compile times matter. [`get_rook_moves`] and [`get_bishop_moves`] stay
+//! `const` with this feature, so that it does not break crates which call them in const
+//! contexts, but they compute the moves ray by ray. Use [`lazy_rook_moves`] and
+//! [`lazy_bishop_moves`] for the lookup in the tables. Without the feature, they are the same
+//! as `get_rook_moves` and `get_bishop_moves`.
+//!
 
 use crate::*;
 
-#[cfg(not(feature = "qugiy"))]
+// The Rook and Bishop move tables, indexed by `get_rook_moves_index` and
+// `get_bishop_moves_index`. Both tables are kept in one static, aligned to the
+// cache line size, so that no table entry straddles two cache lines.
+// See `tables::memory_footprint` for the size.
+#[cfg(not(any(feature = "qugiy", feature = "lazy-tables")))]
+#[repr(C, align(64))]
+pub(crate) struct SlidingMoves {
+    pub(crate) rook: [u128; ROOK_TABLE_SIZE],
+    pub(crate) bishop: [u128; BISHOP_TABLE_SIZE],
+}
+
+#[cfg(not(any(feature = "qugiy", feature = "lazy-tables")))]
 include!(concat!(env!("OUT_DIR"), "/sliding_moves_table.rs"));
 
+// With the `lazy-tables` feature, the tables are not generated by the build script,
+// but built on first use. This takes about 10ms in release builds. `OnceLock` makes
+// sure the tables are built only once, even if several threads race for them.
+#[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+pub(crate) struct SlidingMoves {
+    pub(crate) rook: Box<[u128]>,
+    pub(crate) bishop: Box<[u128]>,
+}
+
+#[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+static SLIDING_MOVES: std::sync::OnceLock<SlidingMoves> = std::sync::OnceLock::new();
+
+#[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+pub(crate) fn sliding_moves() -> &'static SlidingMoves {
+    fn build(
+        size: usize,
+        relevant_blockers: impl Fn(Square) -> BitBoard,
+        table_index: impl Fn(Square, BitBoard) -> usize,
+        slider_moves: impl Fn(Square, BitBoard) -> BitBoard,
+    ) -> Box<[u128]> {
+        let mut table = vec![0; size];
+        for square in Square::ALL {
+            for blockers in relevant_blockers(square).iter_subsets() {
+                table[table_index(square, blockers)] = slider_moves(square, blockers).0;
+            }
+        }
+        table.into_boxed_slice()
+    }
+
+    SLIDING_MOVES.get_or_init(|| SlidingMoves {
+        rook: build(
+            ROOK_TABLE_SIZE,
+            get_rook_relevant_blockers,
+            get_rook_moves_index,
+            get_rook_moves_slow,
+        ),
+        bishop: build(
+            BISHOP_TABLE_SIZE,
+            get_bishop_relevant_blockers,
+            get_bishop_moves_index,
+            get_bishop_moves_slow,
+        ),
+    })
+}
+
+// The Rook and Bishop move tables as slices, for `tables::warmup`.
+#[cfg(not(feature = "qugiy"))]
+pub(crate) fn sliding_moves_tables() -> [&'static [u128]; 2] {
+    #[cfg(not(feature = "lazy-tables"))]
+    let tables = &SLIDING_MOVES;
+    #[cfg(feature = "lazy-tables")]
+    let tables = sliding_moves();
+    [&tables.rook[..], &tables.bishop[..]]
+}
+
 /// Get rook moves.
 ///
 /// # Examples
@@ -90,9 +165,14 @@ pub const fn get_rook_moves(_color: Color, square: Square, occ: BitBoard) -> Bit
         let bb2 = get_rook_file_moves(square, occ);
         bb1.bitor(bb2)
     }
-    #[cfg(not(feature = "qugiy"))]
+    #[cfg(not(any(feature = "qugiy", feature = "lazy-tables")))]
+    {
+        BitBoard(SLIDING_MOVES.rook[get_rook_moves_index(square, occ)])
+    }
+    #[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
     {
-        BitBoard(ROOK_MOVES[get_rook_moves_index(square, occ)])
+        // The tables can't be built in const fns
+        get_rook_moves_slow(square, occ)
     }
 }
 
@@ -185,9 +265,58 @@ pub const fn get_bishop_moves(_color: Color, square: Square, occ: BitBoard) -> B
 
         BitBoard(nw | sw | ne_rev.reverse_bits() | se_rev.reverse_bits())
     }
-    #[cfg(not(feature = "qugiy"))]
+    #[cfg(not(any(feature = "qugiy", feature = "lazy-tables")))]
+    {
+        BitBoard(SLIDING_MOVES.bishop[get_bishop_moves_index(square, occ)])
+    }
+    #[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+    {
+        // The tables can't be built in const fns
+        get_bishop_moves_slow(square, occ)
+    }
+}
+
+/// Get rook moves, from the tables built on first use with the `lazy-tables` feature.
+///
+/// Without the feature, this is the same as [`get_rook_moves`]. Unlike `get_rook_moves`,
+/// this is not `const`.
+///
+/// # Examples
+/// ```
+/// use haitaka::*;
+/// let occ = Board::startpos().occupied();
+/// assert_eq!(
+///     lazy_rook_moves(Color::Black, Square::H2, occ),
+///     get_rook_moves(Color::Black, Square::H2, occ)
+/// );
+/// ```
+#[inline(always)]
+pub fn lazy_rook_moves(color: Color, square: Square, occ: BitBoard) -> BitBoard {
+    #[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+    {
+        let _ = color;
+        BitBoard(sliding_moves().rook[get_rook_moves_index(square, occ)])
+    }
+    #[cfg(not(all(feature = "lazy-tables", not(feature = "qugiy"))))]
+    {
+        get_rook_moves(color, square, occ)
+    }
+}
+
+/// Get bishop moves, from the tables built on first use with the `lazy-tables` feature.
+///
+/// Without the feature, this is the same as [`get_bishop_moves`]. Unlike
+/// `get_bishop_moves`, this is not `const`.
+#[inline(always)]
+pub fn lazy_bishop_moves(color: Color, square: Square, occ: BitBoard) -> BitBoard {
+    #[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+    {
+        let _ = color;
+        BitBoard(sliding_moves().bishop[get_bishop_moves_index(square, occ)])
+    }
+    #[cfg(not(all(feature = "lazy-tables", not(feature = "qugiy"))))]
     {
-        BitBoard(BISHOP_MOVES[get_bishop_moves_index(square, occ)])
+        get_bishop_moves(color, square, occ)
     }
 }
 
@@ -312,4 +441,62 @@ mod tests {
         let actual2 = get_bishop_moves(Color::White, Square::G3, occ);
         assert_eq!(actual2, g3_attacks);
     }
+
+    // With every combination of features, the slider moves can be computed at compile time
+    const E5_ROOK: BitBoard = get_rook_moves(Color::Black, Square::E5, BitBoard::EMPTY);
+    const E5_BISHOP: BitBoard = get_bishop_moves(Color::Black, Square::E5, BitBoard::EMPTY);
+
+    #[test]
+    fn lazy_moves_match_the_const_moves() {
+        assert_eq!(E5_ROOK, rook_pseudo_attacks(Square::E5));
+        assert_eq!(E5_BISHOP, bishop_pseudo_attacks(Square::E5));
+        for board in random_positions(0x1a2, 4, 100) {
+            let occ = board.occupied();
+            for square in Square::ALL {
+                for color in Color::ALL {
+                    assert_eq!(
+                        lazy_rook_moves(color, square, occ),
+                        get_rook_moves(color, square, occ)
+                    );
+                    assert_eq!(
+                        lazy_bishop_moves(color, square, occ),
+                        get_bishop_moves(color, square, occ)
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+    #[test]
+    fn lazy_tables_are_built_once() {
+        let occ = Board::startpos().occupied();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    Square::ALL.map(|square| {
+                        (
+                            lazy_rook_moves(Color::Black, square, occ),
+                            lazy_bishop_moves(Color::Black, square, occ),
+                        )
+                    })
+                })
+            })
+            .collect();
+        for thread in threads {
+            let moves = thread.join().unwrap();
+            for square in Square::ALL {
+                assert_eq!(moves[square as usize].0, get_rook_moves_slow(square, occ));
+                assert_eq!(moves[square as usize].1, get_bishop_moves_slow(square, occ));
+            }
+        }
+        assert!(core::ptr::eq(sliding_moves(), sliding_moves()));
+    }
+
+    #[cfg(all(feature = "lazy-tables", not(feature = "qugiy")))]
+    #[test]
+    fn warmup_builds_the_lazy_tables() {
+        crate::tables::warmup();
+        assert!(SLIDING_MOVES.get().is_some());
+    }
 }
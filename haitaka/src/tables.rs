@@ -0,0 +1,350 @@
+//! Attack tables
+//!
+//! This module exports the attack tables of this crate into a flat buffer, for instance
+//! to run move generation experiments on a GPU or to verify the tables with an
+//! independent implementation.
+//!
+//! # Layout
+//!
+//! All tables are written into one `u64` buffer. A [`BitBoard`] takes two words: the low
+//! 64 bits followed by the high 64 bits of the 128-bit value. Squares are indexed by
+//! `square as usize` (see [`Square`]), colors by `color as usize` (White is 0). The
+//! [`TableLayout`] returned by [`export_into`] holds the offsets (in words) of the
+//! following tables:
+//!
+//! - **Step attacks.** For every piece in [`STEP_PIECES`], every color and every square
+//!   the attacks of that piece (as returned by [`pawn_attacks`], [`knight_attacks`] and so on).
+//!   The bitboard for `(piece_index, color, square)` starts at word
+//!   `step_attacks + 2 * ((piece_index * Color::NUM + color) * Square::NUM + square)`.
+//! - **Slider attacks.** One table for the Lance of each color, one for the Rook and one
+//!   for the Bishop. Each table starts with a header of `4 * Square::NUM` words: for every
+//!   square the relevant blocker mask (two words), the offset of the first moves bitboard
+//!   for that square (one word, relative to the start of the buffer) and the number of
+//!   blocker subsets `2^n`, where `n` is the number of bits in the mask (one word).
+//!   The header is followed by the moves. For subset index `i`, the blockers are the bits
+//!   of `i` deposited into the mask, from low to high bits (as with the `pdep` instruction).
+//!   So, given the occupancy `occ`, the moves are found at subset index `pext(occ, mask)`.
+//!   This is the order in which [`BitBoard::iter_subsets`] yields the subsets.
+//!
+//! The slider tables are enumerated directly over all blocker subsets, so they don't
+//! depend on the magic numbers used internally.
+//!
+//! The memory used by the internal tables is reported by [`memory_footprint`], and
+//! [`warmup`] loads them into memory ahead of time.
+use crate::*;
+
+/// The non-sliding pieces in the order of the step attack tables.
+///
+/// The Gold table also covers the promoted Pawn, Lance, Knight and Silver.
+pub const STEP_PIECES: [Piece; 5] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Silver,
+    Piece::Gold,
+    Piece::King,
+];
+
+// Number of u64 words per bitboard and per slider header entry.
+const BITBOARD_WORDS: usize = 2;
+const HEADER_WORDS: usize = 4;
+
+/// The offsets (in `u64` words) of the tables written by [`export_into`].
+///
+/// See the [module documentation](self) for the layout of each table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TableLayout {
+    /// Offset of the step attack tables.
+    pub step_attacks: usize,
+    /// Offsets of the Lance tables, indexed by color.
+    pub lance: [usize; Color::NUM],
+    /// Offset of the Rook table.
+    pub rook: usize,
+    /// Offset of the Bishop table.
+    pub bishop: usize,
+    /// Total number of words.
+    pub len: usize,
+}
+
+impl TableLayout {
+    /// Compute the layout of the exported tables.
+    ///
+    /// This can be used to allocate a buffer for [`export_into`].
+    pub fn new() -> Self {
+        let step_attacks = 0;
+        let mut offset = STEP_PIECES.len() * Color::NUM * Square::NUM * BITBOARD_WORDS;
+        let mut lance = [0; Color::NUM];
+        for color in Color::ALL {
+            lance[color as usize] = offset;
+            offset += slider_table_len(|square| lance_relevant_blockers(color, square));
+        }
+        let rook = offset;
+        offset += slider_table_len(get_rook_relevant_blockers);
+        let bishop = offset;
+        offset += slider_table_len(get_bishop_relevant_blockers);
+
+        Self {
+            step_attacks,
+            lance,
+            rook,
+            bishop,
+            len: offset,
+        }
+    }
+}
+
+impl Default for TableLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The relevant blockers of a Lance: its ray without the last rank.
+fn lance_relevant_blockers(color: Color, square: Square) -> BitBoard {
+    lance_pseudo_attacks(color, square) & !(Rank::A.bitboard() | Rank::I.bitboard())
+}
+
+// Number of words of one slider table.
+fn slider_table_len(mask: impl Fn(Square) -> BitBoard) -> usize {
+    Square::ALL
+        .iter()
+        .fold(Square::NUM * HEADER_WORDS, |len, &square| {
+            len + (1 << mask(square).len()) * BITBOARD_WORDS
+        })
+}
+
+#[inline(always)]
+fn write_bitboard(buffer: &mut [u64], offset: usize, bb: BitBoard) {
+    buffer[offset] = bb.0 as u64;
+    buffer[offset + 1] = (bb.0 >> 64) as u64;
+}
+
+// Write one slider table starting at `start`.
+fn write_slider_table(
+    buffer: &mut [u64],
+    start: usize,
+    mask: impl Fn(Square) -> BitBoard,
+    moves: impl Fn(Square, BitBoard) -> BitBoard,
+) {
+    let mut offset = start + Square::NUM * HEADER_WORDS;
+    for square in Square::ALL {
+        let mask = mask(square);
+        let header = start + square as usize * HEADER_WORDS;
+        write_bitboard(buffer, header, mask);
+        buffer[header + 2] = offset as u64;
+        buffer[header + 3] = 1 << mask.len();
+        for blockers in mask.iter_subsets() {
+            write_bitboard(buffer, offset, moves(square, blockers));
+            offset += BITBOARD_WORDS;
+        }
+    }
+}
+
+/// Export all attack tables into `buffer`.
+///
+/// See the [module documentation](self) for the layout.
+///
+/// # Panics
+/// This panics if `buffer` is shorter than [`TableLayout::len`].
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let layout = tables::TableLayout::new();
+/// let mut buffer = vec![0u64; layout.len];
+/// assert_eq!(tables::export_into(&mut buffer), layout);
+///
+/// // Black Gold attacks on 5e (the Gold is the fourth step piece)
+/// let index = (3 * Color::NUM + Color::Black as usize) * Square::NUM + Square::E5 as usize;
+/// let i = layout.step_attacks + 2 * index;
+/// let bb = BitBoard::new(buffer[i] as u128 | (buffer[i + 1] as u128) << 64);
+/// assert_eq!(bb, gold_attacks(Color::Black, Square::E5));
+///
+/// // The Rook on 5e on an empty board: subset index 0
+/// let header = layout.rook + 4 * Square::E5 as usize;
+/// let i = buffer[header + 2] as usize;
+/// let bb = BitBoard::new(buffer[i] as u128 | (buffer[i + 1] as u128) << 64);
+/// assert_eq!(bb, rook_pseudo_attacks(Square::E5));
+/// ```
+pub fn export_into(buffer: &mut [u64]) -> TableLayout {
+    let layout = TableLayout::new();
+    assert!(
+        buffer.len() >= layout.len,
+        "The buffer needs at least {} words",
+        layout.len
+    );
+
+    for (i, &piece) in STEP_PIECES.iter().enumerate() {
+        for color in Color::ALL {
+            for square in Square::ALL {
+                let attacks = match piece {
+                    Piece::Pawn => pawn_attacks(color, square),
+                    Piece::Knight => knight_attacks(color, square),
+                    Piece::Silver => silver_attacks(color, square),
+                    Piece::Gold => gold_attacks(color, square),
+                    _ => king_attacks(color, square),
+                };
+                let index = (i * Color::NUM + color as usize) * Square::NUM + square as usize;
+                write_bitboard(
+                    buffer,
+                    layout.step_attacks + index * BITBOARD_WORDS,
+                    attacks,
+                );
+            }
+        }
+    }
+
+    for color in Color::ALL {
+        write_slider_table(
+            buffer,
+            layout.lance[color as usize],
+            |square| lance_relevant_blockers(color, square),
+            |square, blockers| get_lance_moves(color, square, blockers),
+        );
+    }
+    write_slider_table(
+        buffer,
+        layout.rook,
+        get_rook_relevant_blockers,
+        |square, blockers| get_rook_moves(Color::Black, square, blockers),
+    );
+    write_slider_table(
+        buffer,
+        layout.bishop,
+        get_bishop_relevant_blockers,
+        |square, blockers| get_bishop_moves(Color::Black, square, blockers),
+    );
+
+    layout
+}
+
+/// Get the size (in bytes) of the large static lookup tables.
+///
+/// This counts the Rook and Bishop move tables used with magic bitboards (about 8.7 MB,
+/// or nothing with the `qugiy` feature, which computes the slider moves without these
+/// tables) and the tables of squares between and on lines through two squares (about
+/// 200 KB). The smaller tables (step attacks and magic numbers) take a few KB and are
+/// not counted. The Lance has no move table: its moves are computed from its
+/// pseudo-attacks, like the moves of the other sliders with `qugiy`.
+///
+/// With the `lazy-tables` feature, the Rook and Bishop move tables are allocated on
+/// the heap when they are first used, instead of being part of the binary.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let bytes = tables::memory_footprint();
+/// if capabilities().slider_backend == SliderBackend::Magic {
+///     assert!(bytes > 8_000_000);
+/// } else {
+///     assert!(bytes < 1_000_000);
+/// }
+/// ```
+pub fn memory_footprint() -> usize {
+    #[cfg(not(feature = "qugiy"))]
+    let sliders = (ROOK_TABLE_SIZE + BISHOP_TABLE_SIZE) * core::mem::size_of::<u128>();
+    #[cfg(feature = "qugiy")]
+    let sliders = 0;
+
+    // get_between_rays and line_ray
+    let rays = 2 * core::mem::size_of::<[[BitBoard; Square::NUM]; Square::NUM]>();
+
+    sliders + rays
+}
+
+/// Load the large static lookup tables into memory before they are needed.
+///
+/// The tables counted by [`memory_footprint`] are part of the binary, but the operating
+/// system only maps their pages into memory when they are first read. With the
+/// `lazy-tables` feature, the Rook and Bishop move tables are even built on first use.
+/// So the first searches after startup can be noticeably slower.
+///
+/// This function builds the lazy tables (if needed) and reads every cache line of the
+/// tables once, so that this cost can be paid at a convenient moment, for instance when
+/// a USI engine receives `isready`. It is safe to call more than once, from any thread.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// tables::warmup();
+/// assert_eq!(Board::startpos().status(), GameStatus::Ongoing);
+/// ```
+pub fn warmup() {
+    use core::hint::black_box;
+
+    #[cfg(not(feature = "qugiy"))]
+    for table in crate::slider_moves::sliding_moves_tables() {
+        let table = black_box(table);
+        let mut acc = 0;
+        // one read per cache line
+        for &entry in table.iter().step_by(64 / core::mem::size_of::<u128>()) {
+            acc ^= entry;
+        }
+        black_box(acc);
+    }
+
+    let mut acc = BitBoard::EMPTY;
+    for &a in &Square::ALL {
+        for &b in &Square::ALL {
+            acc ^= get_between_rays(black_box(a), b) ^ line_ray(black_box(a), b);
+        }
+    }
+    black_box(acc);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Look up the slider moves as an external consumer would, using `pext`.
+    fn lookup(buffer: &[u64], table: usize, square: Square, occ: BitBoard) -> BitBoard {
+        let header = table + square as usize * HEADER_WORDS;
+        let mask = buffer[header] as u128 | (buffer[header + 1] as u128) << 64;
+        let mut index = 0;
+        let mut bit = 0;
+        for i in 0..128 {
+            if mask & (1 << i) != 0 {
+                if occ.0 & (1 << i) != 0 {
+                    index |= 1 << bit;
+                }
+                bit += 1;
+            }
+        }
+        assert!(index < buffer[header + 3] as usize);
+        let offset = buffer[header + 2] as usize + index * BITBOARD_WORDS;
+        BitBoard::new(buffer[offset] as u128 | (buffer[offset + 1] as u128) << 64)
+    }
+
+    #[test]
+    fn exported_tables_match_move_generation() {
+        let layout = TableLayout::new();
+        let mut buffer = vec![0u64; layout.len];
+        export_into(&mut buffer);
+
+        let board: Board =
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+                .parse()
+                .unwrap();
+        for occ in [
+            BitBoard::EMPTY,
+            Board::startpos().occupied(),
+            board.occupied(),
+        ] {
+            for square in Square::ALL {
+                assert_eq!(
+                    lookup(&buffer, layout.rook, square, occ),
+                    get_rook_moves(Color::Black, square, occ)
+                );
+                assert_eq!(
+                    lookup(&buffer, layout.bishop, square, occ),
+                    get_bishop_moves(Color::Black, square, occ)
+                );
+                for color in Color::ALL {
+                    assert_eq!(
+                        lookup(&buffer, layout.lance[color as usize], square, occ),
+                        get_lance_moves(color, square, occ)
+                    );
+                }
+            }
+        }
+    }
+}
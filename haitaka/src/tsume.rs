@@ -0,0 +1,321 @@
+//! Tsume Shogi solver
+//!
+//! [`solve`] searches for a forced mate with a depth-first proof-number search (df-pn,
+//! as introduced by Nagai). The side to move is the attacker and must give check with
+//! every move. The defender may use all legal moves. The search stops when it has found
+//! a mate, when it has shown that there is no mate within [`TsumeOptions::max_depth`]
+//! plies, or when it has visited [`TsumeOptions::max_nodes`] positions.
+//!
+//! Tsume Shogi positions usually come without a King for the attacker, which is
+//! supported by [`Board::tsume`]. The illegal mate by Pawn drop is never used, since
+//! the move generator doesn't produce it.
+//!
+//! The search doesn't try to find the shortest mate. The principal variation follows
+//! the shortest proven attacks and the longest proven defenses, so for simple problems
+//! it usually is the intended solution. But it does not apply the finer conventions of
+//! Tsume Shogi, such as the rule against useless interpositions (muda-ai).
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! use haitaka::tsume::*;
+//!
+//! // The first problem of Zoku Tsumu-ya-Tsumuzaru-ya, by Ohashi Sokei
+//! let board = Board::tsume("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1").unwrap();
+//! let options = TsumeOptions { max_depth: 15, ..Default::default() };
+//! let TsumeResult::Mate(pv) = solve(&board, &options) else {
+//!     panic!("no mate found");
+//! };
+//! assert_eq!(pv.len(), 15);
+//! assert_eq!(pv[0], "N*7e".parse().unwrap());
+//! ```
+use std::collections::HashMap;
+
+use crate::*;
+
+/// Options for the Tsume Shogi solver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TsumeOptions {
+    /// Maximum number of plies of the mate, counting the moves of both sides.
+    pub max_depth: u32,
+    /// Maximum number of positions to search before giving up.
+    pub max_nodes: u64,
+}
+
+impl Default for TsumeOptions {
+    /// Search for mates of up to 31 plies, in at most a million positions.
+    fn default() -> Self {
+        Self {
+            max_depth: 31,
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+/// The result of [`solve`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TsumeResult {
+    /// A mate was found. This holds the moves of both sides, ending with the mate.
+    Mate(Vec<Move>),
+    /// There is no mate within the maximum depth.
+    NoMate,
+    /// The search ran out of nodes before it could decide.
+    Unknown,
+}
+
+// Proof and disproof numbers are capped at INFINITY.
+const INFINITY: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    pn: u32,
+    dn: u32,
+    // For proven positions, the number of plies to mate.
+    plies: u32,
+}
+
+impl Entry {
+    const UNKNOWN: Self = Self {
+        pn: 1,
+        dn: 1,
+        plies: 0,
+    };
+}
+
+struct Solver {
+    // Keyed by position hash and remaining depth. Results depend on the depth, and
+    // since the depth decreases with every move there are no cycles in the search.
+    table: HashMap<(u64, u32), Entry>,
+    nodes: u64,
+    max_nodes: u64,
+}
+
+/// Search for a forced mate by the side to move.
+///
+/// See the [module documentation](self) for details.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::tsume::*;
+///
+/// let board = Board::tsume("4k4/9/4P4/9/9/9/9/9/9 b G 1").unwrap();
+/// let result = solve(&board, &TsumeOptions::default());
+/// assert_eq!(result, TsumeResult::Mate(vec!["G*5b".parse().unwrap()]));
+///
+/// // With only a Silver in hand, there is no mate
+/// let board = Board::tsume("4k4/9/4P4/9/9/9/9/9/9 b S 1").unwrap();
+/// assert_eq!(solve(&board, &TsumeOptions::default()), TsumeResult::NoMate);
+/// ```
+pub fn solve(board: &Board, options: &TsumeOptions) -> TsumeResult {
+    let mut solver = Solver {
+        table: HashMap::new(),
+        nodes: 0,
+        max_nodes: options.max_nodes,
+    };
+    let depth = options.max_depth;
+    let root = solver.mid(board, depth, true, INFINITY, INFINITY);
+    if root.pn == 0 {
+        TsumeResult::Mate(solver.principal_variation(board, depth))
+    } else if root.dn == 0 {
+        TsumeResult::NoMate
+    } else {
+        TsumeResult::Unknown
+    }
+}
+
+impl Solver {
+    fn lookup(&self, board: &Board, depth: u32) -> Entry {
+        self.table
+            .get(&(board.hash(), depth))
+            .copied()
+            .unwrap_or(Entry::UNKNOWN)
+    }
+
+    // The moves of the attacker (checks) or the defender (evasions).
+    fn children(board: &Board, attacker: bool) -> Vec<(Move, Board)> {
+        let mut children = Vec::new();
+        let mut add = |moves: PieceMoves| {
+            children.extend(moves.into_iter().map(|mv| (mv, board.after_unchecked(mv))));
+            false
+        };
+        if attacker {
+            board.generate_checks(&mut add);
+        } else {
+            board.generate_moves(&mut add);
+        }
+        children
+    }
+
+    // Expand `board` until its proof or disproof number reaches the threshold.
+    fn mid(&mut self, board: &Board, depth: u32, attacker: bool, th_pn: u32, th_dn: u32) -> Entry {
+        let key = (board.hash(), depth);
+        let entry = self.lookup(board, depth);
+        if entry.pn >= th_pn || entry.dn >= th_dn {
+            return entry;
+        }
+        self.nodes += 1;
+
+        let children = if depth > 0 {
+            Self::children(board, attacker)
+        } else {
+            Vec::new()
+        };
+        if children.is_empty() {
+            // The attacker has no checks left, or the defender has no moves (mate).
+            // At depth 0, the defender is mated only if it has no moves.
+            let mated = !attacker && !board.generate_moves(|_| true);
+            let entry = if mated {
+                Entry {
+                    pn: 0,
+                    dn: INFINITY,
+                    plies: 0,
+                }
+            } else {
+                Entry {
+                    pn: INFINITY,
+                    dn: 0,
+                    plies: 0,
+                }
+            };
+            self.table.insert(key, entry);
+            return entry;
+        }
+
+        loop {
+            // Collect the numbers of the children. For the attacker, the proof number
+            // is the minimum and the disproof number the sum, and the other way around
+            // for the defender.
+            let mut min = INFINITY;
+            let mut second = INFINITY;
+            let mut sum: u32 = 0;
+            let mut best = 0;
+            let mut plies = if attacker { INFINITY } else { 0 };
+            for (i, (_, child)) in children.iter().enumerate() {
+                let e = self.lookup(child, depth - 1);
+                let (this, other) = if attacker { (e.pn, e.dn) } else { (e.dn, e.pn) };
+                if this < min {
+                    second = min;
+                    min = this;
+                    best = i;
+                } else if this < second {
+                    second = this;
+                }
+                sum = sum.saturating_add(other);
+                if e.pn == 0 {
+                    plies = if attacker {
+                        plies.min(e.plies + 1)
+                    } else {
+                        plies.max(e.plies + 1)
+                    };
+                }
+            }
+            let (pn, dn) = if attacker { (min, sum) } else { (sum, min) };
+            let entry = Entry { pn, dn, plies };
+            self.table.insert(key, entry);
+
+            if pn >= th_pn || dn >= th_dn || self.nodes >= self.max_nodes {
+                return entry;
+            }
+
+            // Search the most promising child, until it is no longer the best one.
+            let child_entry = self.lookup(&children[best].1, depth - 1);
+            let (child_pn, child_dn) = if attacker {
+                (
+                    th_pn.min(second.saturating_add(1)),
+                    th_dn.saturating_sub(dn).saturating_add(child_entry.dn),
+                )
+            } else {
+                (
+                    th_pn.saturating_sub(pn).saturating_add(child_entry.pn),
+                    th_dn.min(second.saturating_add(1)),
+                )
+            };
+            self.mid(&children[best].1, depth - 1, !attacker, child_pn, child_dn);
+        }
+    }
+
+    // Follow the proven moves: the quickest mate for the attacker and the longest
+    // resistance for the defender.
+    fn principal_variation(&self, board: &Board, mut depth: u32) -> Vec<Move> {
+        let mut pv = Vec::new();
+        let mut board = board.clone();
+        let mut attacker = true;
+        while depth > 0 {
+            let best = Self::children(&board, attacker)
+                .into_iter()
+                .filter_map(|(mv, child)| {
+                    let entry = self.table.get(&(child.hash(), depth - 1))?;
+                    (entry.pn == 0).then_some((entry.plies, mv, child))
+                })
+                .reduce(|a, b| {
+                    if (attacker && b.0 < a.0) || (!attacker && b.0 > a.0) {
+                        b
+                    } else {
+                        a
+                    }
+                });
+            let Some((_, mv, child)) = best else {
+                break;
+            };
+            pv.push(mv);
+            board = child;
+            attacker = !attacker;
+            depth -= 1;
+        }
+        pv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mates_are_checkmates() {
+        for (sfen, plies) in [
+            ("4k4/9/4P4/9/9/9/9/9/9 b G 1", 1),
+            ("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1", 15),
+        ] {
+            let board = Board::tsume(sfen).unwrap();
+            let options = TsumeOptions {
+                max_depth: plies,
+                ..Default::default()
+            };
+            let TsumeResult::Mate(pv) = solve(&board, &options) else {
+                panic!("no mate in {sfen}");
+            };
+            assert!(pv.len() as u32 <= plies, "{sfen}");
+            let mut board = board;
+            for (i, &mv) in pv.iter().enumerate() {
+                assert!(board.is_legal(mv), "{sfen} {mv}");
+                board.play_unchecked(mv);
+                if i % 2 == 0 {
+                    assert!(!board.checkers().is_empty(), "{sfen} {mv}");
+                }
+            }
+            assert!(board.is_checkmate(), "{sfen}");
+        }
+    }
+
+    #[test]
+    fn respects_the_limits() {
+        let board = Board::tsume("lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1").unwrap();
+        let options = TsumeOptions {
+            max_depth: 7,
+            ..Default::default()
+        };
+        assert_eq!(solve(&board, &options), TsumeResult::NoMate);
+        let options = TsumeOptions {
+            max_nodes: 10,
+            ..Default::default()
+        };
+        assert_eq!(solve(&board, &options), TsumeResult::Unknown);
+    }
+
+    #[test]
+    fn pawn_drop_mate_is_not_a_solution() {
+        let board = Board::tsume("3lkl3/3p1p3/4G4/9/9/9/9/9/9 b P 1").unwrap();
+        assert_eq!(solve(&board, &TsumeOptions::default()), TsumeResult::NoMate);
+    }
+}
@@ -0,0 +1,688 @@
+//! USI engine protocol helpers
+//!
+//! USI (Universal Shogi Interface) is the text protocol between Shogi GUIs and engines.
+//! This module parses and formats the messages of the protocol that carry positions,
+//! moves and search parameters:
+//!
+//! - [`Position`] for `position startpos moves ...` and `position sfen ... moves ...`
+//! - [`GoParams`] for `go` and its search limits
+//! - [`BestMove`] for the `bestmove` reply
+//! - [`Info`] for `info` lines
+//!
+//! Each type parses a full line, including the command, with [`FromStr`], and formats it
+//! with [`Display`](fmt::Display). The other commands (`usi`, `isready`, `setoption`, ...)
+//! carry no Shogi data and are left to the engine.
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! use haitaka::usi::*;
+//! let mut board = Board::startpos();
+//! usi::apply_position(&mut board, "position startpos moves 7g7f 3c3d").unwrap();
+//! assert_eq!(board.side_to_move(), Color::Black);
+//!
+//! let go: GoParams = "go btime 60000 wtime 50000 byoyomi 10000".parse().unwrap();
+//! assert_eq!(go.time(board.side_to_move()), Some(core::time::Duration::from_secs(60)));
+//!
+//! let reply = BestMove::Move { mv: "8h2b+".parse().unwrap(), ponder: None };
+//! assert_eq!(reply.to_string(), "bestmove 8h2b+");
+//! ```
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+
+use crate::*;
+
+helpers::simple_error! {
+    /// An error while parsing a USI message.
+    pub enum UsiError {
+        InvalidCommand = "The USI command is invalid",
+        InvalidPosition = "The SFEN of the position is invalid",
+        InvalidMove = "A move is invalid",
+        IllegalMove = "A move is illegal in its position",
+        InvalidValue = "A parameter has an invalid value"
+    }
+}
+
+// Strip the command from a line, returning the arguments.
+fn strip_command<'a>(line: &'a str, command: &str) -> Result<&'a str, UsiError> {
+    let rest = line
+        .trim()
+        .strip_prefix(command)
+        .ok_or(UsiError::InvalidCommand)?;
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        Ok(rest.trim_start())
+    } else {
+        Err(UsiError::InvalidCommand)
+    }
+}
+
+fn parse_value<T: FromStr>(token: Option<&str>) -> Result<T, UsiError> {
+    token
+        .ok_or(UsiError::InvalidValue)?
+        .parse()
+        .map_err(|_| UsiError::InvalidValue)
+}
+
+fn parse_millis(token: Option<&str>) -> Result<Duration, UsiError> {
+    parse_value(token).map(Duration::from_millis)
+}
+
+fn parse_move(token: &str) -> Result<Move, UsiError> {
+    token.parse().map_err(|_| UsiError::InvalidMove)
+}
+
+/// A `position` command: a start position and the moves played from it.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::usi::*;
+/// let position: Position = "position startpos moves 7g7f 3c3d".parse().unwrap();
+/// assert_eq!(position.start, Board::startpos());
+/// assert_eq!(position.moves.len(), 2);
+/// assert_eq!(position.to_string(), "position startpos moves 7g7f 3c3d");
+///
+/// let position: Position = "position sfen 4k4/9/4P4/9/9/9/9/9/4K4 b G 1 moves G*5b"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(position.board().unwrap().status(), GameStatus::Won);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// The start position.
+    pub start: Board,
+    /// The moves played from the start position.
+    pub moves: Vec<Move>,
+}
+
+impl Position {
+    /// Create a position without moves.
+    pub fn new(start: Board) -> Self {
+        Self {
+            start,
+            moves: Vec::new(),
+        }
+    }
+
+    /// Get the board after playing the moves from the start position.
+    ///
+    /// # Errors
+    /// Errors with [`UsiError::IllegalMove`] if a move is illegal.
+    pub fn board(&self) -> Result<Board, UsiError> {
+        let mut board = self.start.clone();
+        for &mv in &self.moves {
+            board.try_play(mv).map_err(|_| UsiError::IllegalMove)?;
+        }
+        Ok(board)
+    }
+}
+
+impl FromStr for Position {
+    type Err = UsiError;
+
+    /// Parse a `position` command.
+    ///
+    /// The SFEN of a `position sfen` command may describe a Tsume Shogi problem (as sent
+    /// with `go mate`): if it is not a valid position for [`Board::from_sfen`], it is
+    /// parsed with [`Board::tsume`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = strip_command(s, "position")?.split_whitespace().peekable();
+        let start = match tokens.next() {
+            Some("startpos") => Board::startpos(),
+            Some("sfen") => {
+                let mut fields = Vec::new();
+                while let Some(field) = tokens.next_if(|&token| token != "moves") {
+                    fields.push(field);
+                }
+                let sfen = fields.join(" ");
+                Board::from_sfen(&sfen)
+                    .or_else(|_| Board::tsume(&sfen))
+                    .map_err(|_| UsiError::InvalidPosition)?
+            }
+            _ => return Err(UsiError::InvalidCommand),
+        };
+        let moves = match tokens.next() {
+            Some("moves") => tokens.map(parse_move).collect::<Result<_, _>>()?,
+            Some(_) => return Err(UsiError::InvalidCommand),
+            None => Vec::new(),
+        };
+        Ok(Self { start, moves })
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == Board::startpos() {
+            write!(f, "position startpos")?;
+        } else {
+            write!(f, "position sfen {}", self.start)?;
+        }
+        if !self.moves.is_empty() {
+            write!(f, " moves")?;
+            for mv in &self.moves {
+                write!(f, " {}", mv)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Set up `board` from a `position` command.
+///
+/// The board is only changed if the command is valid and all moves are legal.
+///
+/// # Errors
+/// Errors with a [`UsiError`] if the command is invalid or a move is illegal.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::usi;
+/// let mut board = Board::startpos();
+/// usi::apply_position(&mut board, "position startpos moves 2g2f 8c8d").unwrap();
+/// assert_eq!(board.move_number(), 3);
+///
+/// assert!(usi::apply_position(&mut board, "position startpos moves 2g2e").is_err());
+/// assert_eq!(board.move_number(), 3);
+/// ```
+pub fn apply_position(board: &mut Board, command: &str) -> Result<(), UsiError> {
+    *board = command.parse::<Position>()?.board()?;
+    Ok(())
+}
+
+/// The time limit of a `go mate` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MateLimit {
+    /// Search for at most this long.
+    Time(Duration),
+    /// Search until the problem is solved (`go mate infinite`).
+    Infinite,
+}
+
+/// The parameters of a `go` command.
+///
+/// Times are given in milliseconds in the protocol. A `go mate` command starts a Tsume
+/// Shogi search, which is answered with `checkmate` rather than `bestmove`.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::usi::*;
+/// use core::time::Duration;
+/// let go: GoParams = "go btime 300000 wtime 280000 binc 5000 winc 5000".parse().unwrap();
+/// assert_eq!(go.time(Color::White), Some(Duration::from_secs(280)));
+/// assert_eq!(go.inc(Color::Black), Some(Duration::from_secs(5)));
+/// assert!(!go.infinite);
+///
+/// let go: GoParams = "go mate infinite".parse().unwrap();
+/// assert_eq!(go.mate, Some(MateLimit::Infinite));
+/// assert_eq!(go.to_string(), "go mate infinite");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoParams {
+    /// Search in ponder mode, on the opponent's time.
+    pub ponder: bool,
+    /// Only search these moves.
+    pub searchmoves: Vec<Move>,
+    /// The time left on Black's clock.
+    pub btime: Option<Duration>,
+    /// The time left on White's clock.
+    pub wtime: Option<Duration>,
+    /// Black's increment per move (Fischer time).
+    pub binc: Option<Duration>,
+    /// White's increment per move (Fischer time).
+    pub winc: Option<Duration>,
+    /// The byoyomi: the time per move once the clock runs out.
+    pub byoyomi: Option<Duration>,
+    /// Search for exactly this long.
+    pub movetime: Option<Duration>,
+    /// Search to this depth.
+    pub depth: Option<u32>,
+    /// Search this many nodes.
+    pub nodes: Option<u64>,
+    /// Search for mate (Tsume Shogi).
+    pub mate: Option<MateLimit>,
+    /// Search until `stop`.
+    pub infinite: bool,
+}
+
+impl GoParams {
+    /// The time left on the clock of `color`.
+    pub fn time(&self, color: Color) -> Option<Duration> {
+        match color {
+            Color::Black => self.btime,
+            Color::White => self.wtime,
+        }
+    }
+
+    /// The increment per move of `color`.
+    pub fn inc(&self, color: Color) -> Option<Duration> {
+        match color {
+            Color::Black => self.binc,
+            Color::White => self.winc,
+        }
+    }
+}
+
+// The keywords of `go`, which end the list of `searchmoves`.
+const GO_KEYWORDS: [&str; 12] = [
+    "ponder",
+    "searchmoves",
+    "btime",
+    "wtime",
+    "binc",
+    "winc",
+    "byoyomi",
+    "movetime",
+    "depth",
+    "nodes",
+    "mate",
+    "infinite",
+];
+
+impl FromStr for GoParams {
+    type Err = UsiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params = Self::default();
+        let mut tokens = strip_command(s, "go")?.split_whitespace().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                "ponder" => params.ponder = true,
+                "infinite" => params.infinite = true,
+                "btime" => params.btime = Some(parse_millis(tokens.next())?),
+                "wtime" => params.wtime = Some(parse_millis(tokens.next())?),
+                "binc" => params.binc = Some(parse_millis(tokens.next())?),
+                "winc" => params.winc = Some(parse_millis(tokens.next())?),
+                "byoyomi" => params.byoyomi = Some(parse_millis(tokens.next())?),
+                "movetime" => params.movetime = Some(parse_millis(tokens.next())?),
+                "depth" => params.depth = Some(parse_value(tokens.next())?),
+                "nodes" => params.nodes = Some(parse_value(tokens.next())?),
+                "mate" => {
+                    params.mate = Some(match tokens.next() {
+                        Some("infinite") => MateLimit::Infinite,
+                        token => MateLimit::Time(parse_millis(token)?),
+                    })
+                }
+                "searchmoves" => {
+                    while let Some(token) = tokens.next_if(|token| !GO_KEYWORDS.contains(token)) {
+                        params.searchmoves.push(parse_move(token)?);
+                    }
+                }
+                _ => return Err(UsiError::InvalidCommand),
+            }
+        }
+        Ok(params)
+    }
+}
+
+impl fmt::Display for GoParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "go")?;
+        if self.ponder {
+            write!(f, " ponder")?;
+        }
+        if !self.searchmoves.is_empty() {
+            write!(f, " searchmoves")?;
+            for mv in &self.searchmoves {
+                write!(f, " {}", mv)?;
+            }
+        }
+        for (name, time) in [
+            ("btime", self.btime),
+            ("wtime", self.wtime),
+            ("binc", self.binc),
+            ("winc", self.winc),
+            ("byoyomi", self.byoyomi),
+            ("movetime", self.movetime),
+        ] {
+            if let Some(time) = time {
+                write!(f, " {} {}", name, time.as_millis())?;
+            }
+        }
+        if let Some(depth) = self.depth {
+            write!(f, " depth {}", depth)?;
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {}", nodes)?;
+        }
+        match self.mate {
+            Some(MateLimit::Time(time)) => write!(f, " mate {}", time.as_millis())?,
+            Some(MateLimit::Infinite) => write!(f, " mate infinite")?,
+            None => {}
+        }
+        if self.infinite {
+            write!(f, " infinite")?;
+        }
+        Ok(())
+    }
+}
+
+/// A `bestmove` reply.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::usi::*;
+/// let reply: BestMove = "bestmove 7g7f ponder 3c3d".parse().unwrap();
+/// assert_eq!(
+///     reply,
+///     BestMove::Move { mv: "7g7f".parse().unwrap(), ponder: Some("3c3d".parse().unwrap()) }
+/// );
+/// assert_eq!(BestMove::Resign.to_string(), "bestmove resign");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BestMove {
+    /// Play a move, optionally with the expected reply to ponder on.
+    Move {
+        /// The move to play.
+        mv: Move,
+        /// The expected reply.
+        ponder: Option<Move>,
+    },
+    /// Resign.
+    Resign,
+    /// Declare a win by entering King (nyuugyoku).
+    Win,
+}
+
+impl FromStr for BestMove {
+    type Err = UsiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = strip_command(s, "bestmove")?.split_whitespace();
+        let best = match tokens.next() {
+            Some("resign") => Self::Resign,
+            Some("win") => Self::Win,
+            Some(token) => {
+                let mv = parse_move(token)?;
+                let ponder = match tokens.next() {
+                    Some("ponder") => {
+                        Some(parse_move(tokens.next().ok_or(UsiError::InvalidMove)?)?)
+                    }
+                    Some(_) => return Err(UsiError::InvalidCommand),
+                    None => None,
+                };
+                Self::Move { mv, ponder }
+            }
+            None => return Err(UsiError::InvalidCommand),
+        };
+        match tokens.next() {
+            Some(_) => Err(UsiError::InvalidCommand),
+            None => Ok(best),
+        }
+    }
+}
+
+impl fmt::Display for BestMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Move { mv, ponder } => {
+                write!(f, "bestmove {}", mv)?;
+                if let Some(ponder) = ponder {
+                    write!(f, " ponder {}", ponder)?;
+                }
+                Ok(())
+            }
+            Self::Resign => write!(f, "bestmove resign"),
+            Self::Win => write!(f, "bestmove win"),
+        }
+    }
+}
+
+/// The score of an `info` line, from the point of view of the engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Score {
+    /// A score in centipawns (`score cp`).
+    Cp(i32),
+    /// Mate in this many plies (`score mate`). Negative if the engine gets mated.
+    Mate(i32),
+    /// Mate in an unknown number of plies (`score mate +` or `score mate -`).
+    MateUnknown {
+        /// Does the engine give mate (`+`) or get mated (`-`)?
+        winning: bool,
+    },
+}
+
+/// A bound on the score of an `info` line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScoreBound {
+    /// The score is a lower bound (`lowerbound`).
+    Lower,
+    /// The score is an upper bound (`upperbound`).
+    Upper,
+}
+
+/// An `info` line.
+///
+/// All fields are optional. They are formatted in the order of the fields, with
+/// `string` last, since it takes the rest of the line.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// use haitaka::usi::*;
+/// let info = Info {
+///     depth: Some(8),
+///     nodes: Some(123456),
+///     score: Some(Score::Cp(-35)),
+///     pv: vec!["3c3d".parse().unwrap(), "2g2f".parse().unwrap()],
+///     ..Default::default()
+/// };
+/// let line = info.to_string();
+/// assert_eq!(line, "info depth 8 nodes 123456 score cp -35 pv 3c3d 2g2f");
+/// assert_eq!(line.parse::<Info>().unwrap(), info);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    /// The search depth.
+    pub depth: Option<u32>,
+    /// The selective search depth.
+    pub seldepth: Option<u32>,
+    /// The time searched.
+    pub time: Option<Duration>,
+    /// The number of nodes searched.
+    pub nodes: Option<u64>,
+    /// The number of nodes searched per second.
+    pub nps: Option<u64>,
+    /// How full the hash table is, in permille.
+    pub hashfull: Option<u32>,
+    /// The number of the line, when searching several lines (MultiPV).
+    pub multipv: Option<u32>,
+    /// The score.
+    pub score: Option<Score>,
+    /// Whether the score is only a bound.
+    pub bound: Option<ScoreBound>,
+    /// The move currently searched.
+    pub currmove: Option<Move>,
+    /// The principal variation.
+    pub pv: Vec<Move>,
+    /// A free-form message.
+    pub string: Option<String>,
+}
+
+impl FromStr for Info {
+    type Err = UsiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut info = Self::default();
+        let rest = strip_command(s, "info")?;
+        let mut tokens = rest.split_whitespace().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = Some(parse_value(tokens.next())?),
+                "seldepth" => info.seldepth = Some(parse_value(tokens.next())?),
+                "time" => info.time = Some(parse_millis(tokens.next())?),
+                "nodes" => info.nodes = Some(parse_value(tokens.next())?),
+                "nps" => info.nps = Some(parse_value(tokens.next())?),
+                "hashfull" => info.hashfull = Some(parse_value(tokens.next())?),
+                "multipv" => info.multipv = Some(parse_value(tokens.next())?),
+                "currmove" => {
+                    info.currmove = Some(parse_move(tokens.next().ok_or(UsiError::InvalidMove)?)?)
+                }
+                "score" => {
+                    info.score = Some(match (tokens.next(), tokens.next()) {
+                        (Some("cp"), value) => Score::Cp(parse_value(value)?),
+                        (Some("mate"), Some("+")) => Score::MateUnknown { winning: true },
+                        (Some("mate"), Some("-")) => Score::MateUnknown { winning: false },
+                        (Some("mate"), value) => Score::Mate(parse_value(value)?),
+                        _ => return Err(UsiError::InvalidValue),
+                    });
+                }
+                "lowerbound" => info.bound = Some(ScoreBound::Lower),
+                "upperbound" => info.bound = Some(ScoreBound::Upper),
+                "pv" => {
+                    while let Some(token) = tokens.next_if(|&token| token != "string") {
+                        info.pv.push(parse_move(token)?);
+                    }
+                }
+                "string" => {
+                    // the message is the rest of the line, with its spacing
+                    let message = rest.split_once("string").map_or("", |(_, message)| message);
+                    let message = message.strip_prefix(' ').unwrap_or(message);
+                    info.string = Some(message.to_string());
+                    break;
+                }
+                _ => return Err(UsiError::InvalidCommand),
+            }
+        }
+        Ok(info)
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "info")?;
+        for (name, value) in [("depth", self.depth), ("seldepth", self.seldepth)] {
+            if let Some(value) = value {
+                write!(f, " {} {}", name, value)?;
+            }
+        }
+        if let Some(time) = self.time {
+            write!(f, " time {}", time.as_millis())?;
+        }
+        for (name, value) in [("nodes", self.nodes), ("nps", self.nps)] {
+            if let Some(value) = value {
+                write!(f, " {} {}", name, value)?;
+            }
+        }
+        for (name, value) in [("hashfull", self.hashfull), ("multipv", self.multipv)] {
+            if let Some(value) = value {
+                write!(f, " {} {}", name, value)?;
+            }
+        }
+        match self.score {
+            Some(Score::Cp(cp)) => write!(f, " score cp {}", cp)?,
+            Some(Score::Mate(plies)) => write!(f, " score mate {}", plies)?,
+            Some(Score::MateUnknown { winning }) => {
+                write!(f, " score mate {}", if winning { '+' } else { '-' })?
+            }
+            None => {}
+        }
+        match self.bound {
+            Some(ScoreBound::Lower) => write!(f, " lowerbound")?,
+            Some(ScoreBound::Upper) => write!(f, " upperbound")?,
+            None => {}
+        }
+        if let Some(mv) = self.currmove {
+            write!(f, " currmove {}", mv)?;
+        }
+        if !self.pv.is_empty() {
+            write!(f, " pv")?;
+            for mv in &self.pv {
+                write!(f, " {}", mv)?;
+            }
+        }
+        if let Some(string) = &self.string {
+            write!(f, " string {}", string)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        for line in [
+            "position startpos",
+            "position startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e",
+            "position sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            "position sfen 8k/9/9/9/9/9/9/9/9 b 2G2r2b2g4s4n4l18p 1 moves G*1b",
+        ] {
+            assert_eq!(line.parse::<Position>().unwrap().to_string(), line);
+        }
+        for line in [
+            "go",
+            "go ponder btime 1000 wtime 2000 byoyomi 3000",
+            "go searchmoves 7g7f 2g2f depth 5",
+            "go btime 0 wtime 0 binc 10000 winc 10000",
+            "go mate 60000",
+            "go nodes 100000 infinite",
+        ] {
+            assert_eq!(line.parse::<GoParams>().unwrap().to_string(), line);
+        }
+        for line in ["bestmove 7g7f", "bestmove P*5e ponder 5d5e", "bestmove win"] {
+            assert_eq!(line.parse::<BestMove>().unwrap().to_string(), line);
+        }
+        for line in [
+            "info depth 1 seldepth 3 time 12 nodes 345 nps 28750 hashfull 1 multipv 2 \
+             score mate -5 upperbound currmove 7g7f pv 7g7f 3c3d",
+            "info score mate + pv G*5b",
+            "info string searching  with two spaces",
+            "info depth 3 pv 2g2f string done",
+        ] {
+            assert_eq!(line.parse::<Info>().unwrap().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn invalid_messages() {
+        assert!(matches!(
+            "position startpo".parse::<Position>(),
+            Err(UsiError::InvalidCommand)
+        ));
+        assert!(matches!(
+            "positions startpos".parse::<Position>(),
+            Err(UsiError::InvalidCommand)
+        ));
+        assert!(matches!(
+            "position sfen 9/9 b - 1".parse::<Position>(),
+            Err(UsiError::InvalidPosition)
+        ));
+        assert!(matches!(
+            "position startpos moves 7g7f 3c3x".parse::<Position>(),
+            Err(UsiError::InvalidMove)
+        ));
+        let position: Position = "position startpos moves 7g7f 7g7f".parse().unwrap();
+        assert!(matches!(position.board(), Err(UsiError::IllegalMove)));
+
+        assert!(matches!(
+            "go btime".parse::<GoParams>(),
+            Err(UsiError::InvalidValue)
+        ));
+        assert!(matches!(
+            "go wtime -5".parse::<GoParams>(),
+            Err(UsiError::InvalidValue)
+        ));
+        assert!(matches!(
+            "go fast".parse::<GoParams>(),
+            Err(UsiError::InvalidCommand)
+        ));
+        assert!(matches!(
+            "bestmove 7g7f 3c3d".parse::<BestMove>(),
+            Err(UsiError::InvalidCommand)
+        ));
+        assert!(matches!(
+            "bestmove".parse::<BestMove>(),
+            Err(UsiError::InvalidCommand)
+        ));
+        assert!(matches!(
+            "info score cp".parse::<Info>(),
+            Err(UsiError::InvalidValue)
+        ));
+    }
+}
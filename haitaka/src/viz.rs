@@ -0,0 +1,252 @@
+//! SVG rendering of boards and bitboards
+//!
+//! These helpers render a [`Board`] or a few labeled [`BitBoard`]s as an SVG image,
+//! returned as a `String`. The images are meant for debugging and documentation, so
+//! they are simple: a 9x9 grid with file numbers on top and rank numbers on the right,
+//! as on a printed Shogi diagram. Pieces are shown by their kanji, with White pieces
+//! upside down.
+//!
+//! This module requires the `viz` feature.
+use std::fmt::Write;
+
+use crate::*;
+
+// Size of a square in pixels.
+const CELL: usize = 40;
+// Margin around the grid (for the coordinates and the pieces in hand).
+const MARGIN: usize = 30;
+// Fill color of highlighted squares.
+const HIGHLIGHT: &str = "#f4c542";
+
+const RANK_NAMES: [&str; Rank::NUM] = ["一", "二", "三", "四", "五", "六", "七", "八", "九"];
+
+/// Get the kanji used for a piece in diagrams.
+///
+/// Promoted pieces use their one-character forms (such as と for the Tokin).
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// assert_eq!(viz::piece_kanji(Piece::Rook), "飛");
+/// assert_eq!(viz::piece_kanji(Piece::PRook), "龍");
+/// ```
+pub const fn piece_kanji(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => "歩",
+        Piece::Lance => "香",
+        Piece::Knight => "桂",
+        Piece::Silver => "銀",
+        Piece::Bishop => "角",
+        Piece::Rook => "飛",
+        Piece::Gold => "金",
+        Piece::King => "玉",
+        Piece::Tokin => "と",
+        Piece::PLance => "杏",
+        Piece::PKnight => "圭",
+        Piece::PSilver => "全",
+        Piece::PBishop => "馬",
+        Piece::PRook => "龍",
+    }
+}
+
+// Top left corner of a square.
+fn cell_origin(square: Square, top: usize) -> (usize, usize) {
+    let x = MARGIN + (File::NUM - 1 - square.file() as usize) * CELL;
+    let y = top + square.rank() as usize * CELL;
+    (x, y)
+}
+
+// Draw one 9x9 grid with coordinates, with its top left corner at (MARGIN, top).
+fn write_grid(svg: &mut String, top: usize, highlights: BitBoard) {
+    for square in highlights {
+        let (x, y) = cell_origin(square, top);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{CELL}" height="{CELL}" fill="{HIGHLIGHT}"/>"#
+        );
+    }
+    let size = 9 * CELL;
+    for i in 0..=9 {
+        let offset = i * CELL;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black"/>"#,
+            MARGIN + offset,
+            top,
+            MARGIN + offset,
+            top + size
+        );
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black"/>"#,
+            MARGIN,
+            top + offset,
+            MARGIN + size,
+            top + offset
+        );
+    }
+    for (i, rank_name) in RANK_NAMES.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="14" text-anchor="middle">{}</text>"#,
+            MARGIN + i * CELL + CELL / 2,
+            top - 6,
+            9 - i
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="14" text-anchor="middle">{}</text>"#,
+            MARGIN + size + 12,
+            top + i * CELL + CELL / 2 + 5,
+            rank_name
+        );
+    }
+}
+
+// Describe the pieces in hand of one player.
+fn hand_text(board: &Board, color: Color) -> String {
+    let mut text = String::from(match color {
+        Color::Black => "☗ ",
+        Color::White => "☖ ",
+    });
+    let mut empty = true;
+    for piece in [
+        Piece::Rook,
+        Piece::Bishop,
+        Piece::Gold,
+        Piece::Silver,
+        Piece::Knight,
+        Piece::Lance,
+        Piece::Pawn,
+    ] {
+        let count = board.num_in_hand(color, piece);
+        if count > 0 {
+            text.push_str(piece_kanji(piece));
+            if count > 1 {
+                let _ = write!(text, "{}", count);
+            }
+            empty = false;
+        }
+    }
+    if empty {
+        text.push_str("なし");
+    }
+    text
+}
+
+/// Render a board as SVG, with the `highlights` squares filled.
+///
+/// The pieces in hand of White are shown above the board and those of Black below.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// let svg = viz::board_svg(&board, Square::E5.bitboard());
+/// assert!(svg.starts_with("<svg"));
+/// assert_eq!(svg.matches("歩").count(), 18);
+/// assert_eq!(svg.matches("<rect").count(), 2);
+/// ```
+pub fn board_svg(board: &Board, highlights: BitBoard) -> String {
+    let width = 2 * MARGIN + 9 * CELL;
+    let height = 4 * MARGIN + 9 * CELL;
+    let top = 2 * MARGIN;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{width}" height="{height}" fill="white"/>"#
+    );
+    write_grid(&mut svg, top, highlights);
+
+    for square in board.occupied() {
+        let ColoredPiece { piece, color } = board.colored_piece_on(square).unwrap();
+        let (x, y) = cell_origin(square, top);
+        let (cx, cy) = (x + CELL / 2, y + CELL / 2);
+        let rotate = match color {
+            Color::Black => String::new(),
+            Color::White => format!(r#" transform="rotate(180 {cx} {cy})""#),
+        };
+        let fill = if piece.is_promoted() {
+            "#c00000"
+        } else {
+            "black"
+        };
+        let _ = writeln!(
+            svg,
+            r#"<text x="{cx}" y="{}" font-size="26" text-anchor="middle" fill="{fill}"{rotate}>{}</text>"#,
+            cy + 9,
+            piece_kanji(piece)
+        );
+    }
+
+    let _ = writeln!(
+        svg,
+        r#"<text x="{MARGIN}" y="{}" font-size="16">{}</text>"#,
+        MARGIN - 8,
+        hand_text(board, Color::White)
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{MARGIN}" y="{}" font-size="16">{}</text>"#,
+        top + 9 * CELL + MARGIN,
+        hand_text(board, Color::Black)
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Render labeled bitboards as SVG, one grid per bitboard, from left to right.
+///
+/// The squares in each bitboard are filled. Labels are shown above the grids.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let svg = viz::bitboards_svg(&[
+///     ("rook", get_rook_moves(Color::Black, Square::E5, BitBoard::EMPTY)),
+///     ("bishop", get_bishop_moves(Color::Black, Square::E5, BitBoard::EMPTY)),
+/// ]);
+/// assert!(svg.contains(">rook</text>"));
+/// assert_eq!(svg.matches("<rect x=").count(), 16 + 16);
+/// ```
+pub fn bitboards_svg(bitboards: &[(&str, BitBoard)]) -> String {
+    let board_width = 2 * MARGIN + 9 * CELL;
+    let width = board_width * bitboards.len().max(1);
+    let height = 3 * MARGIN + 9 * CELL;
+    let top = 2 * MARGIN;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{width}" height="{height}" fill="white"/>"#
+    );
+    for (i, (label, bb)) in bitboards.iter().enumerate() {
+        let _ = writeln!(svg, r#"<g transform="translate({} 0)">"#, i * board_width);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{MARGIN}" y="{}" font-size="16">{}</text>"#,
+            MARGIN - 8,
+            escape(label)
+        );
+        write_grid(&mut svg, top, *bb);
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Escape text for use in SVG.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
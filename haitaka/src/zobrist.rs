@@ -0,0 +1,142 @@
+//! Zobrist hashing utilities
+//!
+//! The [`Board`] maintains a Zobrist hash of the position (see [`Board::hash`]).
+//! This module gives access to the same hash without a full [`Board`], and publishes
+//! the keys it is made of.
+//!
+//! The hash of a position is the XOR of
+//! - [`piece_key`] for every piece on the board,
+//! - `hand_key(color, piece, 0) ^ hand_key(color, piece, count)` for every type of
+//!   piece in hand, with its count (see [`hand_key`]),
+//! - [`side_to_move_key`] if White is to move.
+//!
+//! The keys are generated at compile time from a fixed seed. They are part of the
+//! public API and do not change between releases, so hashes can be stored, for instance
+//! in opening books, and computed by other programs from the same keys.
+//!
+//! # Examples
+//! ```
+//! # use haitaka::*;
+//! let board: Board = "8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124"
+//!     .parse()
+//!     .unwrap();
+//! let mut hash = 0;
+//! for (color, piece, square) in board.iter_pieces() {
+//!     hash ^= zobrist::piece_key(color, piece, square);
+//! }
+//! for color in Color::ALL {
+//!     for &piece in &Piece::ALL[..Piece::HAND_NUM] {
+//!         let count = board.num_in_hand(color, piece);
+//!         hash ^= zobrist::hand_key(color, piece, 0) ^ zobrist::hand_key(color, piece, count);
+//!     }
+//! }
+//! if board.side_to_move() == Color::White {
+//!     hash ^= zobrist::side_to_move_key();
+//! }
+//! assert_eq!(hash, board.hash());
+//! ```
+use crate::*;
+
+/// Get the key of a piece of `color` on `square`.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let board = Board::startpos();
+/// let mv = "7g7f".parse().unwrap();
+/// let key = zobrist::piece_key(Color::Black, Piece::Pawn, Square::G7)
+///     ^ zobrist::piece_key(Color::Black, Piece::Pawn, Square::F7)
+///     ^ zobrist::side_to_move_key();
+/// assert_eq!(board.hash() ^ key, board.hash_after(mv));
+/// ```
+pub const fn piece_key(color: Color, piece: Piece, square: Square) -> u64 {
+    board::piece_key(color, piece, square)
+}
+
+/// Get the key of `count` pieces of type `piece` in the hand of `color`.
+///
+/// A hand without pieces of a type still has a key, which is not 0, so the hash of a
+/// position includes `hand_key(color, piece, 0) ^ hand_key(color, piece, count)`. An
+/// empty hand then adds nothing.
+///
+/// # Panics
+/// This panics if `piece` can't be in hand, or if `count` is more than 19.
+pub const fn hand_key(color: Color, piece: Piece, count: u8) -> u64 {
+    assert!((piece as usize) < Piece::HAND_NUM, "Not a piece in hand");
+    board::hand_key(color, piece, count)
+}
+
+/// Get the key of White to move.
+pub const fn side_to_move_key() -> u64 {
+    board::side_to_move_key()
+}
+
+/// Compute the [`Board::hash`] of a SFEN string.
+///
+/// This parses the SFEN string and computes the hash on the fly, but skips the
+/// validation of the position and the computation of checkers and pins that
+/// [`Board::from_sfen`] does. It is meant for indexing large SFEN datasets.
+/// Since the position is not validated, an illegal position still gets a hash.
+/// The move number is not part of the hash.
+///
+/// # Errors
+/// This returns an [`SfenError`] if the SFEN string is malformed.
+///
+/// # Examples
+/// ```
+/// # use haitaka::*;
+/// let sfen = "lnsgkgsnl/1r5b1/p1ppppppp/9/1p5P1/9/PPPPPPP1P/1B5R1/LNSGKGSNL b - 5";
+/// let board = Board::from_sfen(sfen).unwrap();
+/// assert_eq!(zobrist::hash_sfen(sfen).unwrap(), board.hash());
+/// assert_eq!(zobrist::hash_sfen(SFEN_STARTPOS).unwrap(), Board::startpos().hash());
+/// assert!(zobrist::hash_sfen("lnsgkgsnl/1r5b1 b - 1").is_err());
+/// ```
+pub fn hash_sfen(sfen: &str) -> Result<u64, SfenError> {
+    Board::parse_unvalidated(sfen, false).map(|board| board.hash())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_hash_after(board: &Board) {
+        for &mv in board.legal_moves().iter() {
+            assert_eq!(
+                board.hash_after(mv),
+                board.after_unchecked(mv).hash(),
+                "{board} {mv}"
+            );
+        }
+    }
+
+    #[test]
+    fn hash_after_matches_played_moves() {
+        for sfen in [
+            // capturing the checking Dragon puts a Rook in hand next to the one
+            // already there, and the drops block the check
+            "4k4/9/4+r4/9/9/9/B8/9/4K4 b R2Pp 1",
+            // the last piece of a kind is dropped, or one of many
+            "4k4/9/9/9/9/9/9/9/4K4 b G9P 1",
+            // promotions, and the King capturing
+            "4k4/P1L6/2N6/1S7/9/9/9/4g4/4K4 b - 1",
+        ] {
+            check_hash_after(&sfen.parse().unwrap());
+        }
+        for board in random_positions(0x2b7, 10, 150) {
+            check_hash_after(&board);
+        }
+    }
+
+    #[test]
+    fn hash_sfen_matches_board_hash() {
+        for sfen in include_str!("board/test_data/valid.sfens").lines() {
+            let board = Board::from_sfen(sfen).unwrap();
+            assert_eq!(hash_sfen(sfen).unwrap(), board.hash(), "{}", sfen);
+        }
+        // hands and side to move are part of the hash
+        let sfen = "lpg6/3s2R2/1kpppp3/p8/9/P8/2N6/9/9 b BGN 1";
+        let hash = hash_sfen(sfen).unwrap();
+        assert_ne!(hash, hash_sfen(&sfen.replace(" b ", " w ")).unwrap());
+        assert_ne!(hash, hash_sfen(&sfen.replace("BGN", "BGNP")).unwrap());
+    }
+}
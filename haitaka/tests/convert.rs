@@ -0,0 +1,116 @@
+// Round trips through the `haitaka-convert` binary.
+#![cfg(feature = "std")]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const KIF: &str = "\
+開始日時：2024/01/01 10:00:00
+手合割：平手
+先手：Alice
+後手：Bob
+手数----指手---------消費時間--
+*Start
+   1 ７六歩(77)   ( 0:03/00:00:03)
+   2 ３四歩(33)   ( 0:05/00:00:05)
+   3 ２二角成(88) ( 0:01/00:00:04)
+   4 同　銀(31)   ( 1:02/00:01:07)
+   5 ４五角打     ( 0:10/00:00:14)
+   6 ５二玉(51)   ( 0:00/00:01:07)
+   7 ６三角不成(45) ( 0:01/00:00:15)
+   8 投了         ( 0:00/00:01:07)
+";
+
+fn convert(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_haitaka-convert"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn kif_csa_kif_round_trip() {
+    let kif = convert(&["--to", "kif"], KIF);
+    let csa = convert(&["--to", "csa"], &kif);
+    assert!(csa.contains("N+Alice\nN-Bob\n$START_TIME:2024/01/01 10:00:00\nPI\n+\n"));
+    assert!(csa.contains("-3122GI\nT62\n"));
+    assert!(csa.ends_with("%TORYO\n"));
+    assert_eq!(convert(&["--to", "csa"], &csa), csa);
+
+    // CSA writes the players first
+    let back = convert(&["--to", "kif"], &csa);
+    assert!(
+        back.starts_with("手合割：平手\n先手：Alice\n後手：Bob\n開始日時：2024/01/01 10:00:00\n")
+    );
+    assert_eq!(
+        back.split_once("手数").unwrap().1,
+        kif.split_once("手数").unwrap().1
+    );
+    assert_eq!(convert(&["--to", "csa"], &back), csa);
+}
+
+#[test]
+fn sfen_round_trip() {
+    let sfen = convert(&["--to", "sfen"], KIF);
+    assert_eq!(
+        sfen,
+        "position startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e 5a5b 4e6c\n"
+    );
+    let csa = convert(&["--to", "csa"], &sfen);
+    assert_eq!(convert(&["--to", "sfen"], &csa), sfen);
+    let kif = convert(&["--from", "sfen", "--to", "kif"], &sfen);
+    assert_eq!(convert(&["--to", "sfen"], &kif), sfen);
+
+    // a position without the `position` keyword
+    let sfen = "4k4/9/4P4/9/9/9/9/9/4K4 b G 1 moves G*5b\n";
+    let csa = convert(&["--from", "sfen", "--to", "csa"], sfen);
+    assert!(csa.contains("P+00KI\n"));
+    assert_eq!(
+        convert(&["--to", "sfen"], &csa),
+        format!("position sfen {}", sfen)
+    );
+}
+
+#[test]
+fn errors_name_the_line() {
+    let output = Command::new(env!("CARGO_BIN_EXE_haitaka-convert"))
+        .args(["--to", "csa", "--from", "kif", "/nonexistent.kif"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_haitaka-convert"))
+        .args(["--to", "csa"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("手合割：平手\n   1 ７五歩(77)\n".as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "<stdin>: Illegal move (line 2)\n"
+    );
+}
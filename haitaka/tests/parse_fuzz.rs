@@ -0,0 +1,124 @@
+// Seeded fuzz tests for the text parsers.
+//
+// The parsers are fed random junk and mutations of valid input. They may reject
+// anything, but they must never panic, and whatever they accept must round-trip.
+use haitaka::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const ROUNDS: usize = 20_000;
+
+const ALPHABET: &[char] = &[
+    'P', 'L', 'N', 'S', 'B', 'R', 'G', 'K', 'p', 'l', 'n', 's', 'b', 'r', 'g', 'k', '+', '-', '*',
+    '=', 'x', '/', ' ', '0', '1', '5', '9', 'a', 'e', 'i', 'j', 'z', '歩', '５', '同',
+];
+
+const SEEDS: &[&str] = &[
+    "7g7f", "2c2b+", "P*5e", "+R8bx8f", "B8hx3c+", "R*5e", "2726FU", "0055KA", "+8822UM",
+];
+
+const SFENS: &[&str] = &[
+    "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+    "lnsgkgsnl/6gb1/p1pppp2p/6R2/9/1rP6/P2PPPP1P/1BG6/LNS1KGSNL w 3P2p 16",
+    "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b 18P4L4N4S2B2R4G 1",
+    "7k1/9/7P1/9/9/9/9/9/9 b G2r2b3g4s4n4l17p 1",
+];
+
+fn junk(rng: &mut StdRng) -> String {
+    let len = rng.random_range(0..12);
+    (0..len)
+        .map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())])
+        .collect()
+}
+
+fn mutate(rng: &mut StdRng, s: &str) -> String {
+    let mut chars: Vec<char> = s.chars().collect();
+    for _ in 0..rng.random_range(1..4) {
+        let c = ALPHABET[rng.random_range(0..ALPHABET.len())];
+        let i = rng.random_range(0..=chars.len());
+        match rng.random_range(0..3) {
+            0 => chars.insert(i, c),
+            1 if i < chars.len() => chars[i] = c,
+            _ if i < chars.len() => {
+                chars.remove(i);
+            }
+            _ => chars.push(c),
+        }
+    }
+    chars.into_iter().collect()
+}
+
+fn input(rng: &mut StdRng, seeds: &[&str]) -> String {
+    if rng.random_bool(0.5) {
+        junk(rng)
+    } else {
+        let seed = seeds[rng.random_range(0..seeds.len())];
+        mutate(rng, seed)
+    }
+}
+
+#[test]
+fn move_parsers_never_panic() {
+    let mut rng = StdRng::seed_from_u64(0x5f0b);
+    let board = Board::startpos();
+    for _ in 0..ROUNDS {
+        let s = input(&mut rng, SEEDS);
+
+        if let Ok(mv) = s.parse::<Move>() {
+            assert_eq!(mv.to_string().parse::<Move>().ok(), Some(mv), "{s:?}");
+        }
+        if let Ok(mv) = Move::parse(&s) {
+            assert_eq!(
+                mv.to_string().parse::<Move>().ok().map(|m| m.to()),
+                Some(mv.to())
+            );
+        }
+        for policy in [
+            PromotionPolicy::NeverImplicit,
+            PromotionPolicy::PreferPromotion,
+        ] {
+            let _ = Move::parse_with_policy(&s, policy);
+        }
+        if let Ok(mv) = Move::parse_csa(&s, |sq| board.piece_on(sq)) {
+            assert!(!mv.is_drop() || mv.piece().is_some_and(|p| !p.is_promoted()));
+        }
+        let _ = s.parse::<Square>();
+        let _ = s.parse::<Piece>();
+    }
+}
+
+#[test]
+fn sfen_parsers_never_panic() {
+    let mut rng = StdRng::seed_from_u64(0x5fe7);
+    for _ in 0..ROUNDS {
+        let seed = SFENS[rng.random_range(0..SFENS.len())];
+        let s = mutate(&mut rng, seed);
+
+        if let Ok(board) = Board::from_sfen(&s) {
+            assert_eq!(
+                Board::from_sfen(&board.to_string()).ok(),
+                Some(board),
+                "{s:?}"
+            );
+        }
+        let _ = Board::tsume(&s);
+    }
+}
+
+#[test]
+fn csa_parser_never_panics() {
+    const CSA: &str =
+        "N+Alice\nP1 *  *  *  * -OU\nP3 *  *  *  * +FU\nP+00KI\nP-00AL\n+\n+0052KI,T5\n%TSUMI\n";
+    let mut rng = StdRng::seed_from_u64(0xc5a);
+    for _ in 0..ROUNDS {
+        let s = mutate(&mut rng, CSA);
+
+        if let Ok(record) = csa::parse(&s) {
+            assert_eq!(
+                csa::parse(&csa::write(&record).unwrap()).ok(),
+                Some(record),
+                "{s:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,191 @@
+// Public API snapshot test.
+//
+// This collects the declarations of all `pub` items in the public modules of `haitaka`
+// and `haitaka-types` and compares them with the snapshot in `tests/public-api.txt`.
+// The goal is to make every change of the public API explicit in review, so that new
+// features don't accidentally break downstream engines.
+//
+// After an intentional API change, update the snapshot with:
+//
+//     UPDATE_PUBLIC_API=1 cargo test --test public_api
+//
+// This is a simple source scan (in the spirit of `cargo public-api`, which needs a
+// nightly toolchain). It does not resolve re-exports or macro-generated items.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Source files that are not reachable from the public API.
+const PRIVATE_FILES: &[&str] = &[
+    "haitaka/src/board/movegen/tests.rs",
+    "haitaka/src/board/storage.rs",
+    "haitaka/src/board/zobrist.rs",
+    "haitaka_types/src/helpers.rs",
+];
+
+fn source_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            source_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+// Find the end of a declaration: the first `{`, `;` or `=` outside of brackets,
+// or a trailing `,` (for struct fields).
+fn declaration_end(decl: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in decl.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '{' | ';' | '=' if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    if depth == 0 && decl.ends_with(',') {
+        Some(decl.len())
+    } else {
+        None
+    }
+}
+
+// Extract the declarations of `pub` items, one per line, up to the item body.
+// Items in an `impl` block are prefixed with the `impl` header. Items marked
+// `#[doc(hidden)]` and macro templates are skipped.
+fn public_items(source: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut context: Option<String> = None;
+    let mut hidden = false;
+    let mut lines = source.lines();
+
+    while let Some(raw) = lines.next() {
+        if raw.starts_with("#[cfg(test)]") {
+            match lines.next() {
+                // inline unit tests are always at the end of the file
+                Some(next) if next.ends_with('{') => break,
+                // a declaration of a test module in a separate file
+                _ => continue,
+            }
+        }
+        if raw.starts_with("impl") {
+            if !raw.ends_with("{}") {
+                context = Some(raw.trim_end_matches(['{', ' ']).to_string());
+            }
+            continue;
+        }
+        if raw == "}" {
+            context = None;
+            continue;
+        }
+
+        let line = raw.trim();
+        if line == "#[doc(hidden)]" {
+            hidden = true;
+            continue;
+        }
+        if line.starts_with("#[") || line.starts_with("//") {
+            continue;
+        }
+        if core::mem::take(&mut hidden)
+            || !line.starts_with("pub ")
+            || line.starts_with("pub use")
+            || line.starts_with("pub mod")
+            || line.contains('$')
+        {
+            continue;
+        }
+
+        let mut decl = String::from(line);
+        while declaration_end(&decl).is_none() {
+            match lines.next() {
+                Some(next) => {
+                    let next = next.trim();
+                    if !next.starts_with("//") && !next.starts_with("#[") {
+                        decl.push(' ');
+                        decl.push_str(next);
+                    }
+                }
+                None => break,
+            }
+        }
+        let end = declaration_end(&decl).unwrap_or(decl.len());
+        let decl = decl[..end]
+            .trim_end_matches([' ', ','])
+            .replace("( ", "(")
+            .replace(", )", ")");
+        let decl = decl.split_whitespace().collect::<Vec<_>>().join(" ");
+        match &context {
+            Some(context) => items.push(format!("{} | {}", context, decl)),
+            None => items.push(decl),
+        }
+    }
+    items
+}
+
+fn snapshot() -> String {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let root = manifest_dir.parent().unwrap();
+
+    let mut files = Vec::new();
+    source_files(&root.join("haitaka_types/src"), &mut files);
+    source_files(&manifest_dir.join("src"), &mut files);
+
+    let mut out = String::new();
+    for path in files {
+        let name = path
+            .strip_prefix(root)
+            .unwrap()
+            .to_string_lossy()
+            .replace('\\', "/");
+        if PRIVATE_FILES.contains(&name.as_str()) {
+            continue;
+        }
+        let items = public_items(&fs::read_to_string(&path).unwrap());
+        if items.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("# {}\n", name));
+        for item in items {
+            out.push_str(&item);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[test]
+fn public_api_is_unchanged() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/public-api.txt");
+    let actual = snapshot();
+
+    if std::env::var_os("UPDATE_PUBLIC_API").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    if actual != expected {
+        let removed: Vec<_> = expected
+            .lines()
+            .filter(|line| !actual.lines().any(|l| l == *line))
+            .collect();
+        let added: Vec<_> = actual
+            .lines()
+            .filter(|line| !expected.lines().any(|l| l == *line))
+            .collect();
+        panic!(
+            "The public API changed.\nRemoved:\n  {}\nAdded:\n  {}\n\
+             Run `UPDATE_PUBLIC_API=1 cargo test --test public_api` to accept the change.",
+            removed.join("\n  "),
+            added.join("\n  ")
+        );
+    }
+}
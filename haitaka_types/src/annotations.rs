@@ -0,0 +1,177 @@
+//! [`Annotations`] for marking squares and drawing arrows on a board
+//!
+//! GUIs and teaching material often highlight squares or draw arrows on a position.
+//! [`Annotations`] holds such marks, with a small `u8` tag per mark that applications
+//! can map to colors or styles. Annotations have a compact text form, so that they can
+//! be stored next to a position (for instance after an SFEN string or in a comment of a
+//! game record):
+//!
+//! - a marked square is written as the square, such as `5e`;
+//! - an arrow is written as the from and to square, such as `7g7f`;
+//! - either may be followed by `@` and a tag, such as `5e@2`. The default tag is 0.
+//!
+//! Marks and arrows are separated by spaces. Marks are written before arrows.
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::str::FromStr;
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::*;
+
+/// A marked square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mark {
+    /// The marked square.
+    pub square: Square,
+    /// An application-defined tag, such as a color index.
+    pub tag: u8,
+}
+
+/// An arrow from one square to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Arrow {
+    /// The square the arrow starts on.
+    pub from: Square,
+    /// The square the arrow points to.
+    pub to: Square,
+    /// An application-defined tag, such as a color index.
+    pub tag: u8,
+}
+
+/// Marked squares and arrows on a board.
+///
+/// # Examples
+/// ```
+/// # use haitaka_types::*;
+/// let mut annotations = Annotations::new();
+/// annotations.mark(Square::E5, 0);
+/// annotations.arrow(Square::G7, Square::F7, 2);
+/// assert_eq!(annotations.to_string(), "5e 7g7f@2");
+///
+/// let parsed: Annotations = "5e 7g7f@2".parse().unwrap();
+/// assert_eq!(parsed, annotations);
+/// assert_eq!(parsed.marked_squares(), Square::E5.bitboard());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Annotations {
+    /// The marked squares, in the order they were added.
+    pub marks: Vec<Mark>,
+    /// The arrows, in the order they were added.
+    pub arrows: Vec<Arrow>,
+}
+
+crate::helpers::simple_error! {
+    /// The value was not valid [`Annotations`].
+    pub enum AnnotationsParseError {
+        InvalidSquare = "Invalid square",
+        InvalidTag = "Invalid tag"
+    }
+}
+
+impl Annotations {
+    /// Create empty annotations.
+    pub const fn new() -> Self {
+        Self {
+            marks: Vec::new(),
+            arrows: Vec::new(),
+        }
+    }
+
+    /// Mark a square.
+    pub fn mark(&mut self, square: Square, tag: u8) {
+        self.marks.push(Mark { square, tag });
+    }
+
+    /// Add an arrow.
+    pub fn arrow(&mut self, from: Square, to: Square, tag: u8) {
+        self.arrows.push(Arrow { from, to, tag });
+    }
+
+    /// Are there no marks and no arrows?
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty() && self.arrows.is_empty()
+    }
+
+    /// Remove all marks and arrows.
+    pub fn clear(&mut self) {
+        self.marks.clear();
+        self.arrows.clear();
+    }
+
+    /// Get a [`BitBoard`] of all marked squares (regardless of their tags).
+    pub fn marked_squares(&self) -> BitBoard {
+        self.marks
+            .iter()
+            .fold(BitBoard::EMPTY, |bb, mark| bb | mark.square.bitboard())
+    }
+}
+
+// Write the tag suffix (the default tag is omitted).
+fn write_tag(f: &mut Formatter<'_>, tag: u8) -> FmtResult {
+    if tag != 0 {
+        write!(f, "@{}", tag)
+    } else {
+        Ok(())
+    }
+}
+
+impl Display for Annotations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mut sep = "";
+        for mark in &self.marks {
+            write!(f, "{}{}", sep, mark.square)?;
+            write_tag(f, mark.tag)?;
+            sep = " ";
+        }
+        for arrow in &self.arrows {
+            write!(f, "{}{}{}", sep, arrow.from, arrow.to)?;
+            write_tag(f, arrow.tag)?;
+            sep = " ";
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Annotations {
+    type Err = AnnotationsParseError;
+
+    /// Parse the text form of annotations (see the [module documentation](self)).
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka_types::*;
+    /// let annotations: Annotations = "  2b@1 8h2b@3 ".parse().unwrap();
+    /// assert_eq!(annotations.marks, [Mark { square: Square::B2, tag: 1 }]);
+    /// assert_eq!(annotations.arrows.len(), 1);
+    /// assert!("".parse::<Annotations>().unwrap().is_empty());
+    ///
+    /// assert!("5j".parse::<Annotations>().is_err());
+    /// assert!("5e@".parse::<Annotations>().is_err());
+    /// assert!("5e@256".parse::<Annotations>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut annotations = Annotations::new();
+        for token in s.split_ascii_whitespace() {
+            let (squares, tag) = match token.split_once('@') {
+                Some((squares, tag)) => (
+                    squares,
+                    tag.parse().map_err(|_| AnnotationsParseError::InvalidTag)?,
+                ),
+                None => (token, 0),
+            };
+            let square = |s: &str| s.parse().map_err(|_| AnnotationsParseError::InvalidSquare);
+            match squares.len() {
+                2 => annotations.mark(square(squares)?, tag),
+                4 if squares.is_char_boundary(2) => {
+                    annotations.arrow(square(&squares[..2])?, square(&squares[2..])?, tag)
+                }
+                _ => return Err(AnnotationsParseError::InvalidSquare),
+            }
+        }
+        Ok(annotations)
+    }
+}
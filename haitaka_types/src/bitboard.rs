@@ -978,6 +978,70 @@ impl BitBoard {
         BitBoardIter(self)
     }
 
+    /// Call `f` for every square in the bitboard, ordered by square.
+    ///
+    /// This visits the same squares as [`BitBoard::iter`], but splits the bitboard
+    /// into its low and high 64-bit halves and loops over each half separately. On
+    /// targets where 128-bit `trailing_zeros` compiles to a library call, this keeps
+    /// the loop on native 64-bit instructions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka_types::*;
+    /// let bb = Square::A1.bitboard() | Square::E5.bitboard() | Square::I9.bitboard();
+    /// let mut squares = Vec::new();
+    /// bb.for_each_square(|square| squares.push(square));
+    /// assert_eq!(squares, bb.iter().collect::<Vec<_>>());
+    /// ```
+    #[inline(always)]
+    pub fn for_each_square(self, mut f: impl FnMut(Square)) {
+        self.try_for_each_square(|square| {
+            f(square);
+            false
+        });
+    }
+
+    /// Call `f` for every square in the bitboard, ordered by square, until `f` returns `true`.
+    ///
+    /// Returns `true` if `f` interrupted the loop, otherwise `false`.
+    /// See also [`BitBoard::for_each_square`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka_types::*;
+    /// let bb = Square::A1.bitboard() | Square::E5.bitboard() | Square::I9.bitboard();
+    /// let mut count = 0;
+    /// assert!(bb.try_for_each_square(|square| {
+    ///     count += 1;
+    ///     square == Square::E5
+    /// }));
+    /// assert_eq!(count, 2);
+    /// assert!(!bb.try_for_each_square(|_| false));
+    /// ```
+    #[inline(always)]
+    pub fn try_for_each_square(self, mut f: impl FnMut(Square) -> bool) -> bool {
+        let mut lo = self.0 as u64;
+        let mut hi = (self.0 >> 64) as u64;
+
+        while lo != 0 {
+            let index = lo.trailing_zeros() as usize;
+            lo &= lo - 1;
+            if f(Square::index_const(index)) {
+                return true;
+            }
+        }
+        while hi != 0 {
+            let index = 64 + hi.trailing_zeros() as usize;
+            hi &= hi - 1;
+            if f(Square::index_const(index)) {
+                return true;
+            }
+        }
+        false
+    }
+
     /// Iterate over all subsets of a bitboard.
     ///
     /// Subsets are produced in lexicographic order. Each subset is greater than the last.
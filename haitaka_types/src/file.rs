@@ -136,4 +136,72 @@ impl File {
     pub const fn east(self) -> BitBoard {
         BitBoard::new(BitBoard::FULL.0 >> (9 * (9 - self as usize)))
     }
+
+    /// Iterate over the squares on this file.
+    ///
+    /// Squares are returned from north to south, in the order of [`Rank::ALL`]
+    /// (so from `Rank::A` to `Rank::I`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka_types::*;
+    /// let mut squares = File::Five.squares();
+    /// assert_eq!(squares.len(), 9);
+    /// assert_eq!(squares.next(), Some(Square::A5));
+    /// assert_eq!(squares.next(), Some(Square::B5));
+    /// assert_eq!(squares.last(), Some(Square::I5));
+    ///
+    /// let bb: BitBoard = File::Two.squares().collect();
+    /// assert_eq!(bb, File::Two.bitboard());
+    /// ```
+    #[inline(always)]
+    pub fn squares(self) -> FileSquares {
+        FileSquares {
+            file: self,
+            rank: 0,
+        }
+    }
+}
+
+impl IntoIterator for File {
+    type Item = Square;
+
+    type IntoIter = FileSquares;
+
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.squares()
+    }
+}
+
+/// An iterator over the squares of a file.
+///
+/// This `struct` is created by [`File::squares`]. See its documentation for more.
+pub struct FileSquares {
+    file: File,
+    rank: usize,
+}
+
+impl Iterator for FileSquares {
+    type Item = Square;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let rank = Rank::try_index(self.rank)?;
+        self.rank += 1;
+        Some(Square::new(self.file, rank))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl ExactSizeIterator for FileSquares {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        Rank::NUM.saturating_sub(self.rank)
+    }
 }
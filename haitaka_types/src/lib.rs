@@ -1,15 +1,22 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![doc = include_str!("../README.md")]
+pub mod annotations;
 pub mod bitboard;
 pub mod color;
 pub mod file;
+#[doc(hidden)]
 pub mod helpers;
 pub mod piece;
 pub mod rank;
+#[cfg(feature = "serde")]
+mod serde_impls;
 pub mod shogi_move;
+#[cfg(feature = "interop-shogi-core")]
+mod shogi_core_impls;
 pub mod sliders;
 pub mod square;
 
+pub use annotations::*;
 pub use bitboard::*;
 pub use color::*;
 pub use file::*;
@@ -61,6 +61,35 @@ impl Piece {
     /// Number of simple, unpromoted piece types other than King.
     pub const HAND_NUM: usize = 7;
 
+    /// A heuristic order in which to try drops during search.
+    ///
+    /// Golds and Silvers come first, since drops next to a King (to attack or to
+    /// defend it) are most often good moves. They are followed by the Knight and Lance,
+    /// which are strong when dropped close to the enemy camp, and by the Rook and
+    /// Bishop, which are usually dropped at a distance and have many target squares.
+    /// The Pawn comes last: it is the least valuable piece and its drops are rarely
+    /// decisive, except for tactical tricks that a search will find anyway.
+    ///
+    /// This order is only a default. Use `Board::generate_drops_in_order` (in `haitaka`)
+    /// to generate drops in this or any other order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka_types::*;
+    /// assert_eq!(Piece::DROP_ORDER.len(), Piece::HAND_NUM);
+    /// assert_eq!(Piece::DROP_ORDER[0], Piece::Gold);
+    /// assert_eq!(Piece::DROP_ORDER[Piece::HAND_NUM - 1], Piece::Pawn);
+    /// ```
+    pub const DROP_ORDER: [Self; Self::HAND_NUM] = [
+        Piece::Gold,
+        Piece::Silver,
+        Piece::Knight,
+        Piece::Lance,
+        Piece::Rook,
+        Piece::Bishop,
+        Piece::Pawn,
+    ];
+
     /// Max number of pieces for a piece type to have in hand
     pub const MAX_HAND: [u8; Self::NUM] = [
         18, // Pawn
@@ -140,15 +169,7 @@ impl Piece {
     /// ```
     #[inline(always)]
     pub const fn can_promote(self, color: Color, square: Square) -> bool {
-        if !self.is_promotable() {
-            false
-        } else {
-            let rank = square.rank() as usize;
-            match color {
-                Color::White => rank > 5,
-                Color::Black => rank < 3,
-            }
-        }
+        self.is_promotable() && IN_PROM_ZONE[color as usize][square as usize]
     }
 
     /// Must this piece with given color promote on the given square?
@@ -325,6 +346,78 @@ impl core::str::FromStr for Piece {
     }
 }
 
+/// Must `piece` of the given color promote when it moves to `to`?
+///
+/// This is the case for Pawns and Lances moving to the last rank and for Knights
+/// moving to the last two ranks, since those pieces would otherwise have no
+/// legal moves left. A GUI can use this to promote automatically without asking.
+///
+/// # Examples
+///
+/// ```
+/// use haitaka_types::*;
+/// // Pawns and Lances on the last rank
+/// assert!(must_promote(Piece::Pawn, Color::Black, Square::A5));
+/// assert!(must_promote(Piece::Lance, Color::Black, Square::A9));
+/// assert!(must_promote(Piece::Pawn, Color::White, Square::I5));
+/// assert!(must_promote(Piece::Lance, Color::White, Square::I1));
+/// assert!(!must_promote(Piece::Pawn, Color::Black, Square::B5));
+/// assert!(!must_promote(Piece::Lance, Color::White, Square::H1));
+/// // Knights on the last two ranks
+/// assert!(must_promote(Piece::Knight, Color::Black, Square::A3));
+/// assert!(must_promote(Piece::Knight, Color::Black, Square::B3));
+/// assert!(!must_promote(Piece::Knight, Color::Black, Square::C3));
+/// assert!(must_promote(Piece::Knight, Color::White, Square::I7));
+/// assert!(must_promote(Piece::Knight, Color::White, Square::H7));
+/// assert!(!must_promote(Piece::Knight, Color::White, Square::G7));
+/// // Other pieces never have to promote
+/// assert!(!must_promote(Piece::Silver, Color::Black, Square::A5));
+/// assert!(!must_promote(Piece::Rook, Color::White, Square::I5));
+/// // The last rank of one side is not the last rank of the other side
+/// assert!(!must_promote(Piece::Pawn, Color::White, Square::A5));
+/// assert!(!must_promote(Piece::Knight, Color::Black, Square::H3));
+/// ```
+#[inline(always)]
+pub const fn must_promote(piece: Piece, color: Color, to: Square) -> bool {
+    piece.must_promote(color, to)
+}
+
+/// Can the player choose whether `piece` of the given color promotes when it moves from `from` to `to`?
+///
+/// This is the case if the move starts or ends in the promotion zone, and the piece
+/// is not forced to promote on `to`. A GUI can use this to decide whether to show a
+/// promotion dialog.
+///
+/// # Examples
+///
+/// ```
+/// use haitaka_types::*;
+/// // Moving into, inside and out of the promotion zone
+/// assert!(can_choose_promotion(Piece::Silver, Color::Black, Square::D5, Square::C5));
+/// assert!(can_choose_promotion(Piece::Silver, Color::Black, Square::B5, Square::C4));
+/// assert!(can_choose_promotion(Piece::Silver, Color::Black, Square::C5, Square::D4));
+/// assert!(can_choose_promotion(Piece::Rook, Color::White, Square::B5, Square::G5));
+/// assert!(!can_choose_promotion(Piece::Rook, Color::White, Square::B5, Square::F5));
+/// // Pawns and Lances on the last rank have no choice
+/// assert!(can_choose_promotion(Piece::Pawn, Color::Black, Square::C5, Square::B5));
+/// assert!(!can_choose_promotion(Piece::Pawn, Color::Black, Square::B5, Square::A5));
+/// assert!(can_choose_promotion(Piece::Lance, Color::White, Square::E1, Square::H1));
+/// assert!(!can_choose_promotion(Piece::Lance, Color::White, Square::E1, Square::I1));
+/// // Knights on the last two ranks have no choice
+/// assert!(can_choose_promotion(Piece::Knight, Color::Black, Square::E3, Square::C4));
+/// assert!(!can_choose_promotion(Piece::Knight, Color::Black, Square::D3, Square::B4));
+/// assert!(can_choose_promotion(Piece::Knight, Color::White, Square::E3, Square::G4));
+/// assert!(!can_choose_promotion(Piece::Knight, Color::White, Square::F3, Square::H4));
+/// // Golds, Kings and promoted pieces never promote
+/// assert!(!can_choose_promotion(Piece::Gold, Color::Black, Square::D5, Square::C5));
+/// assert!(!can_choose_promotion(Piece::Tokin, Color::Black, Square::B5, Square::A5));
+/// ```
+#[inline(always)]
+pub const fn can_choose_promotion(piece: Piece, color: Color, from: Square, to: Square) -> bool {
+    (piece.can_promote(color, from) || piece.can_promote(color, to))
+        && !piece.must_promote(color, to)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ColoredPiece {
     pub piece: Piece,
@@ -176,6 +176,67 @@ pub const fn must_prom_zone(color: Color, piece: Piece) -> BitBoard {
     }
 }
 
+// Expand a bitboard into a lookup table indexed by square.
+const fn square_table(bb: BitBoard) -> [bool; Square::NUM] {
+    let mut table = [false; Square::NUM];
+    let mut i = 0;
+    while i < Square::NUM {
+        table[i] = bb.0 & (1u128 << i) != 0;
+        i += 1;
+    }
+    table
+}
+
+/// Is the square in the promotion zone of the color? Indexed by `[color][square]`.
+///
+/// This is [`prom_zone`] as a lookup table, for code that tests single squares
+/// (such as evaluation code). It avoids the branch on the color.
+///
+/// # Examples
+/// ```
+/// # use haitaka_types::*;
+/// for color in Color::ALL {
+///     for square in Square::ALL {
+///         let in_zone = IN_PROM_ZONE[color as usize][square as usize];
+///         assert_eq!(in_zone, prom_zone(color).has(square));
+///     }
+/// }
+/// ```
+pub const IN_PROM_ZONE: [[bool; Square::NUM]; Color::NUM] = [
+    square_table(prom_zone(Color::White)),
+    square_table(prom_zone(Color::Black)),
+];
+
+/// Is the square in the camp of the color? Indexed by `[color][square]`.
+///
+/// The camp of a player consists of the three ranks closest to that player. It is the
+/// promotion zone of the other player.
+///
+/// # Examples
+/// ```
+/// # use haitaka_types::*;
+/// assert!(IN_CAMP[Color::Black as usize][Square::I5 as usize]);
+/// assert!(IN_CAMP[Color::White as usize][Square::A5 as usize]);
+/// assert!(!IN_CAMP[Color::Black as usize][Square::F5 as usize]);
+/// assert_eq!(IN_CAMP[Color::Black as usize], IN_PROM_ZONE[Color::White as usize]);
+/// ```
+pub const IN_CAMP: [[bool; Square::NUM]; Color::NUM] = [
+    square_table(prom_zone(Color::Black)),
+    square_table(prom_zone(Color::White)),
+];
+
+/// The last rank of each color, indexed by color.
+///
+/// Pawns and Lances must promote on the last rank and cannot be dropped there.
+///
+/// # Examples
+/// ```
+/// # use haitaka_types::*;
+/// assert_eq!(LAST_RANKS[Color::Black as usize], Rank::A.bitboard());
+/// assert_eq!(LAST_RANKS[Color::White as usize], must_prom_zone(Color::White, Piece::Pawn));
+/// ```
+pub const LAST_RANKS: [BitBoard; Color::NUM] = [RANK_I, RANK_A];
+
 impl Rank {
     /// Bitboards for the 9 ranks.
     pub const RANK: [BitBoard; Self::NUM] = [
@@ -323,4 +384,75 @@ impl Rank {
             Color::Black => self.flip(),
         }
     }
+
+    /// Iterate over the squares on this rank.
+    ///
+    /// Squares are returned from east to west, in the order of [`File::ALL`]
+    /// (so from `File::One` to `File::Nine`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use haitaka_types::*;
+    /// let mut squares = Rank::E.squares();
+    /// assert_eq!(squares.len(), 9);
+    /// assert_eq!(squares.next(), Some(Square::E1));
+    /// assert_eq!(squares.next(), Some(Square::E2));
+    /// assert_eq!(squares.last(), Some(Square::E9));
+    ///
+    /// let mut bb = BitBoard::EMPTY;
+    /// for square in Rank::C {
+    ///     bb |= square.bitboard();
+    /// }
+    /// assert_eq!(bb, Rank::C.bitboard());
+    /// ```
+    #[inline(always)]
+    pub fn squares(self) -> RankSquares {
+        RankSquares {
+            rank: self,
+            file: 0,
+        }
+    }
+}
+
+impl IntoIterator for Rank {
+    type Item = Square;
+
+    type IntoIter = RankSquares;
+
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.squares()
+    }
+}
+
+/// An iterator over the squares of a rank.
+///
+/// This `struct` is created by [`Rank::squares`]. See its documentation for more.
+pub struct RankSquares {
+    rank: Rank,
+    file: usize,
+}
+
+impl Iterator for RankSquares {
+    type Item = Square;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let file = File::try_index(self.file)?;
+        self.file += 1;
+        Some(Square::new(file, self.rank))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl ExactSizeIterator for RankSquares {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        File::NUM.saturating_sub(self.file)
+    }
 }
@@ -0,0 +1,133 @@
+// Serde support (with the `serde` feature).
+//
+// The types are stored in their usual text forms, so that they are readable in JSON
+// and stable across versions of the crate:
+//
+// - `Color`: `"b"` or `"w"`
+// - `Square`: `"5e"`
+// - `Piece`: the SFEN letter of the Black piece, such as `"P"` or `"+R"`
+// - `Move`: the USI move, such as `"7g7f"` or `"P*5e"`
+// - `BitBoard`: a hex string such as `"0x1ff"` in human-readable formats, and the
+//   backing `u128` in binary formats
+use core::fmt;
+use core::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, Unexpected, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::*;
+
+// Deserialize a type from a string with its `FromStr` impl.
+struct FromStrVisitor<T>(&'static str, core::marker::PhantomData<T>);
+
+impl<T: FromStr> Visitor<'_> for FromStrVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+macro_rules! serde_via_str {
+    ($($type:ty = $expecting:literal),*) => {$(
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_str(FromStrVisitor($expecting, core::marker::PhantomData))
+            }
+        }
+    )*};
+}
+
+serde_via_str! {
+    Color = "a color (\"b\" or \"w\")",
+    Square = "a square such as \"5e\"",
+    Move = "a USI move such as \"7g7f\""
+}
+
+const PIECE_NAMES: [&str; Piece::NUM] = [
+    "P", "L", "N", "S", "B", "R", "G", "K", "+P", "+L", "+N", "+S", "+B", "+R",
+];
+
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(PIECE_NAMES[*self as usize])
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(FromStrVisitor(
+            "a piece such as \"P\" or \"+R\"",
+            core::marker::PhantomData,
+        ))
+    }
+}
+
+impl Serialize for BitBoard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&format_args!("{:#x}", self.0))
+        } else {
+            serializer.serialize_u128(self.0)
+        }
+    }
+}
+
+struct BitBoardVisitor;
+
+impl BitBoardVisitor {
+    fn check<E: de::Error>(self, bits: u128) -> Result<BitBoard, E> {
+        if bits & !BitBoard::FULL.0 == 0 {
+            Ok(BitBoard(bits))
+        } else {
+            Err(E::invalid_value(
+                Unexpected::Other("bits beyond square 81"),
+                &self,
+            ))
+        }
+    }
+}
+
+impl Visitor<'_> for BitBoardVisitor {
+    type Value = BitBoard;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a bitboard of 81 squares, as hex string or integer")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<BitBoard, E> {
+        let digits = v.strip_prefix("0x").unwrap_or(v);
+        let bits = u128::from_str_radix(digits, 16)
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))?;
+        self.check(bits)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<BitBoard, E> {
+        self.check(v as u128)
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<BitBoard, E> {
+        self.check(v)
+    }
+}
+
+impl<'de> Deserialize<'de> for BitBoard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BitBoardVisitor)
+        } else {
+            deserializer.deserialize_u128(BitBoardVisitor)
+        }
+    }
+}
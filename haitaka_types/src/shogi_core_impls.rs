@@ -0,0 +1,143 @@
+// Conversions to and from the types of the `shogi_core` crate (with the
+// `interop-shogi-core` feature).
+//
+// Both crates number the squares in file-major order from 1a, so a square keeps its
+// index: `Square::A1` is `shogi_core::Square::SQ_1A`, and `square as usize` is
+// `shogi_core::Square::array_index`.
+//
+// `shogi_core::Piece` is a piece with its color, which corresponds to a `(Color,
+// Piece)` pair here. The orphan rule does not allow `From` impls for tuples, so use
+// `shogi_core::Piece::new(piece.into(), color.into())` and
+// `shogi_core::Piece::to_parts`. For the same reason, drops need the color of the
+// side to move to be converted, see `Move::to_shogi_core`.
+use crate::*;
+
+impl From<Color> for shogi_core::Color {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Black => shogi_core::Color::Black,
+            Color::White => shogi_core::Color::White,
+        }
+    }
+}
+
+impl From<shogi_core::Color> for Color {
+    fn from(color: shogi_core::Color) -> Self {
+        match color {
+            shogi_core::Color::Black => Color::Black,
+            shogi_core::Color::White => Color::White,
+        }
+    }
+}
+
+impl From<Square> for shogi_core::Square {
+    fn from(square: Square) -> Self {
+        // shogi_core numbers the squares from 1
+        shogi_core::Square::from_u8(square as u8 + 1).unwrap()
+    }
+}
+
+impl From<shogi_core::Square> for Square {
+    fn from(square: shogi_core::Square) -> Self {
+        Square::index_const(square.array_index())
+    }
+}
+
+impl From<Piece> for shogi_core::PieceKind {
+    fn from(piece: Piece) -> Self {
+        use shogi_core::PieceKind as Kind;
+        match piece {
+            Piece::Pawn => Kind::Pawn,
+            Piece::Lance => Kind::Lance,
+            Piece::Knight => Kind::Knight,
+            Piece::Silver => Kind::Silver,
+            Piece::Bishop => Kind::Bishop,
+            Piece::Rook => Kind::Rook,
+            Piece::Gold => Kind::Gold,
+            Piece::King => Kind::King,
+            Piece::Tokin => Kind::ProPawn,
+            Piece::PLance => Kind::ProLance,
+            Piece::PKnight => Kind::ProKnight,
+            Piece::PSilver => Kind::ProSilver,
+            Piece::PBishop => Kind::ProBishop,
+            Piece::PRook => Kind::ProRook,
+        }
+    }
+}
+
+impl From<shogi_core::PieceKind> for Piece {
+    fn from(kind: shogi_core::PieceKind) -> Self {
+        use shogi_core::PieceKind as Kind;
+        match kind {
+            Kind::Pawn => Piece::Pawn,
+            Kind::Lance => Piece::Lance,
+            Kind::Knight => Piece::Knight,
+            Kind::Silver => Piece::Silver,
+            Kind::Bishop => Piece::Bishop,
+            Kind::Rook => Piece::Rook,
+            Kind::Gold => Piece::Gold,
+            Kind::King => Piece::King,
+            Kind::ProPawn => Piece::Tokin,
+            Kind::ProLance => Piece::PLance,
+            Kind::ProKnight => Piece::PKnight,
+            Kind::ProSilver => Piece::PSilver,
+            Kind::ProBishop => Piece::PBishop,
+            Kind::ProRook => Piece::PRook,
+        }
+    }
+}
+
+// The color of a dropped piece is left out, as in USI.
+impl From<shogi_core::Move> for Move {
+    fn from(mv: shogi_core::Move) -> Self {
+        match mv {
+            shogi_core::Move::Normal { from, to, promote } => Move::BoardMove {
+                from: from.into(),
+                to: to.into(),
+                promotion: promote,
+            },
+            shogi_core::Move::Drop { piece, to } => Move::Drop {
+                piece: piece.piece_kind().into(),
+                to: to.into(),
+            },
+        }
+    }
+}
+
+impl Move {
+    /// Convert the move to a [`shogi_core::Move`], played by `color`.
+    ///
+    /// The color is only used for drops, since shogi_core stores the color of the
+    /// dropped piece (`interop-shogi-core` feature).
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka_types::*;
+    /// let mv: Move = "P*5e".parse().unwrap();
+    /// let converted = mv.to_shogi_core(Color::White);
+    /// assert_eq!(
+    ///     converted,
+    ///     shogi_core::Move::Drop { piece: shogi_core::Piece::W_P, to: shogi_core::Square::SQ_5E }
+    /// );
+    /// // `Move::from` is the source square, so use `into` for the conversion back
+    /// let back: Move = converted.into();
+    /// assert_eq!(back, mv);
+    /// ```
+    pub fn to_shogi_core(self, color: Color) -> shogi_core::Move {
+        match self {
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => shogi_core::Move::Normal {
+                from: from.into(),
+                to: to.into(),
+                promote: promotion,
+            },
+            Move::Drop { piece, to } => shogi_core::Move::Drop {
+                piece: shogi_core::Piece::new(piece.into(), color.into()),
+                to: to.into(),
+            },
+        }
+    }
+}
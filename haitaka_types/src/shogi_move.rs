@@ -8,6 +8,30 @@ use crate::*;
 use core::str::FromStr;
 
 /// A Shogi move.
+///
+/// Moves are totally ordered, so that lists of moves can be sorted into a canonical
+/// order (for instance to compare the output of two versions of a move generator).
+/// This order is stable across versions:
+///
+/// - Board moves come before drops.
+/// - Board moves are ordered by source square, then by destination square, and then
+///   a non-promotion comes before a promotion.
+/// - Drops are ordered by piece, then by destination square.
+///
+/// Squares and pieces are ordered by their index (see [`Square`] and [`Piece`]).
+///
+/// # Examples
+/// ```
+/// use haitaka_types::*;
+///
+/// let mut moves: Vec<Move> = ["P*5e", "7g7f", "2c2b+", "2c2b", "G*5e", "B*1a"]
+///     .iter()
+///     .map(|mv| mv.parse().unwrap())
+///     .collect();
+/// moves.sort();
+/// let sorted: Vec<String> = moves.iter().map(|mv| mv.to_string()).collect();
+/// assert_eq!(sorted, ["2c2b", "2c2b+", "7g7f", "P*5e", "B*1a", "G*5e"]);
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Move {
     Drop {
@@ -23,14 +47,35 @@ pub enum Move {
 
 crate::helpers::simple_error! {
     /// The value was not a valid [`Move`].
+    #[non_exhaustive]
     pub enum MoveParseError {
         InvalidPiece = "Invalid piece",
         InvalidSquare = "Invalid square",
         InvalidFormat = "Invalid move format",
-        ExtraCharacters = "Extra characters found"
+        ExtraCharacters = "Extra characters found",
+        InvalidDropPiece = "Only unpromoted pieces other than the King can be dropped",
+        PieceMismatch = "The piece does not match the piece on the board"
     }
 }
 
+/// How to read a board move without promotion marker in [`Move::parse_with_policy`].
+///
+/// USI only marks promotions (with a trailing `+`), but some GUIs and game logs
+/// omit the marker on forced promotions, or mark non-promotions with a trailing `=`.
+/// The policy decides what an unmarked board move means. Use `Board::normalize_move`
+/// (in `haitaka`) afterwards to resolve promotion flags that don't fit the moved piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum PromotionPolicy {
+    /// An unmarked board move is not a promotion (as in USI).
+    #[default]
+    NeverImplicit,
+    /// An unmarked board move is a promotion.
+    ///
+    /// Only a trailing `=` marks a non-promotion.
+    PreferPromotion,
+}
+
 impl Move {
     /// Is this move a promotion?
     #[inline(always)]
@@ -91,6 +136,165 @@ impl Move {
         }
     }
 
+    /// Encode the move in 16 bits, for instance to store it in a transposition table.
+    ///
+    /// The layout is stable across versions:
+    ///
+    /// | Bits   | Board move                  | Drop                         |
+    /// |--------|-----------------------------|------------------------------|
+    /// | 0-6    | destination square          | destination square           |
+    /// | 7-13   | source square               | piece                        |
+    /// | 14     | 0                           | 1                            |
+    /// | 15     | 1 for a promotion           | 0                            |
+    ///
+    /// Squares and pieces are given by their index (see [`Square`] and [`Piece`]). Since
+    /// a move never has the same source and destination square, no move is encoded as 0,
+    /// which leaves 0 for "no move".
+    ///
+    /// # Examples
+    /// ```
+    /// use haitaka_types::*;
+    ///
+    /// let mv: Move = "7g7f".parse().unwrap();
+    /// assert_eq!(mv.to_u16(), Square::F7 as u16 | (Square::G7 as u16) << 7);
+    /// assert_eq!(Move::from_u16(mv.to_u16()), Some(mv));
+    ///
+    /// let mv: Move = "S*5e".parse().unwrap();
+    /// assert_eq!(mv.to_u16(), Square::E5 as u16 | (Piece::Silver as u16) << 7 | 1 << 14);
+    /// assert_eq!(Move::from_u16(mv.to_u16()), Some(mv));
+    ///
+    /// // An `Option<Move>` fits in the same 16 bits
+    /// let no_move: Option<Move> = None;
+    /// assert_eq!(Move::from_u16(no_move.map_or(0, |mv| mv.to_u16())), None);
+    /// ```
+    pub const fn to_u16(&self) -> u16 {
+        match *self {
+            Move::BoardMove {
+                from,
+                to,
+                promotion,
+            } => to as u16 | (from as u16) << 7 | (promotion as u16) << 15,
+            Move::Drop { piece, to } => to as u16 | (piece as u16) << 7 | 1 << 14,
+        }
+    }
+
+    /// Decode a move encoded with [`Move::to_u16`].
+    ///
+    /// Returns None if `value` is not the encoding of a move: if a square or piece is
+    /// out of range, if the source and destination of a board move are the same square,
+    /// or if a drop is a promotion or drops a piece that can't be in hand.
+    ///
+    /// # Examples
+    /// ```
+    /// use haitaka_types::*;
+    ///
+    /// for from in Square::ALL {
+    ///     for to in Square::ALL {
+    ///         for promotion in [false, true] {
+    ///             let mv = Move::BoardMove { from, to, promotion };
+    ///             let expected = (from != to).then_some(mv);
+    ///             assert_eq!(Move::from_u16(mv.to_u16()), expected);
+    ///         }
+    ///     }
+    /// }
+    /// let all_moves = (0..=u16::MAX).filter_map(Move::from_u16).count();
+    /// assert_eq!(all_moves, 81 * 80 * 2 + 7 * 81);
+    /// ```
+    pub const fn from_u16(value: u16) -> Option<Self> {
+        let Some(to) = Square::try_index((value & 0x7f) as usize) else {
+            return None;
+        };
+        let index = ((value >> 7) & 0x7f) as usize;
+        let promotion = value & (1 << 15) != 0;
+        if value & (1 << 14) != 0 {
+            if promotion || index >= Piece::HAND_NUM {
+                return None;
+            }
+            Some(Move::Drop {
+                piece: Piece::index_const(index),
+                to,
+            })
+        } else {
+            match Square::try_index(index) {
+                Some(from) if from as usize != to as usize => Some(Move::BoardMove {
+                    from,
+                    to,
+                    promotion,
+                }),
+                _ => None,
+            }
+        }
+    }
+
+    /// Parse a [USI](http://hgm.nubati.net/usi.html) move string, resolving a missing
+    /// promotion marker according to `policy`.
+    ///
+    /// In addition to the USI syntax accepted by [`Move::from_str`], a board move may
+    /// end with `=` to explicitly mark a non-promotion. The promotion flag is set without
+    /// regard to the piece or the board, so the result may be a "promotion" of a Gold
+    /// or King. Pass the move through `Board::normalize_move` to get a canonical legal move.
+    ///
+    /// # Examples
+    /// ```
+    /// use haitaka_types::*;
+    ///
+    /// let mv = Move::parse_with_policy("7g7f", PromotionPolicy::NeverImplicit).unwrap();
+    /// assert!(!mv.is_promotion());
+    /// let mv = Move::parse_with_policy("7g7f", PromotionPolicy::PreferPromotion).unwrap();
+    /// assert!(mv.is_promotion());
+    ///
+    /// // Explicit markers always win
+    /// let mv = Move::parse_with_policy("2c2b=", PromotionPolicy::PreferPromotion).unwrap();
+    /// assert!(!mv.is_promotion());
+    /// let mv = Move::parse_with_policy("2c2b+", PromotionPolicy::NeverImplicit).unwrap();
+    /// assert!(mv.is_promotion());
+    ///
+    /// // Drops are never promotions
+    /// let mv = Move::parse_with_policy("P*5e", PromotionPolicy::PreferPromotion).unwrap();
+    /// assert!(!mv.is_promotion());
+    ///
+    /// assert!(Move::parse_with_policy("7g7f+=", PromotionPolicy::NeverImplicit).is_err());
+    /// assert!(Move::parse_with_policy("P*5e=", PromotionPolicy::NeverImplicit).is_err());
+    /// ```
+    pub fn parse_with_policy(s: &str, policy: PromotionPolicy) -> Result<Self, MoveParseError> {
+        if let Some(rest) = s.strip_suffix('=') {
+            return match Move::from_str(rest)? {
+                mv @ Move::BoardMove {
+                    promotion: false, ..
+                } => Ok(mv),
+                _ => Err(MoveParseError::ExtraCharacters),
+            };
+        }
+
+        let mv = Move::from_str(s)?;
+        match mv {
+            Move::BoardMove { from, to, .. }
+                if policy == PromotionPolicy::PreferPromotion && !s.ends_with('+') =>
+            {
+                Ok(Move::BoardMove {
+                    from,
+                    to,
+                    promotion: true,
+                })
+            }
+            _ => Ok(mv),
+        }
+    }
+
+    // Helper function to parse a drop such as `P*7b`.
+    fn parse_drop(piece: &str, to: &str) -> Result<Self, MoveParseError> {
+        let piece = piece
+            .parse::<Piece>()
+            .map_err(|_| MoveParseError::InvalidPiece)?;
+        if piece as usize >= Piece::HAND_NUM {
+            return Err(MoveParseError::InvalidDropPiece);
+        }
+        let to = to
+            .parse::<Square>()
+            .map_err(|_| MoveParseError::InvalidSquare)?;
+        Ok(Move::Drop { piece, to })
+    }
+
     // Helper function to parse a square.
     fn parse_square_range(
         s: &str,
@@ -120,7 +324,7 @@ impl Move {
     ///
     /// # Examples
     /// ```
-    /// use haitaka_types::{Move, Square, Piece};
+    /// use haitaka_types::{Move, MoveParseError, Piece, Square};
     ///
     /// let mv = Move::parse("P*7b").unwrap();
     /// assert!(mv.is_drop());
@@ -141,26 +345,24 @@ impl Move {
     /// assert_eq!(mv.from(), Some(Square::H8));
     /// assert_eq!(mv.to(), Square::C3);
     /// assert!(mv.is_promotion());
+    ///
+    /// assert!(matches!(Move::parse(""), Err(MoveParseError::InvalidFormat)));
+    /// assert!(matches!(Move::parse("X8bx8f"), Err(MoveParseError::InvalidPiece)));
+    /// assert!(matches!(Move::parse("+K*5e"), Err(MoveParseError::InvalidPiece)));
+    /// assert!(matches!(Move::parse("R8jx8f"), Err(MoveParseError::InvalidSquare)));
     /// ```
     pub fn parse(s: &str) -> Result<Self, MoveParseError> {
         // Check for a drop move (e.g., "P*7b")
         if let Some((piece_str, rest)) = s.split_once('*') {
-            let piece = piece_str
-                .parse::<Piece>()
-                .map_err(|_| MoveParseError::InvalidPiece)?;
-            let to = rest
-                .parse::<Square>()
-                .map_err(|_| MoveParseError::InvalidSquare)?;
-            return Ok(Move::Drop { piece, to });
+            return Self::parse_drop(piece_str, rest);
         }
 
         // Parse a board move (e.g., "+R8bx8f" or "B8hx3c+")
-        let piece_str = if s.starts_with('+') {
-            &s[0..2] // "+PIECE"
-        } else {
-            &s[0..1] // "PIECE"
-        };
-        let n = piece_str.len();
+        let n = if s.starts_with('+') { 2 } else { 1 };
+        let piece_str = s.get(0..n).ok_or(MoveParseError::InvalidFormat)?;
+        piece_str
+            .parse::<Piece>()
+            .map_err(|_| MoveParseError::InvalidPiece)?;
 
         let from = Self::parse_square_range(s, n..n + 2)?;
         let to = Self::parse_square_range(s, n + 3..n + 5)?;
@@ -189,6 +391,102 @@ impl Move {
     }
 }
 
+// The CSA piece names, in the order of `Piece::ALL`.
+const CSA_PIECES: [&str; Piece::NUM] = [
+    "FU", "KY", "KE", "GI", "KA", "HI", "KI", "OU", "TO", "NY", "NK", "NG", "UM", "RY",
+];
+
+impl Move {
+    /// Parse a move in CSA format, such as `7776FU` or `+8822UM`.
+    ///
+    /// A CSA move gives the from-square and the to-square as digits (file, then rank, so
+    /// `77` is 7g), followed by the piece _after_ the move. Drops have `00` as from-square.
+    /// An optional `+` or `-` in front marks the color of the player; it is not checked.
+    ///
+    /// Whether a board move promotes can only be told from the piece on the from-square,
+    /// so the caller passes a function that returns it (typically `|sq| board.piece_on(sq)`
+    /// with a `haitaka` board).
+    ///
+    /// # Errors
+    /// Errors with [`MoveParseError::PieceMismatch`] if the piece after the move is
+    /// neither the piece on the from-square nor its promoted form.
+    ///
+    /// # Examples
+    /// ```
+    /// use haitaka_types::*;
+    ///
+    /// // A Bishop on 8h, and a Horse on 2b
+    /// let piece_on = |sq: Square| match sq {
+    ///     Square::H8 => Some(Piece::Bishop),
+    ///     Square::B2 => Some(Piece::PBishop),
+    ///     _ => None,
+    /// };
+    /// assert_eq!(Move::parse_csa("+8822UM", piece_on).unwrap(), "8h2b+".parse().unwrap());
+    /// assert_eq!(Move::parse_csa("8822KA", piece_on).unwrap(), "8h2b".parse().unwrap());
+    /// assert_eq!(Move::parse_csa("-2231UM", piece_on).unwrap(), "2b3a".parse().unwrap());
+    /// assert_eq!(Move::parse_csa("0055KA", piece_on).unwrap(), "B*5e".parse().unwrap());
+    ///
+    /// assert!(matches!(Move::parse_csa("8822HI", piece_on), Err(MoveParseError::PieceMismatch)));
+    /// assert!(matches!(Move::parse_csa("0055UM", piece_on), Err(MoveParseError::InvalidDropPiece)));
+    /// assert!(matches!(Move::parse_csa("7776XX", piece_on), Err(MoveParseError::InvalidPiece)));
+    /// assert!(matches!(Move::parse_csa("7076FU", piece_on), Err(MoveParseError::InvalidSquare)));
+    /// ```
+    pub fn parse_csa(
+        s: &str,
+        piece_on: impl FnOnce(Square) -> Option<Piece>,
+    ) -> Result<Self, MoveParseError> {
+        let s = s
+            .strip_prefix('+')
+            .or_else(|| s.strip_prefix('-'))
+            .unwrap_or(s);
+        if !s.is_ascii() || s.len() < 6 {
+            return Err(MoveParseError::InvalidFormat);
+        }
+        if s.len() > 6 {
+            return Err(MoveParseError::ExtraCharacters);
+        }
+
+        let square = |digits: &str| -> Result<Option<Square>, MoveParseError> {
+            let mut digits = digits.chars().map(|c| c.to_digit(10));
+            match (digits.next().flatten(), digits.next().flatten()) {
+                (Some(0), Some(0)) => Ok(None),
+                (Some(file @ 1..=9), Some(rank @ 1..=9)) => Ok(Some(Square::new(
+                    File::index_const(file as usize - 1),
+                    Rank::index_const(rank as usize - 1),
+                ))),
+                _ => Err(MoveParseError::InvalidSquare),
+            }
+        };
+        let from = square(&s[0..2])?;
+        let to = square(&s[2..4])?.ok_or(MoveParseError::InvalidSquare)?;
+        let piece = CSA_PIECES
+            .iter()
+            .position(|&name| name == &s[4..6])
+            .map(Piece::index_const)
+            .ok_or(MoveParseError::InvalidPiece)?;
+
+        match from {
+            None if piece as usize >= Piece::HAND_NUM => Err(MoveParseError::InvalidDropPiece),
+            None => Ok(Move::Drop { piece, to }),
+            Some(from) => {
+                let moved = piece_on(from).ok_or(MoveParseError::PieceMismatch)?;
+                let promotion = if moved == piece {
+                    false
+                } else if !moved.is_promoted() && moved.promote() == piece {
+                    true
+                } else {
+                    return Err(MoveParseError::PieceMismatch);
+                };
+                Ok(Move::BoardMove {
+                    from,
+                    to,
+                    promotion,
+                })
+            }
+        }
+    }
+}
+
 impl FromStr for Move {
     type Err = MoveParseError;
 
@@ -231,17 +529,13 @@ impl FromStr for Move {
     /// assert!(Move::from_str("P*10b").is_err()); // Invalid square
     /// assert!(Move::from_str("7g").is_err()); // Too short
     /// assert!(Move::from_str("7g7f++").is_err()); // Invalid extra characters
+    /// assert!(matches!(Move::from_str("+P*5e"), Err(MoveParseError::InvalidDropPiece)));
+    /// assert!(matches!(Move::from_str("K*5e"), Err(MoveParseError::InvalidDropPiece)));
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // drop
         if let Some((piece_str, rest)) = s.split_once('*') {
-            let piece = piece_str
-                .parse::<Piece>()
-                .map_err(|_| MoveParseError::InvalidPiece)?;
-            let to = rest
-                .parse::<Square>()
-                .map_err(|_| MoveParseError::InvalidSquare)?;
-            return Ok(Move::Drop { piece, to });
+            return Self::parse_drop(piece_str, rest);
         }
 
         // board move
@@ -279,6 +573,36 @@ impl core::convert::TryFrom<&str> for Move {
     }
 }
 
+impl Ord for Move {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        match (self, other) {
+            (
+                Move::BoardMove {
+                    from: f1,
+                    to: t1,
+                    promotion: p1,
+                },
+                Move::BoardMove {
+                    from: f2,
+                    to: t2,
+                    promotion: p2,
+                },
+            ) => (f1, t1, p1).cmp(&(f2, t2, p2)),
+            (Move::Drop { piece: p1, to: t1 }, Move::Drop { piece: p2, to: t2 }) => {
+                (p1, t1).cmp(&(p2, t2))
+            }
+            (Move::BoardMove { .. }, Move::Drop { .. }) => core::cmp::Ordering::Less,
+            (Move::Drop { .. }, Move::BoardMove { .. }) => core::cmp::Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Move {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl core::fmt::Display for Move {
     /// Display a [`Move`] in [USI](http://hgm.nubati.net/usi.html) format.
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
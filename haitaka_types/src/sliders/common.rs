@@ -270,7 +270,7 @@ pub const fn lance_pseudo_attacks(color: Color, square: Square) -> BitBoard {
         let mut index: usize = 0;
 
         while index < Square::NUM {
-            if index % 9 == 0 {
+            if index.is_multiple_of(9) {
                 mask = 0x1FF << index;
             }
 
@@ -475,6 +475,7 @@ pub const fn get_rook_rank_moves(square: Square, occ: BitBoard) -> BitBoard {
 
 /// Get all squares between two squares, if reachable via a ray.
 /// The `from` and `to` square are not included in the returns [`BitBoard`].
+/// See also [`between_exclusive`] and [`between_inclusive`].
 ///
 /// # Examples
 /// ```
@@ -533,7 +534,7 @@ pub const fn get_between_rays(from: Square, to: Square) -> BitBoard {
 
 /// Get a ray on the board that passes through both squares, if it exists.
 ///
-/// These rays include the `from` and `to` square.
+/// These rays include the `from` and `to` square. See also [`ray_through`].
 ///
 /// # Examples
 /// ```
@@ -586,3 +587,93 @@ pub const fn line_ray(from: Square, to: Square) -> BitBoard {
     };
     TABLE[from as usize][to as usize]
 }
+
+/// Get the squares strictly between two squares on a line.
+///
+/// This is [`get_between_rays`] under a name that states what it returns: the squares
+/// that a slider on `a` has to pass to reach `b`. It is the mask of squares where a
+/// piece can interpose against a check, or where a pinned piece stands.
+///
+/// | `a` and `b` are               | result                          |
+/// |-------------------------------|---------------------------------|
+/// | on a rank, file or diagonal   | the squares between, if any     |
+/// | adjacent                      | empty                           |
+/// | the same square               | empty                           |
+/// | not aligned (a Knight's jump) | empty                           |
+///
+/// # Examples
+/// ```
+/// # use haitaka_types::*;
+/// assert_eq!(between_exclusive(Square::A1, Square::D1), Square::B1.bitboard() | Square::C1.bitboard());
+/// assert_eq!(between_exclusive(Square::D1, Square::A1), between_exclusive(Square::A1, Square::D1));
+/// assert!(between_exclusive(Square::A1, Square::B1).is_empty());
+/// assert!(between_exclusive(Square::A1, Square::A1).is_empty());
+/// assert!(between_exclusive(Square::A1, Square::C2).is_empty());
+/// ```
+#[inline(always)]
+pub const fn between_exclusive(a: Square, b: Square) -> BitBoard {
+    get_between_rays(a, b)
+}
+
+/// Get the squares between two squares on a line, including both squares.
+///
+/// This is the segment from `a` to `b`, for instance the squares that a slider on `a`
+/// attacks on its way to `b` together with `a` itself. If the squares are not aligned,
+/// there is no segment and the result is empty.
+///
+/// | `a` and `b` are               | result                           |
+/// |-------------------------------|----------------------------------|
+/// | on a rank, file or diagonal   | `a`, `b` and the squares between |
+/// | adjacent                      | `a` and `b`                      |
+/// | the same square               | that square                      |
+/// | not aligned (a Knight's jump) | empty                            |
+///
+/// # Examples
+/// ```
+/// # use haitaka_types::*;
+/// let segment = between_inclusive(Square::A1, Square::C3);
+/// assert_eq!(segment, Square::A1.bitboard() | Square::B2.bitboard() | Square::C3.bitboard());
+/// assert_eq!(between_inclusive(Square::A1, Square::B1).len(), 2);
+/// assert_eq!(between_inclusive(Square::E5, Square::E5), Square::E5.bitboard());
+/// assert!(between_inclusive(Square::A1, Square::C2).is_empty());
+/// ```
+#[inline(always)]
+pub const fn between_inclusive(a: Square, b: Square) -> BitBoard {
+    if a as u8 == b as u8 {
+        return a.bitboard();
+    }
+    if line_ray(a, b).is_empty() {
+        return BitBoard::EMPTY;
+    }
+    BitBoard(get_between_rays(a, b).0 | a.bitboard().0 | b.bitboard().0)
+}
+
+/// Get the full line through two squares, from edge to edge of the board.
+///
+/// This is [`line_ray`] under a name that states what it returns. The line extends
+/// beyond both squares, so it also holds the squares behind `b` as seen from `a`,
+/// which is what skewer and x-ray logic needs.
+///
+/// | `a` and `b` are               | result                               |
+/// |-------------------------------|--------------------------------------|
+/// | on a rank, file or diagonal   | the whole rank, file or diagonal     |
+/// | adjacent                      | the whole rank, file or diagonal     |
+/// | the same square               | empty (there is no unique line)      |
+/// | not aligned (a Knight's jump) | empty                                |
+///
+/// # Examples
+/// ```
+/// # use haitaka_types::*;
+/// assert_eq!(ray_through(Square::E2, Square::E3), Rank::E.bitboard());
+/// assert_eq!(ray_through(Square::A1, Square::B2), ray_through(Square::H8, Square::I9));
+/// assert!(ray_through(Square::E5, Square::E5).is_empty());
+/// assert!(ray_through(Square::A1, Square::C2).is_empty());
+///
+/// // the line holds the squares on both sides
+/// assert!(ray_through(Square::E4, Square::E5).has(Square::E1));
+/// assert!(ray_through(Square::E4, Square::E5).has(Square::E9));
+/// ```
+#[inline(always)]
+pub const fn ray_through(a: Square, b: Square) -> BitBoard {
+    line_ray(a, b)
+}
@@ -213,10 +213,13 @@ const fn get_magic_index(
     magic.offset as usize + (hash >> magic.shift) as usize
 }
 
+// Internal: used to build and index the slider move tables.
+#[doc(hidden)]
 pub const fn get_rook_moves_index(square: Square, blockers: BitBoard) -> usize {
     get_magic_index(ROOK_MAGICS, square, blockers)
 }
 
+#[doc(hidden)]
 pub const fn get_bishop_moves_index(square: Square, blockers: BitBoard) -> usize {
     get_magic_index(BISHOP_MAGICS, square, blockers)
 }
@@ -11,6 +11,11 @@
 //! of the bitboards. The main reason for choosing this internal layout is that it
 //! makes move generation of Lance moves easier to implement and faster (since Lances
 //! slide along files).
+//!
+//! This layout is part of the stable API: the index of a square is
+//! `file as usize * 9 + rank as usize`, [`Square::ALL`] lists the squares in index
+//! order, and a square maps to bit `square as usize` of a [`BitBoard`]. Use
+//! [`Square::iter_rank_major`] to visit the squares row by row instead.
 //!    
 use core::convert::TryInto;
 use core::str::FromStr;
@@ -21,6 +26,22 @@ macro_rules! define_square_with_docs {
     ($($square:ident),*) => {
         crate::helpers::simple_enum! {
             /// A square on a Shogi board.
+            ///
+            /// The squares are numbered in file-major order (see the [module documentation](self)).
+            /// This order is guaranteed not to change.
+            ///
+            /// # Examples
+            /// ```
+            /// # use haitaka_types::*;
+            /// for (index, &square) in Square::ALL.iter().enumerate() {
+            ///     assert_eq!(square as usize, index);
+            ///     assert_eq!(index, square.file() as usize * File::NUM + square.rank() as usize);
+            ///     assert_eq!(square.bitboard(), BitBoard::new(1 << index));
+            /// }
+            /// assert_eq!(Square::ALL[0], Square::A1);
+            /// assert_eq!(Square::ALL[1], Square::B1);
+            /// assert_eq!(Square::ALL[9], Square::A2);
+            /// ```
             #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
             pub enum Square {
                 $(
@@ -244,6 +265,33 @@ pub const NEG_DIA: [BitBoard; 17] = [
 ];
 
 impl Square {
+    /// Iterate over all squares in rank-major order.
+    ///
+    /// The squares are visited row by row, as they are read on a board diagram and in
+    /// SFEN strings: from rank A to rank I and, within each rank, from file 9 to file 1.
+    /// This is the order renderers and exporters of board planes usually need, while
+    /// [`Square::ALL`] follows the internal file-major order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use haitaka_types::*;
+    /// let squares: Vec<Square> = Square::iter_rank_major().collect();
+    /// assert_eq!(squares.len(), Square::NUM);
+    /// assert_eq!(&squares[..3], &[Square::A9, Square::A8, Square::A7]);
+    /// assert_eq!(squares[8], Square::A1);
+    /// assert_eq!(squares[9], Square::B9);
+    /// assert_eq!(squares[80], Square::I1);
+    /// ```
+    pub fn iter_rank_major() -> impl DoubleEndedIterator<Item = Square> + ExactSizeIterator + Clone
+    {
+        (0..Self::NUM).map(|index| {
+            Self::new(
+                File::index_const(File::NUM - 1 - index % File::NUM),
+                Rank::index_const(index / File::NUM),
+            )
+        })
+    }
+
     /// Make a square from a file and a rank.
     /// # Examples
     /// ```